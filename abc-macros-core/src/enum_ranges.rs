@@ -0,0 +1,388 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Ident, LitInt, Token};
+
+use crate::{with_help, Errors};
+
+/// This represents macro input syntax for a single variant range.
+///
+/// Example: `Foo: 1..10` or `Bar: 11`
+///
+#[derive(Debug, PartialEq)]
+pub struct NamedRange {
+    /// The variant's name.
+    pub name: Ident,
+    /// The start of the range.
+    pub start: u64,
+    /// The end of the range (exclusive), or `None` for a single value.
+    pub end: Option<u64>,
+}
+
+/// The tokens to blame for something that's missing: the token that's
+/// there instead, or `before` if the input ends first.
+fn found_or(input: ParseStream, before: &Ident) -> TokenStream {
+    match input.fork().parse::<TokenTree>() {
+        Ok(found) => found.into_token_stream(),
+        Err(_) => before.into_token_stream(),
+    }
+}
+
+/// Parse a `NamedRange` from macro input.
+impl Parse for NamedRange {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        // Try to parse an Ident (the variant name).
+        let name: Ident = input.parse()?;
+        // Try to parse the ':' after the name.
+        if !input.peek(Token![:]) {
+            let message = format!("expected `:` after `{}`", name);
+            let help = format!("write it like `{}: 1..10`, or `{}: 1`", name, name);
+            return Err(with_help(found_or(input, &name), message, help));
+        }
+        input.parse::<Token![:]>()?;
+        // Try to parse a literal integer.
+        let start_lit: LitInt = input.parse()?;
+        let start = start_lit.base10_parse::<u64>()?;
+        // Catch ranges written the way other languages write them, before
+        // `..` would match the start of them.
+        if input.peek(Token![..=]) {
+            let dots: Token![..=] = input.parse()?;
+            let end = input.parse::<LitInt>()?.base10_parse::<u64>()?;
+            let message = "the end of a range is excluded, so it can't use `..=`";
+            return Err(match end.checked_add(1) {
+                Some(after) => with_help(
+                    dots,
+                    message,
+                    format!("did you mean `{}..{}`?", start, after),
+                ),
+                None => with_help(
+                    dots,
+                    message,
+                    "use `..`, with an end one past the last value",
+                ),
+            });
+        }
+        let wrong = if input.peek(Token![...]) {
+            Some((input.parse::<Token![...]>()?.into_token_stream(), "..."))
+        } else if input.peek(Token![-]) {
+            Some((input.parse::<Token![-]>()?.into_token_stream(), "-"))
+        } else {
+            None
+        };
+        if let Some((tokens, text)) = wrong {
+            let message = format!("expected `..`, found `{}`", text);
+            let help = match input
+                .parse::<LitInt>()
+                .and_then(|end| end.base10_parse::<u64>())
+            {
+                Ok(end) => format!("ranges use `..`, did you mean `{}..{}`?", start, end),
+                Err(_) => "ranges use `..`, like `1..10`".to_string(),
+            };
+            return Err(with_help(tokens, message, help));
+        }
+        // Optional: there may be a ".." followed by another integer.
+        // If dots are present, the integer must be too.
+        let end = if input.peek(Token![..]) {
+            input.parse::<Token![..]>()?;
+            let end_lit: LitInt = input.parse()?;
+            Some(end_lit.base10_parse::<u64>()?)
+        } else {
+            None
+        };
+
+        Ok(NamedRange { name, start, end })
+    }
+}
+
+/// Macro syntax for a list of NamedRange types
+#[derive(Debug, PartialEq)]
+pub struct NamedRangeList {
+    pub list: Vec<NamedRange>,
+}
+
+impl NamedRangeList {
+    /// Parse a comma-separated list, recording the errors instead of
+    /// stopping at the first one.
+    ///
+    /// A range that doesn't parse is skipped, up to the next comma, so
+    /// the ranges after it are still checked, and still returned.
+    ///
+    pub fn parse_all(input: ParseStream, errors: &mut Errors) -> Self {
+        NamedRangeList {
+            list: errors.parse_terminated(input),
+        }
+    }
+}
+
+/// Parse a `NamedRangeList` from macro input.
+impl Parse for NamedRangeList {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut errors = Errors::new();
+        let ranges = NamedRangeList::parse_all(input, &mut errors);
+        errors.finish()?;
+        Ok(ranges)
+    }
+}
+
+/// This is the entire input to the `enum_ranges!` macro.
+///
+/// The input is expected to be in the form:
+/// ```ignore
+/// enum_ranges!(
+///     Color {
+///         Blue: 450..495,
+///         Green: 495..570,
+///         Yellow: 570..590,
+///     }
+/// )
+/// ```
+///
+// Comparing and printing the attributes needs `syn/extra-traits`, which
+// the tests and the `extra-traits` feature turn on.
+#[cfg_attr(any(test, feature = "extra-traits"), derive(Debug, PartialEq))]
+pub struct RangedEnum {
+    // If the user wants to attach e.g. #[derive(...)] attributes, we should
+    // permit them inside the macro, because there's no way to attach them
+    // outside.
+    pub attributes: Vec<Attribute>,
+    pub name: Ident,
+    pub variants: NamedRangeList,
+}
+
+/// A range as it's written, like `450..495` or `7`.
+fn display_range(range: &NamedRange) -> String {
+    match range.end {
+        Some(end) => format!("{}..{}", range.start, end),
+        None => range.start.to_string(),
+    }
+}
+
+/// Check that each variant has its own name, and its own values: a
+/// number can only convert to one variant.
+fn check_ranges(ranges: &[NamedRange], errors: &mut Errors) {
+    // The values each range covers, with the end excluded. A single value
+    // `u64::MAX` ends past the end of a `u64`.
+    let bounds = |range: &NamedRange| {
+        let start = u128::from(range.start);
+        (start, range.end.map_or(start + 1, u128::from))
+    };
+    for (index, range) in ranges.iter().enumerate() {
+        let earlier = &ranges[..index];
+        if earlier.iter().any(|other| other.name == range.name) {
+            errors.push_spanned(&range.name, "duplicate variant name");
+            continue;
+        }
+        let (start, end) = bounds(range);
+        if end <= start {
+            errors.push_help(
+                &range.name,
+                format!("the range {} is empty", display_range(range)),
+                "the end of a range is excluded, so it has to be more than the start",
+            );
+            continue;
+        }
+        let overlap = earlier.iter().find(|other| {
+            let (other_start, other_end) = bounds(other);
+            start < other_end && other_start < end
+        });
+        if let Some(other) = overlap {
+            errors.push_spanned(
+                &range.name,
+                format!(
+                    "this range overlaps `{}: {}`",
+                    other.name,
+                    display_range(other)
+                ),
+            );
+        }
+    }
+}
+
+/// Parse the macro syntax for `enum_ranges!`
+impl Parse for RangedEnum {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        // Parse any attributes. We won't do anything with them, other
+        // than emit them in the final output.
+        let attributes = syn::Attribute::parse_outer(input)?;
+
+        // Try to parse the enum name.
+        let name: Ident = input.parse()?;
+        if !input.peek(syn::token::Brace) {
+            let message = "expected the variants, in braces";
+            let help = format!("write it like `{} {{ Low: 0..10, High: 10 }}`", name);
+            return Err(with_help(found_or(input, &name), message, help));
+        }
+
+        // Generate a sub-stream containing whatever is contained in braces.
+        let content;
+        braced!(content in input);
+
+        // Parse the list that's inside the braces. Every problem with it
+        // is reported, not just the first.
+        let mut errors = Errors::new();
+        let variants = NamedRangeList::parse_all(&content, &mut errors);
+        check_ranges(&variants.list, &mut errors);
+        errors.finish()?;
+
+        Ok(RangedEnum {
+            attributes,
+            name,
+            variants,
+        })
+    }
+}
+
+/// Emit the tokens that will be returned by the macro.
+///
+/// It's probably wrong that Parse and ToTokens aren't symmetrical (Parse
+/// consumes macro input syntax, while ToTokens emits the macro output).
+///
+impl ToTokens for RangedEnum {
+    fn to_tokens(&self, _tokens: &mut TokenStream) {
+        // TODO: implement this!
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use quote::format_ident;
+
+    #[test]
+    fn parse_one_range() {
+        let ranged: NamedRange = syn::parse_str("Foo: 1..10").unwrap();
+
+        assert_eq!(
+            ranged,
+            NamedRange {
+                name: format_ident!("Foo"),
+                start: 1,
+                end: Some(10),
+            }
+        );
+
+        let ranged: NamedRange = syn::parse_str("Foo: 7").unwrap();
+        assert_eq!(
+            ranged,
+            NamedRange {
+                name: format_ident!("Foo"),
+                start: 7,
+                end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_range_list() {
+        let ranges: NamedRangeList = syn::parse_str("Foo: 1..10, Bar: 11").unwrap();
+
+        assert_eq!(
+            ranges.list,
+            vec![
+                NamedRange {
+                    name: format_ident!("Foo"),
+                    start: 1,
+                    end: Some(10),
+                },
+                NamedRange {
+                    name: format_ident!("Bar"),
+                    start: 11,
+                    end: None,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_range_list_errors() {
+        let mut errors = Errors::new();
+        let ranges = syn::parse::Parser::parse_str(
+            |input: ParseStream| Ok(NamedRangeList::parse_all(input, &mut errors)),
+            "Foo: x, Bar: 11, Baz: 1..y z, Qux: 3",
+        )
+        .unwrap();
+        // The good ranges are still there.
+        let names: Vec<String> = ranges.list.iter().map(|r| r.name.to_string()).collect();
+        assert_eq!(names, ["Bar", "Qux"]);
+        let messages: Vec<String> = errors
+            .finish()
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            ["expected integer literal", "expected integer literal"]
+        );
+    }
+
+    #[test]
+    fn parse_range_help() {
+        let error = |input| syn::parse_str::<NamedRange>(input).unwrap_err().to_string();
+        assert_eq!(
+            error("Blue: 450-495"),
+            "expected `..`, found `-`\nhelp: ranges use `..`, did you mean `450..495`?"
+        );
+        assert_eq!(
+            error("Blue: 450...495"),
+            "expected `..`, found `...`\nhelp: ranges use `..`, did you mean `450..495`?"
+        );
+        assert_eq!(
+            error("Blue: 450..=494"),
+            "the end of a range is excluded, so it can't use `..=`\nhelp: did you mean `450..495`?"
+        );
+        assert_eq!(
+            error("Blue 450..495"),
+            "expected `:` after `Blue`\nhelp: write it like `Blue: 1..10`, or `Blue: 1`"
+        );
+        assert_eq!(
+            syn::parse_str::<RangedEnum>("Color").unwrap_err().to_string(),
+            "expected the variants, in braces\nhelp: write it like `Color { Low: 0..10, High: 10 }`"
+        );
+    }
+
+    #[test]
+    fn parse_ranges() {
+        let ranged: RangedEnum = syn::parse_str("MyRanges { Foo: 1..10, Bar: 11 }").unwrap();
+        assert_eq!(ranged.name, "MyRanges");
+        assert_eq!(
+            ranged.variants.list,
+            vec![
+                NamedRange {
+                    name: format_ident!("Foo"),
+                    start: 1,
+                    end: Some(10),
+                },
+                NamedRange {
+                    name: format_ident!("Bar"),
+                    start: 11,
+                    end: None,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn check_ranges() {
+        let error = |input| syn::parse_str::<RangedEnum>(input).unwrap_err();
+        let messages: Vec<String> = error(
+            "Color { Red: 620..750, Blue: 450..495, Violet: 380..451, Red: 0, Black: x, Green: 9..9 }",
+        )
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect();
+        assert_eq!(
+            messages,
+            [
+                "expected integer literal",
+                "this range overlaps `Blue: 450..495`",
+                "duplicate variant name",
+                "the range 9..9 is empty\nhelp: the end of a range is excluded, so it has to be more than the start",
+            ]
+        );
+        // A single value is a range of one.
+        error("Max { Last: 18446744073709551615, Also: 18446744073709551615 }");
+        syn::parse_str::<RangedEnum>("Digits { Low: 0..5, High: 5..10, Ten: 10 }").unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Display;
+
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// A list of errors, so that a macro can report every problem with its
+/// input at once, instead of only the first.
+///
+/// ```
+/// use abc_macros_core::Errors;
+///
+/// let mut errors = Errors::new();
+/// let a: Option<u8> = errors.push_result(syn::parse_str::<syn::LitInt>("1")?.base10_parse());
+/// let b: Option<u8> = errors.push_result(syn::parse_str::<syn::LitInt>("300")?.base10_parse());
+/// assert_eq!((a, b), (Some(1), None));
+/// assert_eq!(errors.finish().unwrap_err().to_string(), "number too large to fit in target type");
+/// # Ok::<(), syn::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Errors {
+    error: Option<syn::Error>,
+}
+
+impl Errors {
+    /// An empty list.
+    pub fn new() -> Self {
+        Errors::default()
+    }
+
+    /// Add an error.
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.error {
+            Some(first) => first.combine(error),
+            None => self.error = Some(error),
+        }
+    }
+
+    /// Add an error at the span of some tokens.
+    pub fn push_spanned<T: ToTokens, U: Display>(&mut self, tokens: T, message: U) {
+        self.push(syn::Error::new_spanned(tokens, message));
+    }
+
+    /// Add an error at the span of some tokens, with a suggestion for
+    /// fixing it; see [`with_help`].
+    pub fn push_help<T: ToTokens, U: Display, V: Display>(
+        &mut self,
+        tokens: T,
+        message: U,
+        help: V,
+    ) {
+        self.push(with_help(tokens, message, help));
+    }
+
+    /// Add the error from a result, if it failed, and return the value if
+    /// it didn't.
+    pub fn push_result<T>(&mut self, result: syn::parse::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.push(error);
+                None
+            }
+        }
+    }
+
+    /// Parse a comma-separated list, recording the errors instead of
+    /// stopping at the first one.
+    ///
+    /// An item that doesn't parse is skipped, up to the next comma, so the
+    /// items after it are still checked, and still returned.
+    ///
+    pub fn parse_terminated<T: Parse>(&mut self, input: ParseStream) -> Vec<T> {
+        let mut list = Vec::new();
+        while !input.is_empty() {
+            let parsed = input.parse().and_then(|item| {
+                list.push(item);
+                if input.is_empty() {
+                    Ok(())
+                } else {
+                    input.parse::<Token![,]>().map(drop)
+                }
+            });
+            if let Err(error) = parsed {
+                self.push(error);
+                // Skip whatever's left of the bad item.
+                while !input.is_empty() && !input.peek(Token![,]) {
+                    if input.parse::<TokenTree>().is_err() {
+                        break;
+                    }
+                }
+                let _ = input.parse::<Option<Token![,]>>();
+            }
+        }
+        list
+    }
+
+    /// Whether there are no errors.
+    pub fn is_empty(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Finish, with all the errors combined into one, if there were any.
+    ///
+    /// A combined `syn::Error` emits a `compile_error!` for each of them.
+    pub fn finish(self) -> syn::parse::Result<()> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An error at the span of some tokens, with a suggestion for fixing it.
+///
+/// A proc macro can't attach a `help:` note to an error the way the
+/// compiler does, so the suggestion goes on a line of its own:
+///
+/// ```text
+/// error: expected `..` between the bounds of a range
+///        help: did you mean `450..495`?
+/// ```
+pub fn with_help<T: ToTokens, U: Display, V: Display>(
+    tokens: T,
+    message: U,
+    help: V,
+) -> syn::Error {
+    syn::Error::new_spanned(tokens, format!("{}\nhelp: {}", message, help))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn combine() {
+        let mut errors = Errors::new();
+        assert!(errors.is_empty());
+        errors.push(syn::Error::new(Span::call_site(), "first"));
+        errors.push_spanned(quote::quote!(x), "second");
+        assert_eq!(errors.push_result(Ok(3)), Some(3));
+        let messages: Vec<String> = errors
+            .finish()
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(messages, ["first", "second"]);
+        Errors::new().finish().unwrap();
+    }
+
+    #[test]
+    fn parse_list() {
+        let mut errors = Errors::new();
+        let list: Vec<syn::LitInt> = syn::parse::Parser::parse_str(
+            |input: ParseStream| Ok(errors.parse_terminated(input)),
+            "1, x, 3 4, 5,",
+        )
+        .unwrap();
+        let list: Vec<String> = list.iter().map(|lit| lit.to_string()).collect();
+        assert_eq!(list, ["1", "3", "5"]);
+        let messages: Vec<String> = errors
+            .finish()
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(messages, ["expected integer literal", "expected `,`"]);
+    }
+
+    #[test]
+    fn help() {
+        let mut errors = Errors::new();
+        errors.push_help(quote::quote!(-), "expected `..`", "did you mean `1..2`?");
+        assert_eq!(
+            errors.finish().unwrap_err().to_string(),
+            "expected `..`\nhelp: did you mean `1..2`?"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! The parsers behind `abc-macros`, usable outside a proc macro.
+//!
+//! A proc-macro crate can only export macros, so the types that parse a
+//! macro's input live here instead, built on `proc-macro2` rather than
+//! `proc_macro`. They can be parsed from a string with `syn::parse_str`,
+//! and tested like any other code:
+//!
+//! ```
+//! use abc_macros_core::RangedEnum;
+//!
+//! let ranged: RangedEnum = syn::parse_str("Color { Blue: 450..495 }").unwrap();
+//! assert_eq!(ranged.name, "Color");
+//! assert_eq!(ranged.variants.list[0].end, Some(495));
+//! ```
+//!
+//! `RangedEnum` only implements `Debug` and `PartialEq` with the
+//! `extra-traits` feature, which turns on syn's feature of the same name.
+
+pub mod enum_ranges;
+mod errors;
+
+pub use enum_ranges::{NamedRange, NamedRangeList, RangedEnum};
+pub use errors::{with_help, Errors};
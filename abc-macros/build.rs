@@ -0,0 +1,35 @@
+//! Record the version of rustc, for `rustc_version_at_least!`, and
+//! whether proc macros can find out where they're called.
+//!
+//! A proc macro is always run by the compiler that built it, so the
+//! version it was built with is the version of the code it expands in.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rustc-check-cfg=cfg(abc_span_locations)");
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = match Command::new(rustc).arg("--version").output() {
+        Ok(output) if output.status.success() => output,
+        // Leave it unset; the macro reports that it doesn't know.
+        _ => return,
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Like `rustc 1.70.0 (90c541806 2023-05-31)` or
+    // `rustc 1.72.0-nightly (...)`.
+    if let Some(version) = text.split_whitespace().nth(1) {
+        println!("cargo:rustc-env=ABC_RUSTC_VERSION={}", version);
+        // `Span::file` and `Span::line`, which name the files written for
+        // `ABC_MACRO_EXPAND_DIR`, are stable since 1.88.
+        let minor = version
+            .split('.')
+            .nth(1)
+            .and_then(|minor| minor.parse::<u32>().ok());
+        if matches!(minor, Some(minor) if minor >= 88) {
+            println!("cargo:rustc-cfg=abc_span_locations");
+        }
+    }
+}
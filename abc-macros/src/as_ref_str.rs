@@ -0,0 +1,164 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_quote, Ident, Meta, NestedMeta};
+
+use crate::attr::{self, lit_str};
+use crate::case::RenameRule;
+use crate::shallow::ShallowEnum;
+
+/// This is the entire input to `#[derive(AsRefStr)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct AsRefStrInput {
+    input: ShallowEnum,
+    // Each variant, a pattern for its fields, and its string.
+    variants: Vec<(Ident, TokenStream, String)>,
+}
+
+/// Parse the input to `#[derive(AsRefStr)]`.
+impl Parse for AsRefStrInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "AsRefStr")?;
+
+        let mut rename_all = None;
+        for nested in attr::options(&input.attrs, "as_str")? {
+            match &nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename_all") => {
+                    rename_all = Some(RenameRule::from_lit(lit_str(&nv.lit)?)?);
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown as_str option",
+                        &["rename_all"],
+                    ))
+                }
+            }
+        }
+
+        let mut variants: Vec<(Ident, TokenStream, String)> = Vec::new();
+        for variant in &input.variants {
+            let variant_name = variant.ident.unraw().to_string();
+            let mut name = match rename_all {
+                Some(rule) => rule.apply(&variant_name),
+                None => variant_name,
+            };
+            for nested in attr::options(&variant.attrs, "as_str")? {
+                match &nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                        name = lit_str(&nv.lit)?.value();
+                    }
+                    _ => {
+                        return Err(attr::unknown_option(
+                            nested,
+                            "unknown as_str option for a variant",
+                            &["rename"],
+                        ))
+                    }
+                }
+            }
+            // Serialization keys have to be unique to be read back.
+            if variants.iter().any(|(_, _, other)| *other == name) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("another variant already has the string \"{}\"", name),
+                ));
+            }
+            variants.push((variant.ident.clone(), variant.rest_pattern(), name));
+        }
+
+        Ok(AsRefStrInput { input, variants })
+    }
+}
+
+/// Emit `as_str`, and the conversions to `&'static str`.
+impl ToTokens for AsRefStrInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        // `From<&'a Enum>` needs a lifetime of its own.
+        let mut ref_generics = self.input.generics.clone();
+        ref_generics.params.insert(0, parse_quote!('__a));
+        let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+
+        let arms = self.variants.iter().map(|(variant, pattern, string)| {
+            quote! { #name::#variant #pattern => #string, }
+        });
+        let body = if self.variants.is_empty() {
+            quote! { match *self {} }
+        } else {
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        };
+
+        tokens.extend(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The name of this variant.
+                pub const fn as_str(&self) -> &'static str {
+                    #body
+                }
+            }
+
+            impl #impl_generics ::core::convert::AsRef<str> for #name #ty_generics #where_clause {
+                fn as_ref(&self) -> &str {
+                    self.as_str()
+                }
+            }
+
+            impl #ref_impl_generics ::core::convert::From<&'__a #name #ty_generics> for &'static str
+            #where_clause
+            {
+                fn from(value: &'__a #name #ty_generics) -> &'static str {
+                    value.as_str()
+                }
+            }
+
+            impl #impl_generics ::core::convert::From<#name #ty_generics> for &'static str
+            #where_clause
+            {
+                fn from(value: #name #ty_generics) -> &'static str {
+                    value.as_str()
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(input: &str) -> syn::parse::Result<Vec<String>> {
+        let input: AsRefStrInput = syn::parse_str(input)?;
+        Ok(input
+            .variants
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect())
+    }
+
+    #[test]
+    fn variant_strings() {
+        assert_eq!(
+            strings("enum E { A, #[as_str(rename = \"bee\")] B(u8), r#C { x: u8 } }").unwrap(),
+            ["A", "bee", "C"]
+        );
+        assert_eq!(
+            strings(
+                "#[as_str(rename_all = \"snake_case\")] enum E { HttpGet, #[as_str(rename = \"X\")] HttpPost }"
+            )
+            .unwrap(),
+            ["http_get", "X"]
+        );
+
+        strings("struct S;").unwrap_err();
+        strings("#[as_str(rename_all = \"bogus\")] enum E { A }").unwrap_err();
+        strings("#[as_str(rename = \"a\")] enum E { A }").unwrap_err();
+        strings("enum E { A, #[as_str(rename = \"A\")] B }").unwrap_err();
+        strings("enum E { #[as_str(skip)] A }").unwrap_err();
+    }
+}
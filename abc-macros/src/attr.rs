@@ -0,0 +1,102 @@
+//! Helpers for reading the options in helper attributes, like
+//! `#[describe(...)]` and `#[builder(...)]`.
+
+use proc_macro2::TokenTree;
+use quote::ToTokens;
+use syn::{Attribute, Lit, LitStr, Meta, NestedMeta};
+
+/// The contents of every `#[name(...)]` attribute in a list.
+pub fn options(attrs: &[Attribute], name: &str) -> syn::parse::Result<Vec<NestedMeta>> {
+    let mut options = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        match attr.parse_meta()? {
+            Meta::List(list) => options.extend(list.nested),
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    format!("expected a list of options, like `#[{}(...)]`", name),
+                ))
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// The string in an option like `label = "..."`.
+pub fn lit_str(lit: &Lit) -> syn::parse::Result<&LitStr> {
+    match lit {
+        Lit::Str(lit) => Ok(lit),
+        other => Err(syn::Error::new_spanned(other, "expected a string")),
+    }
+}
+
+/// The error for an option that isn't one of `known`, with a `help:` line
+/// suggesting the one that was probably meant, or listing them all.
+///
+/// The help goes on a line of its own, as in `abc_macros_core::with_help`.
+pub fn unknown_option<T: ToTokens>(option: T, message: &str, known: &[&str]) -> syn::Error {
+    let name = match option.to_token_stream().into_iter().next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => String::new(),
+    };
+    let closest = known
+        .iter()
+        .map(|candidate| (edit_distance(&name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2 && *distance < name.len())
+        .min();
+    let help = match (closest, known) {
+        (Some((_, candidate)), _) => format!("did you mean `{}`?", candidate),
+        (None, [only]) => format!("the only option is `{}`", only),
+        (None, _) => {
+            let quoted: Vec<String> = known.iter().map(|k| format!("`{}`", k)).collect();
+            format!("the options are {}", quoted.join(", "))
+        }
+    };
+    syn::Error::new_spanned(option, format!("{}\nhelp: {}", message, help))
+}
+
+/// The number of one-character edits that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn unknown_options() {
+        let known = ["default", "into"];
+        let error = |option| unknown_option(option, "unknown builder option", &known).to_string();
+        assert_eq!(
+            error(quote!(defualt)),
+            "unknown builder option\nhelp: did you mean `default`?"
+        );
+        assert_eq!(
+            error(quote!(optional = true)),
+            "unknown builder option\nhelp: the options are `default`, `into`"
+        );
+        assert_eq!(
+            unknown_option(quote!(x), "unknown cmp option", &["skip"]).to_string(),
+            "unknown cmp option\nhelp: the only option is `skip`"
+        );
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "ab"), 2);
+    }
+}
@@ -0,0 +1,205 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+/// This is the entire input to `base64!`.
+///
+/// Example: `base64!("aGVsbG8=")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct Base64Input {
+    bytes: Vec<u8>,
+}
+
+/// The value of a base64 digit, in either the standard or the URL-safe
+/// alphabet.
+fn digit(c: char) -> Option<u8> {
+    Some(match c {
+        'A'..='Z' => c as u8 - b'A',
+        'a'..='z' => c as u8 - b'a' + 26,
+        '0'..='9' => c as u8 - b'0' + 52,
+        '+' | '-' => 62,
+        '/' | '_' => 63,
+        _ => return None,
+    })
+}
+
+/// Which alphabet a base64 character belongs to, if it's one of the
+/// characters where they differ: `Some(true)` for URL-safe.
+fn url_safe(c: char) -> Option<bool> {
+    match c {
+        '+' | '/' => Some(false),
+        '-' | '_' => Some(true),
+        _ => None,
+    }
+}
+
+/// Decode a base64 string. Whitespace is ignored, and the `=` padding at
+/// the end is optional. Either alphabet can be used, but not both.
+fn decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut digits = Vec::new();
+    let mut padding = Vec::new();
+    let mut last_position = 0;
+    // The first character that only one of the alphabets has.
+    let mut alphabet: Option<(char, usize)> = None;
+    for (position, c) in text.char_indices() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if c == '=' {
+            padding.push(position);
+            continue;
+        }
+        if let Some(&pad) = padding.first() {
+            return Err(format!(
+                "`=` at position {} is padding, which can only come at the end",
+                pad
+            ));
+        }
+        if let Some(url) = url_safe(c) {
+            match alphabet {
+                None => alphabet = Some((c, position)),
+                Some((first, first_position)) if url_safe(first) != Some(url) => {
+                    return Err(format!(
+                        "`{}` at position {} is from the {} alphabet, but `{}` at position {} is from the {} one",
+                        c,
+                        position,
+                        if url { "URL-safe" } else { "standard" },
+                        first,
+                        first_position,
+                        if url { "standard" } else { "URL-safe" },
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        match digit(c) {
+            Some(digit) => digits.push(digit),
+            None => {
+                return Err(format!(
+                    "`{}` at position {} is not a base64 character",
+                    c.escape_default(),
+                    position
+                ))
+            }
+        }
+        last_position = position;
+    }
+
+    // Each group of 4 digits is 3 bytes; a partial group of 2 or 3 is
+    // 1 or 2 bytes.
+    let leftover = digits.len() % 4;
+    if leftover == 1 {
+        return Err(format!(
+            "expected {} or {} base64 characters, found {}",
+            digits.len() - 1,
+            digits.len() + 1,
+            digits.len()
+        ));
+    }
+    if !padding.is_empty() && leftover == 0 {
+        return Err(format!(
+            "`=` at position {}: there should be no padding after a whole group of 4 characters",
+            padding[0]
+        ));
+    }
+    if !padding.is_empty() && padding.len() != (4 - leftover) % 4 {
+        return Err(format!(
+            "`=` at position {}: the padding should be {} `=`, not {}",
+            padding[0],
+            (4 - leftover) % 4,
+            padding.len()
+        ));
+    }
+
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &digit)| {
+            bits | (digit as u32) << (18 - 6 * i)
+        });
+        let count = chunk.len() - 1;
+        bytes.extend(&bits.to_be_bytes()[1..1 + count]);
+        // The last digit of a partial group can't have bits beyond the
+        // last byte, or it would decode the same as another string.
+        if bits & (0xff_ffff >> (8 * count)) != 0 {
+            return Err(format!(
+                "the character at position {} has bits set after the end of the data",
+                last_position
+            ));
+        }
+    }
+    Ok(bytes)
+}
+
+/// Parse the input to `base64!`.
+impl Parse for Base64Input {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let bytes = decode(&lit.value()).map_err(|message| syn::Error::new(lit.span(), message))?;
+        Ok(Base64Input { bytes })
+    }
+}
+
+/// Emit a `[u8; N]` array.
+impl ToTokens for Base64Input {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let bytes = &self.bytes;
+        tokens.extend(quote! {
+            [#(#bytes),*]
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(decode("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode("aGVsbG8").unwrap(), b"hello");
+        assert_eq!(decode("aGVs bG8h\n").unwrap(), b"hell\x6f!");
+        assert_eq!(decode("aGk=").unwrap(), b"hi");
+        assert_eq!(decode("aA==").unwrap(), b"h");
+        assert_eq!(decode("-_8=").unwrap(), decode("+/8=").unwrap());
+        assert!(decode("").unwrap().is_empty());
+
+        assert_eq!(
+            decode("aGV*bG8=").unwrap_err(),
+            "`*` at position 3 is not a base64 character"
+        );
+        assert_eq!(
+            decode("aG=Vs").unwrap_err(),
+            "`=` at position 2 is padding, which can only come at the end"
+        );
+        assert_eq!(
+            decode("aGk==").unwrap_err(),
+            "`=` at position 3: the padding should be 1 `=`, not 2"
+        );
+        assert_eq!(
+            decode("aGVs====").unwrap_err(),
+            "`=` at position 4: there should be no padding after a whole group of 4 characters"
+        );
+        assert_eq!(
+            decode("aGVs=").unwrap_err(),
+            "`=` at position 4: there should be no padding after a whole group of 4 characters"
+        );
+        assert_eq!(
+            decode("+/8-").unwrap_err(),
+            "`-` at position 3 is from the URL-safe alphabet, but `+` at position 0 is from the standard one"
+        );
+        assert_eq!(
+            decode("_-8/").unwrap_err(),
+            "`/` at position 3 is from the standard alphabet, but `_` at position 0 is from the URL-safe one"
+        );
+        assert_eq!(
+            decode("aGVsb").unwrap_err(),
+            "expected 4 or 6 base64 characters, found 5"
+        );
+        assert_eq!(
+            decode("aGl=").unwrap_err(),
+            "the character at position 2 has bits set after the end of the data"
+        );
+    }
+}
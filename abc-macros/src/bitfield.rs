@@ -0,0 +1,238 @@
+use abc_macros_core::{Errors, NamedRange, NamedRangeList};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Ident, Token, Visibility};
+
+/// This is the entire input to `bitfield!`.
+///
+/// Example: `bitfield!(Flags: u16 { enable: 0, mode: 1..3, count: 3..8 })`
+///
+/// Each field is a single bit, or a range of bits (with the end
+/// excluded), using the same syntax as `enum_ranges!`.
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct BitfieldInput {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    repr: Ident,
+    fields: Vec<NamedRange>,
+}
+
+/// The smallest unsigned integer type that can hold `bits` bits.
+fn value_type(bits: u64) -> Ident {
+    let ty = match bits {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        33..=64 => "u64",
+        _ => "u128",
+    };
+    format_ident!("{}", ty)
+}
+
+/// Parse the input to `bitfield!`, checking that the fields fit.
+impl Parse for BitfieldInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        let name: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let repr: Ident = input.parse()?;
+        // Every problem is reported, not just the first.
+        let mut errors = Errors::new();
+        let repr_bits = match repr.to_string().as_str() {
+            "u8" => Some(8),
+            "u16" => Some(16),
+            "u32" => Some(32),
+            "u64" => Some(64),
+            "u128" => Some(128),
+            _ => {
+                errors.push_spanned(
+                    &repr,
+                    "a bitfield is stored in an unsigned integer: u8, u16, u32, u64, or u128",
+                );
+                None
+            }
+        };
+
+        let content;
+        braced!(content in input);
+        let fields = NamedRangeList::parse_all(&content, &mut errors).list;
+
+        // Each bit belongs to at most one field.
+        let mut used: u128 = 0;
+        for (index, field) in fields.iter().enumerate() {
+            if fields[..index].iter().any(|other| other.name == field.name) {
+                errors.push_spanned(&field.name, "duplicate field name");
+            }
+            let end = match field.end.or_else(|| field.start.checked_add(1)) {
+                Some(end) => end,
+                None => {
+                    errors
+                        .push_spanned(&field.name, format!("bit {} is out of range", field.start));
+                    continue;
+                }
+            };
+            if end <= field.start {
+                errors.push_spanned(
+                    &field.name,
+                    format!("the bit range {}..{} is empty", field.start, end),
+                );
+                continue;
+            }
+            let repr_bits = match repr_bits {
+                Some(repr_bits) => repr_bits,
+                None => continue,
+            };
+            if end > repr_bits {
+                let message = format!(
+                    "this field needs bit {}, but {} only has {} bits",
+                    end - 1,
+                    repr,
+                    repr_bits
+                );
+                if end <= 128 {
+                    let help = format!("store the bitfield in a {}", value_type(end));
+                    errors.push_help(&field.name, message, help);
+                } else {
+                    errors.push_spanned(&field.name, message);
+                }
+                continue;
+            }
+            let width = end - field.start;
+            let mask = (u128::MAX >> (128 - width)) << field.start;
+            if used & mask != 0 {
+                errors.push_spanned(&field.name, "this field overlaps another one");
+            }
+            used |= mask;
+        }
+        errors.finish()?;
+
+        Ok(BitfieldInput {
+            attributes,
+            vis,
+            name,
+            repr,
+            fields,
+        })
+    }
+}
+
+/// Emit the struct and its accessors.
+impl ToTokens for BitfieldInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let BitfieldInput {
+            attributes,
+            vis,
+            name,
+            repr,
+            ..
+        } = self;
+
+        let accessors = self.fields.iter().map(|field| {
+            let getter = &field.name;
+            let setter = format_ident!("set_{}", field.name);
+            let shift = field.start as u32;
+            match field.end {
+                // A single bit is a `bool`.
+                None => quote! {
+                    #vis const fn #getter(&self) -> bool {
+                        (self.0 >> #shift) & 1 != 0
+                    }
+
+                    #vis fn #setter(&mut self, value: bool) {
+                        self.0 = (self.0 & !(1 << #shift)) | ((value as #repr) << #shift);
+                    }
+                },
+                Some(end) => {
+                    let width = end - field.start;
+                    let ty = value_type(width);
+                    let mask = u128::MAX >> (128 - width);
+                    let mask = proc_macro2::Literal::u128_unsuffixed(mask);
+                    let message = format!("value too large for {}-bit field `{}`", width, getter);
+                    quote! {
+                        #vis const fn #getter(&self) -> #ty {
+                            ((self.0 >> #shift) & #mask) as #ty
+                        }
+
+                        /// Bits of `value` that don't fit in the field are
+                        /// dropped, and are an error in debug builds.
+                        #vis fn #setter(&mut self, value: #ty) {
+                            let value = value as #repr;
+                            ::core::debug_assert!(value <= #mask, #message);
+                            self.0 = (self.0 & !(#mask << #shift)) | ((value & #mask) << #shift);
+                        }
+                    }
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            #(#attributes)*
+            #vis struct #name(#repr);
+
+            impl #name {
+                /// Make a value from its raw bits.
+                #vis const fn from_bits(bits: #repr) -> Self {
+                    #name(bits)
+                }
+
+                /// The raw bits of this value.
+                #vis const fn bits(&self) -> #repr {
+                    self.0
+                }
+
+                #(#accessors)*
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fields() {
+        let input: BitfieldInput =
+            syn::parse_str("pub Flags: u16 { enable: 0, mode: 1..3, count: 3..8 }").unwrap();
+        assert_eq!(input.repr, "u16");
+        assert_eq!(input.fields.len(), 3);
+
+        syn::parse_str::<BitfieldInput>("Flags: i16 { a: 0 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 0..9 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 8 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 3..3 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 0..4, b: 3 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 0, a: 1 }").unwrap_err();
+        syn::parse_str::<BitfieldInput>("Flags: u128 { a: 0..128 }").unwrap();
+        syn::parse_str::<BitfieldInput>("Flags: u8 { a: 18446744073709551615 }").unwrap_err();
+    }
+
+    #[test]
+    fn all_errors() {
+        let error =
+            syn::parse_str::<BitfieldInput>("Flags: u8 { a: 0..4, b: 3, c: x, d: 2..9, a: 7 }")
+                .unwrap_err();
+        let messages: Vec<String> = error.into_iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected integer literal",
+                "this field overlaps another one",
+                "this field needs bit 8, but u8 only has 8 bits\nhelp: store the bitfield in a u16",
+                "duplicate field name",
+            ]
+        );
+    }
+
+    #[test]
+    fn value_types() {
+        assert_eq!(value_type(2), "u8");
+        assert_eq!(value_type(9), "u16");
+        assert_eq!(value_type(64), "u64");
+        assert_eq!(value_type(65), "u128");
+    }
+}
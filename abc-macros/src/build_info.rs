@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::Visibility;
+
+use crate::compile_time;
+
+/// Run git in the directory of the crate being compiled.
+///
+/// Returns `None` if git isn't installed, if the crate isn't in a
+/// repository, or if the command fails for any other reason.
+fn git(args: &[&str]) -> Option<String> {
+    let dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_string())
+}
+
+/// This is the entire input to `git_version!`, which takes no arguments.
+#[cfg_attr(test, derive(Debug))]
+pub struct GitVersionInput {
+    version: String,
+}
+
+/// Parse the input to `git_version!`, and ask git for the version.
+impl Parse for GitVersionInput {
+    fn parse(_input: ParseStream) -> syn::parse::Result<Self> {
+        let version = git(&["describe", "--always", "--dirty"])
+            .filter(|version| !version.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        Ok(GitVersionInput { version })
+    }
+}
+
+/// Emit the version as a string literal.
+impl ToTokens for GitVersionInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.version.to_tokens(tokens);
+    }
+}
+
+/// This is the entire input to `build_info!`.
+///
+/// Example: `build_info!()` or `build_info!(pub)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct BuildInfoInput {
+    // The visibility of the generated consts.
+    vis: Visibility,
+    commit: Option<String>,
+    dirty: bool,
+    timestamp: u64,
+}
+
+/// Parse the input to `build_info!`, and collect the build information.
+impl Parse for BuildInfoInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let vis = input.parse()?;
+        let commit = git(&["rev-parse", "HEAD"]);
+        // Untracked files don't count; they aren't part of the build
+        // unless something refers to them, and then they'd be tracked.
+        let dirty = commit.is_some()
+            && git(&["status", "--porcelain", "--untracked-files=no"])
+                .map_or(false, |status| !status.is_empty());
+        let timestamp = compile_time::unix_now();
+        Ok(BuildInfoInput {
+            vis,
+            commit,
+            dirty,
+            timestamp,
+        })
+    }
+}
+
+/// Emit the `GIT_COMMIT`, `GIT_DIRTY`, and `BUILD_TIMESTAMP` consts.
+impl ToTokens for BuildInfoInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let BuildInfoInput {
+            vis,
+            dirty,
+            timestamp,
+            ..
+        } = self;
+        let commit = match &self.commit {
+            Some(commit) => quote! { ::core::option::Option::Some(#commit) },
+            None => quote! { ::core::option::Option::None },
+        };
+        tokens.extend(quote! {
+            /// The git commit this was built from, if it was built in a
+            /// git repository.
+            #vis const GIT_COMMIT: ::core::option::Option<&'static str> = #commit;
+            /// Whether tracked files had uncommitted changes at build time.
+            #vis const GIT_DIRTY: bool = #dirty;
+            /// When this was built, in seconds since the Unix epoch.
+            #vis const BUILD_TIMESTAMP: u64 = #timestamp;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vis() {
+        let input: BuildInfoInput = syn::parse_str("pub(crate)").unwrap();
+        assert_eq!(input.vis, syn::parse_quote!(pub(crate)));
+        let input: BuildInfoInput = syn::parse_str("").unwrap();
+        assert_eq!(input.vis, Visibility::Inherited);
+        assert!(input.timestamp > 0);
+        syn::parse_str::<BuildInfoInput>("GIT_COMMIT").unwrap_err();
+    }
+}
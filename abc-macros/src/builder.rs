@@ -0,0 +1,225 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_quote, Data, DeriveInput, Field, Fields, Meta, NestedMeta, Path};
+
+use crate::attr::{self, lit_str};
+
+/// This is the entire input to `#[derive(Builder)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct BuilderInput {
+    input: DeriveInput,
+    // `#[builder(crate = "...")]`: the path to the `abc` crate, for
+    // `BuilderError`.
+    crate_path: Path,
+    fields: Vec<BuilderField>,
+}
+
+/// A named field of the struct, along with its `#[builder(...)]` options.
+#[cfg_attr(test, derive(Debug))]
+struct BuilderField {
+    field: Field,
+    // `#[builder(default)]`: use `Default::default()` if the field isn't set.
+    default: bool,
+    // `#[builder(into)]`: the setter accepts anything that implements
+    // `Into<T>`.
+    into: bool,
+}
+
+impl BuilderField {
+    fn new(field: Field) -> syn::parse::Result<Self> {
+        let mut builder_field = BuilderField {
+            field,
+            default: false,
+            into: false,
+        };
+        for nested in attr::options(&builder_field.field.attrs, "builder")? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("default") => {
+                    builder_field.default = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("into") => {
+                    builder_field.into = true;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown builder option for a field",
+                        &["default", "into"],
+                    ))
+                }
+            }
+        }
+        Ok(builder_field)
+    }
+}
+
+/// Parse the input to `#[derive(Builder)]`.
+impl Parse for BuilderInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+
+        let mut crate_path = parse_quote!(::abc);
+        for nested in attr::options(&input.attrs, "builder")? {
+            match &nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown builder option",
+                        &["crate"],
+                    ))
+                }
+            }
+        }
+
+        // A builder has a setter for each field, so the fields need names.
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => fields.named.clone(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        "Builder can only be derived for structs with named fields",
+                    ))
+                }
+            },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Builder can only be derived for structs",
+                ))
+            }
+        };
+        let fields = fields
+            .into_iter()
+            .map(BuilderField::new)
+            .collect::<syn::parse::Result<_>>()?;
+
+        Ok(BuilderInput {
+            input,
+            crate_path,
+            fields,
+        })
+    }
+}
+
+/// Emit the builder struct and its impls.
+impl ToTokens for BuilderInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let vis = &self.input.vis;
+        let builder = format_ident!("{}Builder", name);
+        let krate = &self.crate_path;
+        let generics = &self.input.generics;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        let idents: Vec<_> = self.fields.iter().map(|f| &f.field.ident).collect();
+        let types = self.fields.iter().map(|f| &f.field.ty);
+
+        let setters = self.fields.iter().map(|f| {
+            let ident = &f.field.ident;
+            let ty = &f.field.ty;
+            // Copy the field's docs, so the setter explains what it sets.
+            let docs = f
+                .field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"));
+            let (param, value) = if f.into {
+                (
+                    quote! { impl ::core::convert::Into<#ty> },
+                    quote! { value.into() },
+                )
+            } else {
+                (quote! { #ty }, quote! { value })
+            };
+            quote! {
+                #(#docs)*
+                pub fn #ident(mut self, value: #param) -> Self {
+                    self.#ident = ::core::option::Option::Some(#value);
+                    self
+                }
+            }
+        });
+
+        let values = self.fields.iter().map(|f| {
+            let ident = &f.field.ident;
+            if f.default {
+                quote! { self.#ident.unwrap_or_default() }
+            } else {
+                let field_name = ident.as_ref().map(|ident| ident.unraw().to_string());
+                quote! {
+                    match self.#ident {
+                        ::core::option::Option::Some(value) => value,
+                        ::core::option::Option::None => {
+                            return ::core::result::Result::Err(
+                                #krate::BuilderError::MissingField(#field_name),
+                            )
+                        }
+                    }
+                }
+            }
+        });
+
+        let builder_doc = format!("A builder for [`{}`].", name);
+        tokens.extend(quote! {
+            #[doc = #builder_doc]
+            #vis struct #builder #generics #where_clause {
+                #(#idents: ::core::option::Option<#types>,)*
+            }
+
+            impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+                fn default() -> Self {
+                    #builder {
+                        #(#idents: ::core::option::Option::None,)*
+                    }
+                }
+            }
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Start building a value, with no fields set.
+                pub fn builder() -> #builder #ty_generics {
+                    ::core::default::Default::default()
+                }
+            }
+
+            impl #impl_generics #builder #ty_generics #where_clause {
+                #(#setters)*
+
+                /// Build the value, if every field without a default was set.
+                pub fn build(self) -> ::core::result::Result<#name #ty_generics, #krate::BuilderError> {
+                    ::core::result::Result::Ok(#name {
+                        #(#idents: #values,)*
+                    })
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let input: BuilderInput =
+            syn::parse_str("struct Foo { #[builder(default, into)] a: u8, b: u8 }").unwrap();
+        assert!(input.fields[0].default && input.fields[0].into);
+        assert!(!input.fields[1].default && !input.fields[1].into);
+        assert_eq!(input.crate_path, parse_quote!(::abc));
+
+        let input: BuilderInput =
+            syn::parse_str("#[builder(crate = \"my_facade\")] struct Foo {}").unwrap();
+        assert_eq!(input.crate_path, parse_quote!(my_facade));
+
+        syn::parse_str::<BuilderInput>("struct Foo { #[builder(bogus)] a: u8 }").unwrap_err();
+        syn::parse_str::<BuilderInput>("#[builder(default)] struct Foo {}").unwrap_err();
+        syn::parse_str::<BuilderInput>("struct Foo(u8);").unwrap_err();
+        syn::parse_str::<BuilderInput>("enum Foo { A }").unwrap_err();
+    }
+}
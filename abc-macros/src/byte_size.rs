@@ -0,0 +1,173 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+/// The units that `byte_size!` accepts, and their size in bytes.
+const UNITS: &[(&str, u128)] = &[
+    ("B", 1),
+    ("KB", 1000),
+    ("kB", 1000),
+    ("KiB", 1 << 10),
+    ("MB", 1000 * 1000),
+    ("MiB", 1 << 20),
+    ("GB", 1000 * 1000 * 1000),
+    ("GiB", 1 << 30),
+    ("TB", 1000 * 1000 * 1000 * 1000),
+    ("TiB", 1 << 40),
+];
+
+/// The integer types that `byte_size!` can produce, and their maximums.
+/// `usize` is checked by the compiler instead, since its size depends on
+/// the target.
+const TYPES: &[(&str, u128)] = &[
+    ("u8", u8::MAX as u128),
+    ("u16", u16::MAX as u128),
+    ("u32", u32::MAX as u128),
+    ("u64", u64::MAX as u128),
+    ("u128", u128::MAX),
+    ("usize", u64::MAX as u128),
+];
+
+/// This is the entire input to `byte_size!`.
+///
+/// Example: `byte_size!("4 MiB")` or `byte_size!("1.5 GB" as u64)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct ByteSizeInput {
+    lit: LitStr,
+    bytes: u128,
+    ty: Ident,
+}
+
+/// Parse a size, like `"4 MiB"`, into a number of bytes.
+fn parse_size(text: &str) -> Result<u128, String> {
+    let text = text.trim();
+    let number_end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(number_end);
+    let unit = unit.trim_start();
+    let scale = match UNITS.iter().find(|(name, _)| *name == unit) {
+        Some((_, scale)) => *scale,
+        None if unit.is_empty() => 1,
+        None => {
+            return Err(format!(
+                "unknown unit `{}`; expected B, KB, KiB, MB, MiB, GB, GiB, TB or TiB",
+                unit
+            ))
+        }
+    };
+
+    let number: String = number.chars().filter(|&c| c != '_').collect();
+    let (whole, fraction) = number.split_once('.').unwrap_or((&number, ""));
+    if whole.is_empty() || fraction.contains('.') {
+        return Err(format!("expected a size, like `4 MiB`, found `{}`", text));
+    }
+    let too_big = || "this size doesn't fit in a u128".to_string();
+    let numerator: u128 = format!("{}{}", whole, fraction)
+        .parse()
+        .map_err(|_| too_big())?;
+    let denominator = 10u128
+        .checked_pow(fraction.len() as u32)
+        .ok_or_else(too_big)?;
+    let scaled = numerator.checked_mul(scale).ok_or_else(too_big)?;
+    if scaled % denominator != 0 {
+        return Err(format!("{} isn't a whole number of bytes", text));
+    }
+    Ok(scaled / denominator)
+}
+
+/// Parse the input to `byte_size!`.
+impl Parse for ByteSizeInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let bytes =
+            parse_size(&lit.value()).map_err(|message| syn::Error::new(lit.span(), message))?;
+        let ty = if input.parse::<Option<Token![as]>>()?.is_some() {
+            input.parse()?
+        } else {
+            format_ident!("usize")
+        };
+        let max = match TYPES.iter().find(|(name, _)| ty == name) {
+            Some((_, max)) => *max,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &ty,
+                    "expected an unsigned integer type, like `u64`",
+                ))
+            }
+        };
+        if bytes > max {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!("{} bytes doesn't fit in a {}", bytes, ty),
+            ));
+        }
+        Ok(ByteSizeInput { lit, bytes, ty })
+    }
+}
+
+/// Emit the number of bytes, as a literal of the requested type.
+impl ToTokens for ByteSizeInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ty = &self.ty;
+        let bytes = syn::LitInt::new(&format!("{}u128", self.bytes), self.lit.span());
+        let message = format!("{} doesn't fit in a {}", self.lit.value().trim(), ty);
+        if ty == "usize" {
+            // Whether it fits depends on the target, so let the compiler
+            // check it.
+            tokens.extend(quote! {
+                {
+                    const BYTES: u128 = #bytes;
+                    const _: () = if BYTES > ::core::primitive::usize::MAX as u128 {
+                        ::core::panic!(#message)
+                    };
+                    BYTES as ::core::primitive::usize
+                }
+            });
+        } else {
+            let bytes = syn::LitInt::new(&format!("{}{}", self.bytes, ty), self.lit.span());
+            tokens.extend(quote! { #bytes });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("4 MiB"), Ok(4 << 20));
+        assert_eq!(parse_size("4MiB"), Ok(4 << 20));
+        assert_eq!(parse_size("1.5 KiB"), Ok(1536));
+        assert_eq!(parse_size("2 GB"), Ok(2_000_000_000));
+        assert_eq!(parse_size("10 kB"), Ok(10_000));
+        assert_eq!(parse_size("1_024"), Ok(1024));
+        assert_eq!(parse_size(" 3 B "), Ok(3));
+        assert_eq!(parse_size("1 TiB"), Ok(1 << 40));
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MiB").is_err());
+        assert!(parse_size("1.2.3 KB").is_err());
+        assert!(parse_size("1.0001 KB").is_err());
+        assert!(parse_size("4 mib")
+            .unwrap_err()
+            .contains("unknown unit `mib`"));
+        assert!(parse_size("999999999999999999999999999999999999999999 TB").is_err());
+    }
+
+    #[test]
+    fn parse_input() {
+        let input: ByteSizeInput = syn::parse_str("\"64 KiB\" as u32").unwrap();
+        assert_eq!(input.bytes, 65536);
+        assert_eq!(input.ty, "u32");
+        let input: ByteSizeInput = syn::parse_str("\"1 GiB\"").unwrap();
+        assert_eq!(input.ty, "usize");
+
+        syn::parse_str::<ByteSizeInput>("\"64 KiB\" as u16").unwrap_err();
+        syn::parse_str::<ByteSizeInput>("\"1 KiB\" as i32").unwrap_err();
+        syn::parse_str::<ByteSizeInput>("4096").unwrap_err();
+    }
+}
@@ -0,0 +1,157 @@
+use syn::LitStr;
+
+/// A case conversion, as named in a `rename_all = "..."` attribute.
+///
+/// Names are split into words at underscores, hyphens, and changes of
+/// case, so both `HttpRequest` and `http_request` are the two words
+/// "http" and "request".
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenameRule {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    /// Look up a rule by the name used in attributes.
+    pub fn from_lit(lit: &LitStr) -> syn::parse::Result<Self> {
+        match lit.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebab),
+            _ => Err(syn::Error::new_spanned(
+                lit,
+                "unknown case; expected one of \"lowercase\", \"UPPERCASE\", \
+                 \"PascalCase\", \"camelCase\", \"snake_case\", \"kebab-case\", \
+                 \"SCREAMING_SNAKE_CASE\", \"SCREAMING-KEBAB-CASE\"",
+            )),
+        }
+    }
+
+    /// Convert a name to this case.
+    ///
+    /// As in serde, `lowercase` and `UPPERCASE` only change the case of
+    /// each letter; they don't split words.
+    ///
+    pub fn apply(self, name: &str) -> String {
+        let (separator, upper) = match self {
+            RenameRule::Lower => return name.to_lowercase(),
+            RenameRule::Upper => return name.to_uppercase(),
+            RenameRule::Pascal | RenameRule::Camel => {
+                let words = split_words(name).into_iter().enumerate().map(|(i, word)| {
+                    if i == 0 && self == RenameRule::Camel {
+                        word.to_lowercase()
+                    } else {
+                        capitalize(&word)
+                    }
+                });
+                return words.collect();
+            }
+            RenameRule::Snake => ("_", false),
+            RenameRule::Kebab => ("-", false),
+            RenameRule::ScreamingSnake => ("_", true),
+            RenameRule::ScreamingKebab => ("-", true),
+        };
+        let words = split_words(name).into_iter().map(|word| {
+            if upper {
+                word.to_uppercase()
+            } else {
+                word.to_lowercase()
+            }
+        });
+        words.collect::<Vec<_>>().join(separator)
+    }
+}
+
+/// Make the first letter of a word uppercase, and the rest lowercase.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Split a name into words.
+///
+/// A new word starts after any `_` or `-`, at a lowercase-to-uppercase
+/// change (`fooBar`), and at the last capital of an acronym that's
+/// followed by lowercase letters (`HTTPServer`).
+///
+pub fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == '_' || ch == '-' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if ch.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).map_or(false, |c| c.is_lowercase());
+            if !prev.is_uppercase() || next_lower {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(ch);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn split() {
+        assert_eq!(split_words("HttpRequest"), ["Http", "Request"]);
+        assert_eq!(split_words("retry_count"), ["retry", "count"]);
+        assert_eq!(split_words("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(split_words("fooBar2"), ["foo", "Bar2"]);
+        assert_eq!(split_words("Utf8Decoder"), ["Utf8", "Decoder"]);
+        assert_eq!(split_words("_leading"), ["leading"]);
+    }
+
+    #[test]
+    fn rename() {
+        assert_eq!(RenameRule::Snake.apply("HttpRequest"), "http_request");
+        assert_eq!(RenameRule::Kebab.apply("HttpRequest"), "http-request");
+        assert_eq!(
+            RenameRule::ScreamingSnake.apply("retry_count"),
+            "RETRY_COUNT"
+        );
+        assert_eq!(
+            RenameRule::ScreamingKebab.apply("retry_count"),
+            "RETRY-COUNT"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, Data, DeriveInput, Fields, Ident, Member, Meta, NestedMeta, Type};
+
+use crate::attr;
+use crate::generics::field_type_is_generic;
+
+/// Which trait to derive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CmpTrait {
+    PartialEq,
+    Hash,
+}
+
+/// A struct, or one variant of an enum, and the fields that are compared.
+#[cfg_attr(test, derive(Debug))]
+struct Shape {
+    variant: Option<Ident>,
+    fields: Vec<(Member, Type)>,
+}
+
+/// This is the input to `#[derive(PartialEqSkip)]` or `#[derive(HashSkip)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct CmpInput {
+    input: DeriveInput,
+    which: CmpTrait,
+    // One for a struct, or one for each variant of an enum.
+    shapes: Vec<Shape>,
+    is_enum: bool,
+}
+
+/// Whether a field has `#[cmp(skip)]`.
+fn is_skipped(attrs: &[syn::Attribute]) -> syn::parse::Result<bool> {
+    let mut skip = false;
+    for nested in attr::options(attrs, "cmp")? {
+        match &nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => skip = true,
+            _ => {
+                return Err(attr::unknown_option(
+                    nested,
+                    "unknown cmp option",
+                    &["skip"],
+                ))
+            }
+        }
+    }
+    Ok(skip)
+}
+
+/// The fields that aren't skipped.
+fn compared_fields(fields: &Fields) -> syn::parse::Result<Vec<(Member, Type)>> {
+    let mut compared = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        if is_skipped(&field.attrs)? {
+            continue;
+        }
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        compared.push((member, field.ty.clone()));
+    }
+    Ok(compared)
+}
+
+impl CmpInput {
+    pub fn new(input: DeriveInput, which: CmpTrait) -> syn::parse::Result<Self> {
+        let (shapes, is_enum) = match &input.data {
+            Data::Struct(data) => (
+                vec![Shape {
+                    variant: None,
+                    fields: compared_fields(&data.fields)?,
+                }],
+                false,
+            ),
+            Data::Enum(data) => {
+                let mut shapes = Vec::new();
+                for variant in &data.variants {
+                    shapes.push(Shape {
+                        variant: Some(variant.ident.clone()),
+                        fields: compared_fields(&variant.fields)?,
+                    });
+                }
+                (shapes, true)
+            }
+            Data::Union(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("{:?}Skip can't be derived for unions", which),
+                ))
+            }
+        };
+        Ok(CmpInput {
+            input,
+            which,
+            shapes,
+            is_enum,
+        })
+    }
+}
+
+impl Shape {
+    /// A pattern that binds each compared field to `{prefix}{index}`.
+    fn pattern(&self, prefix: &str) -> TokenStream {
+        let path = match &self.variant {
+            Some(variant) => quote! { Self::#variant },
+            None => quote! { Self },
+        };
+        let members = self.fields.iter().map(|(member, _)| member);
+        let bindings = self.bindings(prefix);
+        quote! { #path { #(#members: #bindings,)* .. } }
+    }
+
+    fn bindings(&self, prefix: &str) -> Vec<Ident> {
+        (0..self.fields.len())
+            .map(|index| format_ident!("{}{}", prefix, index))
+            .collect()
+    }
+
+    /// An expression comparing the `__self` and `__other` bindings.
+    fn eq(&self) -> TokenStream {
+        if self.fields.is_empty() {
+            return quote! { true };
+        }
+        let lhs = self.bindings("__self_");
+        let rhs = self.bindings("__other_");
+        quote! { #(#lhs == #rhs)&&* }
+    }
+
+    /// Statements hashing the `__self` bindings.
+    fn hash(&self) -> TokenStream {
+        if self.fields.is_empty() {
+            // Don't leave `state` unused.
+            return quote! { let _ = state; };
+        }
+        let bindings = self.bindings("__self_");
+        quote! { #(::core::hash::Hash::hash(#bindings, state);)* }
+    }
+}
+
+/// Emit the `PartialEq` or `Hash` impl.
+impl ToTokens for CmpInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, _) = self.input.generics.split_for_impl();
+        let bound: TokenStream = match self.which {
+            CmpTrait::PartialEq => quote! { ::core::cmp::PartialEq },
+            CmpTrait::Hash => quote! { ::core::hash::Hash },
+        };
+
+        // Fields of a generic type need the trait too. Skipped fields
+        // don't, which is half the point.
+        let mut where_clause = match &self.input.generics.where_clause {
+            Some(where_clause) => where_clause.clone(),
+            None => parse_quote!(where),
+        };
+        let mut bounded: Vec<String> = Vec::new();
+        for (_, ty) in self.shapes.iter().flat_map(|shape| &shape.fields) {
+            let key = ty.to_token_stream().to_string();
+            if field_type_is_generic(&self.input.generics, ty) && !bounded.contains(&key) {
+                where_clause.predicates.push(parse_quote!(#ty: #bound));
+                bounded.push(key);
+            }
+        }
+
+        let body = match (self.which, self.is_enum) {
+            (CmpTrait::PartialEq, false) => {
+                let shape = &self.shapes[0];
+                let lhs = shape.pattern("__self_");
+                let rhs = shape.pattern("__other_");
+                let eq = shape.eq();
+                quote! {
+                    let #lhs = self;
+                    let #rhs = other;
+                    #eq
+                }
+            }
+            (CmpTrait::Hash, false) => {
+                let shape = &self.shapes[0];
+                let pattern = shape.pattern("__self_");
+                let hash = shape.hash();
+                quote! {
+                    let #pattern = self;
+                    #hash
+                }
+            }
+            (_, true) if self.shapes.is_empty() => quote! { match *self {} },
+            (CmpTrait::PartialEq, true) => {
+                let arms = self.shapes.iter().map(|shape| {
+                    let lhs = shape.pattern("__self_");
+                    let rhs = shape.pattern("__other_");
+                    let eq = shape.eq();
+                    quote! { (#lhs, #rhs) => #eq, }
+                });
+                // With one variant, a catch-all arm would be unreachable.
+                let otherwise = if self.shapes.len() > 1 {
+                    quote! { _ => false, }
+                } else {
+                    quote! {}
+                };
+                quote! {
+                    match (self, other) {
+                        #(#arms)*
+                        #otherwise
+                    }
+                }
+            }
+            (CmpTrait::Hash, true) => {
+                let arms = self.shapes.iter().map(|shape| {
+                    let pattern = shape.pattern("__self_");
+                    let hash = shape.hash();
+                    quote! { #pattern => { #hash } }
+                });
+                quote! {
+                    ::core::hash::Hash::hash(&::core::mem::discriminant(self), state);
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        };
+
+        tokens.extend(match self.which {
+            CmpTrait::PartialEq => quote! {
+                impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+                    fn eq(&self, other: &Self) -> bool {
+                        #body
+                    }
+                }
+            },
+            CmpTrait::Hash => quote! {
+                impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+                    fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                        #body
+                    }
+                }
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compared(input: &str) -> syn::parse::Result<Vec<Vec<String>>> {
+        let input = CmpInput::new(syn::parse_str(input)?, CmpTrait::PartialEq)?;
+        Ok(input
+            .shapes
+            .iter()
+            .map(|shape| {
+                shape
+                    .fields
+                    .iter()
+                    .map(|(member, _)| member.to_token_stream().to_string())
+                    .collect()
+            })
+            .collect())
+    }
+
+    #[test]
+    fn skipped_fields() {
+        assert_eq!(
+            compared("struct S { a: u8, #[cmp(skip)] b: u8, c: u8 }").unwrap(),
+            [["a", "c"]]
+        );
+        assert_eq!(compared("struct S(#[cmp(skip)] u8, u8);").unwrap(), [["1"]]);
+        assert_eq!(
+            compared("enum E { A, B(#[cmp(skip)] u8), C { x: u8 } }").unwrap(),
+            [vec![], vec![], vec!["x".to_string()]]
+        );
+
+        compared("struct S { #[cmp(bogus)] a: u8 }").unwrap_err();
+        compared("union U { a: u8 }").unwrap_err();
+    }
+}
@@ -0,0 +1,106 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{LitStr, Path, Token};
+
+/// This is the entire input to `color!`.
+///
+/// Example: `color!("#1e90ff")` or `color!("#1e90ff80", Rgba::new)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct ColorInput {
+    rgba: [u8; 4],
+    // A function to call with the four components, instead of a tuple.
+    constructor: Option<Path>,
+}
+
+/// Decode a CSS-style hex color: `#rgb`, `#rgba`, `#rrggbb` or
+/// `#rrggbbaa`. Without an alpha component, the color is opaque.
+fn decode(color: &str) -> Result<[u8; 4], String> {
+    let hex = color
+        .strip_prefix('#')
+        .ok_or("expected a color like `#1e90ff`, starting with `#`")?;
+    let mut digits = Vec::new();
+    for (position, c) in hex.char_indices() {
+        match c.to_digit(16) {
+            Some(digit) => digits.push(digit as u8),
+            None => {
+                return Err(format!(
+                    "`{}` at position {} is not a hex digit",
+                    c.escape_default(),
+                    position + 1
+                ))
+            }
+        }
+    }
+    let mut rgba: Vec<u8> = match digits.len() {
+        // Each digit is doubled, so `#f80` is `#ff8800`.
+        3 | 4 => digits.iter().map(|digit| digit * 0x11).collect(),
+        6 | 8 => digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+        count => return Err(format!("expected 3, 4, 6 or 8 hex digits, found {}", count)),
+    };
+    if rgba.len() == 3 {
+        rgba.push(0xff);
+    }
+    Ok([rgba[0], rgba[1], rgba[2], rgba[3]])
+}
+
+/// Parse the input to `color!`.
+impl Parse for ColorInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let rgba = decode(&lit.value()).map_err(|message| syn::Error::new(lit.span(), message))?;
+        let constructor = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(ColorInput { rgba, constructor })
+    }
+}
+
+/// Emit the `(r, g, b, a)` tuple, or the call to the constructor.
+impl ToTokens for ColorInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let [r, g, b, a] = self.rgba;
+        tokens.extend(match &self.constructor {
+            Some(constructor) => quote! { #constructor(#r, #g, #b, #a) },
+            None => quote! { (#r, #g, #b, #a) },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_colors() {
+        assert_eq!(decode("#1e90ff"), Ok([0x1e, 0x90, 0xff, 0xff]));
+        assert_eq!(decode("#1E90FF80"), Ok([0x1e, 0x90, 0xff, 0x80]));
+        assert_eq!(decode("#f80"), Ok([0xff, 0x88, 0x00, 0xff]));
+        assert_eq!(decode("#f808"), Ok([0xff, 0x88, 0x00, 0x88]));
+
+        assert!(decode("1e90ff").unwrap_err().contains("starting with `#`"));
+        assert!(decode("#1e90fg")
+            .unwrap_err()
+            .contains("`g` at position 6 is not a hex digit"));
+        assert!(decode("#1e90f").unwrap_err().contains("found 5"));
+        assert!(decode("#").is_err());
+    }
+
+    #[test]
+    fn parse_input() {
+        let input: ColorInput = syn::parse_str("\"#fff\", Rgba::new").unwrap();
+        assert_eq!(input.rgba, [255; 4]);
+        assert!(input.constructor.is_some());
+        let input: ColorInput = syn::parse_str("\"#000\",").unwrap();
+        assert!(input.constructor.is_none());
+
+        syn::parse_str::<ColorInput>("\"#000\", 5").unwrap_err();
+        syn::parse_str::<ColorInput>("0x000000").unwrap_err();
+    }
+}
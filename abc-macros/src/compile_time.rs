@@ -0,0 +1,179 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+use crate::attr;
+
+/// The current time, in seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) date.
+///
+/// This is Howard Hinnant's `civil_from_days`, for the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Format a Unix timestamp, in UTC, like `strftime`.
+///
+/// Only the numeric specifiers are supported: `%Y`, `%m`, `%d`, `%H`,
+/// `%M`, `%S`, `%j`, `%s`, and `%%`.
+///
+fn format_time(format: &str, timestamp: u64) -> Result<String, String> {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", year)),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('H') => out.push_str(&format!("{:02}", seconds / 3600)),
+            Some('M') => out.push_str(&format!("{:02}", seconds / 60 % 60)),
+            Some('S') => out.push_str(&format!("{:02}", seconds % 60)),
+            Some('j') => {
+                let day_of_year = days - days_from_civil(year, 1, 1) + 1;
+                out.push_str(&format!("{:03}", day_of_year));
+            }
+            Some('s') => out.push_str(&timestamp.to_string()),
+            Some('%') => out.push('%'),
+            Some(other) => return Err(format!("unsupported format specifier `%{}`", other)),
+            None => return Err("the format string ends with a lone `%`".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+/// The inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// This is the entire input to `compile_time!`.
+///
+/// Example: `compile_time!("%Y-%m-%d", source_date_epoch)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct CompileTimeInput {
+    formatted: LitStr,
+}
+
+/// Parse the input to `compile_time!`, and format the current time.
+impl Parse for CompileTimeInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let format: LitStr = if input.is_empty() {
+            LitStr::new("%Y-%m-%dT%H:%M:%SZ", input.span())
+        } else {
+            input.parse()?
+        };
+
+        // `source_date_epoch`: use the time in `SOURCE_DATE_EPOCH`, if
+        // it's set, so that builds are reproducible.
+        let mut timestamp = unix_now();
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let option: Ident = input.parse()?;
+            if option != "source_date_epoch" {
+                return Err(attr::unknown_option(
+                    option,
+                    "unknown compile_time option",
+                    &["source_date_epoch"],
+                ));
+            }
+            if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") {
+                timestamp = epoch.trim().parse().map_err(|_| {
+                    syn::Error::new_spanned(
+                        &option,
+                        format!("SOURCE_DATE_EPOCH is `{}`, which isn't a timestamp", epoch),
+                    )
+                })?;
+            }
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        let formatted = format_time(&format.value(), timestamp)
+            .map_err(|message| syn::Error::new(format.span(), message))?;
+        Ok(CompileTimeInput {
+            formatted: LitStr::new(&formatted, format.span()),
+        })
+    }
+}
+
+/// Emit the formatted time as a string literal.
+impl ToTokens for CompileTimeInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.formatted.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let format = "%Y-%m-%d %H:%M:%S";
+        assert_eq!(format_time(format, 0).unwrap(), "1970-01-01 00:00:00");
+        assert_eq!(
+            format_time(format, 1_709_210_096).unwrap(),
+            "2024-02-29 12:34:56"
+        );
+        assert_eq!(
+            format_time("%j %s 100%%", 86_400 * 40).unwrap(),
+            "041 3456000 100%"
+        );
+        assert_eq!(format_time("%j", 1_735_603_200).unwrap(), "366");
+        assert_eq!(
+            format_time("%A", 0).unwrap_err(),
+            "unsupported format specifier `%A`"
+        );
+        format_time("100%", 0).unwrap_err();
+    }
+
+    #[test]
+    fn days() {
+        for days in [-800_000, -1, 0, 11_016, 19_782, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn parse_options() {
+        syn::parse_str::<CompileTimeInput>("").unwrap();
+        syn::parse_str::<CompileTimeInput>("\"%Y\", source_date_epoch").unwrap();
+        syn::parse_str::<CompileTimeInput>("\"%Y\", reproducible").unwrap_err();
+        syn::parse_str::<CompileTimeInput>("\"%Y\", source_date_epoch, x").unwrap_err();
+    }
+}
@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Lit, LitByteStr};
+
+use crate::msrv;
+
+/// This is the entire input to `cstr!`.
+///
+/// Example: `cstr!("hello")` or `cstr!(b"hello")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct CStrInput {
+    // The string's bytes, with the terminating NUL added.
+    bytes: LitByteStr,
+}
+
+/// Parse the input to `cstr!`, checking for NUL bytes.
+impl Parse for CStrInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: Lit = input.parse()?;
+        let mut bytes = match &lit {
+            Lit::Str(s) => s.value().into_bytes(),
+            Lit::ByteStr(s) => s.value(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "expected a string or byte string literal",
+                ))
+            }
+        };
+        if let Some(position) = bytes.iter().position(|&b| b == 0) {
+            return Err(syn::Error::new_spanned(
+                lit,
+                format!(
+                    "a C string can't contain a NUL byte, but there's one at position {}",
+                    position
+                ),
+            ));
+        }
+        bytes.push(0);
+        Ok(CStrInput {
+            bytes: LitByteStr::new(&bytes, lit.span()),
+        })
+    }
+}
+
+/// Emit a `&'static CStr`.
+impl ToTokens for CStrInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let bytes = &self.bytes;
+        // `CStr` moved into `core` in Rust 1.64.
+        let ffi = if msrv::at_least(64) {
+            quote! { ::core::ffi }
+        } else {
+            quote! { ::abc::__private::std::ffi }
+        };
+        // The bytes were checked while parsing: they end with the only NUL.
+        tokens.extend(quote! {
+            unsafe { #ffi::CStr::from_bytes_with_nul_unchecked(#bytes) }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_nul() {
+        let input: CStrInput = syn::parse_str("\"hi\"").unwrap();
+        assert_eq!(input.bytes.value(), b"hi\0");
+        let input: CStrInput = syn::parse_str("b\"\\xff\"").unwrap();
+        assert_eq!(input.bytes.value(), b"\xff\0");
+        syn::parse_str::<CStrInput>("\"a\\0b\"").unwrap_err();
+        syn::parse_str::<CStrInput>("\"done\\0\"").unwrap_err();
+        syn::parse_str::<CStrInput>("'c'").unwrap_err();
+    }
+
+    // This crate's `rust-version` is 1.60, older than `core::ffi::CStr`.
+    #[test]
+    fn old_rust_version() {
+        let input: CStrInput = syn::parse_str("\"hi\"").unwrap();
+        let output = input.to_token_stream().to_string();
+        assert!(
+            output.contains(":: abc :: __private :: std :: ffi :: CStr"),
+            "{}",
+            output
+        );
+    }
+}
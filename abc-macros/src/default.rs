@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Data, DeriveInput, Expr, ExprLit, Fields, Lit};
+
+/// This is the entire input to `#[derive(DefaultWithValues)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct DefaultInput {
+    input: DeriveInput,
+    // The `#[default(...)]` value for each field, if it has one.
+    values: Vec<Option<Expr>>,
+}
+
+/// Parse the input to `#[derive(DefaultWithValues)]`.
+impl Parse for DefaultInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+        let fields = match &input.data {
+            Data::Struct(data) => &data.fields,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "DefaultWithValues can only be derived for structs",
+                ))
+            }
+        };
+        let mut values = Vec::new();
+        for field in fields {
+            let mut value = None;
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("default"))
+            {
+                if value.is_some() {
+                    return Err(syn::Error::new_spanned(attr, "duplicate default attribute"));
+                }
+                value = Some(attr.parse_args::<Expr>()?);
+            }
+            values.push(value);
+        }
+        Ok(DefaultInput { input, values })
+    }
+}
+
+/// The expression for a field's default value.
+///
+/// A string literal is converted with `Into`, so `#[default("hello")]`
+/// works for a `String` as well as a `&str`. Other values are used as
+/// they are; converting `42` with `Into` would leave its type ambiguous.
+///
+fn default_value(value: &Option<Expr>) -> TokenStream {
+    match value {
+        Some(
+            expr @ Expr::Lit(ExprLit {
+                lit: Lit::Str(_), ..
+            }),
+        ) => quote! { ::core::convert::Into::into(#expr) },
+        Some(expr) => quote! { #expr },
+        None => quote! { ::core::default::Default::default() },
+    }
+}
+
+/// Emit the `Default` impl.
+impl ToTokens for DefaultInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let fields = match &self.input.data {
+            Data::Struct(data) => &data.fields,
+            _ => unreachable!("rejected while parsing"),
+        };
+        let values = self.values.iter().map(default_value);
+        let body = match fields {
+            Fields::Named(fields) => {
+                let idents = fields.named.iter().map(|field| &field.ident);
+                quote! { #name { #(#idents: #values,)* } }
+            }
+            Fields::Unnamed(_) => quote! { #name(#(#values,)*) },
+            Fields::Unit => quote! { #name },
+        };
+        tokens.extend(quote! {
+            impl #impl_generics ::core::default::Default for #name #ty_generics #where_clause {
+                fn default() -> Self {
+                    #body
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let input: DefaultInput = syn::parse_str(
+            "struct Foo { #[default(42)] a: u8, b: u8, #[default(\"x\")] c: String }",
+        )
+        .unwrap();
+        assert_eq!(input.values.len(), 3);
+        assert_eq!(
+            default_value(&input.values[0]).to_string(),
+            quote!(42).to_string()
+        );
+        assert!(input.values[1].is_none());
+        assert_eq!(
+            default_value(&input.values[2]).to_string(),
+            quote!(::core::convert::Into::into("x")).to_string()
+        );
+
+        syn::parse_str::<DefaultInput>("struct Foo(#[default] u8);").unwrap_err();
+        syn::parse_str::<DefaultInput>("struct Foo(#[default(1)] #[default(2)] u8);").unwrap_err();
+        syn::parse_str::<DefaultInput>("enum Foo { A }").unwrap_err();
+    }
+}
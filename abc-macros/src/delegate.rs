@@ -0,0 +1,205 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Expr, FnArg, Ident, TraitItemMethod, Visibility};
+
+use crate::wrap::Forward;
+
+/// One method to forward, like `pub fn len(&self) -> usize;`.
+struct Method {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    method: TraitItemMethod,
+    // The inner method to call, from `#[call(name)]`, if it has a
+    // different name.
+    call: Option<Ident>,
+}
+
+impl Parse for Method {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let method: TraitItemMethod = input.parse()?;
+        if let Some(block) = &method.default {
+            return Err(syn::Error::new_spanned(
+                block,
+                "a delegated method ends with `;`, not a body",
+            ));
+        }
+        if !method
+            .sig
+            .inputs
+            .iter()
+            .any(|input| matches!(input, FnArg::Receiver(_)))
+        {
+            return Err(syn::Error::new_spanned(
+                &method.sig.ident,
+                "a delegated method needs a `self` parameter",
+            ));
+        }
+        if let Some(variadic) = &method.sig.variadic {
+            return Err(syn::Error::new_spanned(
+                variadic,
+                "delegate! can't forward variadic arguments",
+            ));
+        }
+        let mut call = None;
+        let mut error = None;
+        attrs.retain(|attr| {
+            if !attr.path.is_ident("call") {
+                return true;
+            }
+            match attr.parse_args::<Ident>() {
+                Ok(ident) => call = Some(ident),
+                Err(_) => {
+                    error = Some(syn::Error::new_spanned(
+                        attr,
+                        "expected `#[call(method_name)]`",
+                    ))
+                }
+            }
+            false
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        Ok(Method {
+            attrs,
+            vis,
+            method,
+            call,
+        })
+    }
+}
+
+/// A `to <expr> { ... }` block: the methods to forward to one field.
+struct Target {
+    target: Expr,
+    methods: Vec<Method>,
+}
+
+impl Parse for Target {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let to: Ident = input.parse()?;
+        if to != "to" {
+            return Err(syn::Error::new_spanned(to, "expected `to`"));
+        }
+        // Like `if`, the target can't be a struct literal, so that the
+        // brace starts the list of methods.
+        let target = input.call(Expr::parse_without_eager_brace)?;
+        let content;
+        braced!(content in input);
+        let mut methods = Vec::new();
+        while !content.is_empty() {
+            methods.push(content.parse()?);
+        }
+        Ok(Target { target, methods })
+    }
+}
+
+/// This is the entire input to `delegate!`.
+///
+/// Example: `delegate! { to self.inner { pub fn len(&self) -> usize; } }`
+///
+pub struct DelegateInput {
+    targets: Vec<Target>,
+}
+
+/// Parse the input to `delegate!`.
+impl Parse for DelegateInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut targets = Vec::new();
+        while !input.is_empty() {
+            targets.push(input.parse()?);
+        }
+        Ok(DelegateInput { targets })
+    }
+}
+
+/// Emit the forwarding methods.
+impl ToTokens for DelegateInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for Target { target, methods } in &self.targets {
+            for method in methods {
+                let forward = Forward::new(&method.method.sig);
+                let name = method.call.as_ref().unwrap_or(&method.method.sig.ident);
+                // The inner method's generic parameters aren't necessarily
+                // the same, so they're left to inference.
+                let args = &forward.args;
+                let call = forward.finish(quote! { #target.#name(#(#args),*) });
+                let attrs = method.method.attrs.iter().chain(&method.attrs);
+                let vis = &method.vis;
+                let sig = &forward.sig;
+                tokens.extend(quote! {
+                    #(#attrs)*
+                    #[inline]
+                    #vis #sig {
+                        #call
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> syn::parse::Result<String> {
+        let input: DelegateInput = syn::parse_str(input)?;
+        Ok(input.into_token_stream().to_string())
+    }
+
+    #[test]
+    fn forwarding() {
+        let expanded = expand(
+            "to self.inner {
+                pub fn len(&self) -> usize;
+                #[call(get)]
+                /// Docs.
+                fn item<I: SliceIndex<[T]>>(&self, index: I) -> Option<&I::Output>;
+            }
+            to self.0.lock() {
+                async fn fetch(&mut self, (a, b): (u8, u8)) -> u8;
+            }",
+        )
+        .unwrap();
+        let expected = quote! {
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.inner.len()
+            }
+            #[doc = " Docs."]
+            #[inline]
+            fn item<I: SliceIndex<[T]>>(&self, __arg1: I) -> Option<&I::Output> {
+                self.inner.get(__arg1)
+            }
+            #[inline]
+            async fn fetch(&mut self, __arg1: (u8, u8)) -> u8 {
+                self.0.lock().fetch(__arg1).await
+            }
+        };
+        // Spacing differs between parsed and quoted tokens.
+        let unspaced = |text: String| text.replace(' ', "");
+        assert_eq!(unspaced(expanded), unspaced(expected.to_string()));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input| expand(input).unwrap_err().to_string();
+        assert_eq!(
+            error("to self.inner { fn len(&self) -> usize { 0 } }"),
+            "a delegated method ends with `;`, not a body"
+        );
+        assert_eq!(
+            error("to self.inner { fn new() -> Self; }"),
+            "a delegated method needs a `self` parameter"
+        );
+        assert_eq!(
+            error("to self.inner { #[call = \"x\"] fn len(&self) -> usize; }"),
+            "expected `#[call(method_name)]`"
+        );
+        assert_eq!(error("into self.inner {}"), "expected `to`");
+    }
+}
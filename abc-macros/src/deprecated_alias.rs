@@ -0,0 +1,215 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, ItemFn, LitStr, Meta, NestedMeta, Token};
+
+use crate::attr::{self, lit_str};
+use crate::wrap::{mentions, Forward};
+
+/// The arguments to `#[deprecated_alias(...)]`.
+///
+/// Example: `#[deprecated_alias(old_name)]` or
+/// `#[deprecated_alias(old_name, since = "0.4.0")]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct DeprecatedAliasArgs {
+    alias: Ident,
+    // `since = "..."` and `note = "..."`, passed on to `#[deprecated]`.
+    since: Option<LitStr>,
+    note: Option<LitStr>,
+}
+
+impl Parse for DeprecatedAliasArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = DeprecatedAliasArgs {
+            alias: input.parse()?,
+            since: None,
+            note: None,
+        };
+        if input.parse::<Option<Token![,]>>()?.is_none() {
+            return Ok(args);
+        }
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("since") => {
+                    args.since = Some(lit_str(&nv.lit)?.clone());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("note") => {
+                    args.note = Some(lit_str(&nv.lit)?.clone());
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown deprecated_alias option",
+                        &["since", "note"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a function with `#[deprecated_alias]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct DeprecatedAlias {
+    args: DeprecatedAliasArgs,
+    function: ItemFn,
+}
+
+impl DeprecatedAlias {
+    pub fn new(args: DeprecatedAliasArgs, function: ItemFn) -> syn::parse::Result<Self> {
+        if args.alias == function.sig.ident {
+            return Err(syn::Error::new_spanned(
+                &args.alias,
+                "the alias needs a different name from the function",
+            ));
+        }
+        if let Some(variadic) = &function.sig.variadic {
+            return Err(syn::Error::new_spanned(
+                variadic,
+                "deprecated_alias can't forward variadic arguments",
+            ));
+        }
+        Ok(DeprecatedAlias { args, function })
+    }
+}
+
+/// Emit the function, and the deprecated alias that calls it.
+impl ToTokens for DeprecatedAlias {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let function = &self.function;
+        let name = &function.sig.ident;
+
+        // The alias takes the same arguments, but under plain names.
+        let mut forward = Forward::new(&function.sig);
+        forward.sig.ident = self.args.alias.clone();
+
+        // A method, or an associated function that mentions `Self`, is
+        // called through `Self`; anything else is a free function.
+        let in_impl = forward.receiver || mentions(function.sig.to_token_stream(), "Self");
+        let path = if in_impl {
+            quote! { Self::#name }
+        } else {
+            quote! { #name }
+        };
+        let receiver = if forward.receiver {
+            Some(quote! { self, })
+        } else {
+            None
+        };
+        let turbofish = &forward.turbofish;
+        let args = &forward.args;
+        let alias_sig = &forward.sig;
+        let call = forward.finish(quote! { #path #turbofish(#receiver #(#args),*) });
+
+        let note = match &self.args.note {
+            Some(note) => note.clone(),
+            None => LitStr::new(&format!("renamed to `{}`", name), name.span()),
+        };
+        let since = self
+            .args
+            .since
+            .as_ref()
+            .map(|since| quote! { since = #since, });
+        let doc = format!(" Deprecated alias of [`{}`](Self::{}).", name, name);
+        let doc = if in_impl {
+            doc
+        } else {
+            format!(" Deprecated alias of [`{}`].", name)
+        };
+        let vis = &function.vis;
+        // `#[cfg]`s apply to the alias too; other attributes, like
+        // `#[test]` or `#[no_mangle]`, shouldn't be duplicated.
+        let cfgs = function
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"));
+
+        tokens.extend(quote! {
+            #function
+
+            #(#cfgs)*
+            #[doc = #doc]
+            #[deprecated(#since note = #note)]
+            #[inline]
+            #vis #alias_sig {
+                #call
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(args: TokenStream, function: TokenStream) -> syn::parse::Result<String> {
+        let alias = DeprecatedAlias::new(syn::parse2(args)?, syn::parse2(function)?)?;
+        Ok(alias.into_token_stream().to_string())
+    }
+
+    #[test]
+    fn forwarding() {
+        let expanded = alias(
+            quote!(old),
+            quote!(
+                fn new<T, const N: usize>(x: [T; N]) {}
+            ),
+        )
+        .unwrap();
+        assert!(expanded.contains(&quote!(fn old<T, const N: usize>(__arg0: [T; N])).to_string()));
+        assert!(expanded.contains(&quote!(new::<T, N>(__arg0)).to_string()));
+
+        let expanded = alias(
+            quote!(old),
+            quote!(
+                fn new(&self, s: impl AsRef<str>) {}
+            ),
+        )
+        .unwrap();
+        assert!(expanded.contains(&quote!(Self::new(self, __arg1)).to_string()));
+
+        let expanded = alias(
+            quote!(old),
+            quote!(
+                async unsafe fn new() {}
+            ),
+        )
+        .unwrap();
+        assert!(expanded.contains(&quote!(unsafe { new().await }).to_string()));
+
+        let expanded = alias(
+            quote!(old, since = "0.4.0"),
+            quote!(
+                fn new() {}
+            ),
+        )
+        .unwrap();
+        assert!(expanded.contains(&quote!(since = "0.4.0", note = "renamed to `new`").to_string()));
+
+        alias(
+            quote!(new),
+            quote!(
+                fn new() {}
+            ),
+        )
+        .unwrap_err();
+        alias(
+            quote!(old, bogus = "x"),
+            quote!(
+                fn new() {}
+            ),
+        )
+        .unwrap_err();
+        alias(
+            quote!("old"),
+            quote!(
+                fn new() {}
+            ),
+        )
+        .unwrap_err();
+    }
+}
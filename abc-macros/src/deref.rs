@@ -0,0 +1,142 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Member, Type};
+
+/// This is the input to `#[derive(Deref)]` or `#[derive(DerefMut)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct DerefInput {
+    input: DeriveInput,
+    // The field to dereference to, and its type.
+    member: Member,
+    ty: Type,
+    // `DerefMut` rather than `Deref`.
+    mutable: bool,
+}
+
+impl DerefInput {
+    pub fn new(input: DeriveInput, mutable: bool) -> syn::parse::Result<Self> {
+        let trait_name = if mutable { "DerefMut" } else { "Deref" };
+        let fields = match &input.data {
+            Data::Struct(data) => &data.fields,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("{} can only be derived for structs", trait_name),
+                ))
+            }
+        };
+        let fields: Vec<(Member, &syn::Field)> = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                };
+                (member, field)
+            })
+            .collect();
+
+        let marked: Vec<&(Member, &syn::Field)> = fields
+            .iter()
+            .filter(|(_, field)| field.attrs.iter().any(|attr| attr.path.is_ident("deref")))
+            .collect();
+        for (_, field) in &marked {
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("deref"))
+            {
+                if !attr.tokens.is_empty() {
+                    return Err(syn::Error::new_spanned(attr, "expected `#[deref]`"));
+                }
+            }
+        }
+        let (member, field) = match (marked.as_slice(), fields.as_slice()) {
+            ([(member, field)], _) => (member.clone(), *field),
+            ([], [(member, field)]) => (member.clone(), *field),
+            ([], []) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("{} needs a struct with a field", trait_name),
+                ))
+            }
+            ([], _) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!(
+                    "{} is ambiguous for a struct with several fields; mark one with `#[deref]`",
+                    trait_name
+                ),
+                ))
+            }
+            ([_, (_, second), ..], _) => {
+                return Err(syn::Error::new_spanned(
+                    second,
+                    "only one field can be marked `#[deref]`",
+                ))
+            }
+        };
+        let ty = field.ty.clone();
+        Ok(DerefInput {
+            input,
+            member,
+            ty,
+            mutable,
+        })
+    }
+}
+
+/// Emit the `Deref` or `DerefMut` impl.
+impl ToTokens for DerefInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let member = &self.member;
+        let ty = &self.ty;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        tokens.extend(if self.mutable {
+            quote! {
+                impl #impl_generics ::core::ops::DerefMut for #name #ty_generics #where_clause {
+                    fn deref_mut(&mut self) -> &mut Self::Target {
+                        &mut self.#member
+                    }
+                }
+            }
+        } else {
+            quote! {
+                impl #impl_generics ::core::ops::Deref for #name #ty_generics #where_clause {
+                    type Target = #ty;
+
+                    fn deref(&self) -> &#ty {
+                        &self.#member
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deref(input: &str) -> syn::parse::Result<DerefInput> {
+        DerefInput::new(syn::parse_str(input)?, false)
+    }
+
+    #[test]
+    fn choose_field() {
+        let input = deref("struct Name(String);").unwrap();
+        assert_eq!(input.member, Member::Unnamed(0.into()));
+        let input = deref("struct User { id: u32, #[deref] name: String }").unwrap();
+        assert_eq!(input.member, Member::Named(syn::parse_quote!(name)));
+        let input = deref("struct Pair(u8, #[deref] String);").unwrap();
+        assert_eq!(input.member, Member::Unnamed(1.into()));
+
+        deref("struct User { id: u32, name: String }").unwrap_err();
+        deref("struct User { #[deref] id: u32, #[deref] name: String }").unwrap_err();
+        deref("struct User { #[deref(mut)] name: String }").unwrap_err();
+        deref("struct Unit;").unwrap_err();
+        deref("enum E { A(u8) }").unwrap_err();
+    }
+}
@@ -0,0 +1,1696 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parse_quote, Attribute, Data, DataEnum, DeriveInput, Fields, GenericParam, Generics, Ident,
+    Index, Lit, Meta, NestedMeta, Path, Type, Variant,
+};
+
+#[cfg(feature = "describe_regex")]
+use regex::Regex;
+
+use crate::attr::{self, lit_str};
+use crate::case::RenameRule;
+use crate::generics::field_type_is_generic;
+use crate::msrv;
+use crate::schema::{self, Json};
+
+/// This is the entire input to `#[derive(DescribeStruct)]`.
+///
+/// Along with the original syntax tree, it carries anything we've
+/// extracted from it while parsing.
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct DescribeInput {
+    input: DeriveInput,
+    // The container's `///` doc comments, joined by newlines.
+    description: String,
+    options: ContainerOptions,
+    // Options for each of the struct or union fields, in order.
+    field_options: Vec<FieldOptions>,
+    // Options for the fields of each enum variant, in order.
+    variant_field_options: Vec<Vec<FieldOptions>>,
+}
+
+/// Options set by `#[describe(...)]` attributes on a field.
+#[derive(Default)]
+#[cfg_attr(test, derive(Debug))]
+struct FieldOptions {
+    // `#[describe(label = "...")]`: a human-friendly name.
+    label: Option<String>,
+    // `#[describe(doc = "...")]`: a human-friendly description.
+    doc: Option<String>,
+}
+
+impl FieldOptions {
+    /// Collect the options from every `#[describe(...)]` attribute on a field.
+    fn from_attributes(attrs: &[Attribute]) -> syn::parse::Result<Self> {
+        let mut options = FieldOptions::default();
+        for nested in attr::options(attrs, "describe")? {
+            match &nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("label") => {
+                    options.label = Some(lit_str(&nv.lit)?.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("doc") => {
+                    options.doc = Some(lit_str(&nv.lit)?.value());
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown describe option for a field",
+                        &["label", "doc"],
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Options set by `#[describe(...)]` attributes on the struct or enum.
+#[cfg_attr(test, derive(Debug))]
+struct ContainerOptions {
+    // `#[describe(crate = "...")]`: the path to the `abc` crate, used for the
+    // trait and support types in the generated code.
+    crate_path: Path,
+    // `#[describe(display)]`: also implement `Display`.
+    display: bool,
+    // `#[describe(json_schema)]`: also generate `fn json_schema()`.
+    json_schema: bool,
+    // `#[describe(debug_expand)]`: write out the generated code.
+    debug_expand: bool,
+    // `#[describe(instrument)]`: record the type's name and path as fields
+    // of the span from `trace_span`.
+    instrument: bool,
+    // `#[describe(rename_all = "...")]`: change the case of reported names.
+    rename_all: Option<RenameRule>,
+    // `#[describe(skip_zst)]`: leave zero-sized fields out of `describe` and
+    // `visit_fields`.
+    skip_zst: bool,
+    // `#[describe(deny_names(...))]`: type names that are rejected.
+    deny_names: Vec<String>,
+    // `#[describe(require_name_matches = "...")]`: a pattern that the type
+    // name must match. This needs the `describe_regex` feature.
+    #[cfg(feature = "describe_regex")]
+    require_name_matches: Option<Regex>,
+    // `#[describe(use_serde_names)]`: prefer the names from `#[serde(...)]`
+    // attributes, which are collected here.
+    use_serde_names: bool,
+    serde: SerdeNames,
+}
+
+/// Names set by `#[serde(rename = "...")]` and `#[serde(rename_all = "...")]`.
+///
+/// Only the serialized names are used, if serialize and deserialize are
+/// given separately. We don't report errors in serde attributes, because
+/// serde's derive will do that; anything we don't understand is ignored.
+///
+#[derive(Default)]
+#[cfg_attr(test, derive(Debug))]
+struct SerdeNames {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+}
+
+impl SerdeNames {
+    fn from_attributes(attrs: &[Attribute]) -> Self {
+        let mut names = SerdeNames::default();
+        let metas = attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) => Some(list.nested),
+                _ => None,
+            })
+            .flatten();
+        for nested in metas {
+            let (path, lit) = match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => (nv.path, nv.lit),
+                // `rename(serialize = "...", deserialize = "...")`
+                NestedMeta::Meta(Meta::List(list)) => {
+                    let serialize = list.nested.into_iter().find_map(|nested| match nested {
+                        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("serialize") => {
+                            Some(nv.lit)
+                        }
+                        _ => None,
+                    });
+                    match serialize {
+                        Some(lit) => (list.path, lit),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let lit = match lit {
+                Lit::Str(lit) => lit,
+                _ => continue,
+            };
+            if path.is_ident("rename") {
+                names.rename = Some(lit.value());
+            } else if path.is_ident("rename_all") {
+                names.rename_all = RenameRule::from_lit(&lit).ok();
+            }
+        }
+        names
+    }
+}
+
+impl Default for ContainerOptions {
+    fn default() -> Self {
+        ContainerOptions {
+            crate_path: parse_quote!(::abc),
+            display: false,
+            json_schema: false,
+            debug_expand: false,
+            instrument: false,
+            rename_all: None,
+            skip_zst: false,
+            deny_names: vec!["OhNo".to_string()],
+            #[cfg(feature = "describe_regex")]
+            require_name_matches: None,
+            use_serde_names: false,
+            serde: SerdeNames::default(),
+        }
+    }
+}
+
+impl ContainerOptions {
+    /// The name to report for the type, after any renaming.
+    fn type_name(&self, name: &str) -> String {
+        if let (true, Some(rename)) = (self.use_serde_names, &self.serde.rename) {
+            return rename.clone();
+        }
+        match self.rename_all {
+            Some(rule) => rule.apply(name),
+            None => name.to_string(),
+        }
+    }
+
+    /// The rule for renaming struct fields and enum variants.
+    ///
+    /// Like serde, a container's `#[serde(rename_all)]` doesn't apply to
+    /// the fields inside enum variants; see `variant_field_rule`.
+    ///
+    fn member_rule(&self) -> Option<RenameRule> {
+        match (self.use_serde_names, self.serde.rename_all) {
+            (true, Some(rule)) => Some(rule),
+            _ => self.rename_all,
+        }
+    }
+
+    /// The rule for renaming the fields of an enum variant.
+    fn variant_field_rule(&self, variant: &Variant) -> Option<RenameRule> {
+        if self.use_serde_names {
+            if let Some(rule) = SerdeNames::from_attributes(&variant.attrs).rename_all {
+                return Some(rule);
+            }
+        }
+        self.rename_all
+    }
+
+    /// The name to report for a field or variant.
+    ///
+    /// An explicit `#[serde(rename)]` wins, if we're using serde names;
+    /// otherwise `rule` is applied.
+    ///
+    fn member_name(&self, ident: &Ident, attrs: &[Attribute], rule: Option<RenameRule>) -> String {
+        if self.use_serde_names {
+            if let Some(rename) = SerdeNames::from_attributes(attrs).rename {
+                return rename;
+            }
+        }
+        match rule {
+            Some(rule) => rule.apply(&unraw(ident)),
+            None => unraw(ident),
+        }
+    }
+
+    /// Collect the options from every `#[describe(...)]` attribute.
+    fn from_attributes(attrs: &[Attribute]) -> syn::parse::Result<Self> {
+        let mut options = ContainerOptions::default();
+        for nested in attr::options(attrs, "describe")? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("display") => {
+                    options.display = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("json_schema") => {
+                    options.json_schema = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip_zst") => {
+                    options.skip_zst = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("debug_expand") => {
+                    options.debug_expand = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("instrument") => {
+                    options.instrument = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("use_serde_names") => {
+                    options.use_serde_names = true;
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("deny_names") => {
+                    options.deny_names = list
+                        .nested
+                        .iter()
+                        .map(|name| match name {
+                            NestedMeta::Lit(lit) => Ok(lit_str(lit)?.value()),
+                            other => Err(syn::Error::new_spanned(other, "expected a string")),
+                        })
+                        .collect::<syn::parse::Result<_>>()?;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    options.crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                #[cfg(not(feature = "describe_regex"))]
+                NestedMeta::Meta(Meta::NameValue(nv))
+                    if nv.path.is_ident("require_name_matches") =>
+                {
+                    return Err(syn::Error::new_spanned(
+                        &nv.path,
+                        "`require_name_matches` needs abc's `describe_regex` feature",
+                    ));
+                }
+                #[cfg(feature = "describe_regex")]
+                NestedMeta::Meta(Meta::NameValue(nv))
+                    if nv.path.is_ident("require_name_matches") =>
+                {
+                    let pattern = lit_str(&nv.lit)?;
+                    let regex = Regex::new(&pattern.value()).map_err(|e| {
+                        syn::Error::new_spanned(pattern, format!("invalid pattern: {}", e))
+                    })?;
+                    options.require_name_matches = Some(regex);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename_all") => {
+                    options.rename_all = Some(RenameRule::from_lit(lit_str(&nv.lit)?)?);
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown describe option",
+                        &[
+                            "display",
+                            "json_schema",
+                            "skip_zst",
+                            "debug_expand",
+                            "instrument",
+                            "use_serde_names",
+                            "deny_names",
+                            "crate",
+                            "require_name_matches",
+                            "rename_all",
+                        ],
+                    ))
+                }
+            }
+        }
+        if options.use_serde_names {
+            options.serde = SerdeNames::from_attributes(attrs);
+        }
+        Ok(options)
+    }
+}
+
+/// Parse the input to `#[derive(DescribeStruct)]`.
+impl Parse for DescribeInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        // parse the input into a DeriveInput syntax tree
+        let input: DeriveInput = input.parse()?;
+
+        let description = doc_string(&input.attrs);
+        let options = ContainerOptions::from_attributes(&input.attrs)?;
+
+        // Return a compile error if the struct has a forbidden name.
+        if options.deny_names.contains(&unraw(&input.ident)) {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "That name is not allowed",
+            ));
+        }
+        #[cfg(feature = "describe_regex")]
+        if let Some(pattern) = &options.require_name_matches {
+            let name = unraw(&input.ident);
+            if !pattern.is_match(&name) {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!(
+                        "type name `{}` doesn't match the required pattern `{}`",
+                        name,
+                        pattern.as_str()
+                    ),
+                ));
+            }
+        }
+
+        // The compiler removes disabled `#[cfg]` fields and variants and
+        // expands `#[cfg_attr]` before running a derive, so everything below
+        // only ever sees the fields that are compiled in.
+        let variant_field_options = match &input.data {
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .map(|variant| {
+                    variant
+                        .fields
+                        .iter()
+                        .map(|field| FieldOptions::from_attributes(&field.attrs))
+                        .collect()
+                })
+                .collect::<syn::parse::Result<_>>()?,
+            _ => Vec::new(),
+        };
+        let mut described = DescribeInput {
+            input,
+            description,
+            options,
+            field_options: Vec::new(),
+            variant_field_options,
+        };
+        described.field_options = described
+            .fields()
+            .iter()
+            .map(|field| FieldOptions::from_attributes(&field.attrs))
+            .collect::<syn::parse::Result<_>>()?;
+
+        Ok(described)
+    }
+}
+
+/// The name of an identifier, without the `r#` of a raw identifier.
+///
+/// `r#type` is how you write a field named "type", so that's the name
+/// we report.
+///
+fn unraw(ident: &Ident) -> String {
+    ident.unraw().to_string()
+}
+
+/// Collect the text of any `///` doc comments.
+///
+/// Each `///` line arrives as a separate `#[doc = "..."]` attribute,
+/// with the space after the slashes still attached. We strip that
+/// space, the same way rustdoc does.
+///
+fn doc_string(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Format a type the way a person would write it.
+///
+/// `TokenStream::to_string` puts spaces between every token, which gives
+/// us `Vec < u8 >`. Instead, only put spaces where they're needed to
+/// separate words, and after commas and semicolons.
+///
+fn type_string(ty: &Type) -> String {
+    let mut out = String::new();
+    write_tokens(&mut out, ty.to_token_stream());
+    out
+}
+
+fn write_tokens(out: &mut String, tokens: TokenStream) {
+    // The previous word (an identifier or literal), if the last token was one.
+    let mut prev_word: Option<String> = None;
+    // Whether the last token was the `-` of a `->`.
+    let mut in_arrow = false;
+    for tt in tokens {
+        match tt {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                // `Fn(u8)` and `fn(u8)` are written without a space, but
+                // `&mut [u8]` and `dyn (Trait)` need one.
+                if let Some(word) = &prev_word {
+                    if !matches!(word.as_str(), "Fn" | "FnMut" | "FnOnce" | "fn") {
+                        out.push(' ');
+                    }
+                }
+                out.push_str(open);
+                write_tokens(out, group.stream());
+                out.push_str(close);
+                prev_word = None;
+            }
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if prev_word.is_some() {
+                    out.push(' ');
+                }
+                let word = match &tt {
+                    TokenTree::Ident(ident) => unraw(ident),
+                    _ => tt.to_string(),
+                };
+                out.push_str(&word);
+                prev_word = Some(word);
+            }
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+                let joint = punct.spacing() == Spacing::Joint;
+                if in_arrow && ch == '>' {
+                    out.push_str("> ");
+                    in_arrow = false;
+                } else if ch == '-' && joint {
+                    out.push_str(" -");
+                    in_arrow = true;
+                } else if matches!(ch, '+' | '=') {
+                    out.push(' ');
+                    out.push(ch);
+                    out.push(' ');
+                } else {
+                    out.push(ch);
+                    if matches!(ch, ',' | ';') {
+                        out.push(' ');
+                    }
+                }
+                prev_word = None;
+            }
+        }
+    }
+}
+
+/// The names of a type's generic parameters, like `<'a, T, N>`.
+///
+/// This is empty if the type isn't generic.
+///
+fn generic_params(generics: &Generics) -> String {
+    if generics.params.is_empty() {
+        return String::new();
+    }
+    let names: Vec<String> = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(param) => param.lifetime.to_string(),
+            GenericParam::Type(param) => unraw(&param.ident),
+            GenericParam::Const(param) => unraw(&param.ident),
+        })
+        .collect();
+    format!("<{}>", names.join(", "))
+}
+
+/// Generate `variant_names` and `discriminant` for an enum.
+///
+/// Discriminants are computed by the compiler, not by us: each one is a
+/// const expression ending with `as i128`, and a variant without an
+/// explicit discriminant is the previous one plus one. They're only
+/// reported if the enum has an explicit discriminant or has no fields at
+/// all; otherwise the discriminant values aren't meaningful.
+///
+fn variant_methods(options: &ContainerOptions, data: &DataEnum) -> TokenStream {
+    let has_discriminants = data.variants.iter().any(|v| v.discriminant.is_some())
+        || data.variants.iter().all(|v| v.fields.is_empty());
+
+    let mut prev = None;
+    let discriminants: Vec<TokenStream> = data
+        .variants
+        .iter()
+        .map(|variant| {
+            if !has_discriminants {
+                return quote! { ::core::option::Option::None };
+            }
+            let value = match (&variant.discriminant, &prev) {
+                (Some((_, expr)), _) => quote! { (#expr) as i128 },
+                (None, Some(prev)) => quote! { #prev + 1 },
+                (None, None) => quote! { 0 },
+            };
+            prev = Some(value.clone());
+            quote! { ::core::option::Option::Some(#value) }
+        })
+        .collect();
+
+    let names = data
+        .variants
+        .iter()
+        .map(|variant| options.member_name(&variant.ident, &variant.attrs, options.member_rule()));
+    let idents = data.variants.iter().map(|variant| &variant.ident);
+    let list_entries = names
+        .zip(&discriminants)
+        .map(|(name, discriminant)| quote! { (#name, #discriminant) });
+
+    // An empty match on a reference isn't exhaustive; see `to_tokens`.
+    let body = if data.variants.is_empty() {
+        quote! { match *self {} }
+    } else {
+        quote! {
+            match self {
+                #(Self::#idents { .. } => #discriminants,)*
+            }
+        }
+    };
+
+    quote! {
+        fn variant_names(&self) -> &'static [(&'static str, ::core::option::Option<i128>)] {
+            const VARIANTS: &[(&str, ::core::option::Option<i128>)] = &[#(#list_entries),*];
+            VARIANTS
+        }
+
+        #[allow(clippy::identity_op)]
+        fn discriminant(&self) -> ::core::option::Option<i128> {
+            #body
+        }
+    }
+}
+
+/// The name to report for a field, renamed with `rule`.
+///
+/// Tuple struct fields are named by position, the same way they're
+/// accessed: `self.0`, `self.1`, ...
+///
+fn field_name(
+    options: &ContainerOptions,
+    field: &syn::Field,
+    index: usize,
+    rule: Option<RenameRule>,
+) -> String {
+    match &field.ident {
+        Some(ident) => options.member_name(ident, &field.attrs, rule),
+        None => index.to_string(),
+    }
+}
+
+/// Generate the statements that pass each field to a `DescribeVisitor`.
+///
+/// `values` are the expressions for each field's value, which must be
+/// references.
+///
+fn visit_fields(
+    options: &ContainerOptions,
+    fields: &Fields,
+    rule: Option<RenameRule>,
+    values: impl IntoIterator<Item = TokenStream>,
+) -> TokenStream {
+    let krate = &options.crate_path;
+    let visits = fields
+        .iter()
+        .enumerate()
+        .zip(values)
+        .map(|((index, field), value)| {
+            let name = field_name(options, field, index, rule);
+            let visit = quote! {
+                visitor.field(#name, (&#krate::__private::Probe(#value)).as_debug());
+            };
+            if options.skip_zst {
+                let ty = &field.ty;
+                quote! {
+                    if ::core::mem::size_of::<#ty>() != 0 {
+                        #visit
+                    }
+                }
+            } else {
+                visit
+            }
+        });
+    quote! {
+        use #krate::__private::{DebugField as _, OpaqueField as _};
+        #(#visits)*
+    }
+}
+
+/// References to each of a struct's fields, through `self`.
+fn self_fields(fields: &Fields) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote! { &self.#ident },
+            None => {
+                let index = Index::from(index);
+                quote! { &self.#index }
+            }
+        })
+        .collect()
+}
+
+/// A pattern matching an enum variant, and the names it binds each of
+/// the variant's fields to.
+fn variant_pattern(variant: &Variant) -> (TokenStream, Vec<Ident>) {
+    let variant_name = &variant.ident;
+    let bindings: Vec<Ident> = (0..variant.fields.len())
+        .map(|index| format_ident!("__field{}", index))
+        .collect();
+    let pattern = match &variant.fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote! { Self::#variant_name { #(#names: #bindings),* } }
+        }
+        Fields::Unnamed(_) => quote! { Self::#variant_name ( #(#bindings),* ) },
+        Fields::Unit => quote! { Self::#variant_name },
+    };
+    (pattern, bindings)
+}
+
+/// Generate the match arms that return each field as `&dyn Any`.
+///
+/// `Any` is only implemented for `'static` types, so fields whose types
+/// borrow anything or use a generic parameter are left out.
+///
+fn any_field_arms(
+    options: &ContainerOptions,
+    generics: &Generics,
+    fields: &Fields,
+    rule: Option<RenameRule>,
+    values: impl IntoIterator<Item = TokenStream>,
+) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .zip(values)
+        .filter(|((_, field), _)| !field_type_is_generic(generics, &field.ty))
+        .map(|((index, field), value)| {
+            let name = field_name(options, field, index, rule);
+            quote! {
+                #name => ::core::option::Option::Some(#value as &dyn ::core::any::Any),
+            }
+        })
+        .collect()
+}
+
+/// Generate the match arms that assign a `Box<dyn Any>` to each field.
+///
+/// `places` are the assignable expressions for each field. The fields
+/// are the same ones that `any_field_arms` returns.
+///
+fn set_field_arms(
+    options: &ContainerOptions,
+    generics: &Generics,
+    fields: &Fields,
+    rule: Option<RenameRule>,
+    places: impl IntoIterator<Item = TokenStream>,
+) -> Vec<TokenStream> {
+    let krate = &options.crate_path;
+    fields
+        .iter()
+        .enumerate()
+        .zip(places)
+        .filter(|((_, field), _)| !field_type_is_generic(generics, &field.ty))
+        .map(|((index, field), place)| {
+            let name = field_name(options, field, index, rule);
+            let ty = &field.ty;
+            let type_name = type_string(ty);
+            quote! {
+                #name => match value.downcast::<#ty>() {
+                    ::core::result::Result::Ok(value) => {
+                        #place = *value;
+                        ::core::result::Result::Ok(())
+                    }
+                    ::core::result::Result::Err(_) => {
+                        ::core::result::Result::Err(#krate::FieldError::WrongType {
+                            field: #name,
+                            expected: #type_name,
+                        })
+                    }
+                },
+            }
+        })
+        .collect()
+}
+
+/// Generate an `Option<&'static str>` expression.
+fn option_str(value: &Option<String>) -> TokenStream {
+    match value {
+        Some(value) => quote! { ::core::option::Option::Some(#value) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
+
+/// Generate the expression that builds a `Vec<FieldDescription>`.
+///
+/// If `readable` is set, each field is reached through `self` so that
+/// nested descriptions can be generated. Union fields can't be read
+/// without knowing which one is active, so they aren't nested.
+///
+fn field_descriptions(
+    options: &ContainerOptions,
+    fields: &Fields,
+    field_options: &[FieldOptions],
+    rule: Option<RenameRule>,
+    readable: bool,
+) -> TokenStream {
+    let krate = &options.crate_path;
+    let fields = fields.iter().zip(field_options).enumerate();
+    let fields = fields.map(|(index, (field, field_options))| {
+        let name = field_name(options, field, index, rule);
+        let type_name = type_string(&field.ty);
+        let nested = if readable {
+            let value = match &field.ident {
+                Some(ident) => quote! { self.#ident },
+                None => {
+                    let index = Index::from(index);
+                    quote! { self.#index }
+                }
+            };
+            quote! {
+                {
+                    use #krate::__private::{DescribeNested as _, DescribeOpaque as _};
+                    (&#krate::__private::Probe(&#value)).nested_description()
+                }
+            }
+        } else {
+            quote! { ::core::option::Option::None }
+        };
+        let ty = &field.ty;
+        let label = option_str(&field_options.label);
+        let doc = option_str(&field_options.doc);
+        quote! {
+            #krate::FieldDescription {
+                name: #name,
+                type_name: #type_name,
+                label: #label,
+                doc: #doc,
+                size: ::core::mem::size_of::<#ty>(),
+                nested: #nested,
+            }
+        }
+    });
+    // Sizes aren't known until the type is compiled, so zero-sized fields
+    // are left out at runtime.
+    if options.skip_zst {
+        quote! {
+            {
+                let mut fields: #krate::__private::alloc::vec::Vec<#krate::FieldDescription> = #krate::__private::alloc::vec![#(#fields),*];
+                fields.retain(|field| field.size != 0);
+                fields
+            }
+        }
+    } else {
+        quote! { #krate::__private::alloc::vec![#(#fields),*] }
+    }
+}
+
+impl DescribeInput {
+    /// Write the generated code somewhere it can be read, if the type has
+    /// `#[describe(debug_expand)]`.
+    ///
+    /// The code goes to `<out_dir>/abc_expansions/<Type>.rs` if there's an
+    /// `out_dir` (crates with a build script have `$OUT_DIR`), and to
+    /// stderr otherwise or if the file can't be written.
+    ///
+    pub fn debug_expand(&self, expanded: &TokenStream, out_dir: Option<&std::path::Path>) {
+        if !self.options.debug_expand {
+            return;
+        }
+        let name = unraw(&self.input.ident);
+        let code = expanded.to_string();
+        if let Some(out_dir) = out_dir {
+            let dir = out_dir.join("abc_expansions");
+            let path = dir.join(format!("{}.rs", name));
+            let written = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, &code));
+            match written {
+                Ok(()) => return,
+                Err(e) => eprintln!("abc: can't write {}: {}", path.display(), e),
+            }
+        }
+        eprintln!(
+            "abc: expansion of #[derive(DescribeStruct)] for {}:\n{}",
+            name, code
+        );
+    }
+
+    /// The fields of a struct or union. Enums have none at this level.
+    fn fields(&self) -> Fields {
+        match &self.input.data {
+            Data::Struct(data) => data.fields.clone(),
+            Data::Union(data) => Fields::Named(data.fields.clone()),
+            Data::Enum(_) => Fields::Unit,
+        }
+    }
+
+    /// The hash returned by `structure_version`.
+    ///
+    /// This hashes a canonical string built from the type's name and the
+    /// names and types of its fields (or variants, and their fields), so
+    /// it only changes when one of those does. Documentation, attributes,
+    /// and formatting don't affect it.
+    ///
+    fn structure_version(&self) -> u64 {
+        let body = match &self.input.data {
+            Data::Struct(data) => fields_signature(&data.fields),
+            Data::Union(data) => fields_signature(&Fields::Named(data.fields.clone())),
+            Data::Enum(data) => {
+                let variants = data.variants.iter().map(|variant| {
+                    format!(
+                        "{}{}",
+                        unraw(&variant.ident),
+                        fields_signature(&variant.fields)
+                    )
+                });
+                format!("{{{}}}", variants.collect::<Vec<_>>().join(","))
+            }
+        };
+        let signature = format!(
+            "{}{}{}",
+            unraw(&self.input.ident),
+            generic_params(&self.input.generics),
+            body
+        );
+        fnv1a(signature.as_bytes())
+    }
+
+    /// Build the JSON Schema for `#[describe(json_schema)]`.
+    ///
+    /// Structs are described the way serde would serialize them: named
+    /// fields as an object, a newtype as its contents, and other tuple
+    /// structs as an array. Enums with only unit variants are a set of
+    /// strings; other enums and unions can be anything.
+    ///
+    fn json_schema(&self, reported_name: &str) -> Json {
+        let mut schema = vec![
+            (
+                "$schema",
+                Json::string("http://json-schema.org/draft-07/schema#"),
+            ),
+            ("title", Json::string(reported_name)),
+        ];
+        if !self.description.is_empty() {
+            schema.push(("description", Json::string(self.description.as_str())));
+        }
+        let mut shape = match &self.input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => {
+                    let rule = self.options.member_rule();
+                    let mut properties = Vec::new();
+                    let mut required = Vec::new();
+                    for (index, field) in fields.named.iter().enumerate() {
+                        let name = field_name(&self.options, field, index, rule);
+                        let mut property = schema::type_schema(&field.ty);
+                        let docs = doc_string(&field.attrs);
+                        if let (Json::Object(pairs), false) = (&mut property, docs.is_empty()) {
+                            pairs.push(("description".to_string(), Json::String(docs)));
+                        }
+                        if schema::option_inner(&field.ty).is_none() {
+                            required.push(Json::string(name.as_str()));
+                        }
+                        properties.push((name, property));
+                    }
+                    vec![
+                        ("type", Json::string("object")),
+                        ("properties", Json::Object(properties)),
+                        ("required", Json::Array(required)),
+                    ]
+                }
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    match schema::type_schema(&fields.unnamed[0].ty) {
+                        Json::Object(pairs) => {
+                            return Json::Object(
+                                schema
+                                    .into_iter()
+                                    .map(|(key, value)| (key.to_string(), value))
+                                    .chain(pairs)
+                                    .collect(),
+                            );
+                        }
+                        _ => vec![],
+                    }
+                }
+                Fields::Unnamed(fields) => vec![
+                    ("type", Json::string("array")),
+                    (
+                        "items",
+                        Json::Array(
+                            fields
+                                .unnamed
+                                .iter()
+                                .map(|field| schema::type_schema(&field.ty))
+                                .collect(),
+                        ),
+                    ),
+                ],
+                Fields::Unit => vec![("type", Json::string("null"))],
+            },
+            Data::Enum(data) if data.variants.iter().all(|v| v.fields.is_empty()) => {
+                let rule = self.options.member_rule();
+                let names = data.variants.iter().map(|variant| {
+                    Json::String(
+                        self.options
+                            .member_name(&variant.ident, &variant.attrs, rule),
+                    )
+                });
+                vec![
+                    ("type", Json::string("string")),
+                    ("enum", Json::Array(names.collect())),
+                ]
+            }
+            Data::Enum(_) | Data::Union(_) => vec![],
+        };
+        schema.append(&mut shape);
+        Json::object(schema)
+    }
+}
+
+/// A canonical string for a list of fields, like `{a:u8,b:u16}` or `(u8,u16)`.
+fn fields_signature(fields: &Fields) -> String {
+    let types = fields.iter().map(|field| {
+        let ty = type_string(&field.ty);
+        match &field.ident {
+            Some(ident) => format!("{}:{}", unraw(ident), ty),
+            None => ty,
+        }
+    });
+    let types = types.collect::<Vec<_>>().join(",");
+    match fields {
+        Fields::Named(_) => format!("{{{}}}", types),
+        Fields::Unnamed(_) => format!("({})", types),
+        Fields::Unit => String::new(),
+    }
+}
+
+/// The 64-bit FNV-1a hash.
+///
+/// We can't use `std::hash`, because its hashers aren't guaranteed to
+/// give the same results between Rust releases.
+///
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Generate the `FieldDescriptor` list for a `StructDescriptor`.
+fn field_descriptors(
+    options: &ContainerOptions,
+    fields: &Fields,
+    field_options: &[FieldOptions],
+) -> TokenStream {
+    let krate = &options.crate_path;
+    let fields = fields.iter().zip(field_options).enumerate();
+    let fields = fields.map(|(index, (field, field_options))| {
+        let name = field_name(options, field, index, options.member_rule());
+        let type_name = type_string(&field.ty);
+        let label = option_str(&field_options.label);
+        let doc = option_str(&field_options.doc);
+        quote! {
+            #krate::FieldDescriptor {
+                name: #name,
+                type_name: #type_name,
+                label: #label,
+                doc: #doc,
+            }
+        }
+    });
+    quote! { &[#(#fields),*] }
+}
+
+/// Emit the `DescribeStruct` impl.
+impl ToTokens for DescribeInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        // Retrieve the Ident that is the struct name
+        let name = &self.input.ident;
+
+        // The name as a string literal.
+        let name_str = unraw(name);
+
+        // Generic types are reported along with the names of their generic
+        // parameters, e.g. `Buf<N>`. Renaming only applies to the type name.
+        let params = generic_params(&self.input.generics);
+        let reported_name = self.options.type_name(&name_str) + &params;
+        let name_str = name_str + &params;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+
+        let description = &self.description;
+        let krate = &self.options.crate_path;
+
+        // Fields and enum variants are counted by different methods; the
+        // trait provides a default of zero for whichever one doesn't apply.
+        // Unions are treated like structs with named fields.
+        let counts = match &self.input.data {
+            Data::Struct(data) => {
+                let count = data.fields.len();
+                quote! {
+                    fn field_count(&self) -> usize {
+                        #count
+                    }
+                }
+            }
+            Data::Enum(data) => {
+                let count = data.variants.len();
+                quote! {
+                    fn variant_count(&self) -> usize {
+                        #count
+                    }
+                }
+            }
+            Data::Union(data) => {
+                let count = data.fields.named.len();
+                quote! {
+                    fn field_count(&self) -> usize {
+                        #count
+                    }
+                }
+            }
+        };
+
+        let variants = match &self.input.data {
+            Data::Enum(data) => variant_methods(&self.options, data),
+            _ => quote! {},
+        };
+
+        // Enum variants can have fields with the same name, so only
+        // struct and union fields are listed.
+        let field_docs = match &self.input.data {
+            Data::Enum(_) => quote! {},
+            _ => {
+                let fields = self.fields();
+                let docs = fields.iter().enumerate().map(|(index, field)| {
+                    let name = field_name(&self.options, field, index, self.options.member_rule());
+                    let doc = doc_string(&field.attrs);
+                    quote! { (#name, #doc) }
+                });
+                quote! {
+                    fn field_docs(&self) -> &'static [(&'static str, &'static str)] {
+                        const DOCS: &[(&str, &str)] = &[#(#docs),*];
+                        DOCS
+                    }
+                }
+            }
+        };
+
+        // Offsets are computed once, in an inherent const; enum variant
+        // fields can't be named by `offset_of!`, so enums report none.
+        // Neither can anything before Rust 1.77, so a crate with an older
+        // `rust-version` gets the trait's default, with no offsets.
+        let (offsets, field_offsets) =
+            match &self.input.data {
+                Data::Enum(_) => (quote! {}, quote! {}),
+                _ if !msrv::at_least(77) => (quote! {}, quote! {}),
+                _ => {
+                    let members = self.fields().into_iter().enumerate().map(|(index, field)| {
+                        match field.ident {
+                            Some(ident) => quote! { #ident },
+                            None => Index::from(index).into_token_stream(),
+                        }
+                    });
+                    (
+                        quote! {
+                            fn field_offsets(&self) -> &'static [usize] {
+                                Self::FIELD_OFFSETS
+                            }
+                        },
+                        quote! {
+                            /// The byte offset of each field, in declaration order.
+                            pub const FIELD_OFFSETS: &'static [usize] =
+                                &[#(::core::mem::offset_of!(Self, #members)),*];
+                        },
+                    )
+                }
+            };
+
+        let (kind, fields) = match &self.input.data {
+            Data::Struct(data) => (
+                quote! { Struct },
+                field_descriptions(
+                    &self.options,
+                    &data.fields,
+                    &self.field_options,
+                    self.options.member_rule(),
+                    true,
+                ),
+            ),
+            Data::Enum(_) => (quote! { Enum }, quote! { #krate::__private::alloc::vec![] }),
+            Data::Union(data) => (
+                quote! { Union },
+                field_descriptions(
+                    &self.options,
+                    &Fields::Named(data.fields.clone()),
+                    &self.field_options,
+                    self.options.member_rule(),
+                    false,
+                ),
+            ),
+        };
+
+        // Every variant is described, not just the current one, so there's
+        // no value to find nested descriptions in.
+        let variant_descriptions = match &self.input.data {
+            Data::Enum(data) => {
+                let variants = data.variants.iter().zip(&self.variant_field_options).map(
+                    |(variant, field_options)| {
+                        let name = self.options.member_name(
+                            &variant.ident,
+                            &variant.attrs,
+                            self.options.member_rule(),
+                        );
+                        let shape = match &variant.fields {
+                            Fields::Named(_) => quote! { Struct },
+                            Fields::Unnamed(_) => quote! { Tuple },
+                            Fields::Unit => quote! { Unit },
+                        };
+                        let fields = field_descriptions(
+                            &self.options,
+                            &variant.fields,
+                            field_options,
+                            self.options.variant_field_rule(variant),
+                            false,
+                        );
+                        quote! {
+                            #krate::VariantDescription {
+                                name: #name,
+                                shape: #krate::VariantShape::#shape,
+                                fields: #fields,
+                            }
+                        }
+                    },
+                );
+                quote! { #krate::__private::alloc::vec![#(#variants),*] }
+            }
+            _ => quote! { #krate::__private::alloc::vec![] },
+        };
+
+        // Structs are visited through `self`; enums have to match each
+        // variant to bind its fields.
+        let visit = match &self.input.data {
+            Data::Struct(data) => {
+                let values = self_fields(&data.fields);
+                visit_fields(
+                    &self.options,
+                    &data.fields,
+                    self.options.member_rule(),
+                    values,
+                )
+            }
+            Data::Enum(data) => {
+                let arms = data.variants.iter().map(|variant| {
+                    let (pattern, bindings) = variant_pattern(variant);
+                    let values = bindings.iter().map(|binding| quote! { #binding });
+                    let rule = self.options.variant_field_rule(variant);
+                    let visits = visit_fields(&self.options, &variant.fields, rule, values);
+                    quote! {
+                        #pattern => { #visits }
+                    }
+                });
+                // A reference to an empty enum is still inhabited, as far
+                // as an empty match is concerned; match the value instead.
+                // `variant_methods` does the same.
+                if data.variants.is_empty() {
+                    quote! { match *self {} }
+                } else {
+                    quote! {
+                        match self {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+            Data::Union(_) => quote! {},
+        };
+
+        // Like `visit_fields`, but matching on the field name. Reading a
+        // union field isn't safe, so unions keep the default.
+        let get_field = match &self.input.data {
+            Data::Struct(data) => {
+                let values = self_fields(&data.fields);
+                let arms = any_field_arms(
+                    &self.options,
+                    &self.input.generics,
+                    &data.fields,
+                    self.options.member_rule(),
+                    values,
+                );
+                quote! {
+                    match name {
+                        #(#arms)*
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+            Data::Enum(data) => {
+                let arms = data.variants.iter().map(|variant| {
+                    let (pattern, bindings) = variant_pattern(variant);
+                    let values = bindings.iter().map(|binding| quote! { #binding });
+                    let rule = self.options.variant_field_rule(variant);
+                    let field_arms = any_field_arms(
+                        &self.options,
+                        &self.input.generics,
+                        &variant.fields,
+                        rule,
+                        values,
+                    );
+                    quote! {
+                        #pattern => match name {
+                            #(#field_arms)*
+                            _ => ::core::option::Option::None,
+                        },
+                    }
+                });
+                if data.variants.is_empty() {
+                    quote! { match *self {} }
+                } else {
+                    quote! {
+                        match self {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+            Data::Union(_) => quote! { ::core::option::Option::None },
+        };
+
+        // The same fields as `get_field`, matched through `&mut self`.
+        let not_found = quote! {
+            _ => ::core::result::Result::Err(#krate::FieldError::NotFound(name.into())),
+        };
+        let set_field = match &self.input.data {
+            Data::Struct(data) => {
+                let places =
+                    data.fields
+                        .iter()
+                        .enumerate()
+                        .map(|(index, field)| match &field.ident {
+                            Some(ident) => quote! { self.#ident },
+                            None => {
+                                let index = Index::from(index);
+                                quote! { self.#index }
+                            }
+                        });
+                let arms = set_field_arms(
+                    &self.options,
+                    &self.input.generics,
+                    &data.fields,
+                    self.options.member_rule(),
+                    places,
+                );
+                quote! {
+                    match name {
+                        #(#arms)*
+                        #not_found
+                    }
+                }
+            }
+            Data::Enum(data) => {
+                let arms = data.variants.iter().map(|variant| {
+                    let (pattern, bindings) = variant_pattern(variant);
+                    let places = bindings.iter().map(|binding| quote! { *#binding });
+                    let rule = self.options.variant_field_rule(variant);
+                    let field_arms = set_field_arms(
+                        &self.options,
+                        &self.input.generics,
+                        &variant.fields,
+                        rule,
+                        places,
+                    );
+                    quote! {
+                        #pattern => match name {
+                            #(#field_arms)*
+                            #not_found
+                        },
+                    }
+                });
+                if data.variants.is_empty() {
+                    quote! { match *self {} }
+                } else {
+                    quote! {
+                        match self {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+            Data::Union(_) => quote! {
+                ::core::result::Result::Err(#krate::FieldError::NotFound(name.into()))
+            },
+        };
+
+        let structure_version = self.structure_version();
+
+        // The span name has to be a literal, so this can't be done by a
+        // default method in the trait; that's why the macro crate needs to
+        // know whether the `tracing` feature is on.
+        let trace_span = if cfg!(feature = "tracing") {
+            let fields = if self.options.instrument {
+                quote! { , type_name = #reported_name, type_path = ::core::concat!(::core::module_path!(), "::", #name_str) }
+            } else {
+                quote! {}
+            };
+            quote! {
+                fn trace_span(&self) -> #krate::__private::tracing::Span {
+                    #krate::__private::tracing::info_span!(#reported_name #fields)
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        // Generate the output tokens.
+        // `module_path!()` is expanded at the derive site, so it names the
+        // module containing the struct, not this crate.
+        tokens.extend(quote! {
+            impl #impl_generics #krate::DescribeStruct for #name #ty_generics #where_clause {
+                fn struct_name(&self) -> &'static str {
+                    #reported_name
+                }
+
+                fn type_path(&self) -> &'static str {
+                    ::core::concat!(::core::module_path!(), "::", #name_str)
+                }
+
+                fn description(&self) -> &'static str {
+                    #description
+                }
+
+                #counts
+
+                #variants
+
+                fn structure_version(&self) -> u64 {
+                    #structure_version
+                }
+
+                fn size_of(&self) -> usize {
+                    ::core::mem::size_of::<Self>()
+                }
+
+                fn align_of(&self) -> usize {
+                    ::core::mem::align_of::<Self>()
+                }
+
+                #offsets
+
+                #field_docs
+
+                #[allow(unused_variables)]
+                fn visit_fields(&self, visitor: &mut dyn #krate::DescribeVisitor) {
+                    #visit
+                }
+
+                #[allow(unused_variables)]
+                fn get_field(&self, name: &str) -> ::core::option::Option<&dyn ::core::any::Any> {
+                    #get_field
+                }
+
+                #[allow(unused_variables)]
+                fn set_field(
+                    &mut self,
+                    name: &str,
+                    value: #krate::__private::alloc::boxed::Box<dyn ::core::any::Any>,
+                ) -> ::core::result::Result<(), #krate::FieldError> {
+                    #set_field
+                }
+
+                #trace_span
+
+                fn describe(&self) -> #krate::Description {
+                    #krate::Description {
+                        name: #reported_name,
+                        kind: #krate::Kind::#kind,
+                        fields: #fields,
+                        variants: #variant_descriptions,
+                    }
+                }
+            }
+        });
+
+        // The descriptor is an inherent associated const, rather than part
+        // of the trait, so that the trait stays usable as `dyn DescribeStruct`.
+        // So is `json_schema`, which has no `self`.
+        let field_descriptors =
+            field_descriptors(&self.options, &self.fields(), &self.field_options);
+        let json_schema = if self.options.json_schema {
+            let schema = self.json_schema(&reported_name).to_string();
+            quote! {
+                /// A JSON Schema describing how this type is serialized.
+                pub fn json_schema() -> &'static str {
+                    #schema
+                }
+            }
+        } else {
+            quote! {}
+        };
+        tokens.extend(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// A description of this type that can be used in const contexts.
+                pub const DESCRIPTOR: #krate::StructDescriptor = #krate::StructDescriptor {
+                    name: #reported_name,
+                    fields: #field_descriptors,
+                };
+
+                #field_offsets
+
+                #json_schema
+            }
+        });
+
+        // Register the type, so `abc::registry` can find it at runtime.
+        // A generic type has no single concrete type to register.
+        if cfg!(feature = "registry") && self.input.generics.params.is_empty() {
+            tokens.extend(quote! {
+                #krate::__private::inventory::submit! {
+                    #krate::registry::TypeInfo {
+                        name: #reported_name,
+                        type_path: ::core::concat!(::core::module_path!(), "::", #name_str),
+                        description: #description,
+                    }
+                }
+            });
+        }
+
+        if self.options.display {
+            tokens.extend(quote! {
+                impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.pad(#reported_name)
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_doc_string() {
+        let described: DescribeInput = syn::parse_str(
+            r#"
+            /// A short summary.
+            ///
+            /// More detail.
+            #[derive(Debug)]
+            struct Foo;
+            "#,
+        )
+        .unwrap();
+        assert_eq!(described.description, "A short summary.\n\nMore detail.");
+
+        let described: DescribeInput = syn::parse_str("struct Foo;").unwrap();
+        assert_eq!(described.description, "");
+    }
+
+    #[test]
+    fn parse_options() {
+        let described: DescribeInput = syn::parse_str("struct Foo;").unwrap();
+        assert!(!described.options.display);
+
+        let described: DescribeInput = syn::parse_str("#[describe(display)] struct Foo;").unwrap();
+        assert!(described.options.display);
+
+        syn::parse_str::<DescribeInput>("#[describe(bogus)] struct Foo;").unwrap_err();
+
+        let described: DescribeInput = syn::parse_str(
+            "struct Foo { #[describe(label = \"Retry count\", doc = \"How many\")] a: u8, b: u8 }",
+        )
+        .unwrap();
+        assert_eq!(
+            described.field_options[0].label.as_deref(),
+            Some("Retry count")
+        );
+        assert_eq!(described.field_options[0].doc.as_deref(), Some("How many"));
+        assert_eq!(described.field_options[1].label, None);
+        syn::parse_str::<DescribeInput>("struct Foo { #[describe(display)] a: u8 }").unwrap_err();
+        syn::parse_str::<DescribeInput>("enum Foo { A { #[describe(label = 1)] a: u8 } }")
+            .unwrap_err();
+
+        let described: DescribeInput =
+            syn::parse_str("#[describe(rename_all = \"kebab-case\")] struct Foo;").unwrap();
+        assert_eq!(described.options.rename_all, Some(RenameRule::Kebab));
+        syn::parse_str::<DescribeInput>("#[describe(rename_all = \"Title Case\")] struct Foo;")
+            .unwrap_err();
+
+        let described: DescribeInput = syn::parse_str("struct Foo;").unwrap();
+        assert_eq!(described.options.crate_path, parse_quote!(::abc));
+
+        syn::parse_str::<DescribeInput>("struct OhNo;").unwrap_err();
+        syn::parse_str::<DescribeInput>("#[describe(deny_names(\"Temp\"))] struct Temp;")
+            .unwrap_err();
+        syn::parse_str::<DescribeInput>("#[describe(deny_names(\"Temp\"))] struct OhNo;").unwrap();
+        syn::parse_str::<DescribeInput>("#[describe(deny_names(Temp))] struct Foo;").unwrap_err();
+
+        let described: DescribeInput =
+            syn::parse_str("#[describe(crate = \"::my_facade\")] struct Foo;").unwrap();
+        assert_eq!(described.options.crate_path, parse_quote!(::my_facade));
+        syn::parse_str::<DescribeInput>("#[describe = \"display\"] struct Foo;").unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "describe_regex")]
+    fn parse_name_pattern() {
+        let pattern = "#[describe(require_name_matches = \"^[A-Z][A-Za-z0-9]*Request$\")]";
+        syn::parse_str::<DescribeInput>(&format!("{} struct GetRequest;", pattern)).unwrap();
+        syn::parse_str::<DescribeInput>(&format!("{} struct r#GetRequest;", pattern)).unwrap();
+        let err =
+            syn::parse_str::<DescribeInput>(&format!("{} struct GetReply;", pattern)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type name `GetReply` doesn't match the required pattern `^[A-Z][A-Za-z0-9]*Request$`"
+        );
+        syn::parse_str::<DescribeInput>("#[describe(require_name_matches = \"(\")] struct Foo;")
+            .unwrap_err();
+    }
+
+    #[test]
+    #[cfg(not(feature = "describe_regex"))]
+    fn name_pattern_needs_feature() {
+        let err = syn::parse_str::<DescribeInput>(
+            "#[describe(require_name_matches = \"Request$\")] struct GetRequest;",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`require_name_matches` needs abc's `describe_regex` feature"
+        );
+    }
+
+    #[test]
+    fn parse_serde_names() {
+        let names = SerdeNames::from_attributes(&[
+            parse_quote!(#[serde(rename = "foo", default)]),
+            parse_quote!(#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]),
+        ]);
+        assert_eq!(names.rename, Some("foo".to_string()));
+        assert_eq!(names.rename_all, Some(RenameRule::Camel));
+
+        // Serde attributes are only read if asked.
+        let described: DescribeInput =
+            syn::parse_str("#[serde(rename = \"foo\")] struct Foo;").unwrap();
+        assert_eq!(described.options.type_name("Foo"), "Foo");
+        let described: DescribeInput =
+            syn::parse_str("#[describe(use_serde_names)] #[serde(rename = \"foo\")] struct Foo;")
+                .unwrap();
+        assert_eq!(described.options.type_name("Foo"), "foo");
+
+        // Malformed serde attributes are left for serde to report.
+        let names = SerdeNames::from_attributes(&[parse_quote!(#[serde(rename = 5)])]);
+        assert_eq!(names.rename, None);
+    }
+
+    #[test]
+    fn generic_names() {
+        let check = |input: &str, expected: &str| {
+            let described: DescribeInput = syn::parse_str(input).unwrap();
+            assert_eq!(generic_params(&described.input.generics), expected);
+        };
+        check("struct Foo;", "");
+        check("struct Buf<const N: usize>([u8; N]);", "<N>");
+        check(
+            "struct Foo<'a, T: Clone + 'a, const N: usize> where T: Copy { x: &'a [T; N] }",
+            "<'a, T, N>",
+        );
+    }
+
+    #[test]
+    fn structure_versions() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+
+        let version = |input: &str| {
+            let described: DescribeInput = syn::parse_str(input).unwrap();
+            described.structure_version()
+        };
+        let base = version("struct Foo { a: u8, b: Vec<u16> }");
+        assert_eq!(base, fnv1a(b"Foo{a:u8,b:Vec<u16>}"));
+        // Docs, attributes, and formatting don't matter.
+        assert_eq!(
+            base,
+            version("/// Hi\n#[derive(Debug)] struct Foo { a: u8, b: Vec < u16 >, }")
+        );
+        assert_ne!(base, version("struct Foo { a: u8, b: Vec<u32> }"));
+        assert_ne!(base, version("struct Foo { b: Vec<u16>, a: u8 }"));
+        assert_ne!(base, version("struct Bar { a: u8, b: Vec<u16> }"));
+
+        assert_eq!(
+            version("enum E { A, B(u8), C { x: i32 } }"),
+            fnv1a(b"E{A,B(u8),C{x:i32}}")
+        );
+    }
+
+    #[test]
+    fn debug_expand() {
+        let out_dir = std::env::temp_dir().join(format!("abc_debug_expand_{}", std::process::id()));
+        let described: DescribeInput =
+            syn::parse_str("#[describe(debug_expand)] struct r#Dumped;").unwrap();
+        let expanded = described.to_token_stream();
+        described.debug_expand(&expanded, Some(&out_dir));
+        let path = out_dir.join("abc_expansions").join("Dumped.rs");
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, expanded.to_string());
+        std::fs::remove_dir_all(&out_dir).unwrap();
+
+        // Without the option, nothing is written.
+        let described: DescribeInput = syn::parse_str("struct Quiet;").unwrap();
+        described.debug_expand(&expanded, Some(&out_dir));
+        assert!(!out_dir.exists());
+    }
+
+    #[test]
+    fn generic_field_types() {
+        let generics: Generics = syn::parse_str("<'a, T, const N: usize>").unwrap();
+        let check = |input: &str| {
+            let ty: Type = syn::parse_str(input).unwrap();
+            field_type_is_generic(&generics, &ty)
+        };
+        assert!(check("T"));
+        assert!(check("Vec<Option<T>>"));
+        assert!(check("&'a str"));
+        assert!(check("Box<dyn Fn() + 'a>"));
+        assert!(!check("&'static str"));
+        assert!(!check("[u8; N]"));
+        assert!(!check("Tee"));
+    }
+
+    #[test]
+    fn format_types() {
+        let check = |input: &str, expected: &str| {
+            let ty: Type = syn::parse_str(input).unwrap();
+            assert_eq!(type_string(&ty), expected);
+        };
+        check("u8", "u8");
+        check("Vec < u8 >", "Vec<u8>");
+        check(
+            "::std::collections::HashMap<String, Vec<u8>>",
+            "::std::collections::HashMap<String, Vec<u8>>",
+        );
+        check("&'static mut [u8; 4]", "&'static mut [u8; 4]");
+        check(
+            "(u8, Box<dyn Fn() -> u8 + Send>)",
+            "(u8, Box<dyn Fn() -> u8 + Send>)",
+        );
+        check("&'static [u8]", "&'static [u8]");
+        check("r#Loop<r#type>", "Loop<type>");
+    }
+
+    // This crate's `rust-version` is 1.60, older than `offset_of!`.
+    #[test]
+    fn old_rust_version() {
+        let described: DescribeInput = syn::parse_str("struct Foo { a: u8 }").unwrap();
+        let output = described.to_token_stream().to_string();
+        assert!(!output.contains("offset_of"), "{}", output);
+        assert!(!output.contains("FIELD_OFFSETS"), "{}", output);
+    }
+}
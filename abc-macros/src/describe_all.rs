@@ -0,0 +1,209 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Attribute, Item, ItemMod, Lit, Meta, NestedMeta, Path, Token};
+
+use crate::attr;
+
+/// The arguments to `#[describe_all(...)]`.
+///
+/// Example: `#[describe_all]` or `#[describe_all(crate = "my_facade::abc")]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct DescribeAllArgs {
+    // `crate = "..."`: where to find the derive, passed on to each item as
+    // `#[describe(crate = "...")]`.
+    crate_path: Option<Path>,
+}
+
+impl Parse for DescribeAllArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = DescribeAllArgs { crate_path: None };
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    let path = match &nv.lit {
+                        Lit::Str(s) => s.parse()?,
+                        lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                    };
+                    args.crate_path = Some(path);
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown describe_all option",
+                        &["crate"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a module with `#[describe_all]` applied.
+///
+/// Parsing adds the derive to every struct and enum in the module, and to
+/// those in any inline modules nested inside it.
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct DescribeAll {
+    module: ItemMod,
+}
+
+impl DescribeAll {
+    pub fn new(args: DescribeAllArgs, mut module: ItemMod) -> syn::parse::Result<Self> {
+        let content = match &mut module.content {
+            Some((_, content)) => content,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &module,
+                    "describe_all only works on inline modules (`mod name { ... }`)",
+                ))
+            }
+        };
+        add_derives(&args, content);
+        Ok(DescribeAll { module })
+    }
+}
+
+fn add_derives(args: &DescribeAllArgs, items: &mut [Item]) {
+    let krate = args
+        .crate_path
+        .clone()
+        .unwrap_or_else(|| parse_quote!(::abc));
+    for item in items {
+        let attrs = match item {
+            Item::Struct(item) => &mut item.attrs,
+            Item::Enum(item) => &mut item.attrs,
+            Item::Mod(ItemMod {
+                content: Some((_, content)),
+                ..
+            }) => {
+                add_derives(args, content);
+                continue;
+            }
+            _ => continue,
+        };
+        if already_derived(attrs) {
+            continue;
+        }
+        // The derive goes first, so that its helper attribute is allowed
+        // on everything after it.
+        let mut added: Vec<Attribute> = vec![parse_quote!(#[derive(#krate::DescribeStruct)])];
+        if let Some(path) = &args.crate_path {
+            let path = path.to_token_stream().to_string().replace(' ', "");
+            added.push(parse_quote!(#[describe(crate = #path)]));
+        }
+        attrs.splice(0..0, added);
+    }
+}
+
+/// Check whether an item already has `#[derive(DescribeStruct)]`.
+///
+/// A derive inside `#[cfg_attr(...)]` counts too: `#[cfg]` hasn't been
+/// evaluated yet when this runs, and adding a second derive would
+/// conflict with it whenever the condition holds.
+fn already_derived(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| derives_describe(&meta))
+}
+
+fn derives_describe(meta: &Meta) -> bool {
+    match meta {
+        Meta::List(list) if list.path.is_ident("derive") => list.nested.iter().any(|nested| {
+            matches!(nested, NestedMeta::Meta(Meta::Path(path))
+                if path.segments.last().map_or(false, |s| s.ident == "DescribeStruct"))
+        }),
+        // The first argument is the condition; the rest are attributes.
+        Meta::List(list) if list.path.is_ident("cfg_attr") => {
+            list.nested.iter().skip(1).any(|nested| match nested {
+                NestedMeta::Meta(meta) => derives_describe(meta),
+                NestedMeta::Lit(_) => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+impl ToTokens for DescribeAll {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.module.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn expand(args: TokenStream, module: TokenStream) -> String {
+        let args: DescribeAllArgs = syn::parse2(args).unwrap();
+        let module: ItemMod = syn::parse2(module).unwrap();
+        let expanded = DescribeAll::new(args, module).unwrap();
+        expanded.into_token_stream().to_string()
+    }
+
+    #[test]
+    fn adds_derives() {
+        let expanded = expand(
+            quote!(),
+            quote! {
+                mod messages {
+                    pub struct Ping;
+                    #[derive(Debug)]
+                    pub enum Reply { Pong }
+                    #[derive(abc::DescribeStruct)]
+                    #[describe(display)]
+                    pub struct Done;
+                    #[cfg_attr(feature = "describe", derive(Clone, DescribeStruct))]
+                    pub struct Maybe;
+                    fn helper() {}
+                    mod inner {
+                        struct Nested;
+                    }
+                }
+            },
+        );
+        let derive = quote!(#[derive(::abc::DescribeStruct)]).to_string();
+        assert_eq!(expanded.matches(&derive).count(), 3);
+        assert!(expanded.contains(&format!("{} pub struct Ping", derive)));
+        assert!(expanded.contains(&format!("{} # [derive (Debug)] pub enum Reply", derive)));
+        assert!(expanded.contains(&format!("{} struct Nested", derive)));
+    }
+
+    #[test]
+    fn crate_path() {
+        let expanded = expand(
+            quote!(crate = "facade::abc"),
+            quote!(
+                mod m {
+                    struct A;
+                }
+            ),
+        );
+        let expected = quote! {
+            mod m {
+                #[derive(facade::abc::DescribeStruct)]
+                #[describe(crate = "facade::abc")]
+                struct A;
+            }
+        };
+        assert_eq!(expanded, expected.to_string());
+    }
+
+    #[test]
+    fn bad_input() {
+        syn::parse2::<DescribeAllArgs>(quote!(display)).unwrap_err();
+        let module: ItemMod = syn::parse2(quote!(
+            mod elsewhere;
+        ))
+        .unwrap();
+        let args = DescribeAllArgs { crate_path: None };
+        DescribeAll::new(args, module).unwrap_err();
+    }
+}
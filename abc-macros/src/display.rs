@@ -0,0 +1,280 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// This is the entire input to `#[derive(Display)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct DisplayInput {
+    input: DeriveInput,
+    // `#[display("...")]` on the struct or enum.
+    format: Option<LitStr>,
+    // `#[display("...")]` on each enum variant.
+    variant_formats: Vec<Option<LitStr>>,
+}
+
+/// Find the format string in a `#[display("...")]` attribute, or another
+/// attribute with the same syntax.
+fn display_format(attrs: &[Attribute], name: &str) -> syn::parse::Result<Option<LitStr>> {
+    let mut format = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        if format.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("duplicate {} attribute", name),
+            ));
+        }
+        format = Some(attr.parse_args::<LitStr>()?);
+    }
+    Ok(format)
+}
+
+impl DisplayInput {
+    /// The struct or enum the impl is for.
+    #[cfg(feature = "error")]
+    pub fn input(&self) -> &DeriveInput {
+        &self.input
+    }
+
+    /// Read the format strings from `#[name("...")]` attributes.
+    ///
+    /// `#[derive(Error)]` uses this too, with `#[error("...")]`.
+    ///
+    pub fn with_attribute(input: DeriveInput, name: &str) -> syn::parse::Result<Self> {
+        let format = display_format(&input.attrs, name)?;
+        let variant_formats = match &input.data {
+            Data::Struct(_) => {
+                if format.is_none() {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        format!("expected a format string, like `#[{}(\"{{name}}\")]`", name),
+                    ));
+                }
+                Vec::new()
+            }
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .map(|variant| display_format(&variant.attrs, name))
+                .collect::<syn::parse::Result<_>>()?,
+            Data::Union(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Display can't be derived for unions",
+                ))
+            }
+        };
+        Ok(DisplayInput {
+            input,
+            format,
+            variant_formats,
+        })
+    }
+}
+
+/// Parse the input to `#[derive(Display)]`.
+impl Parse for DisplayInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        DisplayInput::with_attribute(input.parse()?, "display")
+    }
+}
+
+/// Rename the positional arguments in a format string.
+///
+/// `{0}` can't be passed as a named argument, so tuple fields are
+/// renamed to `{_0}`. This returns the new format string, and the names
+/// of the arguments it uses.
+///
+fn rewrite_format(format: &str) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut args = Vec::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            out.push(chars.next().unwrap());
+            continue;
+        }
+        let mut arg = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '}' || c == ':' {
+                break;
+            }
+            arg.push(c);
+            chars.next();
+        }
+        let arg = arg.trim().to_string();
+        if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_digit()) {
+            out.push('_');
+        }
+        out.push_str(&arg);
+        if !arg.is_empty() {
+            let arg = if arg.chars().all(|c| c.is_ascii_digit()) {
+                format!("_{}", arg)
+            } else {
+                arg
+            };
+            if !args.contains(&arg) {
+                args.push(arg);
+            }
+        }
+    }
+    (out, args)
+}
+
+/// Generate a `write!` of `format`, where `fields` are the bindings that
+/// arguments can refer to.
+///
+/// `fields` pairs each argument name with the expression for that field.
+/// Anything else in the format string is left for the compiler, which
+/// will report it as an error.
+///
+fn write_format(format: &LitStr, fields: &[(String, TokenStream)]) -> TokenStream {
+    let (rewritten, used) = rewrite_format(&format.value());
+    let rewritten = LitStr::new(&rewritten, format.span());
+    let args = fields
+        .iter()
+        .filter(|(name, _)| used.contains(name))
+        .map(|(name, value)| {
+            let name = format_ident!("{}", name);
+            quote! { , #name = #value }
+        });
+    quote! {
+        ::core::write!(__formatter, #rewritten #(#args)*)
+    }
+}
+
+/// The format argument name for each field, and the binding to use for
+/// it in a pattern.
+fn field_names(fields: &Fields) -> Vec<(String, Ident)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => (ident.unraw().to_string(), ident.clone()),
+            None => (format!("_{}", index), format_ident!("_{}", index)),
+        })
+        .collect()
+}
+
+/// Emit the `Display` impl.
+impl ToTokens for DisplayInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+
+        let body = match &self.input.data {
+            Data::Struct(data) => {
+                let fields: Vec<(String, TokenStream)> = field_names(&data.fields)
+                    .into_iter()
+                    .zip(&data.fields)
+                    .enumerate()
+                    .map(|(index, ((arg, _), field))| {
+                        let value = match &field.ident {
+                            Some(ident) => quote! { self.#ident },
+                            None => {
+                                let index = syn::Index::from(index);
+                                quote! { self.#index }
+                            }
+                        };
+                        (arg, value)
+                    })
+                    .collect();
+                // There's always a format for a struct; see `parse`.
+                write_format(self.format.as_ref().unwrap(), &fields)
+            }
+            Data::Enum(data) => {
+                let arms =
+                    data.variants
+                        .iter()
+                        .zip(&self.variant_formats)
+                        .map(|(variant, format)| {
+                            let variant_name = &variant.ident;
+                            let names = field_names(&variant.fields);
+                            let bindings = names.iter().map(|(_, binding)| binding);
+                            let pattern = match &variant.fields {
+                                Fields::Named(_) => quote! { { #(#bindings),* } },
+                                Fields::Unnamed(_) => quote! { ( #(#bindings),* ) },
+                                Fields::Unit => quote! {},
+                            };
+                            let fields: Vec<(String, TokenStream)> = names
+                                .iter()
+                                .map(|(arg, binding)| (arg.clone(), quote! { #binding }))
+                                .collect();
+                            // Without a format of its own, a variant uses the
+                            // enum's, or else its name.
+                            let write = match format.as_ref().or(self.format.as_ref()) {
+                                Some(format) => write_format(format, &fields),
+                                None => {
+                                    let variant_str = variant_name.unraw().to_string();
+                                    quote! { __formatter.write_str(#variant_str) }
+                                }
+                            };
+                            quote! {
+                                Self::#variant_name #pattern => #write,
+                            }
+                        });
+                if data.variants.is_empty() {
+                    quote! { match *self {} }
+                } else {
+                    quote! {
+                        match self {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+            Data::Union(_) => unreachable!("rejected while parsing"),
+        };
+
+        tokens.extend(quote! {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #body
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_formats() {
+        let check = |format: &str, expected: &str, args: &[&str]| {
+            let (rewritten, used) = rewrite_format(format);
+            assert_eq!(rewritten, expected);
+            assert_eq!(used, args);
+        };
+        check("{name} ({id})", "{name} ({id})", &["name", "id"]);
+        check("{0}: {1:>4} {0}", "{_0}: {_1:>4} {_0}", &["_0", "_1"]);
+        check("{{literal}} {}", "{{literal}} {}", &[]);
+        check("{ name }", "{name}", &["name"]);
+    }
+
+    #[test]
+    fn parse_formats() {
+        let input: DisplayInput =
+            syn::parse_str("#[display(\"{a}\")] struct Foo { a: u8 }").unwrap();
+        assert_eq!(input.format.unwrap().value(), "{a}");
+
+        let input: DisplayInput =
+            syn::parse_str("enum Foo { #[display(\"lost\")] Lost, Other }").unwrap();
+        assert!(input.format.is_none());
+        assert_eq!(input.variant_formats[0].as_ref().unwrap().value(), "lost");
+        assert!(input.variant_formats[1].is_none());
+
+        syn::parse_str::<DisplayInput>("struct Foo { a: u8 }").unwrap_err();
+        syn::parse_str::<DisplayInput>("#[display(name)] struct Foo;").unwrap_err();
+        syn::parse_str::<DisplayInput>("#[display(\"a\")] #[display(\"b\")] struct Foo;")
+            .unwrap_err();
+        syn::parse_str::<DisplayInput>("#[display(\"a\")] union Foo { a: u8 }").unwrap_err();
+    }
+}
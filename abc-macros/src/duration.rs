@@ -0,0 +1,197 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Lit, LitStr};
+
+/// The units that `duration!` accepts, and their size in nanoseconds.
+const UNITS: &[(&str, u128)] = &[
+    ("ns", 1),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("min", 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+];
+
+/// This is the entire input to `duration!`.
+///
+/// Example: `duration!("1h 30m")` or `duration!(1h 30m)` or `duration!(250ms)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct DurationInput {
+    nanos: u128,
+}
+
+/// The length of one amount, like `1.5h`, in nanoseconds.
+fn amount(number: &str, unit: &str) -> Result<u128, String> {
+    let scale = match UNITS.iter().find(|(name, _)| *name == unit) {
+        Some((_, scale)) => *scale,
+        None if unit.is_empty() => {
+            return Err(format!("`{}` needs a unit, like `{}s`", number, number));
+        }
+        None => {
+            return Err(format!(
+                "unknown unit `{}`; expected one of ns, us, ms, s, m, h or d",
+                unit
+            ))
+        }
+    };
+    let (whole, fraction) = match number.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (number, ""),
+    };
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit() || c == '_');
+    if !whole.starts_with(|c: char| c.is_ascii_digit()) || !is_digits(whole) || !is_digits(fraction)
+    {
+        return Err(format!("`{}` is not a number", number));
+    }
+    let too_long = || "this duration is too long".to_string();
+    let parse = |digits: &str| -> Result<u128, String> {
+        digits
+            .chars()
+            .filter(|&c| c != '_')
+            .try_fold(0u128, |total, c| {
+                total
+                    .checked_mul(10)?
+                    .checked_add(c.to_digit(10).unwrap() as u128)
+            })
+            .ok_or_else(too_long)
+    };
+    let mut nanos = parse(whole)?.checked_mul(scale).ok_or_else(too_long)?;
+    let fraction: String = fraction.chars().filter(|&c| c != '_').collect();
+    if !fraction.is_empty() {
+        let denominator = 10u128
+            .checked_pow(fraction.len() as u32)
+            .ok_or_else(|| format!("`{}` has too many digits", number))?;
+        let numerator = parse(&fraction)?.checked_mul(scale).ok_or_else(too_long)?;
+        if numerator % denominator != 0 {
+            return Err(format!(
+                "`{}{}` isn't a whole number of nanoseconds",
+                number, unit
+            ));
+        }
+        nanos += numerator / denominator;
+    }
+    Ok(nanos)
+}
+
+/// Parse a duration string, like `"1h 30m"`, into nanoseconds.
+fn parse_str(text: &str) -> Result<u128, String> {
+    let mut total: u128 = 0;
+    let mut rest = text.trim_start();
+    if rest.is_empty() {
+        return Err("expected a duration, like `1h 30m`".to_string());
+    }
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_end);
+        if number.is_empty() {
+            return Err(format!("expected a number, found `{}`", after));
+        }
+        let unit_end = after
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+        total = total
+            .checked_add(amount(number, unit)?)
+            .ok_or("this duration is too long")?;
+        rest = after.trim_start();
+    }
+    Ok(total)
+}
+
+/// Parse the input to `duration!`.
+impl Parse for DurationInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let error = |span: Span| move |message: String| syn::Error::new(span, message);
+        let mut nanos: u128 = 0;
+        if input.peek(LitStr) {
+            let lit: LitStr = input.parse()?;
+            nanos = parse_str(&lit.value()).map_err(error(lit.span()))?;
+        } else {
+            // Literals with a unit as their suffix, like `1h 30m`.
+            if input.is_empty() {
+                return Err(input.error("expected a duration, like `1h 30m`"));
+            }
+            while !input.is_empty() {
+                let lit: Lit = input.parse()?;
+                let (digits, suffix) = match &lit {
+                    Lit::Int(int) => (int.base10_digits(), int.suffix()),
+                    Lit::Float(float) => (float.base10_digits(), float.suffix()),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &lit,
+                            "expected a duration, like `1h`",
+                        ))
+                    }
+                };
+                let amount = amount(digits, suffix).map_err(error(lit.span()))?;
+                nanos = nanos
+                    .checked_add(amount)
+                    .ok_or_else(|| syn::Error::new(lit.span(), "this duration is too long"))?;
+            }
+        }
+        if nanos / 1_000_000_000 > u64::MAX as u128 {
+            return Err(input.error("this duration is too long"));
+        }
+        Ok(DurationInput { nanos })
+    }
+}
+
+/// Emit a `Duration`.
+impl ToTokens for DurationInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let secs = (self.nanos / 1_000_000_000) as u64;
+        let nanos = (self.nanos % 1_000_000_000) as u32;
+        tokens.extend(quote! {
+            ::core::time::Duration::new(#secs, #nanos)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_strings() {
+        const SEC: u128 = 1_000_000_000;
+        assert_eq!(parse_str("1h 30m"), Ok(90 * 60 * SEC));
+        assert_eq!(parse_str("1h30m"), Ok(90 * 60 * SEC));
+        assert_eq!(parse_str(" 250ms "), Ok(SEC / 4));
+        assert_eq!(parse_str("1.5s"), Ok(3 * SEC / 2));
+        assert_eq!(parse_str("2d 1_000ns"), Ok(2 * 24 * 3600 * SEC + 1000));
+        assert_eq!(parse_str("10µs 5us"), Ok(15_000));
+        assert_eq!(parse_str("1min"), Ok(60 * SEC));
+
+        assert!(parse_str("").is_err());
+        assert!(parse_str("5").unwrap_err().contains("needs a unit"));
+        assert!(parse_str("5 s").unwrap_err().contains("needs a unit"));
+        assert!(parse_str("5y").unwrap_err().contains("unknown unit `y`"));
+        assert!(parse_str("h").is_err());
+        assert!(parse_str(".5s").is_err());
+        assert!(parse_str("1.2.3s").is_err());
+        assert!(parse_str("1.5ns").is_err());
+    }
+
+    #[test]
+    fn parse_input() {
+        let input: DurationInput = syn::parse_str("1h 30m").unwrap();
+        assert_eq!(input.nanos, 90 * 60 * 1_000_000_000);
+        let input: DurationInput = syn::parse_str("2.5s").unwrap();
+        assert_eq!(input.nanos, 2_500_000_000);
+        let input: DurationInput = syn::parse_str("\"250ms\"").unwrap();
+        assert_eq!(input.nanos, 250_000_000);
+
+        syn::parse_str::<DurationInput>("").unwrap_err();
+        syn::parse_str::<DurationInput>("5").unwrap_err();
+        syn::parse_str::<DurationInput>("5kg").unwrap_err();
+        syn::parse_str::<DurationInput>("'x'").unwrap_err();
+        syn::parse_str::<DurationInput>("\"99999999999999999999999d\"").unwrap_err();
+    }
+}
@@ -0,0 +1,326 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Ident, LitInt, Token, Visibility};
+
+/// One flag, and its bit.
+///
+/// Example: `Read: 1 << 0` or `Write: 0x2`
+///
+#[cfg_attr(test, derive(Debug))]
+struct Flag {
+    attributes: Vec<Attribute>,
+    name: Ident,
+    mask: u128,
+}
+
+impl Parse for Flag {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let value: LitInt = input.parse()?;
+        let mut mask = value.base10_parse::<u128>()?;
+        // A mask may be written as a shift, like `1 << 3`.
+        if input.peek(Token![<<]) {
+            input.parse::<Token![<<]>()?;
+            let shift_lit: LitInt = input.parse()?;
+            let shift = shift_lit.base10_parse::<u32>()?;
+            mask = mask
+                .checked_shl(shift)
+                .filter(|shifted| shifted >> shift == mask)
+                .ok_or_else(|| syn::Error::new_spanned(&shift_lit, "shift is too large"))?;
+        }
+        if mask.count_ones() != 1 {
+            return Err(syn::Error::new_spanned(
+                &value,
+                "a flag must be exactly one bit, like `1 << 3`",
+            ));
+        }
+        Ok(Flag {
+            attributes,
+            name,
+            mask,
+        })
+    }
+}
+
+/// This is the entire input to the `enum_bitmask!` macro.
+///
+/// ```ignore
+/// enum_bitmask!(
+///     pub Permission: u8 => Permissions {
+///         Read: 1 << 0,
+///         Write: 1 << 1,
+///     }
+/// )
+/// ```
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct BitmaskEnum {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    repr: Ident,
+    // The name of the set type; by default, the enum name plus `Flags`.
+    flags_name: Ident,
+    flags: Vec<Flag>,
+}
+
+/// Parse the macro syntax for `enum_bitmask!`.
+impl Parse for BitmaskEnum {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        let name: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let repr: Ident = input.parse()?;
+        let width = match repr.to_string().as_str() {
+            "u8" => 8,
+            "u16" => 16,
+            "u32" => 32,
+            "u64" => 64,
+            "u128" => 128,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &repr,
+                    "expected an unsigned integer type, like `u8`",
+                ))
+            }
+        };
+        let flags_name = if input.peek(Token![=>]) {
+            input.parse::<Token![=>]>()?;
+            input.parse()?
+        } else {
+            format_ident!("{}Flags", name.unraw())
+        };
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let flags: Vec<Flag> = errors.parse_terminated(&content);
+        for (index, flag) in flags.iter().enumerate() {
+            if flag.mask.trailing_zeros() >= width {
+                errors.push_spanned(&flag.name, format!("this bit doesn't fit in a {}", repr));
+            } else if let Some(other) = flags[..index].iter().find(|other| other.mask == flag.mask)
+            {
+                errors.push_spanned(
+                    &flag.name,
+                    format!("this is the same bit as `{}`", other.name),
+                );
+            }
+        }
+        errors.finish()?;
+
+        Ok(BitmaskEnum {
+            attributes,
+            vis,
+            name,
+            repr,
+            flags_name,
+            flags,
+        })
+    }
+}
+
+/// Emit the enum, and the set type.
+impl ToTokens for BitmaskEnum {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let BitmaskEnum {
+            attributes,
+            vis,
+            name,
+            repr,
+            flags_name,
+            ..
+        } = self;
+        let variant_defs = self.flags.iter().map(|flag| {
+            let attributes = &flag.attributes;
+            let flag_name = &flag.name;
+            let mask = LitInt::new(&format!("{:#x}", flag.mask), flag.name.span());
+            quote! { #(#attributes)* #flag_name = #mask }
+        });
+        let names: Vec<&Ident> = self.flags.iter().map(|flag| &flag.name).collect();
+        let name_strs = names.iter().map(|name| name.unraw().to_string());
+        let count = self.flags.len();
+        let set_doc = format!("A set of [`{}`] flags.", name);
+
+        tokens.extend(quote! {
+            #(#attributes)*
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #[repr(#repr)]
+            #vis enum #name {
+                #(#variant_defs,)*
+            }
+
+            impl #name {
+                /// Every flag, in order.
+                pub const ALL: [#name; #count] = [#(#name::#names),*];
+
+                /// The bit for this flag.
+                pub const fn mask(self) -> #repr {
+                    self as #repr
+                }
+
+                /// The name of this flag.
+                pub const fn name(self) -> &'static str {
+                    match self {
+                        #(#name::#names => #name_strs,)*
+                    }
+                }
+            }
+
+            #[doc = #set_doc]
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::cmp::PartialEq, ::core::cmp::Eq, ::core::hash::Hash, ::core::default::Default)]
+            #vis struct #flags_name(#repr);
+
+            impl #flags_name {
+                /// The set with no flags.
+                pub const fn empty() -> Self {
+                    #flags_name(0)
+                }
+
+                /// The set with every flag.
+                pub const fn all() -> Self {
+                    #flags_name(0 #(| #name::#names.mask())*)
+                }
+
+                /// The set with these bits, or `None` if any bit isn't a flag.
+                pub const fn from_bits(bits: #repr) -> ::core::option::Option<Self> {
+                    if bits & !Self::all().0 == 0 {
+                        ::core::option::Option::Some(#flags_name(bits))
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+
+                /// The bits of this set.
+                pub const fn bits(self) -> #repr {
+                    self.0
+                }
+
+                /// Whether this set has no flags.
+                pub const fn is_empty(self) -> bool {
+                    self.0 == 0
+                }
+
+                /// Whether this set has `flag`.
+                pub const fn contains(self, flag: #name) -> bool {
+                    self.0 & flag.mask() != 0
+                }
+
+                /// Add `flag` to this set.
+                pub fn insert(&mut self, flag: #name) {
+                    self.0 |= flag.mask();
+                }
+
+                /// Remove `flag` from this set.
+                pub fn remove(&mut self, flag: #name) {
+                    self.0 &= !flag.mask();
+                }
+
+                /// The flags in this set, in order.
+                pub fn iter(self) -> impl ::core::iter::Iterator<Item = #name> {
+                    #name::ALL.iter().copied().filter(move |flag| self.contains(*flag))
+                }
+            }
+
+            impl ::core::convert::From<#name> for #flags_name {
+                fn from(flag: #name) -> Self {
+                    #flags_name(flag.mask())
+                }
+            }
+
+            impl ::core::ops::BitOr for #name {
+                type Output = #flags_name;
+
+                fn bitor(self, other: #name) -> #flags_name {
+                    #flags_name(self.mask() | other.mask())
+                }
+            }
+
+            impl ::core::ops::BitOr<#name> for #flags_name {
+                type Output = #flags_name;
+
+                fn bitor(self, flag: #name) -> #flags_name {
+                    #flags_name(self.0 | flag.mask())
+                }
+            }
+
+            impl ::core::ops::BitOr for #flags_name {
+                type Output = #flags_name;
+
+                fn bitor(self, other: #flags_name) -> #flags_name {
+                    #flags_name(self.0 | other.0)
+                }
+            }
+
+            impl ::core::ops::BitOrAssign<#name> for #flags_name {
+                fn bitor_assign(&mut self, flag: #name) {
+                    self.0 |= flag.mask();
+                }
+            }
+
+            impl ::core::ops::BitOrAssign for #flags_name {
+                fn bitor_assign(&mut self, other: #flags_name) {
+                    self.0 |= other.0;
+                }
+            }
+
+            impl ::core::iter::FromIterator<#name> for #flags_name {
+                fn from_iter<I: ::core::iter::IntoIterator<Item = #name>>(iter: I) -> Self {
+                    let mut flags = #flags_name::empty();
+                    for flag in iter {
+                        flags.insert(flag);
+                    }
+                    flags
+                }
+            }
+
+            /// Formats like `Permissions(Read | Write)`.
+            impl ::core::fmt::Debug for #flags_name {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(::core::stringify!(#flags_name))?;
+                    f.write_str("(")?;
+                    for (index, flag) in self.iter().enumerate() {
+                        if index > 0 {
+                            f.write_str(" | ")?;
+                        }
+                        f.write_str(flag.name())?;
+                    }
+                    f.write_str(")")
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_enum() {
+        let input: BitmaskEnum =
+            syn::parse_str("pub Perm: u8 { Read: 1 << 0, Write: 0x2, Exec: 1 << 7 }").unwrap();
+        let masks: Vec<u128> = input.flags.iter().map(|flag| flag.mask).collect();
+        assert_eq!(masks, [1, 2, 0x80]);
+        assert_eq!(input.flags_name, "PermFlags");
+
+        let input: BitmaskEnum = syn::parse_str("Perm: u16 => Perms { Read: 1 }").unwrap();
+        assert_eq!(input.flags_name, "Perms");
+
+        syn::parse_str::<BitmaskEnum>("Perm: u8 { Read: 3 }").unwrap_err();
+        syn::parse_str::<BitmaskEnum>("Perm: u8 { Read: 0 }").unwrap_err();
+        syn::parse_str::<BitmaskEnum>("Perm: u8 { Read: 1 << 8 }").unwrap_err();
+        syn::parse_str::<BitmaskEnum>("Perm: u128 { Read: 1 << 200 }").unwrap_err();
+        syn::parse_str::<BitmaskEnum>("Perm: u8 { Read: 1, Write: 1 << 0 }").unwrap_err();
+        syn::parse_str::<BitmaskEnum>("Perm: i8 { Read: 1 }").unwrap_err();
+        let error =
+            syn::parse_str::<BitmaskEnum>("Perm: u8 { Read: 1, Write: 1 << 8, Run: 1 << 0 }")
+                .err()
+                .unwrap();
+        assert_eq!(error.into_iter().count(), 2);
+    }
+}
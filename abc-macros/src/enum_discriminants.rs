@@ -0,0 +1,159 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Ident, Meta, NestedMeta, Path};
+
+use crate::attr::{self, lit_str};
+use crate::shallow::ShallowEnum;
+
+/// This is the entire input to `#[derive(EnumDiscriminants)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct EnumDiscriminantsInput {
+    input: ShallowEnum,
+    // The name of the fieldless enum, `FooKind` by default.
+    kind: Ident,
+    // Extra derives for it, from `#[discriminants(derive(...))]`.
+    derives: Vec<Path>,
+    // Each variant, and its `#[doc]` and `#[cfg]` attributes.
+    variants: Vec<(Ident, Vec<Attribute>)>,
+}
+
+/// Parse the input to `#[derive(EnumDiscriminants)]`.
+impl Parse for EnumDiscriminantsInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "EnumDiscriminants")?;
+
+        let mut kind = format_ident!("{}Kind", input.ident);
+        let mut derives = Vec::new();
+        for nested in attr::options(&input.attrs, "discriminants")? {
+            match &nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    kind = lit_str(&nv.lit)?.parse()?;
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("derive") => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::Path(path)) => derives.push(path.clone()),
+                            _ => return Err(syn::Error::new_spanned(nested, "expected a trait")),
+                        }
+                    }
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown discriminants option",
+                        &["name", "derive"],
+                    ))
+                }
+            }
+        }
+
+        let variants = input
+            .variants
+            .iter()
+            .map(|variant| {
+                let attrs = variant
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path.is_ident("doc") || attr.path.is_ident("cfg"))
+                    .cloned()
+                    .collect();
+                (variant.ident.clone(), attrs)
+            })
+            .collect();
+
+        Ok(EnumDiscriminantsInput {
+            input,
+            kind,
+            derives,
+            variants,
+        })
+    }
+}
+
+/// Emit the fieldless enum, `fn kind()`, and the conversions to it.
+impl ToTokens for EnumDiscriminantsInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let vis = &self.input.vis;
+        let kind = &self.kind;
+        let derives = &self.derives;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let doc = format!("The variants of [`{}`], without their fields.", name);
+        let variant_names: Vec<&Ident> = self.variants.iter().map(|(ident, _)| ident).collect();
+        let variant_attrs: Vec<&Vec<Attribute>> =
+            self.variants.iter().map(|(_, attrs)| attrs).collect();
+        // Only the `#[cfg]`s apply to the match arms.
+        let arm_cfgs = self.variants.iter().map(|(_, attrs)| {
+            attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("cfg"))
+                .collect::<Vec<_>>()
+        });
+        let body = if self.variants.is_empty() {
+            quote! { match *self {} }
+        } else {
+            quote! {
+                match self {
+                    #(
+                        #(#arm_cfgs)*
+                        Self::#variant_names { .. } => #kind::#variant_names,
+                    )*
+                }
+            }
+        };
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::fmt::Debug, ::core::cmp::PartialEq, ::core::cmp::Eq, ::core::hash::Hash #(, #derives)*)]
+            #vis enum #kind {
+                #(
+                    #(#variant_attrs)*
+                    #variant_names,
+                )*
+            }
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Which variant this is.
+                #vis const fn kind(&self) -> #kind {
+                    #body
+                }
+            }
+
+            impl #impl_generics ::core::convert::From<&#name #ty_generics> for #kind #where_clause {
+                fn from(value: &#name #ty_generics) -> Self {
+                    value.kind()
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> syn::parse::Result<EnumDiscriminantsInput> {
+        syn::parse_str(input)
+    }
+
+    #[test]
+    fn parse_discriminants() {
+        let input = parse("enum Shape { Empty, Circle(f64), Rect { w: f64, h: f64 } }").unwrap();
+        assert_eq!(input.kind, "ShapeKind");
+        let names: Vec<String> = input.variants.iter().map(|(v, _)| v.to_string()).collect();
+        assert_eq!(names, ["Empty", "Circle", "Rect"]);
+
+        let input = parse(
+            r#"#[discriminants(name = "Tag", derive(PartialOrd, Ord))] enum E { #[cfg(x)] #[serde(skip)] A }"#,
+        )
+        .unwrap();
+        assert_eq!(input.kind, "Tag");
+        assert_eq!(input.derives.len(), 2);
+        // Only `#[cfg]` and `#[doc]` are copied.
+        assert_eq!(input.variants[0].1.len(), 1);
+
+        parse("#[discriminants(bogus)] enum E { A }").unwrap_err();
+        parse(r#"#[discriminants(derive("Ord"))] enum E { A }"#).unwrap_err();
+        parse("struct S;").unwrap_err();
+    }
+}
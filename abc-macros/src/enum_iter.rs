@@ -0,0 +1,69 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::Ident;
+
+use crate::shallow::ShallowEnum;
+
+/// This is the entire input to `#[derive(EnumIter)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct EnumIterInput {
+    input: ShallowEnum,
+    variants: Vec<Ident>,
+}
+
+/// Parse the input to `#[derive(EnumIter)]`.
+impl Parse for EnumIterInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "EnumIter")?;
+        // There's no value to give a variant's fields, so every variant
+        // has to be a unit variant.
+        let variants = input
+            .variants
+            .iter()
+            .map(|variant| match variant.fields {
+                None => Ok(variant.ident.clone()),
+                Some(_) => Err(syn::Error::new_spanned(
+                    variant,
+                    "EnumIter can only be derived for enums without fields",
+                )),
+            })
+            .collect::<syn::parse::Result<_>>()?;
+        Ok(EnumIterInput { input, variants })
+    }
+}
+
+/// Emit `fn iter()`.
+impl ToTokens for EnumIterInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let variants = &self.variants;
+        // Calling `into_iter` through the trait iterates over the array by
+        // value, even in the 2018 edition.
+        tokens.extend(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Iterate over every variant, in declaration order.
+                pub fn iter() -> impl ::core::iter::Iterator<Item = Self> {
+                    ::core::iter::IntoIterator::into_iter([#(Self::#variants),*])
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_variants() {
+        let input: EnumIterInput = syn::parse_str("enum Foo { A, B = 3, r#C }").unwrap();
+        let names: Vec<String> = input.variants.iter().map(|v| v.to_string()).collect();
+        assert_eq!(names, ["A", "B", "r#C"]);
+
+        syn::parse_str::<EnumIterInput>("enum Foo { A, B(u8) }").unwrap_err();
+        syn::parse_str::<EnumIterInput>("enum Foo { A { a: u8 } }").unwrap_err();
+        syn::parse_str::<EnumIterInput>("struct Foo;").unwrap_err();
+    }
+}
@@ -0,0 +1,267 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, Attribute, Ident, LitStr, Token, Visibility};
+
+/// A string pattern, from the kinds that `enum_strings!` can match.
+#[derive(Debug, PartialEq)]
+enum Pattern {
+    /// `"readme"`: only this string.
+    Exact(String),
+    /// `"img_*"`: strings starting with this.
+    Prefix(String),
+    /// `"*.rs"`: strings ending with this.
+    Suffix(String),
+    /// `"test_*.rs"`, or anything else using `*` or `?`.
+    Glob(String),
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Self {
+        let is_literal = |s: &str| !s.contains(['*', '?']);
+        if is_literal(pattern) {
+            return Pattern::Exact(pattern.to_string());
+        }
+        if let Some(prefix) = pattern.strip_suffix('*').filter(|p| is_literal(p)) {
+            return Pattern::Prefix(prefix.to_string());
+        }
+        if let Some(suffix) = pattern.strip_prefix('*').filter(|s| is_literal(s)) {
+            return Pattern::Suffix(suffix.to_string());
+        }
+        Pattern::Glob(pattern.to_string())
+    }
+
+    /// A condition testing whether `text` matches. (Exact patterns are
+    /// all matched at once, in a `match`.)
+    fn condition(&self) -> TokenStream {
+        match self {
+            Pattern::Exact(exact) => quote! { text == #exact },
+            Pattern::Prefix(prefix) => quote! { text.starts_with(#prefix) },
+            Pattern::Suffix(suffix) => quote! { text.ends_with(#suffix) },
+            Pattern::Glob(glob) => quote! { ::abc::__private::glob_match(#glob, text) },
+        }
+    }
+}
+
+/// A variant and the patterns that map to it.
+///
+/// Example: `Rust: "*.rs" | "*.rs.in"` or `Other: _`
+///
+#[cfg_attr(test, derive(Debug))]
+struct StringVariant {
+    attributes: Vec<Attribute>,
+    name: Ident,
+    // `None` for the catch-all variant, `_`.
+    patterns: Option<Vec<Pattern>>,
+}
+
+impl Parse for StringVariant {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        if input.parse::<Option<Token![_]>>()?.is_some() {
+            return Ok(StringVariant {
+                attributes,
+                name,
+                patterns: None,
+            });
+        }
+        let lits = Punctuated::<LitStr, Token![|]>::parse_separated_nonempty(input)?;
+        let patterns = lits.iter().map(|lit| Pattern::new(&lit.value())).collect();
+        Ok(StringVariant {
+            attributes,
+            name,
+            patterns: Some(patterns),
+        })
+    }
+}
+
+/// This is the entire input to the `enum_strings!` macro.
+///
+/// ```ignore
+/// enum_strings!(
+///     FileKind {
+///         Readme: "README" | "README.md",
+///         Rust: "*.rs",
+///         Other: _,
+///     }
+/// )
+/// ```
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct StringEnum {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    variants: Vec<StringVariant>,
+}
+
+/// Parse the macro syntax for `enum_strings!`.
+impl Parse for StringEnum {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        let name: Ident = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let variants: Vec<StringVariant> = errors.parse_terminated(&content);
+
+        let catch_alls = variants.iter().filter(|variant| variant.patterns.is_none());
+        for extra in catch_alls.skip(1) {
+            errors.push_spanned(
+                &extra.name,
+                "only one variant can match everything else (`_`)",
+            );
+        }
+        // The same exact string twice would be an unreachable match arm.
+        let mut exact = Vec::new();
+        for variant in &variants {
+            for pattern in variant.patterns.iter().flatten() {
+                if let Pattern::Exact(s) = pattern {
+                    if exact.contains(&s) {
+                        errors.push_spanned(
+                            &variant.name,
+                            format!("the string {:?} is already matched by another variant", s),
+                        );
+                    } else {
+                        exact.push(s);
+                    }
+                }
+            }
+        }
+        errors.finish()?;
+
+        Ok(StringEnum {
+            attributes,
+            vis,
+            name,
+            variants,
+        })
+    }
+}
+
+/// Emit the enum, and its `From<&str>` or `TryFrom<&str>` impl.
+impl ToTokens for StringEnum {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let StringEnum {
+            attributes,
+            vis,
+            name,
+            ..
+        } = self;
+        let variant_defs = self.variants.iter().map(|variant| {
+            let attributes = &variant.attributes;
+            let name = &variant.name;
+            quote! { #(#attributes)* #name }
+        });
+
+        // Exact strings are all checked in one `match`; then the other
+        // patterns are tried in order.
+        let mut exact_arms = Vec::new();
+        let mut checks = Vec::new();
+        for variant in &self.variants {
+            let variant_name = &variant.name;
+            for pattern in variant.patterns.iter().flatten() {
+                match pattern {
+                    Pattern::Exact(s) => exact_arms.push(quote! {
+                        #s => return ::core::option::Option::Some(#name::#variant_name),
+                    }),
+                    other => {
+                        let condition = other.condition();
+                        checks.push(quote! {
+                            if #condition {
+                                return ::core::option::Option::Some(#name::#variant_name);
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        let catch_all = self
+            .variants
+            .iter()
+            .find(|variant| variant.patterns.is_none());
+        let conversion = match catch_all {
+            Some(variant) => {
+                let variant_name = &variant.name;
+                quote! {
+                    impl ::core::convert::From<&str> for #name {
+                        fn from(text: &str) -> Self {
+                            Self::classify(text).unwrap_or(#name::#variant_name)
+                        }
+                    }
+                }
+            }
+            None => quote! {
+                impl<'a> ::core::convert::TryFrom<&'a str> for #name {
+                    type Error = &'a str;
+
+                    fn try_from(text: &'a str) -> ::core::result::Result<Self, &'a str> {
+                        Self::classify(text).ok_or(text)
+                    }
+                }
+            },
+        };
+
+        tokens.extend(quote! {
+            #(#attributes)*
+            #vis enum #name {
+                #(#variant_defs,)*
+            }
+
+            impl #name {
+                /// Find the variant matching `text`, other than a
+                /// catch-all.
+                #[allow(unreachable_code)]
+                fn classify(text: &str) -> ::core::option::Option<Self> {
+                    match text {
+                        #(#exact_arms)*
+                        _ => {}
+                    }
+                    #(#checks)*
+                    ::core::option::Option::None
+                }
+            }
+
+            #conversion
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        assert_eq!(Pattern::new("readme"), Pattern::Exact("readme".into()));
+        assert_eq!(Pattern::new("img_*"), Pattern::Prefix("img_".into()));
+        assert_eq!(Pattern::new("*.rs"), Pattern::Suffix(".rs".into()));
+        assert_eq!(Pattern::new("test_*.rs"), Pattern::Glob("test_*.rs".into()));
+        assert_eq!(Pattern::new("*"), Pattern::Prefix("".into()));
+        assert_eq!(Pattern::new("v?"), Pattern::Glob("v?".into()));
+    }
+
+    #[test]
+    fn parse_enum() {
+        let input: StringEnum =
+            syn::parse_str("pub Kind { A: \"a\" | \"b*\", #[doc = \"x\"] B: \"*c\", C: _ }")
+                .unwrap();
+        assert_eq!(input.variants.len(), 3);
+        assert_eq!(input.variants[0].patterns.as_ref().unwrap().len(), 2);
+        assert!(input.variants[2].patterns.is_none());
+
+        syn::parse_str::<StringEnum>("Kind { A: _, B: _ }").unwrap_err();
+        syn::parse_str::<StringEnum>("Kind { A: \"a\", B: \"a\" }").unwrap_err();
+        syn::parse_str::<StringEnum>("Kind { A: 1 }").unwrap_err();
+        let error = syn::parse_str::<StringEnum>("Kind { A: _, B: \"b\", C: _, D: 1, E: \"b\" }")
+            .err()
+            .unwrap();
+        assert_eq!(error.into_iter().count(), 3);
+    }
+}
@@ -0,0 +1,203 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Lit, LitBool, LitChar, LitFloat, LitInt, LitStr, Token};
+
+/// This is the entire input to `env_or!`.
+///
+/// Example: `env_or!("PORT", 8080u16)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct EnvOrInput {
+    name: LitStr,
+    // Whether the value is negative; `-` isn't part of the literal.
+    negative: bool,
+    // The value from the environment, or else the default.
+    value: Lit,
+}
+
+/// Check that `text` is a valid value for an integer with this suffix.
+fn check_int(text: &str, suffix: &str) -> bool {
+    fn fits<T: std::str::FromStr>(text: &str) -> bool {
+        text.parse::<T>().is_ok()
+    }
+    match suffix {
+        "u8" => fits::<u8>(text),
+        "u16" => fits::<u16>(text),
+        "u32" => fits::<u32>(text),
+        "u64" | "usize" => fits::<u64>(text),
+        "u128" => fits::<u128>(text),
+        "i8" => fits::<i8>(text),
+        "i16" => fits::<i16>(text),
+        "i32" => fits::<i32>(text),
+        "i64" | "isize" => fits::<i64>(text),
+        "i128" => fits::<i128>(text),
+        // An unsuffixed integer's type depends on where it's used, so
+        // the compiler checks its range once it's in the code.
+        "" => fits::<i128>(text),
+        _ => false,
+    }
+}
+
+/// Parse an environment variable's value as the same kind of literal as
+/// the default, returning the new literal and whether it's negative.
+fn parse_value(text: &str, default: &Lit, span: Span) -> Option<(bool, Lit)> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let lit = match default {
+        Lit::Str(_) => return Some((false, Lit::Str(LitStr::new(text, span)))),
+        Lit::Bool(_) => match text {
+            "true" | "false" => Lit::Bool(LitBool::new(text == "true", span)),
+            _ => return None,
+        },
+        Lit::Char(_) => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Lit::Char(LitChar::new(c, span)),
+                _ => return None,
+            }
+        }
+        Lit::Int(default) => {
+            let suffix = default.suffix();
+            if !digits.chars().all(|c| c.is_ascii_digit())
+                || (negative && suffix.starts_with('u'))
+                || !check_int(text, suffix)
+            {
+                return None;
+            }
+            Lit::Int(LitInt::new(&format!("{}{}", digits, suffix), span))
+        }
+        Lit::Float(default) => {
+            if digits.parse::<f64>().is_err() || !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            // A float literal needs a `.` or an exponent, to tell it apart
+            // from an integer.
+            let point = if digits.contains(['.', 'e', 'E']) {
+                ""
+            } else {
+                ".0"
+            };
+            let text = format!("{}{}{}", digits, point, default.suffix());
+            Lit::Float(LitFloat::new(&text, span))
+        }
+        _ => return None,
+    };
+    Some((negative, lit))
+}
+
+/// Parse the input to `env_or!`, and read the environment variable.
+impl Parse for EnvOrInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let name: LitStr = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let negative = input.parse::<Option<Token![-]>>()?.is_some();
+        let default: Lit = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        match &default {
+            Lit::Str(_) | Lit::Bool(_) | Lit::Char(_) if negative => {
+                return Err(syn::Error::new_spanned(&default, "this can't be negative"));
+            }
+            Lit::Str(_) | Lit::Bool(_) | Lit::Char(_) | Lit::Int(_) | Lit::Float(_) => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &default,
+                    "the default must be a string, char, bool, integer, or float literal",
+                ))
+            }
+        }
+
+        let value = match std::env::var(name.value()) {
+            Ok(text) => parse_value(&text, &default, default.span()).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &default,
+                    format!(
+                        "the environment variable {} is `{}`, which doesn't fit the type \
+                         of the default",
+                        name.value(),
+                        text
+                    ),
+                )
+            })?,
+            Err(std::env::VarError::NotUnicode(_)) => {
+                return Err(syn::Error::new_spanned(
+                    &name,
+                    "this environment variable isn't valid unicode",
+                ))
+            }
+            Err(std::env::VarError::NotPresent) => (negative, default),
+        };
+        Ok(EnvOrInput {
+            name,
+            negative: value.0,
+            value: value.1,
+        })
+    }
+}
+
+/// Emit the value.
+impl ToTokens for EnvOrInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let value = &self.value;
+        let minus = if self.negative {
+            quote! { - }
+        } else {
+            quote! {}
+        };
+        // `option_env!` tells cargo to build again when the variable
+        // changes; a proc macro can't do that on its own.
+        tokens.extend(quote! {
+            {
+                const _: ::core::option::Option<&str> = ::core::option_env!(#name);
+                #minus #value
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn parse(text: &str, default: Lit) -> Option<String> {
+        parse_value(text, &default, Span::call_site()).map(|(negative, lit)| {
+            let minus = if negative { "-" } else { "" };
+            format!("{}{}", minus, lit.to_token_stream())
+        })
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse("9000", parse_quote!(8080u16)).unwrap(), "9000u16");
+        assert_eq!(parse(" 12 ", parse_quote!(1)).unwrap(), "12");
+        assert_eq!(parse("-3", parse_quote!(1i8)).unwrap(), "-3i8");
+        assert!(parse("70000", parse_quote!(8080u16)).is_none());
+        assert!(parse("-3", parse_quote!(1u8)).is_none());
+        assert!(parse("0x10", parse_quote!(1u8)).is_none());
+        assert!(parse("1_000", parse_quote!(1u32)).is_none());
+
+        assert_eq!(parse("2.5", parse_quote!(1.0)).unwrap(), "2.5");
+        assert_eq!(parse("3", parse_quote!(1.0f32)).unwrap(), "3.0f32");
+        assert_eq!(parse("-1e3", parse_quote!(1.0)).unwrap(), "-1e3");
+        assert!(parse("inf", parse_quote!(1.0)).is_none());
+
+        assert_eq!(parse("true", parse_quote!(false)).unwrap(), "true");
+        assert!(parse("yes", parse_quote!(false)).is_none());
+        assert_eq!(parse("x", parse_quote!('a')).unwrap(), "'x'");
+        assert!(parse("xy", parse_quote!('a')).is_none());
+        assert_eq!(parse("hi", parse_quote!("")).unwrap(), "\"hi\"");
+    }
+
+    #[test]
+    fn bad_defaults() {
+        syn::parse_str::<EnvOrInput>("\"ABC_UNSET\", -\"x\"").unwrap_err();
+        syn::parse_str::<EnvOrInput>("\"ABC_UNSET\", b\"x\"").unwrap_err();
+        syn::parse_str::<EnvOrInput>("\"ABC_UNSET\"").unwrap_err();
+    }
+}
@@ -0,0 +1,303 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Data, DeriveInput, Fields, Ident, Member, Type};
+
+use crate::display::DisplayInput;
+use crate::msrv;
+
+/// The source of one struct or variant, if it has one.
+#[cfg_attr(test, derive(Debug))]
+struct Source {
+    member: Member,
+    ty: Type,
+    // `#[from]`: also emit `From` for the field's type.
+    from: bool,
+}
+
+/// This is the entire input to `#[derive(Error)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct ErrorInput {
+    // The `Display` impl, from the `#[error("...")]` attributes.
+    display: DisplayInput,
+    // Each variant (`None` for a struct), its fields, and its source.
+    sources: Vec<(Option<Ident>, Fields, Option<Source>)>,
+}
+
+/// Find the source field: one marked `#[source]` or `#[from]`, or else
+/// one named `source`.
+fn find_source(fields: &Fields) -> syn::parse::Result<Option<Source>> {
+    let mut marked: Option<Source> = None;
+    let mut named = None;
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+        let is_source = field.attrs.iter().any(|attr| attr.path.is_ident("source"));
+        let from = field.attrs.iter().any(|attr| attr.path.is_ident("from"));
+        for attr in &field.attrs {
+            if (attr.path.is_ident("source") || attr.path.is_ident("from"))
+                && !attr.tokens.is_empty()
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected `#[source]` or `#[from]`",
+                ));
+            }
+        }
+        if from && fields.len() != 1 {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`#[from]` can only be used on the only field of a struct or variant",
+            ));
+        }
+        if is_source || from {
+            if marked.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "there can only be one source",
+                ));
+            }
+            marked = Some(Source {
+                member,
+                ty: field.ty.clone(),
+                from,
+            });
+        } else if field
+            .ident
+            .as_ref()
+            .map_or(false, |ident| ident == "source")
+        {
+            named = Some(Source {
+                member,
+                ty: field.ty.clone(),
+                from: false,
+            });
+        }
+    }
+    Ok(marked.or(named))
+}
+
+/// Parse the input to `#[derive(Error)]`.
+impl Parse for ErrorInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+
+        let sources = match &input.data {
+            Data::Struct(data) => vec![(None, data.fields.clone(), find_source(&data.fields)?)],
+            Data::Enum(data) => data
+                .variants
+                .iter()
+                .map(|variant| {
+                    Ok((
+                        Some(variant.ident.clone()),
+                        variant.fields.clone(),
+                        find_source(&variant.fields)?,
+                    ))
+                })
+                .collect::<syn::parse::Result<_>>()?,
+            Data::Union(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Error can't be derived for unions",
+                ))
+            }
+        };
+        // `#[from]`s for the same type would be conflicting impls.
+        let from_types: Vec<String> = sources
+            .iter()
+            .filter_map(|(_, _, source)| source.as_ref().filter(|source| source.from))
+            .map(|source| source.ty.to_token_stream().to_string())
+            .collect();
+        for (index, ty) in from_types.iter().enumerate() {
+            if from_types[..index].contains(ty) {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("more than one variant has `#[from]` for `{}`", ty),
+                ));
+            }
+        }
+        let display = DisplayInput::with_attribute(input, "error")?;
+        Ok(ErrorInput { display, sources })
+    }
+}
+
+/// Emit the `Display`, `Error`, and `From` impls.
+impl ToTokens for ErrorInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let input = self.display.input();
+        let name = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        // `Error` moved into `core` in Rust 1.81.
+        let error = if msrv::at_least(81) {
+            quote! { ::core::error::Error }
+        } else {
+            quote! { ::abc::__private::std::error::Error }
+        };
+
+        let arms = self.sources.iter().map(|(variant, fields, source)| {
+            let path = match variant {
+                Some(variant) => quote! { #name::#variant },
+                None => quote! { #name },
+            };
+            let source = match source {
+                Some(source) => source,
+                None => {
+                    let pattern = match fields {
+                        Fields::Named(_) => quote! { { .. } },
+                        Fields::Unnamed(_) => quote! { (..) },
+                        Fields::Unit => quote! {},
+                    };
+                    return quote! { #path #pattern => ::core::option::Option::None, };
+                }
+            };
+            let member = &source.member;
+            let binding = format_ident!("__source");
+            let pattern = match (fields, member) {
+                (Fields::Named(_), Member::Named(field)) => quote! { { #field: #binding, .. } },
+                (_, Member::Unnamed(index)) => {
+                    let before = (0..index.index).map(|_| quote! { _, });
+                    quote! { ( #(#before)* #binding, .. ) }
+                }
+                _ => unreachable!("named members come from named fields"),
+            };
+            quote! {
+                #path #pattern => ::core::option::Option::Some(#binding.as_dyn_error()),
+            }
+        });
+        // A source can be a concrete error type, or a boxed trait object
+        // like `Box<dyn Error + Send + Sync>`, which doesn't implement
+        // `Error` itself. Method resolution finds whichever impl fits.
+        let as_dyn_error = quote! {
+            #[allow(dead_code)]
+            trait AsDynError {
+                fn as_dyn_error(&self) -> &(dyn #error + 'static);
+            }
+            impl<T: #error + 'static> AsDynError for T {
+                fn as_dyn_error(&self) -> &(dyn #error + 'static) {
+                    self
+                }
+            }
+            impl AsDynError for dyn #error + 'static {
+                fn as_dyn_error(&self) -> &(dyn #error + 'static) {
+                    self
+                }
+            }
+            impl AsDynError for dyn #error + ::core::marker::Send + 'static {
+                fn as_dyn_error(&self) -> &(dyn #error + 'static) {
+                    self
+                }
+            }
+            impl AsDynError
+                for dyn #error + ::core::marker::Send + ::core::marker::Sync + 'static
+            {
+                fn as_dyn_error(&self) -> &(dyn #error + 'static) {
+                    self
+                }
+            }
+        };
+        let body = if self.sources.is_empty() {
+            quote! { match *self {} }
+        } else {
+            quote! {
+                #as_dyn_error
+                match self {
+                    #(#arms)*
+                }
+            }
+        };
+
+        let froms = self.sources.iter().filter_map(|(variant, _, source)| {
+            let source = source.as_ref().filter(|source| source.from)?;
+            let ty = &source.ty;
+            let path = match variant {
+                Some(variant) => quote! { #name::#variant },
+                None => quote! { #name },
+            };
+            let value = match &source.member {
+                Member::Named(field) => quote! { #path { #field: source } },
+                Member::Unnamed(_) => quote! { #path(source) },
+            };
+            Some(quote! {
+                impl #impl_generics ::core::convert::From<#ty> for #name #ty_generics #where_clause {
+                    fn from(source: #ty) -> Self {
+                        #value
+                    }
+                }
+            })
+        });
+
+        let display = &self.display;
+        tokens.extend(quote! {
+            #display
+
+            impl #impl_generics #error for #name #ty_generics #where_clause {
+                fn source(&self) -> ::core::option::Option<&(dyn #error + 'static)> {
+                    #body
+                }
+            }
+
+            #(#froms)*
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(input: &str) -> syn::parse::Result<Vec<Option<(String, bool)>>> {
+        let input: ErrorInput = syn::parse_str(input)?;
+        Ok(input
+            .sources
+            .iter()
+            .map(|(_, _, source)| {
+                source
+                    .as_ref()
+                    .map(|source| (source.member.to_token_stream().to_string(), source.from))
+            })
+            .collect())
+    }
+
+    #[test]
+    fn find_sources() {
+        assert_eq!(
+            sources(
+                "enum E {
+                    #[error(\"io\")] Io(#[from] io::Error),
+                    #[error(\"parse\")] Parse { line: u32, #[source] cause: ParseIntError },
+                    #[error(\"other\")] Other { source: Box<dyn Error> },
+                    #[error(\"unit\")] Unit,
+                }"
+            )
+            .unwrap(),
+            [
+                Some(("0".to_string(), true)),
+                Some(("cause".to_string(), false)),
+                Some(("source".to_string(), false)),
+                None
+            ]
+        );
+        assert_eq!(sources("#[error(\"x\")] struct S(u8);").unwrap(), [None]);
+
+        sources("struct S(#[source] io::Error);").unwrap_err();
+        sources("#[error(\"x\")] struct S(u8, #[from] io::Error);").unwrap_err();
+        sources("#[error(\"x\")] struct S(#[source] A, #[source] B);").unwrap_err();
+        sources("#[error(\"x\")] struct S(#[source(x)] A);").unwrap_err();
+        sources("enum E { A(#[from] u8), B(#[from] u8) }").unwrap_err();
+    }
+
+    // This crate's `rust-version` is 1.60, older than `core::error`.
+    #[test]
+    fn old_rust_version() {
+        let input: ErrorInput = syn::parse_str("#[error(\"x\")] struct S;").unwrap();
+        let output = input.to_token_stream().to_string();
+        assert!(
+            output.contains(":: abc :: __private :: std :: error :: Error"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("core :: error"), "{}", output);
+    }
+}
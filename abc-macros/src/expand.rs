@@ -0,0 +1,166 @@
+//! `ABC_MACRO_EXPAND_DIR`: write each macro's expansion to a file, for
+//! debugging the generated code without expanding a whole crate.
+//!
+//! The files are only pretty-printed with the `pretty_expand` feature.
+//! Without it, each expansion is one long line of tokens, with a comment
+//! saying how to get it formatted.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+
+/// The environment variable naming the directory to write expansions to.
+pub const EXPAND_DIR: &str = "ABC_MACRO_EXPAND_DIR";
+
+/// Pretty-print an expansion.
+///
+/// Most expansions are items, which print as a file. An expansion that's
+/// an expression is printed as the body of a function, and anything else
+/// is printed as plain tokens.
+///
+#[cfg(feature = "pretty_expand")]
+fn pretty(tokens: &TokenStream) -> String {
+    if let Ok(file) = syn::parse2::<syn::File>(tokens.clone()) {
+        return prettyplease::unparse(&file);
+    }
+    if let Ok(expr) = syn::parse2::<syn::Expr>(tokens.clone()) {
+        let file: syn::File = syn::parse_quote! {
+            fn expansion() {
+                #expr
+            }
+        };
+        return prettyplease::unparse(&file);
+    }
+    format!("{}\n", tokens)
+}
+
+/// Without `pretty_expand`, an expansion is written as plain tokens.
+#[cfg(not(feature = "pretty_expand"))]
+fn pretty(tokens: &TokenStream) -> String {
+    format!(
+        "// Enable abc's `pretty_expand` feature to format this.\n{}\n",
+        tokens
+    )
+}
+
+/// Turn a call site like `src/lib.rs:12:5` into part of a file name.
+fn file_stem(location: &str) -> String {
+    location
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Write an expansion to `dir`, in a file named after the call site and
+/// the macro, and return the file's path.
+pub fn write(dir: &Path, location: &str, name: &str, tokens: &TokenStream) -> io::Result<PathBuf> {
+    let bare_name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    let bare_name = bare_name.strip_prefix("derive").unwrap_or(&bare_name);
+    let path = dir.join(format!("{}-{}.rs", file_stem(location), bare_name));
+    let mut code = String::new();
+    let _ = writeln!(code, "// The expansion of {} at {}.\n", name, location);
+    code.push_str(&pretty(tokens));
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, code)?;
+    Ok(path)
+}
+
+/// The call site of the macro being expanded, like `src/lib.rs:12:5`.
+///
+/// Before Rust 1.88, a proc macro can't find out where it's called, so
+/// this names the expansion by a hash of its code instead.
+///
+fn location(tokens: &TokenStream) -> String {
+    // The build script only sets `abc_span_locations` on Rust 1.88 or
+    // later.
+    #[cfg(abc_span_locations)]
+    #[allow(clippy::incompatible_msrv)]
+    {
+        let _ = tokens;
+        let span = proc_macro::Span::call_site();
+        format!("{}:{}:{}", span.file(), span.line(), span.column())
+    }
+    #[cfg(not(abc_span_locations))]
+    {
+        // FNV-1a, as in `structure_version`.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in tokens.to_string().bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+}
+
+/// Finish a macro: write its expansion to `$ABC_MACRO_EXPAND_DIR`, if
+/// that's set, and return it.
+///
+/// `name` is the macro as it's written, like `json!`, `#[trace]`, or
+/// `#[derive(Builder)]`. If the file can't be written, the error goes to
+/// stderr; it doesn't stop the build.
+///
+pub fn finish(name: &str, tokens: TokenStream) -> proc_macro::TokenStream {
+    if let Some(dir) = std::env::var_os(EXPAND_DIR) {
+        let location = location(&tokens);
+        if let Err(e) = write(Path::new(&dir), &location, name, &tokens) {
+            eprintln!("abc: can't write the expansion of {}: {}", name, e);
+        }
+    }
+    tokens.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn file_stems() {
+        assert_eq!(file_stem("src/lib.rs:12:5"), "src_lib_rs_12_5");
+        assert_eq!(file_stem("0123abcd"), "0123abcd");
+    }
+
+    #[test]
+    #[cfg(not(feature = "pretty_expand"))]
+    fn write_plain_expansions() {
+        let dir = std::env::temp_dir().join(format!("abc_plain_expand_dir_{}", std::process::id()));
+        let items = quote! { struct Foo; };
+        let path = write(&dir, "src/lib.rs:1:1", "bitfield!", &items).unwrap();
+        assert_eq!(path, dir.join("src_lib_rs_1_1-bitfield.rs"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "// The expansion of bitfield! at src/lib.rs:1:1.\n\n\
+             // Enable abc's `pretty_expand` feature to format this.\n\
+             struct Foo ;\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "pretty_expand")]
+    fn write_expansions() {
+        let dir = std::env::temp_dir().join(format!("abc_expand_dir_{}", std::process::id()));
+
+        let items = quote! { impl Foo { fn get(&self) -> u8 { self.0 } } };
+        let path = write(&dir, "src/lib.rs:12:5", "#[derive(Getters)]", &items).unwrap();
+        assert_eq!(path, dir.join("src_lib_rs_12_5-Getters.rs"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "// The expansion of #[derive(Getters)] at src/lib.rs:12:5.\n\n\
+             impl Foo {\n    fn get(&self) -> u8 {\n        self.0\n    }\n}\n"
+        );
+
+        let expr = quote! { [1u8, 2u8] };
+        let path = write(&dir, "src/main.rs:3:14", "json!", &expr).unwrap();
+        assert_eq!(path, dir.join("src_main_rs_3_14-json.rs"));
+        let code = std::fs::read_to_string(&path).unwrap();
+        assert!(code.ends_with("fn expansion() {\n    [1u8, 2u8]\n}\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
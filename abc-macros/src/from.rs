@@ -0,0 +1,152 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Data, DeriveInput, Fields, Ident, Meta, NestedMeta, Type};
+
+use crate::attr;
+
+/// This is the entire input to `#[derive(From)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct FromInput {
+    input: DeriveInput,
+    // Each conversion: the variant (`None` for a struct), whether its
+    // field is named, and the field's type.
+    conversions: Vec<(Option<Ident>, Option<Ident>, Type)>,
+}
+
+/// Whether a variant has `#[from(skip)]`.
+fn is_skipped(attrs: &[syn::Attribute]) -> syn::parse::Result<bool> {
+    let mut skip = false;
+    for nested in attr::options(attrs, "from")? {
+        match &nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => skip = true,
+            _ => {
+                return Err(attr::unknown_option(
+                    nested,
+                    "unknown from option",
+                    &["skip"],
+                ))
+            }
+        }
+    }
+    Ok(skip)
+}
+
+/// The field of a struct or variant with exactly one.
+fn single_field(fields: &Fields) -> Option<(Option<Ident>, Type)> {
+    match fields.iter().collect::<Vec<_>>().as_slice() {
+        [field] => Some((field.ident.clone(), field.ty.clone())),
+        _ => None,
+    }
+}
+
+/// Parse the input to `#[derive(From)]`.
+impl Parse for FromInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+        let mut conversions = Vec::new();
+        match &input.data {
+            Data::Struct(data) => match single_field(&data.fields) {
+                Some((field, ty)) => conversions.push((None, field, ty)),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        "From can only be derived for a struct with one field",
+                    ))
+                }
+            },
+            Data::Enum(data) => {
+                for variant in &data.variants {
+                    if is_skipped(&variant.attrs)? {
+                        continue;
+                    }
+                    // Variants without exactly one field have nothing to
+                    // convert from.
+                    let (field, ty) = match single_field(&variant.fields) {
+                        Some(field) => field,
+                        None => continue,
+                    };
+                    let same_type = |(_, _, other): &(_, _, Type)| {
+                        other.to_token_stream().to_string() == ty.to_token_stream().to_string()
+                    };
+                    if conversions.iter().any(same_type) {
+                        return Err(syn::Error::new_spanned(
+                            &variant.ident,
+                            format!(
+                                "another variant already converts from `{}`; \
+                                 mark one of them `#[from(skip)]`",
+                                ty.to_token_stream()
+                            ),
+                        ));
+                    }
+                    conversions.push((Some(variant.ident.clone()), field, ty));
+                }
+            }
+            Data::Union(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "From can't be derived for unions",
+                ))
+            }
+        }
+        Ok(FromInput { input, conversions })
+    }
+}
+
+/// Emit the `From` impls.
+impl ToTokens for FromInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        for (variant, field, ty) in &self.conversions {
+            let path = match variant {
+                Some(variant) => quote! { #name::#variant },
+                None => quote! { #name },
+            };
+            let value = match field {
+                Some(field) => quote! { #path { #field: value } },
+                None => quote! { #path(value) },
+            };
+            tokens.extend(quote! {
+                impl #impl_generics ::core::convert::From<#ty> for #name #ty_generics #where_clause {
+                    fn from(value: #ty) -> Self {
+                        #value
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(input: &str) -> syn::parse::Result<Vec<String>> {
+        let input: FromInput = syn::parse_str(input)?;
+        Ok(input
+            .conversions
+            .iter()
+            .map(|(_, _, ty)| ty.to_token_stream().to_string())
+            .collect())
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(sources("struct Id(u32);").unwrap(), ["u32"]);
+        assert_eq!(sources("struct Id { value: u32 }").unwrap(), ["u32"]);
+        assert_eq!(
+            sources(
+                "enum E { Io(io::Error), Code { code: i32 }, #[from(skip)] Other(i32), Unit, Pair(u8, u8) }"
+            )
+            .unwrap(),
+            ["io :: Error", "i32"]
+        );
+
+        sources("struct Pair(u8, u8);").unwrap_err();
+        sources("struct Unit;").unwrap_err();
+        sources("enum E { A(u8), B(u8) }").unwrap_err();
+        sources("enum E { #[from(bogus)] A(u8) }").unwrap_err();
+        sources("union U { a: u8 }").unwrap_err();
+    }
+}
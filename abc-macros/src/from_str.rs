@@ -0,0 +1,179 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_quote, Ident, Meta, NestedMeta, Path};
+
+use crate::attr::{self, lit_str};
+use crate::shallow::ShallowEnum;
+
+/// This is the entire input to `#[derive(FromStr)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct FromStrInput {
+    input: ShallowEnum,
+    // `#[from_str(crate = "...")]`: the path to the `abc` crate, for
+    // `ParseEnumError`.
+    crate_path: Path,
+    variants: Vec<FromStrVariant>,
+}
+
+/// A variant, along with the string that parses to it.
+#[cfg_attr(test, derive(Debug))]
+struct FromStrVariant {
+    ident: Ident,
+    // The variant name, or `#[from_str(rename = "...")]`.
+    name: String,
+    // `#[from_str(ascii_case_insensitive)]`, on the variant or the enum.
+    case_insensitive: bool,
+}
+
+/// Parse the input to `#[derive(FromStr)]`.
+impl Parse for FromStrInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "FromStr")?;
+
+        let mut crate_path = parse_quote!(::abc);
+        let mut case_insensitive = false;
+        for nested in attr::options(&input.attrs, "from_str")? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("ascii_case_insensitive") => {
+                    case_insensitive = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown from_str option",
+                        &["ascii_case_insensitive", "crate"],
+                    ))
+                }
+            }
+        }
+
+        let mut variants: Vec<FromStrVariant> = Vec::new();
+        for variant in &input.variants {
+            if variant.fields.is_some() {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "FromStr can only be derived for enums without fields",
+                ));
+            }
+            let mut parsed = FromStrVariant {
+                ident: variant.ident.clone(),
+                name: variant.ident.unraw().to_string(),
+                case_insensitive,
+            };
+            for nested in attr::options(&variant.attrs, "from_str")? {
+                match &nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                        parsed.name = lit_str(&nv.lit)?.value();
+                    }
+                    NestedMeta::Meta(Meta::Path(path))
+                        if path.is_ident("ascii_case_insensitive") =>
+                    {
+                        parsed.case_insensitive = true;
+                    }
+                    _ => {
+                        return Err(attr::unknown_option(
+                            nested,
+                            "unknown from_str option for a variant",
+                            &["rename", "ascii_case_insensitive"],
+                        ))
+                    }
+                }
+            }
+            // Two variants that parse from the same string would make the
+            // second one unreachable.
+            let duplicate = variants.iter().any(|other| {
+                let insensitive = other.case_insensitive || parsed.case_insensitive;
+                other.name == parsed.name
+                    || (insensitive && other.name.eq_ignore_ascii_case(&parsed.name))
+            });
+            if duplicate {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("another variant already parses from \"{}\"", parsed.name),
+                ));
+            }
+            variants.push(parsed);
+        }
+
+        Ok(FromStrInput {
+            input,
+            crate_path,
+            variants,
+        })
+    }
+}
+
+/// Emit the `FromStr` impl.
+impl ToTokens for FromStrInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let name_str = name.unraw().to_string();
+        let krate = &self.crate_path;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+
+        let checks = self.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let variant_name = &variant.name;
+            let matches = if variant.case_insensitive {
+                quote! { s.eq_ignore_ascii_case(#variant_name) }
+            } else {
+                quote! { s == #variant_name }
+            };
+            quote! {
+                if #matches {
+                    return ::core::result::Result::Ok(Self::#ident);
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #impl_generics ::core::str::FromStr for #name #ty_generics #where_clause {
+                type Err = #krate::ParseEnumError;
+
+                fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                    #(#checks)*
+                    ::core::result::Result::Err(#krate::ParseEnumError {
+                        type_name: #name_str,
+                        input: s.into(),
+                    })
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let input: FromStrInput = syn::parse_str(
+            "#[from_str(ascii_case_insensitive)]
+            enum Foo { #[from_str(rename = \"first\")] A, r#B }",
+        )
+        .unwrap();
+        assert_eq!(input.variants[0].name, "first");
+        assert_eq!(input.variants[1].name, "B");
+        assert!(input.variants[1].case_insensitive);
+
+        let input: FromStrInput =
+            syn::parse_str("enum Foo { A, #[from_str(ascii_case_insensitive)] B }").unwrap();
+        assert!(!input.variants[0].case_insensitive);
+        assert!(input.variants[1].case_insensitive);
+
+        syn::parse_str::<FromStrInput>("enum Foo { A(u8) }").unwrap_err();
+        syn::parse_str::<FromStrInput>("struct Foo;").unwrap_err();
+        syn::parse_str::<FromStrInput>("enum Foo { #[from_str(bogus)] A }").unwrap_err();
+        syn::parse_str::<FromStrInput>("enum Foo { A, #[from_str(rename = \"A\")] B }")
+            .unwrap_err();
+        syn::parse_str::<FromStrInput>("#[from_str(ascii_case_insensitive)] enum Foo { Ab, AB }")
+            .unwrap_err();
+        syn::parse_str::<FromStrInput>("enum Foo { Ab, AB }").unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+//! Helpers for working with a container's generic parameters.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Generics, Ident, Type};
+
+/// Check whether a field type mentions a lifetime other than `'static`
+/// or one of the container's generic type parameters.
+pub fn field_type_is_generic(generics: &Generics, ty: &Type) -> bool {
+    fn scan(tokens: TokenStream, params: &[Ident]) -> bool {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Punct(p) if p.as_char() == '\'' => {
+                    if let Some(TokenTree::Ident(lifetime)) = tokens.peek() {
+                        if lifetime != "static" {
+                            return true;
+                        }
+                    }
+                }
+                TokenTree::Ident(ident) if params.contains(&ident) => return true,
+                TokenTree::Group(group) if scan(group.stream(), params) => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    scan(ty.to_token_stream(), &params)
+}
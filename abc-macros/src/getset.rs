@@ -0,0 +1,199 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_quote, Data, DeriveInput, Field, Fields, Meta, NestedMeta, Visibility};
+
+use crate::attr::{self, lit_str};
+
+/// The input to `#[derive(Getters)]` or `#[derive(Setters)]`.
+///
+/// Both derives read the same `#[getset(...)]` options, so they share
+/// this; `getters()` and `setters()` generate the output for each.
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct GetSetInput {
+    input: DeriveInput,
+    fields: Vec<GetSetField>,
+}
+
+/// A named field of the struct, along with its `#[getset(...)]` options.
+#[cfg_attr(test, derive(Debug))]
+struct GetSetField {
+    field: Field,
+    // `#[getset(skip)]`: no getter or setter for this field.
+    skip: bool,
+    // `#[getset(vis = "...")]`: the visibility of the generated methods.
+    vis: Visibility,
+}
+
+/// Options from `#[getset(...)]`, on the struct or on a field.
+struct GetSetOptions {
+    skip: bool,
+    vis: Option<Visibility>,
+}
+
+impl GetSetOptions {
+    fn from_attributes(field: &Field) -> syn::parse::Result<Self> {
+        let mut options = GetSetOptions {
+            skip: false,
+            vis: None,
+        };
+        for nested in attr::options(&field.attrs, "getset")? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    options.skip = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("vis") => {
+                    options.vis = Some(parse_vis(&nv.lit)?);
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown getset option for a field",
+                        &["skip", "vis"],
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Parse a visibility like `vis = "pub(crate)"`. An empty string means
+/// private.
+fn parse_vis(lit: &syn::Lit) -> syn::parse::Result<Visibility> {
+    let lit = lit_str(lit)?;
+    if lit.value().is_empty() {
+        return Ok(Visibility::Inherited);
+    }
+    lit.parse()
+}
+
+/// Parse the input to `#[derive(Getters)]` or `#[derive(Setters)]`.
+impl Parse for GetSetInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+
+        // The container can set the default visibility for every field.
+        let mut vis: Visibility = parse_quote!(pub);
+        for nested in attr::options(&input.attrs, "getset")? {
+            match &nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("vis") => {
+                    vis = parse_vis(&nv.lit)?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown getset option",
+                        &["vis"],
+                    ))
+                }
+            }
+        }
+
+        // Methods are named after the fields, so the fields need names.
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => fields.named.clone(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        "getters and setters can only be derived for structs with named fields",
+                    ))
+                }
+            },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "getters and setters can only be derived for structs",
+                ))
+            }
+        };
+        let fields = fields
+            .into_iter()
+            .map(|field| {
+                let options = GetSetOptions::from_attributes(&field)?;
+                Ok(GetSetField {
+                    field,
+                    skip: options.skip,
+                    vis: options.vis.unwrap_or_else(|| vis.clone()),
+                })
+            })
+            .collect::<syn::parse::Result<_>>()?;
+
+        Ok(GetSetInput { input, fields })
+    }
+}
+
+impl GetSetInput {
+    /// Generate `fn field(&self) -> &T` for each field.
+    pub fn getters(&self) -> TokenStream {
+        let methods = self.fields.iter().filter(|f| !f.skip).map(|f| {
+            let GetSetField { field, vis, .. } = f;
+            let ident = &field.ident;
+            let ty = &field.ty;
+            let docs = field.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+            quote! {
+                #(#docs)*
+                #vis fn #ident(&self) -> &#ty {
+                    &self.#ident
+                }
+            }
+        });
+        self.inherent_impl(methods)
+    }
+
+    /// Generate `fn set_field(&mut self, value: T)` for each field.
+    pub fn setters(&self) -> TokenStream {
+        let methods = self.fields.iter().filter(|f| !f.skip).map(|f| {
+            let GetSetField { field, vis, .. } = f;
+            let ident = &field.ident;
+            let setter = format_ident!("set_{}", ident.as_ref().unwrap());
+            let ty = &field.ty;
+            let docs = field.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+            quote! {
+                #(#docs)*
+                #vis fn #setter(&mut self, value: #ty) {
+                    self.#ident = value;
+                }
+            }
+        });
+        self.inherent_impl(methods)
+    }
+
+    fn inherent_impl(&self, methods: impl Iterator<Item = TokenStream>) -> TokenStream {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #(#methods)*
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let input: GetSetInput = syn::parse_str(
+            "#[getset(vis = \"pub(crate)\")]
+            struct Foo { a: u8, #[getset(skip)] b: u8, #[getset(vis = \"\")] c: u8 }",
+        )
+        .unwrap();
+        assert!(!input.fields[0].skip);
+        assert_eq!(input.fields[0].vis, parse_quote!(pub(crate)));
+        assert!(input.fields[1].skip);
+        assert_eq!(input.fields[2].vis, Visibility::Inherited);
+
+        let input: GetSetInput = syn::parse_str("struct Foo { a: u8 }").unwrap();
+        assert_eq!(input.fields[0].vis, parse_quote!(pub));
+
+        syn::parse_str::<GetSetInput>("struct Foo { #[getset(bogus)] a: u8 }").unwrap_err();
+        syn::parse_str::<GetSetInput>("#[getset(skip)] struct Foo {}").unwrap_err();
+        syn::parse_str::<GetSetInput>("#[getset(vis = \"nope\")] struct Foo {}").unwrap_err();
+        syn::parse_str::<GetSetInput>("struct Foo(u8);").unwrap_err();
+    }
+}
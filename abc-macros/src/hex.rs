@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+/// This is the entire input to `hex!`.
+///
+/// Example: `hex!("deadbeef")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct HexInput {
+    bytes: Vec<u8>,
+}
+
+/// Decode a string of hex digits. Whitespace between bytes is ignored.
+fn decode(hex: &str) -> Result<Vec<u8>, String> {
+    let mut digits = Vec::new();
+    for (position, c) in hex.char_indices() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        match c.to_digit(16) {
+            Some(digit) => digits.push(digit as u8),
+            None => {
+                return Err(format!(
+                    "`{}` at position {} is not a hex digit",
+                    c.escape_default(),
+                    position
+                ))
+            }
+        }
+    }
+    if digits.len() % 2 != 0 {
+        return Err(format!(
+            "expected an even number of hex digits, found {}",
+            digits.len()
+        ));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
+
+/// Parse the input to `hex!`.
+impl Parse for HexInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let bytes = decode(&lit.value()).map_err(|message| syn::Error::new(lit.span(), message))?;
+        Ok(HexInput { bytes })
+    }
+}
+
+/// Emit a `[u8; N]` array.
+impl ToTokens for HexInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let bytes = &self.bytes;
+        tokens.extend(quote! {
+            [#(#bytes),*]
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_hex() {
+        assert_eq!(decode("deadBEEF").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(decode("00 ff\n10").unwrap(), [0x00, 0xff, 0x10]);
+        assert!(decode("").unwrap().is_empty());
+        assert_eq!(
+            decode("abc").unwrap_err(),
+            "expected an even number of hex digits, found 3"
+        );
+        assert_eq!(
+            decode("0x12").unwrap_err(),
+            "`x` at position 1 is not a hex digit"
+        );
+    }
+}
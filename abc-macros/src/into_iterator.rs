@@ -0,0 +1,223 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Data, DeriveInput, Ident, Member, Token, Type, WhereClause};
+
+use crate::attr;
+
+/// This is the entire input to `#[derive(IntoIterator)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct IntoIteratorInput {
+    input: DeriveInput,
+    // The collection field, and its type.
+    member: Member,
+    ty: Type,
+    // Which impls to emit: for the struct, `&` and `&mut`.
+    owned: bool,
+    by_ref: bool,
+    by_mut: bool,
+}
+
+/// One option in `#[into_iterator(...)]`: `field = items`, `owned`,
+/// `ref` or `ref_mut`.
+struct IntoIteratorOption {
+    name: Ident,
+    field: Option<Member>,
+}
+
+impl Parse for IntoIteratorOption {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        // `ref` is a keyword, so it isn't an ordinary `Ident`.
+        let name = if input.peek(Token![ref]) {
+            let token: Token![ref] = input.parse()?;
+            Ident::new("ref", token.span)
+        } else {
+            input.parse()?
+        };
+        let field = if name == "field" {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(IntoIteratorOption { name, field })
+    }
+}
+
+/// Parse the input to `#[derive(IntoIterator)]`.
+impl Parse for IntoIteratorInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+
+        let mut field = None;
+        let mut selected = Vec::new();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("into_iterator"))
+        {
+            let options = attr
+                .parse_args_with(Punctuated::<IntoIteratorOption, Token![,]>::parse_terminated)?;
+            for option in options {
+                match (option.name.to_string().as_str(), option.field) {
+                    ("field", Some(member)) => field = Some(member),
+                    ("owned" | "ref" | "ref_mut", None) => selected.push(option.name),
+                    _ => {
+                        return Err(attr::unknown_option(
+                            option.name,
+                            "unknown into_iterator option",
+                            &["field", "owned", "ref", "ref_mut"],
+                        ))
+                    }
+                }
+            }
+        }
+
+        let fields = match &input.data {
+            Data::Struct(data) => &data.fields,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "IntoIterator can only be derived for structs",
+                ))
+            }
+        };
+        let members: Vec<(Member, &Type)> = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(index.into()),
+                };
+                (member, &field.ty)
+            })
+            .collect();
+        let (member, ty) = match field {
+            Some(field) => match members.iter().find(|(member, _)| *member == field) {
+                Some((member, ty)) => (member.clone(), (*ty).clone()),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "this struct has no field with this name",
+                    ))
+                }
+            },
+            None if members.len() == 1 => (members[0].0.clone(), members[0].1.clone()),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "a struct without exactly one field needs \
+                     `#[into_iterator(field = ...)]` to choose the collection",
+                ))
+            }
+        };
+
+        // Without a selection, all three impls are emitted.
+        let all = selected.is_empty();
+        let has = |name: &str| all || selected.iter().any(|selected| selected == name);
+        Ok(IntoIteratorInput {
+            owned: has("owned"),
+            by_ref: has("ref"),
+            by_mut: has("ref_mut"),
+            input,
+            member,
+            ty,
+        })
+    }
+}
+
+/// Emit the `IntoIterator` impls.
+impl ToTokens for IntoIteratorInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let member = &self.member;
+        let ty = &self.ty;
+        let (impl_generics, ty_generics, _) = self.input.generics.split_for_impl();
+        // The `&` and `&mut` impls need a lifetime of their own.
+        let mut ref_generics = self.input.generics.clone();
+        ref_generics.params.insert(0, parse_quote!('__a));
+        let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+
+        // Each impl forwards to the field's impl, with a bound saying it
+        // exists, so it works for generic collections too.
+        let emit = |self_ty: TokenStream,
+                    field_ty: TokenStream,
+                    generics: TokenStream,
+                    field: TokenStream| {
+            let mut where_clause: WhereClause = match &self.input.generics.where_clause {
+                Some(where_clause) => where_clause.clone(),
+                None => parse_quote!(where),
+            };
+            where_clause
+                .predicates
+                .push(parse_quote!(#field_ty: ::core::iter::IntoIterator));
+            quote! {
+                impl #generics ::core::iter::IntoIterator for #self_ty #where_clause {
+                    type Item = <#field_ty as ::core::iter::IntoIterator>::Item;
+                    type IntoIter = <#field_ty as ::core::iter::IntoIterator>::IntoIter;
+
+                    fn into_iter(self) -> Self::IntoIter {
+                        ::core::iter::IntoIterator::into_iter(#field)
+                    }
+                }
+            }
+        };
+        if self.owned {
+            tokens.extend(emit(
+                quote! { #name #ty_generics },
+                quote! { #ty },
+                quote! { #impl_generics },
+                quote! { self.#member },
+            ));
+        }
+        if self.by_ref {
+            tokens.extend(emit(
+                quote! { &'__a #name #ty_generics },
+                quote! { &'__a #ty },
+                quote! { #ref_impl_generics },
+                quote! { &self.#member },
+            ));
+        }
+        if self.by_mut {
+            tokens.extend(emit(
+                quote! { &'__a mut #name #ty_generics },
+                quote! { &'__a mut #ty },
+                quote! { #ref_impl_generics },
+                quote! { &mut self.#member },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_field() {
+        let input: IntoIteratorInput = syn::parse_str("struct Names(Vec<String>);").unwrap();
+        assert_eq!(input.member, Member::Unnamed(0.into()));
+        assert!(input.owned && input.by_ref && input.by_mut);
+
+        let input: IntoIteratorInput = syn::parse_str(
+            "#[into_iterator(field = items, owned, ref)] struct Cart { id: u32, items: Vec<u8> }",
+        )
+        .unwrap();
+        assert_eq!(input.member, Member::Named(parse_quote!(items)));
+        assert!(input.owned && input.by_ref && !input.by_mut);
+
+        let input: IntoIteratorInput =
+            syn::parse_str("#[into_iterator(field = 1)] struct Pair(u8, Vec<u8>);").unwrap();
+        assert_eq!(input.member, Member::Unnamed(1.into()));
+
+        syn::parse_str::<IntoIteratorInput>("struct Cart { id: u32, items: Vec<u8> }").unwrap_err();
+        syn::parse_str::<IntoIteratorInput>("#[into_iterator(field = x)] struct S(Vec<u8>);")
+            .unwrap_err();
+        syn::parse_str::<IntoIteratorInput>("#[into_iterator(bogus)] struct S(Vec<u8>);")
+            .unwrap_err();
+        syn::parse_str::<IntoIteratorInput>("struct S;").unwrap_err();
+        syn::parse_str::<IntoIteratorInput>("enum E { A(Vec<u8>) }").unwrap_err();
+    }
+}
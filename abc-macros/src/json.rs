@@ -0,0 +1,351 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+/// A parsed JSON value.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    // Sorted by key.
+    Object(Vec<(String, Value)>),
+}
+
+/// A recursive-descent parser for JSON, as in RFC 8259.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("{} at position {}", message, self.position))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// Consume `expected`, if it's next.
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.text[self.position..].starts_with(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Value::String(self.string()?),
+            Some('-' | '0'..='9') => self.number()?,
+            _ if self.eat("null") => Value::Null,
+            _ if self.eat("true") => Value::Bool(true),
+            _ if self.eat("false") => Value::Bool(false),
+            Some(c) => return self.error(&format!("unexpected `{}`", c.escape_default())),
+            None => return self.error("expected a value"),
+        };
+        self.skip_whitespace();
+        Ok(value)
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.eat("{");
+        let mut entries: Vec<(String, Value)> = Vec::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return self.error("expected a string key");
+            }
+            let key_position = self.position;
+            let key = self.string()?;
+            if entries.iter().any(|(other, _)| *other == key) {
+                self.position = key_position;
+                return self.error(&format!("duplicate key {:?}", key));
+            }
+            self.skip_whitespace();
+            if !self.eat(":") {
+                return self.error("expected `:`");
+            }
+            let value = self.value()?;
+            entries.push((key, value));
+            if self.eat("}") {
+                break;
+            }
+            if !self.eat(",") {
+                return self.error("expected `,` or `}`");
+            }
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Value::Object(entries))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.eat("[");
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.eat("]") {
+            return Ok(Value::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            if self.eat("]") {
+                break;
+            }
+            if !self.eat(",") {
+                return self.error("expected `,` or `]`");
+            }
+        }
+        Ok(Value::Array(elements))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.eat("\"");
+        let mut string = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return self.error("unterminated string"),
+            };
+            match c {
+                '"' => {
+                    self.position += 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    self.position += 1;
+                    string.push(self.escape()?);
+                }
+                '\0'..='\x1f' => return self.error("control character in a string"),
+                _ => {
+                    self.position += c.len_utf8();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    /// The character for an escape sequence, after the `\`.
+    fn escape(&mut self) -> Result<char, String> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return self.error("unterminated string"),
+        };
+        self.position += c.len_utf8();
+        Ok(match c {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\x08',
+            'f' => '\x0c',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = self.hex4()?;
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    // A UTF-16 surrogate pair.
+                    if !self.eat("\\u") {
+                        return self.error("expected the second half of a surrogate pair");
+                    }
+                    let low = self.hex4()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return self.error("invalid surrogate pair");
+                    }
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                } else {
+                    high
+                };
+                match char::from_u32(code) {
+                    Some(c) => c,
+                    None => return self.error("invalid unicode escape"),
+                }
+            }
+            _ => return self.error(&format!("unknown escape `\\{}`", c.escape_default())),
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        // `from_str_radix` would also accept a sign.
+        match self.text.get(self.position..self.position + 4) {
+            Some(digits) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.position += 4;
+                Ok(u32::from_str_radix(digits, 16).unwrap())
+            }
+            _ => self.error("expected 4 hex digits"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            let count = parser.text[parser.position..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            parser.position += count;
+            count
+        };
+        self.eat("-");
+        if self.eat("0") {
+            if self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                return self.error("leading zero in a number");
+            }
+        } else if digits(self) == 0 {
+            return self.error("expected a digit");
+        }
+        if self.eat(".") && digits(self) == 0 {
+            return self.error("expected a digit after `.`");
+        }
+        if self.eat("e") || self.eat("E") {
+            if !self.eat("+") {
+                self.eat("-");
+            }
+            if digits(self) == 0 {
+                return self.error("expected a digit in the exponent");
+            }
+        }
+        let number: f64 = self.text[start..self.position].parse().unwrap();
+        if !number.is_finite() {
+            self.position = start;
+            return self.error("number is too large");
+        }
+        Ok(Value::Number(number))
+    }
+}
+
+/// Parse a complete JSON document.
+fn parse_json(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.value()?;
+    if parser.position < text.len() {
+        return parser.error("unexpected text after the value");
+    }
+    Ok(value)
+}
+
+/// This is the entire input to `json!`.
+///
+/// Example: `json!(r#"{"name": "abc", "tags": [1, 2]}"#)`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct JsonInput {
+    value: Value,
+}
+
+/// Parse the input to `json!`.
+impl Parse for JsonInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let value =
+            parse_json(&lit.value()).map_err(|message| syn::Error::new(lit.span(), message))?;
+        Ok(JsonInput { value })
+    }
+}
+
+impl ToTokens for Value {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Value::Null => quote! { ::abc::Json::Null },
+            Value::Bool(value) => quote! { ::abc::Json::Bool(#value) },
+            Value::Number(value) => quote! { ::abc::Json::Number(#value) },
+            Value::String(value) => quote! { ::abc::Json::String(#value) },
+            Value::Array(elements) => quote! { ::abc::Json::Array(&[#(#elements),*]) },
+            Value::Object(entries) => {
+                let keys = entries.iter().map(|(key, _)| key);
+                let values = entries.iter().map(|(_, value)| value);
+                quote! { ::abc::Json::Object(&[#((#keys, #values)),*]) }
+            }
+        });
+    }
+}
+
+/// Emit the value, as a constant `abc::Json`.
+impl ToTokens for JsonInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = &self.value;
+        tokens.extend(quote! {
+            {
+                const JSON: ::abc::Json = #value;
+                JSON
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        parse_json(text).unwrap_err()
+    }
+
+    #[test]
+    fn parse_values() {
+        use Value::*;
+        assert_eq!(parse_json(" null "), Ok(Null));
+        assert_eq!(
+            parse_json("[true, false]"),
+            Ok(Array(vec![Bool(true), Bool(false)]))
+        );
+        assert_eq!(parse_json("-1.5e2"), Ok(Number(-150.0)));
+        assert_eq!(parse_json("0"), Ok(Number(0.0)));
+        assert_eq!(
+            parse_json(r#""a\"\\\/\n\u00e9\ud83d\ude00""#),
+            Ok(String("a\"\\/\né😀".into()))
+        );
+        assert_eq!(
+            parse_json(r#"{"b": {}, "a": []}"#),
+            Ok(Object(vec![
+                ("a".into(), Array(vec![])),
+                ("b".into(), Object(vec![])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(error(""), "expected a value at position 0");
+        assert_eq!(error("[1, 2"), "expected `,` or `]` at position 5");
+        assert_eq!(error("[1,]"), "unexpected `]` at position 3");
+        assert_eq!(
+            error(r#"{"a": 1, "a": 2}"#),
+            "duplicate key \"a\" at position 9"
+        );
+        assert_eq!(error("{a: 1}"), "expected a string key at position 1");
+        assert_eq!(error("01"), "leading zero in a number at position 1");
+        assert_eq!(error("1."), "expected a digit after `.` at position 2");
+        assert_eq!(error("1e999"), "number is too large at position 0");
+        assert_eq!(error("\"\\x\""), "unknown escape `\\x` at position 3");
+        assert_eq!(
+            error("\"\\ud800\""),
+            "expected the second half of a surrogate pair at position 7"
+        );
+        assert_eq!(error("\"abc"), "unterminated string at position 4");
+        assert_eq!(error("nul"), "unexpected `n` at position 0");
+        assert_eq!(
+            error("1 2"),
+            "unexpected text after the value at position 2"
+        );
+        assert_eq!(error("'a'"), "unexpected `\\'` at position 0");
+    }
+}
@@ -0,0 +1,265 @@
+use abc_macros_core::Errors;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parse_quote, Attribute, Expr, Ident, Lit, Token, Type, UnOp, Visibility};
+
+/// One `key = value` or `key: Type = value` entry.
+struct Entry {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    ty: Type,
+    value: Expr,
+}
+
+/// The type of a literal: its suffix if it has one, or the type Rust
+/// would infer if it doesn't.
+fn literal_type(lit: &Lit) -> Option<Type> {
+    let suffixed = |suffix: &str, default: &str| {
+        let name = if suffix.is_empty() { default } else { suffix };
+        Ident::new(name, Span::call_site())
+    };
+    Some(match lit {
+        Lit::Str(_) => parse_quote!(&'static str),
+        Lit::ByteStr(_) => parse_quote!(&'static [u8]),
+        Lit::Byte(_) => parse_quote!(u8),
+        Lit::Char(_) => parse_quote!(char),
+        Lit::Bool(_) => parse_quote!(bool),
+        Lit::Int(int) => {
+            let ty = suffixed(int.suffix(), "i32");
+            parse_quote!(#ty)
+        }
+        Lit::Float(float) => {
+            let ty = suffixed(float.suffix(), "f64");
+            parse_quote!(#ty)
+        }
+        Lit::Verbatim(_) => return None,
+    })
+}
+
+/// The type of a value that's a literal, a negative number, or an array
+/// of those, and the expression to initialize the field with.
+fn infer(value: &Expr) -> syn::parse::Result<(Type, Expr)> {
+    let unknown = || {
+        syn::Error::new_spanned(
+            value,
+            "can't infer a type for this value; write `key: Type = value`",
+        )
+    };
+    match value {
+        Expr::Lit(lit) => Ok((literal_type(&lit.lit).ok_or_else(unknown)?, value.clone())),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => match &*unary.expr {
+            Expr::Lit(lit) if matches!(lit.lit, Lit::Int(_) | Lit::Float(_)) => {
+                Ok((literal_type(&lit.lit).ok_or_else(unknown)?, value.clone()))
+            }
+            _ => Err(unknown()),
+        },
+        Expr::Array(array) => {
+            let mut element_type: Option<Type> = None;
+            for element in &array.elems {
+                let (ty, _) = infer(element)?;
+                // Types are compared as tokens: `syn` only implements `Eq`
+                // for its syntax trees with the `extra-traits` feature.
+                match &element_type {
+                    Some(first)
+                        if first.to_token_stream().to_string()
+                            != ty.to_token_stream().to_string() =>
+                    {
+                        return Err(syn::Error::new_spanned(
+                            element,
+                            format!("expected an element of type `{}`", first.to_token_stream()),
+                        ))
+                    }
+                    Some(_) => {}
+                    None => element_type = Some(ty),
+                }
+            }
+            match element_type {
+                Some(ty) => Ok((parse_quote!(&'static [#ty]), parse_quote!(&#array))),
+                None => Err(syn::Error::new_spanned(
+                    value,
+                    "can't infer the type of an empty array; write `key: Type = []`",
+                )),
+            }
+        }
+        _ => Err(unknown()),
+    }
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name: Ident = input.parse()?;
+        let ty: Option<Type> = if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        input.parse::<Token![=]>()?;
+        let value: Expr = input.parse()?;
+        let (ty, value) = match ty {
+            Some(ty) => (ty, value),
+            None => infer(&value)?,
+        };
+        Ok(Entry {
+            attrs,
+            name,
+            ty,
+            value,
+        })
+    }
+}
+
+/// This is the entire input to `kv_config!`.
+///
+/// Example: `kv_config!(pub Config { host = "localhost", port: u16 = 8080 })`
+///
+pub struct KvConfigInput {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    entries: Vec<Entry>,
+}
+
+/// Parse the input to `kv_config!`.
+impl Parse for KvConfigInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let mut entries: Vec<Entry> = Vec::new();
+        for entry in errors.parse_terminated::<Entry>(&content) {
+            if entries.iter().any(|other| other.name == entry.name) {
+                errors.push_spanned(&entry.name, "duplicate key");
+                continue;
+            }
+            entries.push(entry);
+        }
+        errors.finish()?;
+        Ok(KvConfigInput {
+            attrs,
+            vis,
+            name,
+            entries,
+        })
+    }
+}
+
+/// Emit the struct and its `Default` impl.
+impl ToTokens for KvConfigInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let KvConfigInput {
+            attrs, vis, name, ..
+        } = self;
+        let field_attrs = self.entries.iter().map(|entry| &entry.attrs);
+        let names: Vec<&Ident> = self.entries.iter().map(|entry| &entry.name).collect();
+        let types = self.entries.iter().map(|entry| &entry.ty);
+        let values = self.entries.iter().map(|entry| &entry.value);
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis struct #name {
+                #(
+                    #(#field_attrs)*
+                    #vis #names: #types,
+                )*
+            }
+
+            impl ::core::default::Default for #name {
+                fn default() -> Self {
+                    #name {
+                        #(#names: #values,)*
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(input: &str) -> syn::parse::Result<Vec<String>> {
+        let input: KvConfigInput = syn::parse_str(input)?;
+        Ok(input
+            .entries
+            .iter()
+            .map(|entry| entry.ty.to_token_stream().to_string())
+            .collect())
+    }
+
+    #[test]
+    fn infer_types() {
+        assert_eq!(
+            types(
+                r#"Config {
+                    host = "localhost",
+                    port = 8080,
+                    small = 3u8,
+                    offset = -2,
+                    ratio = 0.5,
+                    precise = 1.5f32,
+                    verbose = false,
+                    sep = ',',
+                    magic = b"abc",
+                    tags = ["a", "b"],
+                    limits = [-1, 2],
+                    timeout: Duration = Duration::from_secs(3),
+                }"#
+            )
+            .unwrap(),
+            [
+                "& 'static str",
+                "i32",
+                "u8",
+                "i32",
+                "f64",
+                "f32",
+                "bool",
+                "char",
+                "& 'static [u8]",
+                "& 'static [& 'static str]",
+                "& 'static [i32]",
+                "Duration",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |input| types(input).unwrap_err().to_string();
+        assert_eq!(error("Config { a = 1, a = 2 }"), "duplicate key");
+        assert_eq!(
+            error("Config { tags = [] }"),
+            "can't infer the type of an empty array; write `key: Type = []`"
+        );
+        assert_eq!(
+            error("Config { tags = [1, \"two\"] }"),
+            "expected an element of type `i32`"
+        );
+        assert_eq!(
+            error("Config { timeout = Duration::from_secs(3) }"),
+            "can't infer a type for this value; write `key: Type = value`"
+        );
+        assert_eq!(
+            error("Config { on = !false }"),
+            "can't infer a type for this value; write `key: Type = value`"
+        );
+        let messages: Vec<String> = types("Config { a = 1, tags = [], a = 2 }")
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "can't infer the type of an empty array; write `key: Type = []`",
+                "duplicate key",
+            ]
+        );
+    }
+}
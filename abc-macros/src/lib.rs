@@ -1,56 +1,1994 @@
 //! ## abc-macros: macros used by the `abc` crate.
 //!
+//! Setting `ABC_MACRO_EXPAND_DIR` to a directory while building writes
+//! each macro's expansion there, in a file named after the call site and
+//! the macro, like `src_lib_rs_12_5-Builder.rs`. Changing the variable
+//! doesn't make cargo rebuild anything.
+//!
+//! The expansions are only pretty-printed with the `pretty_expand`
+//! feature, which isn't on by default; without it, each one is written
+//! as a single line of tokens.
+//!
+//! The expansions build on the oldest Rust that the calling crate's
+//! `rust-version` allows, down to 1.60, falling back to older APIs where
+//! the newer ones would be too new.
+//!
 
 extern crate proc_macro;
 
+// Which of these are used depends on which macros are enabled.
+#[allow(unused_imports)]
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, DeriveInput, LitStr};
+#[cfg(feature = "file_words")]
+use quote::quote;
+#[allow(unused_imports)]
+use quote::ToTokens;
+#[allow(unused_imports)]
+use syn::parse_macro_input;
+#[cfg(feature = "file_words")]
+use syn::LitStr;
+
+#[cfg(feature = "as_ref_str")]
+mod as_ref_str;
+// Shared helpers; with only some macros enabled, parts of them go unused.
+#[allow(dead_code)]
+mod attr;
+#[cfg(feature = "base64")]
+mod base64;
+#[cfg(feature = "bitfield")]
+mod bitfield;
+#[cfg(feature = "build_info")]
+mod build_info;
+#[cfg(feature = "builder")]
+mod builder;
+#[cfg(feature = "byte_size")]
+mod byte_size;
+#[allow(dead_code)]
+mod case;
+#[cfg(feature = "cmp")]
+mod cmp;
+#[cfg(feature = "color")]
+mod color;
+#[cfg(feature = "compile_time")]
+mod compile_time;
+#[cfg(feature = "cstr")]
+mod cstr;
+#[cfg(feature = "default_with_values")]
+mod default;
+#[cfg(feature = "delegate")]
+mod delegate;
+#[cfg(feature = "deprecated_alias")]
+mod deprecated_alias;
+#[cfg(feature = "deref")]
+mod deref;
+#[cfg(feature = "describe")]
+mod describe;
+#[cfg(feature = "describe_all")]
+mod describe_all;
+#[cfg(feature = "display")]
+mod display;
+#[cfg(feature = "duration")]
+mod duration;
+#[cfg(feature = "enum_bitmask")]
+mod enum_bitmask;
+#[cfg(feature = "enum_discriminants")]
+mod enum_discriminants;
+#[cfg(feature = "enum_iter")]
+mod enum_iter;
+#[cfg(feature = "enum_strings")]
+mod enum_strings;
+#[cfg(feature = "env_or")]
+mod env_or;
+#[cfg(feature = "error")]
+mod error;
+// Every macro finishes with `expand::finish`, so with no macro features
+// enabled nothing here is used.
+#[allow(dead_code)]
+mod expand;
+#[cfg(feature = "from")]
+mod from;
+#[cfg(feature = "from_str")]
+mod from_str;
+#[cfg(any(feature = "cmp", feature = "describe"))]
+mod generics;
+#[cfg(feature = "getset")]
+mod getset;
+#[cfg(feature = "hex")]
+mod hex;
+#[cfg(feature = "into_iterator")]
+mod into_iterator;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "kv_config")]
+mod kv_config;
+#[cfg(feature = "log_errors")]
+mod log_errors;
+#[cfg(feature = "make_ident")]
+mod make_ident;
+#[cfg(feature = "mock_trait")]
+mod mock_trait;
+#[cfg(any(
+    feature = "cstr",
+    feature = "describe",
+    feature = "error",
+    feature = "net",
+    feature = "regex",
+    feature = "singleton",
+))]
+mod msrv;
+#[cfg(feature = "must_use_all")]
+mod must_use_all;
+#[cfg(feature = "net")]
+mod net;
+#[cfg(feature = "newtype")]
+mod newtype;
+#[cfg(feature = "regex")]
+mod regex;
+#[cfg(feature = "retry")]
+mod retry;
+#[cfg(feature = "rustc_version")]
+mod rustc_version;
+#[cfg(feature = "describe")]
+mod schema;
+#[cfg(feature = "semver_macro")]
+mod semver;
+// Shared by the derives for fieldless enums; with only some of them
+// enabled, parts of it go unused.
+#[cfg(any(
+    feature = "as_ref_str",
+    feature = "enum_discriminants",
+    feature = "enum_iter",
+    feature = "from_str",
+    feature = "try_from_primitive",
+    feature = "variant_count",
+))]
+#[allow(dead_code)]
+mod shallow;
+#[cfg(feature = "singleton")]
+mod singleton;
+#[cfg(feature = "soa")]
+mod soa;
+#[cfg(feature = "sorted")]
+mod sorted;
+#[cfg(feature = "state_machine")]
+mod state_machine;
+#[cfg(feature = "static_assert")]
+mod static_assert;
+#[cfg(feature = "static_map")]
+mod static_map;
+#[cfg(feature = "table")]
+mod table;
+#[cfg(feature = "test_matrix")]
+mod test_matrix;
+#[cfg(feature = "timed")]
+mod timed;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "try_from_primitive")]
+mod try_from_primitive;
+#[cfg(feature = "typestate")]
+mod typestate;
+#[cfg(feature = "units")]
+mod units;
+#[cfg(feature = "uuid_macro")]
+mod uuid;
+#[cfg(feature = "variant_count")]
+mod variant_count;
+#[cfg(any(
+    feature = "delegate",
+    feature = "deprecated_alias",
+    feature = "log_errors",
+    feature = "mock_trait",
+    feature = "retry",
+    feature = "timed",
+    feature = "trace",
+))]
+#[allow(dead_code)]
+mod wrap;
+#[cfg(feature = "as_ref_str")]
+use as_ref_str::AsRefStrInput;
+#[cfg(feature = "base64")]
+use base64::Base64Input;
+#[cfg(feature = "bitfield")]
+use bitfield::BitfieldInput;
+#[cfg(feature = "build_info")]
+use build_info::{BuildInfoInput, GitVersionInput};
+#[cfg(feature = "builder")]
+use builder::BuilderInput;
+#[cfg(feature = "byte_size")]
+use byte_size::ByteSizeInput;
+#[cfg(feature = "cmp")]
+use cmp::{CmpInput, CmpTrait};
+#[cfg(feature = "color")]
+use color::ColorInput;
+#[cfg(feature = "compile_time")]
+use compile_time::CompileTimeInput;
+#[cfg(feature = "cstr")]
+use cstr::CStrInput;
+#[cfg(feature = "default_with_values")]
+use default::DefaultInput;
+#[cfg(feature = "delegate")]
+use delegate::DelegateInput;
+#[cfg(feature = "deprecated_alias")]
+use deprecated_alias::{DeprecatedAlias, DeprecatedAliasArgs};
+#[cfg(feature = "deref")]
+use deref::DerefInput;
+#[cfg(feature = "describe")]
+use describe::DescribeInput;
+#[cfg(feature = "describe_all")]
+use describe_all::{DescribeAll, DescribeAllArgs};
+#[cfg(feature = "display")]
+use display::DisplayInput;
+#[cfg(feature = "duration")]
+use duration::DurationInput;
+#[cfg(feature = "enum_bitmask")]
+use enum_bitmask::BitmaskEnum;
+#[cfg(feature = "enum_discriminants")]
+use enum_discriminants::EnumDiscriminantsInput;
+#[cfg(feature = "enum_iter")]
+use enum_iter::EnumIterInput;
+#[cfg(feature = "enum_strings")]
+use enum_strings::StringEnum;
+#[cfg(feature = "env_or")]
+use env_or::EnvOrInput;
+#[cfg(feature = "error")]
+use error::ErrorInput;
+#[cfg(feature = "from")]
+use from::FromInput;
+#[cfg(feature = "from_str")]
+use from_str::FromStrInput;
+#[cfg(feature = "getset")]
+use getset::GetSetInput;
+#[cfg(feature = "hex")]
+use hex::HexInput;
+#[cfg(feature = "into_iterator")]
+use into_iterator::IntoIteratorInput;
+#[cfg(feature = "json")]
+use json::JsonInput;
+#[cfg(feature = "kv_config")]
+use kv_config::KvConfigInput;
+#[cfg(feature = "log_errors")]
+use log_errors::{LogErrors, LogErrorsArgs};
+#[cfg(feature = "make_ident")]
+use make_ident::MakeIdentInput;
+#[cfg(feature = "mock_trait")]
+use mock_trait::MockTraitInput;
+#[cfg(feature = "must_use_all")]
+use must_use_all::MustUseAll;
+#[cfg(feature = "net")]
+use net::{IpInput, SockAddrInput};
+#[cfg(feature = "newtype")]
+use newtype::NewtypeInput;
+#[cfg(feature = "regex")]
+use regex::RegexInput;
+#[cfg(feature = "retry")]
+use retry::{Retry, RetryArgs};
+#[cfg(feature = "rustc_version")]
+use rustc_version::RustcVersionInput;
+#[cfg(feature = "semver_macro")]
+use semver::SemverInput;
+#[cfg(feature = "singleton")]
+use singleton::{Singleton, SingletonArgs};
+#[cfg(feature = "soa")]
+use soa::SoaInput;
+#[cfg(feature = "sorted")]
+use sorted::Sorted;
+#[cfg(feature = "state_machine")]
+use state_machine::StateMachine;
+#[cfg(feature = "static_assert")]
+use static_assert::StaticAssertInput;
+#[cfg(feature = "static_map")]
+use static_map::StaticMapInput;
+#[cfg(feature = "table")]
+use table::TableInput;
+#[cfg(feature = "test_matrix")]
+use test_matrix::{TestMatrix, TestMatrixArgs};
+#[cfg(feature = "timed")]
+use timed::{Timed, TimedArgs};
+#[cfg(feature = "trace")]
+use trace::{Trace, TraceArgs};
+#[cfg(feature = "try_from_primitive")]
+use try_from_primitive::TryFromPrimitiveInput;
+#[cfg(feature = "typestate")]
+use typestate::TypestateInput;
+#[cfg(feature = "units")]
+use units::UnitsInput;
+#[cfg(feature = "uuid_macro")]
+use uuid::UuidInput;
+#[cfg(feature = "variant_count")]
+use variant_count::VariantCountInput;
 
-/// Derive the `DescribeStruct` trait on a struct (or enum).
+/// Derive the `DescribeStruct` trait on a struct (or enum, or union).
+///
+/// Unions are described like structs, except that their fields are never
+/// read, so no nested descriptions are generated for them.
 ///
 /// This macro will output code like:
 /// ```ignore
-/// impl DescribeStruct for Foo {
+/// impl ::abc::DescribeStruct for Foo {
 ///     fn struct_name(&self) -> &'static str {
 ///         "Foo"
 ///     }
+///
+///     fn type_path(&self) -> &'static str {
+///         concat!(module_path!(), "::", "Foo")
+///     }
+///
+///     fn description(&self) -> &'static str {
+///         "The doc comments on Foo."
+///     }
+///
+///     // For an enum, this is `variant_count` instead.
+///     fn field_count(&self) -> usize {
+///         2
+///     }
+///
+///     fn size_of(&self) -> usize {
+///         ::core::mem::size_of::<Self>()
+///     }
+///
+///     // ... and so on, for the rest of the trait.
 /// }
 /// ```
 ///
-/// As a special case, if the name of the struct is `OhNo`, the
-/// macro will return a compile error.
+/// It also adds an associated const, `Foo::DESCRIPTOR`, holding an
+/// `abc::StructDescriptor` with the type's name and fields. Structs and
+/// unions also get `Foo::FIELD_OFFSETS`, the byte offset of each field.
+///
+/// Generic types are supported, and report the names of their generic
+/// parameters along with the type name, e.g. `"Buf<N>"`.
+///
+/// Options can be set with a `#[describe(...)]` attribute on the struct:
 ///
-#[proc_macro_derive(DescribeStruct)]
+/// - `#[describe(display)]` also implements `Display`, printing the
+///   struct name.
+/// - `#[describe(json_schema)]` also adds `Foo::json_schema()`, returning
+///   a JSON Schema for the type as serde would serialize it. The schema is
+///   built from the type definition, so field types that aren't
+///   primitives, strings, or std containers can hold any value.
+/// - `#[describe(instrument)]` adds `type_name` and `type_path` fields to
+///   the span created by `trace_span` (with the `tracing` feature).
+/// - `#[describe(rename_all = "snake_case")]` converts the reported
+///   struct, field, and variant names. The same cases as serde are
+///   available: `"lowercase"`, `"UPPERCASE"`, `"PascalCase"`,
+///   `"camelCase"`, `"snake_case"`, `"kebab-case"`,
+///   `"SCREAMING_SNAKE_CASE"` and `"SCREAMING-KEBAB-CASE"`. The
+///   `type_path` is not renamed.
+/// - `#[describe(debug_expand)]` writes the code generated for the type to
+///   `$OUT_DIR/abc_expansions/<Type>.rs` during compilation, for crates
+///   with a build script, or to stderr otherwise.
+/// - `#[describe(skip_zst)]` leaves zero-sized fields, like `PhantomData`
+///   markers, out of `describe()` and `visit_fields`. They're still
+///   counted by `field_count` and listed in `DESCRIPTOR`.
+/// - `#[describe(use_serde_names)]` reports the names from any
+///   `#[serde(rename = "...")]` and `#[serde(rename_all = "...")]`
+///   attributes, following serde's rules. Where serde doesn't rename
+///   something, `rename_all` still applies.
+/// - `#[describe(crate = "::my_facade")]` changes the path used to find the
+///   `DescribeStruct` trait and its supporting types, for crates that
+///   re-export `abc`. The default is `::abc`.
+///
+/// - `#[describe(deny_names("Temp", "Foo2"))]` sets a list of names that
+///   aren't allowed, replacing the default list.
+///
+/// Fields can have options too:
+///
+/// - `#[describe(label = "Retry count")]` and
+///   `#[describe(doc = "Number of times to retry")]` attach a human-facing
+///   name and description, reported in `describe()` and `DESCRIPTOR`.
+///
+/// With the `tracing` feature enabled, the macro implements `trace_span`,
+/// creating a span named after the type.
+///
+/// With the `registry` feature enabled, the macro also registers the type
+/// so that it can be found with `abc::registry::lookup`.
+///
+/// If the name of the struct is in the deny list (by default, just `OhNo`),
+/// the macro will return a compile error.
+///
+/// `#[describe(require_name_matches = "^[A-Z][A-Za-z0-9]*Request$")]`
+/// makes it a compile error for the type name not to match a regular
+/// expression. This needs the `describe_regex` feature, which is on by
+/// default.
+///
+#[cfg(feature = "describe")]
+#[proc_macro_derive(DescribeStruct, attributes(describe))]
 pub fn derive_describe_struct(input: TokenStream) -> TokenStream {
-    // parse the input into a DeriveInput syntax tree
-    let input = parse_macro_input!(input as DeriveInput);
+    let described = parse_macro_input!(input as DescribeInput);
+    let expanded = described.to_token_stream();
+    let out_dir = std::env::var_os("OUT_DIR").map(std::path::PathBuf::from);
+    described.debug_expand(&expanded, out_dir.as_deref());
+
+    // proc_macro2::TokenStream -> proc_macro::TokenStream
+    expand::finish("#[derive(DescribeStruct)]", expanded)
+}
+
+/// Derive a builder for a struct with named fields.
+///
+/// For a struct `Foo`, this generates `FooBuilder`, with a setter method
+/// for each field, and `Foo::builder()` to create one:
+///
+/// ```ignore
+/// #[derive(Builder)]
+/// struct Request {
+///     url: String,
+///     #[builder(default)]
+///     retries: u8,
+/// }
+///
+/// let request = Request::builder().url("/".to_string()).build()?;
+/// ```
+///
+/// `build()` returns `Err(abc::BuilderError::MissingField(..))` if a
+/// field wasn't set. Field options:
+///
+/// - `#[builder(default)]` uses `Default::default()` for a field that
+///   wasn't set.
+/// - `#[builder(into)]` makes the setter accept any `impl Into<T>`.
+///
+/// Like `DescribeStruct`, `#[builder(crate = "::my_facade")]` changes the
+/// path used to find `abc`.
+#[cfg(feature = "builder")]
+#[proc_macro_derive(Builder, attributes(builder))]
+pub fn derive_builder(input: TokenStream) -> TokenStream {
+    let builder = parse_macro_input!(input as BuilderInput);
+    expand::finish("#[derive(Builder)]", builder.into_token_stream())
+}
 
-    // Retrieve the Ident that is the struct name
-    let name = &input.ident;
+/// Derive a getter method for each field of a struct.
+///
+/// A field `name: T` gets `fn name(&self) -> &T`, with the field's doc
+/// comments. Options:
+///
+/// - `#[getset(skip)]` on a field leaves it out.
+/// - `#[getset(vis = "pub(crate)")]`, on the struct or on a field, sets
+///   the visibility of the methods. The default is `pub`, and `vis = ""`
+///   makes them private.
+///
+/// The same options apply to `#[derive(Setters)]`.
+#[cfg(feature = "getset")]
+#[proc_macro_derive(Getters, attributes(getset))]
+pub fn derive_getters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as GetSetInput);
+    expand::finish("#[derive(Getters)]", input.getters())
+}
 
-    // TODO #2: Return a compile error if the name of the struct is "OhNo"
+/// Derive a setter method for each field of a struct.
+///
+/// A field `name: T` gets `fn set_name(&mut self, value: T)`. See
+/// `#[derive(Getters)]` for the options.
+#[cfg(feature = "getset")]
+#[proc_macro_derive(Setters, attributes(getset))]
+pub fn derive_setters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as GetSetInput);
+    expand::finish("#[derive(Setters)]", input.setters())
+}
 
-    // Generate the output tokens.
-    // TODO #1: return the correct result instead of "STRUCT_NAME_HERE"
-    let expanded = quote! {
-        impl DescribeStruct for #name {
-            fn struct_name(&self) -> &'static str {
-                "STRUCT_NAME_HERE"
-            }
-        }
-    };
+/// Derive `fn iter()`, iterating over every variant of an enum.
+///
+/// ```ignore
+/// #[derive(EnumIter)]
+/// enum Suit {
+///     Clubs,
+///     Diamonds,
+///     Hearts,
+///     Spades,
+/// }
+///
+/// assert_eq!(Suit::iter().count(), 4);
+/// ```
+///
+/// Every variant must be a unit variant; a variant with fields is a
+/// compile error.
+#[cfg(feature = "enum_iter")]
+#[proc_macro_derive(EnumIter)]
+pub fn derive_enum_iter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as EnumIterInput);
+    expand::finish("#[derive(EnumIter)]", input.into_token_stream())
+}
 
-    // proc_macro2::TokenStream -> proc_macro::TokenStream
-    expanded.into()
+/// Derive `TryFrom<int>` for an enum, from its discriminants.
+///
+/// The integer type is the one in the enum's `#[repr(...)]`, which is
+/// required:
+///
+/// ```ignore
+/// #[derive(TryFromPrimitive)]
+/// #[repr(u8)]
+/// enum Command {
+///     Read = 1,
+///     Write = 2,
+///     Erase = 0x10,
+/// }
+///
+/// assert_eq!(Command::try_from(2), Ok(Command::Write));
+/// assert_eq!(Command::try_from(3), Err(3));
+/// ```
+///
+/// Like `enum_ranges!`, the error is the value that didn't match. Every
+/// variant must be a unit variant.
+#[cfg(feature = "try_from_primitive")]
+#[proc_macro_derive(TryFromPrimitive)]
+pub fn derive_try_from_primitive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as TryFromPrimitiveInput);
+    expand::finish("#[derive(TryFromPrimitive)]", input.into_token_stream())
+}
+
+/// Derive `Display` from a format string.
+///
+/// The format string goes in a `#[display("...")]` attribute, and can
+/// refer to fields by name, or by position for tuple fields:
+///
+/// ```ignore
+/// #[derive(Display)]
+/// #[display("{name} ({id})")]
+/// struct User {
+///     name: String,
+///     id: u32,
+/// }
+///
+/// #[derive(Display)]
+/// enum NetError {
+///     #[display("connection lost")]
+///     Lost,
+///     #[display("timed out after {0} seconds")]
+///     Timeout(u32),
+///     Refused,
+/// }
+/// ```
+///
+/// Structs need a format string. An enum variant without one uses the
+/// enum's format string if it has one, or else prints the variant name.
+/// No bounds are added for generic parameters, so a `T` that's formatted
+/// needs a `T: Display` bound on the type.
+#[cfg(feature = "display")]
+#[proc_macro_derive(Display, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DisplayInput);
+    expand::finish("#[derive(Display)]", input.into_token_stream())
+}
+
+/// Derive `FromStr` for an enum, parsing the variant names.
+///
+/// ```ignore
+/// #[derive(FromStr)]
+/// #[from_str(ascii_case_insensitive)]
+/// enum Level {
+///     Debug,
+///     #[from_str(rename = "warn")]
+///     Warning,
+/// }
+///
+/// assert!(matches!("DEBUG".parse(), Ok(Level::Debug)));
+/// ```
+///
+/// Every variant must be a unit variant. Options:
+///
+/// - `#[from_str(rename = "...")]` on a variant parses it from a different
+///   string.
+/// - `#[from_str(ascii_case_insensitive)]`, on the enum or on a variant,
+///   ignores ASCII case when matching.
+/// - `#[from_str(crate = "::my_facade")]` changes the path used to find
+///   `abc`.
+///
+/// The error is an `abc::ParseEnumError` holding the input that didn't
+/// match.
+#[cfg(feature = "from_str")]
+#[proc_macro_derive(FromStr, attributes(from_str))]
+pub fn derive_from_str(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as FromStrInput);
+    expand::finish("#[derive(FromStr)]", input.into_token_stream())
+}
+
+/// Derive `Default`, with values chosen per field.
+///
+/// ```ignore
+/// #[derive(DefaultWithValues)]
+/// struct Config {
+///     #[default(8080)]
+///     port: u16,
+///     #[default("localhost")]
+///     host: String,
+///     verbose: bool,
+/// }
+/// ```
+///
+/// A field with `#[default(...)]` starts out with that value, which can
+/// be any expression. A string literal is converted with `Into`, so it
+/// works for `String` fields. Fields without the attribute use
+/// `Default::default()`.
+///
+/// This works for structs of any kind. No bounds are added for generic
+/// parameters, so add them to the struct if they're needed.
+#[cfg(feature = "default_with_values")]
+#[proc_macro_derive(DefaultWithValues, attributes(default))]
+pub fn derive_default_with_values(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DefaultInput);
+    expand::finish("#[derive(DefaultWithValues)]", input.into_token_stream())
+}
+
+/// Derive `as_str` for an enum, returning the name of the variant.
+///
+/// ```ignore
+/// #[derive(AsRefStr)]
+/// #[as_str(rename_all = "snake_case")]
+/// enum Event {
+///     KeyDown(u32),
+///     #[as_str(rename = "click")]
+///     MouseClick { x: i32, y: i32 },
+/// }
+///
+/// assert_eq!(Event::KeyDown(13).as_str(), "key_down");
+/// ```
+///
+/// This emits `const fn as_str(&self) -> &'static str`, and implements
+/// `AsRef<str>` and `From<&Enum>` and `From<Enum>` for `&'static str`.
+/// Variants can have fields, which are ignored. Options:
+///
+/// - `#[as_str(rename_all = "...")]` on the enum changes the case of
+///   every variant name, as in serde.
+/// - `#[as_str(rename = "...")]` on a variant uses a different string.
+///
+/// Two variants with the same string are a compile error.
+#[cfg(feature = "as_ref_str")]
+#[proc_macro_derive(AsRefStr, attributes(as_str))]
+pub fn derive_as_ref_str(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as AsRefStrInput);
+    expand::finish("#[derive(AsRefStr)]", input.into_token_stream())
+}
+
+/// Derive `IntoIterator` for a struct wrapping a collection.
+///
+/// ```ignore
+/// #[derive(IntoIterator)]
+/// struct Names(Vec<String>);
+///
+/// #[derive(IntoIterator)]
+/// #[into_iterator(field = items)]
+/// struct Cart {
+///     id: u32,
+///     items: Vec<Item>,
+/// }
+/// ```
+///
+/// This forwards `IntoIterator` for the struct, `&` the struct, and
+/// `&mut` the struct, to the same impls on the field. A struct with more
+/// than one field needs `#[into_iterator(field = ...)]` to choose it. To
+/// emit only some of the impls, list them, like
+/// `#[into_iterator(owned, ref)]`; that's needed for collections like
+/// `HashSet` that can't be iterated through `&mut`.
+#[cfg(feature = "into_iterator")]
+#[proc_macro_derive(IntoIterator, attributes(into_iterator))]
+pub fn derive_into_iterator(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as IntoIteratorInput);
+    expand::finish("#[derive(IntoIterator)]", input.into_token_stream())
+}
+
+/// Derive `Deref` for a struct, dereferencing to one of its fields.
+///
+/// ```ignore
+/// #[derive(Deref, DerefMut)]
+/// struct Name(String);
+///
+/// #[derive(Deref)]
+/// struct User {
+///     id: u32,
+///     #[deref]
+///     name: String,
+/// }
+/// ```
+///
+/// A struct with one field dereferences to it. A struct with more fields
+/// needs one of them marked `#[deref]`; otherwise it's ambiguous, which is
+/// a compile error.
+#[cfg(feature = "deref")]
+#[proc_macro_derive(Deref, attributes(deref))]
+pub fn derive_deref(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match DerefInput::new(input, false) {
+        Ok(deref) => expand::finish("#[derive(Deref)]", deref.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derive `DerefMut` for a struct, dereferencing to one of its fields.
+///
+/// The field is chosen the same way as for `#[derive(Deref)]`, which is
+/// also needed.
+#[cfg(feature = "deref")]
+#[proc_macro_derive(DerefMut, attributes(deref))]
+pub fn derive_deref_mut(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match DerefInput::new(input, true) {
+        Ok(deref) => expand::finish("#[derive(DerefMut)]", deref.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derive `From` for a wrapper struct, or for the variants of an enum.
+///
+/// ```ignore
+/// #[derive(From)]
+/// struct UserId(u32);
+///
+/// #[derive(From)]
+/// enum Error {
+///     Io(io::Error),
+///     Parse { source: ParseIntError },
+///     #[from(skip)]
+///     Exit(i32),
+///     Unknown,
+/// }
+/// ```
+///
+/// A struct must have exactly one field, and gets `From` that field's
+/// type. An enum gets `From<T>` for each variant with a single field of
+/// type `T`; variants with no fields or several are left out, and so are
+/// ones marked `#[from(skip)]`. Two variants converting from the same
+/// type would conflict, so that's a compile error.
+#[cfg(feature = "from")]
+#[proc_macro_derive(From, attributes(from))]
+pub fn derive_from(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as FromInput);
+    expand::finish("#[derive(From)]", input.into_token_stream())
+}
+
+/// Derive `Display` and `std::error::Error` for an error type.
+///
+/// ```ignore
+/// #[derive(Error, Debug)]
+/// enum ConfigError {
+///     #[error("can't read the config file")]
+///     Io(#[from] io::Error),
+///     #[error("line {line}: bad number")]
+///     Parse {
+///         line: u32,
+///         #[source]
+///         cause: ParseIntError,
+///     },
+///     #[error("missing key `{0}`")]
+///     Missing(String),
+/// }
+/// ```
+///
+/// The messages in `#[error("...")]` work the same as the format strings
+/// of `#[derive(Display)]`, including falling back to the variant name.
+///
+/// `Error::source` returns the field marked `#[source]`, or else the
+/// field named `source`, if there is one. `#[from]` marks the source too,
+/// and also implements `From` for its type; it has to be the only field
+/// of its struct or variant. A source can be any `Error + 'static` type,
+/// or a `Box<dyn Error>` (with or without `Send` and `Sync`).
+#[cfg(feature = "error")]
+#[proc_macro_derive(Error, attributes(error, source, from))]
+pub fn derive_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ErrorInput);
+    expand::finish("#[derive(Error)]", input.into_token_stream())
+}
+
+/// Derive `PartialEq`, leaving out the fields marked `#[cmp(skip)]`.
+///
+/// ```ignore
+/// #[derive(PartialEqSkip, HashSkip)]
+/// struct Document {
+///     text: String,
+///     #[cmp(skip)]
+///     word_count: Cell<Option<usize>>,
+/// }
+/// ```
+///
+/// This is like the standard derive, for structs and enums, except that
+/// skipped fields don't affect equality: a cache, a timestamp, or
+/// anything else derived from the other fields. Values of different enum
+/// variants are never equal.
+///
+/// Fields whose types mention a generic parameter get a `PartialEq`
+/// bound; skipped fields don't need one. If `Eq` is wanted too, add an
+/// empty `impl Eq` by hand, since `#[derive(Eq)]` checks every field.
+#[cfg(feature = "cmp")]
+#[proc_macro_derive(PartialEqSkip, attributes(cmp))]
+pub fn derive_partial_eq_skip(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match CmpInput::new(input, CmpTrait::PartialEq) {
+        Ok(cmp) => expand::finish("#[derive(PartialEqSkip)]", cmp.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derive `Hash`, leaving out the fields marked `#[cmp(skip)]`.
+///
+/// Use it with `#[derive(PartialEqSkip)]`, which skips the same fields,
+/// so that values that are equal have the same hash. An enum's variant is
+/// hashed along with its fields.
+#[cfg(feature = "cmp")]
+#[proc_macro_derive(HashSkip, attributes(cmp))]
+pub fn derive_hash_skip(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match CmpInput::new(input, CmpTrait::Hash) {
+        Ok(cmp) => expand::finish("#[derive(HashSkip)]", cmp.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derive `const VARIANT_COUNT: usize`, the number of variants of an enum.
+///
+/// ```ignore
+/// #[derive(VariantCount)]
+/// enum Message {
+///     Ping,
+///     Text(String),
+///     Move { x: i32, y: i32 },
+/// }
+///
+/// let mut seen = [0; Message::VARIANT_COUNT];
+/// ```
+///
+/// Every variant counts, whether or not it has fields. A variant behind
+/// `#[cfg]` counts only when it's compiled in.
+#[cfg(feature = "variant_count")]
+#[proc_macro_derive(VariantCount)]
+pub fn derive_variant_count(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as VariantCountInput);
+    expand::finish("#[derive(VariantCount)]", input.into_token_stream())
+}
+
+/// Derive a fieldless enum of an enum's variants, and `fn kind()`.
+///
+/// ```ignore
+/// #[derive(EnumDiscriminants)]
+/// enum Shape {
+///     Empty,
+///     Circle(f64),
+///     Rect { width: f64, height: f64 },
+/// }
+///
+/// assert_eq!(Shape::Circle(1.0).kind(), ShapeKind::Circle);
+/// ```
+///
+/// The new enum is named after the original with `Kind` on the end, and
+/// has the same visibility. It derives `Clone`, `Copy`, `Debug`,
+/// `PartialEq`, `Eq` and `Hash`, and `ShapeKind: From<&Shape>`. Options on
+/// the enum:
+///
+/// - `#[discriminants(name = "ShapeTag")]` names it something else.
+/// - `#[discriminants(derive(PartialOrd, Ord))]` adds more derives.
+///
+/// `#[doc]` and `#[cfg]` attributes on a variant are copied to it.
+#[cfg(feature = "enum_discriminants")]
+#[proc_macro_derive(EnumDiscriminants, attributes(discriminants))]
+pub fn derive_enum_discriminants(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as EnumDiscriminantsInput);
+    expand::finish("#[derive(EnumDiscriminants)]", input.into_token_stream())
+}
+
+/// Derive `DescribeStruct` for every struct and enum in a module.
+///
+/// ```ignore
+/// #[describe_all]
+/// mod messages {
+///     pub struct Ping;
+///     #[describe(display)]
+///     pub struct Pong;
+/// }
+/// ```
+///
+/// Structs and enums in nested inline modules get the derive too, and
+/// items that already derive `DescribeStruct` are left alone. Per-item
+/// `#[describe(...)]` options still work as usual.
+///
+/// The module must be inline; `mod messages;` can't be changed by a macro.
+/// If `abc` is only reachable through a re-export, pass its path with
+/// `#[describe_all(crate = "my_facade::abc")]`.
+#[cfg(feature = "describe_all")]
+#[proc_macro_attribute]
+pub fn describe_all(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as DescribeAllArgs);
+    let module = parse_macro_input!(item as syn::ItemMod);
+    match DescribeAll::new(args, module) {
+        Ok(described) => expand::finish("#[describe_all]", described.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Log a function's arguments when it's called, and its return value.
+///
+/// ```ignore
+/// #[trace(skip(password))]
+/// fn login(user: &str, password: &str) -> bool {
+///     // ...
+/// }
+/// ```
+///
+/// Calling `login("alice", "hunter2")` prints `-> login(user = "alice")`
+/// to stderr, and then `<- login = true` when it returns. Values that
+/// don't implement `Debug` (including generic ones) print as `..`, and
+/// arguments bound by a pattern, like `(a, b): (u8, u8)`, aren't shown.
+/// `async fn`s log when their future finishes.
+///
+/// `#[trace(skip(arg, ...))]` leaves arguments out of the log, and
+/// `#[trace(crate = "my_facade::abc")]` changes the path used to find
+/// `abc`.
+#[cfg(feature = "trace")]
+#[proc_macro_attribute]
+pub fn trace(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as TraceArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match Trace::new(args, function) {
+        Ok(traced) => expand::finish("#[trace]", traced.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Measure how long a function takes, and report it.
+///
+/// ```ignore
+/// #[timed]
+/// fn load(path: &Path) -> io::Result<Vec<u8>> {
+///     // ...
+/// }
+/// ```
+///
+/// Each call prints something like `load took 1.2ms` to stderr. The
+/// report goes somewhere else with `#[timed(sink = "...")]`:
+///
+/// - `sink = "tracing"` emits a `tracing` event at the info level, with
+///   `function` and `elapsed` fields. This needs abc's `tracing` feature.
+/// - `sink = "path::to::record"` calls `record(name, elapsed)`, where
+///   `name` is a `&'static str` and `elapsed` is a `Duration`.
+///
+/// For an `async fn`, the time is measured from the first poll until the
+/// future finishes. `#[timed(crate = "my_facade::abc")]` changes the path
+/// used to find `abc`.
+#[cfg(feature = "timed")]
+#[proc_macro_attribute]
+pub fn timed(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as TimedArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match Timed::new(args, function) {
+        Ok(timed) => expand::finish("#[timed]", timed.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Call a function again if it returns an error.
+///
+/// ```ignore
+/// #[retry(times = 3, backoff_ms = 100)]
+/// fn fetch(url: &str) -> io::Result<String> {
+///     // ...
+/// }
+/// ```
+///
+/// The function must return a `Result`. Its body runs up to `times`
+/// times (3 by default), and the first `Ok`, or the last `Err`, is
+/// returned. Because the body may run more than once, it can't move
+/// out of the function's arguments.
+///
+/// With `backoff_ms`, the first retry waits that long, and each later one
+/// waits twice as long as the one before. Waiting uses
+/// `std::thread::sleep`, unless `sleep = "path::to::sleep"` names another
+/// function taking a `Duration`. An `async fn` with a backoff must name
+/// an async sleep, like `sleep = "tokio::time::sleep"`, since blocking
+/// would stall its executor.
+///
+/// `#[retry(crate = "my_facade::abc")]` changes the path used to find
+/// `abc`.
+#[cfg(feature = "retry")]
+#[proc_macro_attribute]
+pub fn retry(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as RetryArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match Retry::new(args, function) {
+        Ok(retry) => expand::finish("#[retry]", retry.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Decode a string of hex digits into a byte array, at compile time.
+///
+/// `hex!("deadbeef")` expands to `[0xde, 0xad, 0xbe, 0xef]`, a `[u8; 4]`.
+/// Whitespace is ignored, so long values can be split up, like
+/// `hex!("0011 2233")`. A character that isn't a hex digit, or an odd
+/// number of digits, is a compile error.
+#[cfg(feature = "hex")]
+#[proc_macro]
+pub fn hex(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as HexInput);
+    expand::finish("hex!", input.into_token_stream())
+}
+
+/// Parse a UUID at compile time.
+///
+/// `uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8")` expands to the UUID's
+/// 16 bytes, as a `[u8; 16]`. With abc's `uuid` feature, it expands to a
+/// `uuid::Uuid` instead. The hyphenated form is the usual one, but 32
+/// bare hex digits, braces (`{...}`), and `urn:uuid:...` work too.
+///
+/// A malformed UUID is a compile error.
+#[cfg(feature = "uuid_macro")]
+#[proc_macro]
+pub fn uuid(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as UuidInput);
+    expand::finish("uuid!", input.into_token_stream())
+}
+
+/// A regular expression, checked at compile time.
+///
+/// `regex!(r"^\d{4}-\d{2}-\d{2}$")` expands to a `&'static regex::Regex`.
+/// The pattern is compiled once, the first time the expression runs, so
+/// it's cheap to use inside a loop or a frequently called function.
+///
+/// An invalid pattern is a compile error, which points out what's wrong
+/// with it. This is only available with abc's `regex` feature.
+#[cfg(feature = "regex")]
+#[proc_macro]
+pub fn regex(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as RegexInput);
+    expand::finish("regex!", input.into_token_stream())
+}
+
+/// The git version of the crate being compiled, as a string literal.
+///
+/// This is the output of `git describe --always --dirty`, like
+/// `"v1.2.0-3-g1a2b3c4-dirty"`, or `"unknown"` if git isn't available or
+/// the crate isn't in a repository.
+///
+/// The version is read when the macro expands, and cargo doesn't know to
+/// expand it again after a commit; a change to the calling file, or a
+/// `cargo clean`, will update it.
+#[cfg(feature = "build_info")]
+#[proc_macro]
+pub fn git_version(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as GitVersionInput);
+    expand::finish("git_version!", input.into_token_stream())
+}
+
+/// Define consts describing the build.
+///
+/// `build_info!()` (or `build_info!(pub)`, to make them public) defines:
+///
+/// - `GIT_COMMIT: Option<&'static str>`, the full hash of the commit
+///   being built, or `None` outside a git repository.
+/// - `GIT_DIRTY: bool`, whether tracked files have uncommitted changes.
+/// - `BUILD_TIMESTAMP: u64`, the time of the build in seconds since the
+///   Unix epoch.
+///
+/// Like `git_version!`, these are only updated when the macro expands
+/// again, which cargo may skip if nothing in the crate changed.
+#[cfg(feature = "build_info")]
+#[proc_macro]
+pub fn build_info(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as BuildInfoInput);
+    expand::finish("build_info!", input.into_token_stream())
+}
+
+/// The time of the build, formatted as a string literal.
+///
+/// `compile_time!("%Y-%m-%d %H:%M:%S")` expands to something like
+/// `"2021-03-04 05:06:07"`. The time is in UTC, and the format supports
+/// `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`, `%j` (day of the year), `%s`
+/// (seconds since the Unix epoch), and `%%`. Without a format, it's
+/// `"%Y-%m-%dT%H:%M:%SZ"`.
+///
+/// For reproducible builds, `compile_time!("%Y-%m-%d", source_date_epoch)`
+/// uses the time in the `SOURCE_DATE_EPOCH` environment variable, if it's
+/// set.
+#[cfg(feature = "compile_time")]
+#[proc_macro]
+pub fn compile_time(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as CompileTimeInput);
+    expand::finish("compile_time!", input.into_token_stream())
+}
+
+/// Read an environment variable at compile time, with a default.
+///
+/// `env_or!("PORT", 8080u16)` expands to the value of `PORT` when the
+/// crate is built, as a `u16`, or to `8080u16` if `PORT` isn't set. The
+/// value is parsed as the same type as the default, which can be a
+/// string, char, bool, integer, or float literal; a value that doesn't
+/// parse, like `PORT=http`, is a compile error.
+///
+/// The expansion is a constant expression, so it can be used to
+/// initialize a `const` or a `static`. Changing the variable makes
+/// cargo build the crate again.
+#[cfg(feature = "env_or")]
+#[proc_macro]
+pub fn env_or(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as EnvOrInput);
+    expand::finish("env_or!", input.into_token_stream())
+}
+
+/// Check a condition at compile time.
+///
+/// ```ignore
+/// static_assert!(size_of::<Header>() == 16, "a header must fit in one block");
+/// ```
+///
+/// The condition must be a `bool` that can be evaluated in a `const`. If
+/// it's false, the build fails with an error showing the message and
+/// the condition:
+///
+/// ```text
+/// static assertion failed: a header must fit in one block: `size_of :: < Header > () == 16`
+/// ```
+///
+/// The message is optional. Like any item, this can be used in a module
+/// or inside a function.
+#[cfg(feature = "static_assert")]
+#[proc_macro]
+pub fn static_assert(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as StaticAssertInput);
+    expand::finish("static_assert!", input.into_token_stream())
+}
+
+/// Define a newtype, a struct wrapping a single value, with the trait
+/// impls that are usually written by hand.
+///
+/// ```ignore
+/// newtype! {
+///     #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+///     pub struct Meters(f64);
+///     derive(Add, Sub, AddAssign, Display, From, Into)
+/// }
+/// ```
+///
+/// The struct is emitted as written, so attributes and ordinary derives
+/// go on it as usual. The traits in `derive(...)` are implemented by
+/// forwarding to the inner value:
+///
+/// - `Add`, `Sub`, `Mul`, `Div`, and `Rem`, between two values of the
+///   newtype, and their `...Assign` forms.
+/// - `Neg` and `Display`.
+/// - `Deref` and `DerefMut`, to the inner type. These are opt-in, since
+///   they make the newtype much less distinct from what it wraps.
+/// - `From`, to make the newtype from the inner type, and `Into`, to get
+///   the inner value back out.
+///
+/// Generic newtypes aren't supported.
+#[cfg(feature = "newtype")]
+#[proc_macro]
+pub fn newtype(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as NewtypeInput);
+    expand::finish("newtype!", input.into_token_stream())
+}
+
+/// Define a struct that packs fields into the bits of an integer.
+///
+/// ```ignore
+/// bitfield!(
+///     #[derive(Clone, Copy, Debug, Default)]
+///     pub Flags: u16 {
+///         enable: 0,
+///         mode: 1..3,
+///         count: 3..8,
+///     }
+/// );
+/// ```
+///
+/// A field is either a single bit, like `enable`, which reads and writes
+/// a `bool`, or a range of bits with the end excluded, like `mode` (bits
+/// 1 and 2). A range reads and writes the smallest unsigned integer type
+/// that fits it, so `mode` and `count` are `u8`s.
+///
+/// Each field gets a getter (`flags.mode()`) and a setter
+/// (`flags.set_mode(2)`). The struct also has `from_bits` and `bits`, to
+/// convert to and from the underlying integer. Fields that don't fit in
+/// the integer, or that overlap, are compile errors, and all of them are
+/// reported at once.
+#[cfg(feature = "bitfield")]
+#[proc_macro]
+pub fn bitfield(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as BitfieldInput);
+    expand::finish("bitfield!", input.into_token_stream())
+}
+
+/// Check a semantic version number at compile time.
+///
+/// `semver!("1.2.3-beta.1")` expands to a `(u64, u64, u64, &str)` tuple of
+/// the major, minor, and patch versions and the pre-release, like
+/// `(1, 2, 3, "beta.1")`. The pre-release is `""` if there isn't one, and
+/// build metadata (`+...`) is checked, but left out. The tuple is a
+/// constant expression.
+///
+/// With abc's `semver` feature, it expands to a `semver::Version`
+/// instead. A version that doesn't follow the Semantic Versioning rules
+/// is a compile error.
+#[cfg(feature = "semver_macro")]
+#[proc_macro]
+pub fn semver(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as SemverInput);
+    expand::finish("semver!", input.into_token_stream())
+}
+
+/// An IP address, checked at compile time.
+///
+/// `ip!("10.0.0.1")` expands to `Ipv4Addr::new(10, 0, 0, 1)`, and an IPv6
+/// address like `ip!("::1")` expands to an `Ipv6Addr`. Either can be
+/// used in a `const`, or converted to an `IpAddr` with `.into()`.
+///
+/// An invalid address is a compile error.
+#[cfg(feature = "net")]
+#[proc_macro]
+pub fn ip(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as IpInput);
+    expand::finish("ip!", input.into_token_stream())
+}
+
+/// A socket address, checked at compile time.
+///
+/// `sockaddr!("10.0.0.1:8080")` and `sockaddr!("[::1]:8080")` expand to a
+/// `SocketAddr`, which can be used in a `const` from Rust 1.69 on.
+///
+/// An invalid address, or a missing port, is a compile error.
+#[cfg(feature = "net")]
+#[proc_macro]
+pub fn sockaddr(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as SockAddrInput);
+    expand::finish("sockaddr!", input.into_token_stream())
+}
+
+/// A C string literal, checked at compile time.
+///
+/// `cstr!("hello")` expands to a `&'static CStr`, with the terminating
+/// NUL added. Byte strings work too, for text that isn't UTF-8:
+/// `cstr!(b"caf\xe9")`. A NUL inside the string is a compile error,
+/// since C would see it as the end of the string. The expansion can be
+/// used in a `const`.
+#[cfg(feature = "cstr")]
+#[proc_macro]
+pub fn cstr(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as CStrInput);
+    expand::finish("cstr!", input.into_token_stream())
+}
+
+/// Build identifiers out of pieces.
+///
+/// Inside `make_ident!`, each `[< ... >]` becomes one identifier, made by
+/// joining the pieces inside it. This is mostly useful in `macro_rules!`
+/// macros, to name things after their arguments:
+///
+/// ```ignore
+/// macro_rules! getter {
+///     ($field:ident: $ty:ty) => {
+///         make_ident! {
+///             fn [<get_ $field>](&self) -> &$ty {
+///                 &self.$field
+///             }
+///         }
+///     };
+/// }
+/// ```
+///
+/// A piece can be an identifier, a string, or an integer, and can be
+/// followed by a case conversion: `:lower`, `:upper`, `:snake`, `:camel`
+/// (`camelCase`), `:pascal` (`PascalCase`), or `:screaming`
+/// (`SCREAMING_SNAKE_CASE`). So `[<$name:pascal Builder>]` turns
+/// `retry_policy` into `RetryPolicyBuilder`.
+///
+/// Everything outside of `[< ... >]` is passed through unchanged.
+#[cfg(feature = "make_ident")]
+#[proc_macro]
+pub fn make_ident(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as MakeIdentInput);
+    expand::finish("make_ident!", input.into_token_stream())
+}
+
+/// Check that a list is in sorted order.
+///
+/// On an enum, this checks that the variants are sorted by name. On a
+/// `const` or `static`, it checks that an array or slice literal is
+/// sorted; numbers are compared as numbers, strings as strings, and
+/// tuples by their first element, so a table of `(key, value)` pairs is
+/// sorted by key.
+///
+/// ```ignore
+/// #[sorted]
+/// const ALIASES: &[(&str, &str)] = &[
+///     ("co", "checkout"),
+///     ("st", "status"),
+/// ];
+/// ```
+///
+/// On a fn, it checks every `match` and `let` inside it that's marked
+/// `#[sorted]`. Match arms are sorted by their patterns, with catch-all
+/// arms like `_` left out, and a `let` must be initialized with an array.
+///
+/// ```ignore
+/// #[sorted]
+/// fn describe(error: &Error) -> &str {
+///     #[sorted]
+///     match error {
+///         Error::Io(_) => "I/O error",
+///         Error::Parse(_) => "parse error",
+///         _ => "other error",
+///     }
+/// }
+/// ```
+///
+/// An element that's out of order is a compile error, which names the
+/// element it should have come before.
+#[cfg(feature = "sorted")]
+#[proc_macro_attribute]
+pub fn sorted(args: TokenStream, item: TokenStream) -> TokenStream {
+    let _ = parse_macro_input!(args as syn::parse::Nothing);
+    let item = parse_macro_input!(item as syn::Item);
+    match Sorted::new(item) {
+        Ok(sorted) => expand::finish("#[sorted]", sorted.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Define a static lookup table, with a struct for its rows.
+///
+/// ```ignore
+/// table! {
+///     #[derive(Debug)]
+///     pub Country { code: &str, #[key] dial: u16, name: &str }
+///     ["US", 1, "United States"],
+///     ["DE", 49, "Germany"],
+/// }
+/// ```
+///
+/// This defines `struct Country`, with a field for each column, and:
+///
+/// - `Country::rows()`, a `&'static [Country]` of every row, in order.
+/// - A lookup for each key column, like `Country::by_dial(49)`, which
+///   returns the first matching row, if there is one.
+///
+/// Columns marked `#[key]` get lookups; if none are marked, the first
+/// column is the key. A key column's type must implement `PartialEq`,
+/// and a repeated key value is a compile error. References in column
+/// types are `'static`, and every value must be a constant expression.
+#[cfg(feature = "table")]
+#[proc_macro]
+pub fn table(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as TableInput);
+    expand::finish("table!", input.into_token_stream())
+}
+
+/// Create an enum of bit flags, and a set type for them.
+///
+/// This is like `enum_ranges!`, but for flag registers:
+///
+/// ```ignore
+/// enum_bitmask!(
+///     #[derive(Debug)]
+///     pub Permission: u8 => Permissions {
+///         Read: 1 << 0,
+///         Write: 1 << 1,
+///         Exec: 1 << 2,
+///     }
+/// )
+/// ```
+///
+/// Each flag must be a single bit that fits in the integer type, and no
+/// two flags can share a bit. The enum is always `Clone`, `Copy`,
+/// `PartialEq` and `Eq`, and each variant's discriminant is its mask.
+///
+/// The set type is named after `=>`, or else the enum name plus `Flags`.
+/// It has `empty`, `all`, `from_bits`, `bits`, `contains`, `insert`,
+/// `remove` and `iter` (over the flags that are set, in order). Flags
+/// combine with `|`, and the set's `Debug` output looks like
+/// `Permissions(Read | Write)`.
+#[cfg(feature = "enum_bitmask")]
+#[proc_macro]
+pub fn enum_bitmask(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as BitmaskEnum);
+    expand::finish("enum_bitmask!", input.into_token_stream())
+}
+
+/// Create an enum whose variants classify strings.
+///
+/// This is like `enum_ranges!`, but with string patterns:
+///
+/// ```ignore
+/// enum_strings!(
+///     #[derive(Debug, PartialEq)]
+///     pub FileKind {
+///         Readme: "README" | "README.md",
+///         Image: "img_*",
+///         Rust: "*.rs",
+///         Test: "test_?*.py",
+///         Other: _,
+///     }
+/// )
+/// ```
+///
+/// A pattern is an exact string, unless it uses `*` (any run of
+/// characters) or `?` (any one character). Exact strings are checked
+/// first, all at once; then the other patterns are tried in order, and
+/// the first match wins. Prefixes (`"img_*"`) and suffixes (`"*.rs"`) are
+/// checked with `starts_with` and `ends_with`.
+///
+/// With a catch-all variant (`Other: _`), the enum implements
+/// `From<&str>`. Otherwise it implements `TryFrom<&str>`, and the error
+/// is the string that didn't match.
+#[cfg(feature = "enum_strings")]
+#[proc_macro]
+pub fn enum_strings(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as StringEnum);
+    expand::finish("enum_strings!", input.into_token_stream())
+}
+
+/// Define a state machine from its transition table.
+///
+/// ```ignore
+/// state_machine! {
+///     #[derive(Hash)]
+///     pub enum State { Idle, Running, Done }
+///     pub enum Event { Start, Finish, Reset }
+///
+///     on_enter = log_state;
+///
+///     transitions {
+///         Idle + Start => Running,
+///         Running + Finish => Done,
+///         Running | Done + Reset => Idle,
+///     }
+/// }
+/// ```
+///
+/// This emits both enums, which always derive `Clone`, `Copy`, `Debug`,
+/// `PartialEq` and `Eq`. The state enum gets a `const fn
+/// next_state(self, event) -> Option<State>` that looks up the table, and
+/// `fn transition(&mut self, event) -> Result<State, InvalidTransition>`
+/// that moves to the new state, or leaves it unchanged and returns an
+/// [`InvalidTransition`] error.
+///
+/// The optional `on_exit = path;` and `on_enter = path;` hooks are called
+/// around every successful transition, with the old or the new state and
+/// the event: `fn(State, Event)`.
+///
+/// Every name in the table must be a declared state or event, and each
+/// state can have only one transition for an event.
+///
+/// [`InvalidTransition`]: ../abc/struct.InvalidTransition.html
+#[cfg(feature = "state_machine")]
+#[proc_macro]
+pub fn state_machine(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as StateMachine);
+    expand::finish("state_machine!", input.into_token_stream())
+}
+
+/// Define a family of units, as newtypes that convert between each other.
+///
+/// ```ignore
+/// units!(
+///     pub Length {
+///         Meters = 1.0,
+///         Feet = 0.3048,
+///         Kilometers = 1000.0,
+///     }
+/// )
+/// ```
+///
+/// Each unit becomes a `struct Feet(pub f64)`, deriving `Clone`, `Copy`,
+/// `Debug`, `Default`, `PartialEq` and `PartialOrd`. The number is the
+/// size of one of that unit, relative to the others; the values are `f64`
+/// unless another type is given, like `Length: f32 { ... }`.
+///
+/// Every pair of units gets `From` conversions. Within a unit, amounts
+/// can be added, subtracted, negated and summed, scaled by a number, and
+/// divided to get their ratio; adding feet to meters needs a conversion
+/// first.
+///
+/// The family name becomes a trait implemented by all of its units, with
+/// the unit's `FACTOR`, `new` and `value`, and `convert` to any other
+/// unit, for code that's generic over units. Attributes before the
+/// family name, like doc comments, go on the trait.
+#[cfg(feature = "units")]
+#[proc_macro]
+pub fn units(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as UnitsInput);
+    expand::finish("units!", input.into_token_stream())
+}
+
+/// A `Duration`, written the way people write them.
+///
+/// `duration!("1h 30m")` expands to `Duration::new(5400, 0)`. The units
+/// are `ns`, `us` (or `µs`), `ms`, `s`, `m` (or `min`), `h` and `d`, and
+/// amounts can have a fraction, like `1.5s`. Without the quotes, like
+/// `duration!(250ms)` or `duration!(1h 30m)`, each amount is a separate
+/// number, with the unit as its suffix.
+///
+/// The expansion is a `const` expression. A missing or unknown unit, or
+/// a duration finer than a nanosecond, is a compile error.
+#[cfg(feature = "duration")]
+#[proc_macro]
+pub fn duration(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as DurationInput);
+    expand::finish("duration!", input.into_token_stream())
+}
+
+/// A number of bytes, written with a unit.
+///
+/// `byte_size!("4 MiB")` expands to `4194304`, a `usize`. The units are
+/// `B`, `KB` (or `kB`), `KiB`, `MB`, `MiB`, `GB`, `GiB`, `TB` and `TiB`;
+/// without a unit, the number is in bytes. A fraction is allowed if the
+/// result is a whole number of bytes, like `"1.5 KiB"`.
+///
+/// For another integer type, add `as`: `byte_size!("4 GiB" as u64)`. A
+/// size that doesn't fit in the type is a compile error; for `usize`,
+/// that depends on the target.
+#[cfg(feature = "byte_size")]
+#[proc_macro]
+pub fn byte_size(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as ByteSizeInput);
+    expand::finish("byte_size!", input.into_token_stream())
+}
+
+/// A color, written as a hex string and checked at compile time.
+///
+/// `color!("#1e90ff")` expands to `(0x1e, 0x90, 0xff, 0xff)`, a
+/// `(u8, u8, u8, u8)` of red, green, blue and alpha. The string can have
+/// 3, 4, 6 or 8 digits, as in CSS: `"#f80"` is short for `"#ff8800"`, and
+/// a color without an alpha component is opaque.
+///
+/// To build some other color type, name a function or tuple struct that
+/// takes the four components: `color!("#1e90ff", Rgba::new)` expands to
+/// `Rgba::new(0x1e, 0x90, 0xff, 0xff)`.
+#[cfg(feature = "color")]
+#[proc_macro]
+pub fn color(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as ColorInput);
+    expand::finish("color!", input.into_token_stream())
+}
+
+/// A JSON value, parsed at compile time.
+///
+/// ```ignore
+/// const CONFIG: abc::Json = json!(r#"{"name": "abc", "retries": 3, "tags": ["x"]}"#);
+/// assert_eq!(CONFIG.get("retries").and_then(|n| n.as_f64()), Some(3.0));
+/// ```
+///
+/// The string is parsed as JSON when the macro expands, so a typo is a
+/// compile error that gives the position of the problem, rather than a
+/// panic at runtime. The expansion is a constant `abc::Json`, made of
+/// static slices: arrays are `&'static [Json]`, and objects are
+/// `&'static [(&'static str, Json)]` sorted by key. Duplicate keys are an
+/// error.
+#[cfg(feature = "json")]
+#[proc_macro]
+pub fn json(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as JsonInput);
+    expand::finish("json!", input.into_token_stream())
+}
+
+/// Decode a base64 string into a byte array, at compile time.
+///
+/// `base64!("aGVsbG8=")` expands to `[104, 101, 108, 108, 111]`, a
+/// `[u8; 5]`. Either the standard alphabet (`+` and `/`) or the URL-safe
+/// one (`-` and `_`) is accepted, but not a mix of the two. The `=`
+/// padding is optional, and whitespace is ignored. An invalid character
+/// or the wrong padding is a compile error, which gives its position in
+/// the string.
+#[cfg(feature = "base64")]
+#[proc_macro]
+pub fn base64(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as Base64Input);
+    expand::finish("base64!", input.into_token_stream())
+}
+
+/// Make a global instance of a type, created the first time it's used.
+///
+/// On a constructor function, this adds `instance()` to the type it
+/// returns:
+///
+/// ```ignore
+/// #[singleton]
+/// pub fn load_config() -> Config {
+///     Config::from_env()
+/// }
+///
+/// let config: &'static Config = Config::instance();
+/// ```
+///
+/// On a static, the initializer doesn't need to be `const`. It runs the
+/// first time `instance()` is called, and the static becomes the
+/// `OnceLock` holding the instance:
+///
+/// ```ignore
+/// #[singleton]
+/// static REGISTRY: Registry = Registry::new();
+/// ```
+///
+/// With `#[singleton(try)]`, the constructor returns a `Result<T, E>`,
+/// and the accessor is `try_instance() -> Result<&'static T, E>`. A
+/// failure isn't cached, so the next call tries again.
+///
+/// The instance is created at most once, and it's safe to call the
+/// accessor from any thread. The accessor has the visibility of the item.
+/// It goes in an `impl` block of its own, so the type has to be defined
+/// in the same crate, and it has to be named rather than `Self`.
+#[cfg(feature = "singleton")]
+#[proc_macro_attribute]
+pub fn singleton(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as SingletonArgs);
+    let item = parse_macro_input!(item as syn::Item);
+    match Singleton::new(args, item) {
+        Ok(singleton) => expand::finish("#[singleton]", singleton.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Mark every method in an `impl` block that returns something
+/// `#[must_use]`.
+///
+/// ```ignore
+/// #[must_use_all]
+/// impl RequestBuilder {
+///     pub fn header(mut self, name: &str, value: &str) -> Self {
+///         // ...
+///     }
+///
+///     #[allow_unused_result]
+///     pub fn send(&mut self) -> Result<(), Error> {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// This applies to methods that aren't private and return anything other
+/// than `()` or `!`, so that forgetting to use the result is a warning.
+/// `#[allow_unused_result]` on a method leaves it alone, and a method that
+/// already has `#[must_use]` keeps its own message. Trait impls aren't
+/// allowed, because `#[must_use]` has to go on the trait's methods.
+#[cfg(feature = "must_use_all")]
+#[proc_macro_attribute]
+pub fn must_use_all(args: TokenStream, item: TokenStream) -> TokenStream {
+    let _ = parse_macro_input!(args as syn::parse::Nothing);
+    let item = parse_macro_input!(item as syn::ItemImpl);
+    match MustUseAll::new(item) {
+        Ok(must_use_all) => expand::finish("#[must_use_all]", must_use_all.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Turn a test function into one test for each combination of values.
+///
+/// ```ignore
+/// #[test_matrix(width = [1, 8, 64], text = ["", "hello"])]
+/// fn wraps(width: usize, text: &str) {
+///     assert!(wrap(text, width).lines().all(|line| line.len() <= width));
+/// }
+/// ```
+///
+/// Each parameter gets a list of values, and there's a `#[test]` for
+/// every combination, named after the function and the values: this
+/// example generates `wraps_width_1_text_0`, `wraps_width_1_text_1`, and
+/// so on, six tests in all. A value is named after its text when that
+/// gives every value of the parameter a different name, and by its
+/// position otherwise. Each test fails on its own, so one bad combination
+/// doesn't hide the rest.
+///
+/// Other attributes, like `#[should_panic]` or `#[ignore]`, are copied
+/// to every test, and the function can return a `Result` as a test can.
+#[cfg(feature = "test_matrix")]
+#[proc_macro_attribute]
+pub fn test_matrix(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as TestMatrixArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match TestMatrix::new(args, function) {
+        Ok(matrix) => expand::finish("#[test_matrix]", matrix.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Log the error when a function returns one.
+///
+/// ```ignore
+/// #[log_errors]
+/// fn load(path: &Path) -> io::Result<Vec<u8>> {
+///     std::fs::read(path)
+/// }
+/// ```
+///
+/// When `load` returns an `Err`, it prints something like
+/// `load failed: Os { code: 2, .. }` to stderr, and then returns the
+/// error as usual. With `#[log_errors(args)]`, the arguments are logged
+/// too, like `load(path = "a.txt") failed: ...`; they're formatted before
+/// the body runs, so that has a cost on every call. Errors and arguments
+/// that don't implement `Debug` print as `..`.
+///
+/// The log goes somewhere else with `#[log_errors(sink = "...")]`:
+///
+/// - `sink = "tracing"` emits a `tracing` event at the error level, with
+///   `function`, `error`, and (if enabled) `args` fields. This needs abc's
+///   `tracing` feature.
+/// - `sink = "path::to::report"` calls `report(name, args, error)`, where
+///   `name` is a `&'static str`, `args` is a `&str` (empty without
+///   `args`), and `error` is a `&dyn Debug`.
+///
+/// This works on `async fn`s too. `#[log_errors(crate = "my_facade::abc")]`
+/// changes the path used to find `abc`.
+#[cfg(feature = "log_errors")]
+#[proc_macro_attribute]
+pub fn log_errors(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as LogErrorsArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match LogErrors::new(args, function) {
+        Ok(logged) => expand::finish("#[log_errors]", logged.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Keep a function's old name working, as a deprecated alias.
+///
+/// ```ignore
+/// #[deprecated_alias(open_file)]
+/// pub fn open(path: &Path) -> io::Result<File> {
+///     // ...
+/// }
+/// ```
+///
+/// This emits the function, and `open_file` with the same signature,
+/// marked `#[deprecated(note = "renamed to `open`")]`, which calls it.
+/// Generics, `async`, `unsafe` and the visibility carry over.
+/// `#[deprecated_alias(open_file, since = "0.4.0", note = "...")]` sets the
+/// deprecation's `since` and `note`.
+///
+/// Methods work too, in an `impl` block. The alias calls `Self::open` if
+/// the function takes `self` or mentions `Self`, and a free function
+/// `open` otherwise.
+#[cfg(feature = "deprecated_alias")]
+#[proc_macro_attribute]
+pub fn deprecated_alias(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as DeprecatedAliasArgs);
+    let function = parse_macro_input!(item as syn::ItemFn);
+    match DeprecatedAlias::new(args, function) {
+        Ok(alias) => expand::finish("#[deprecated_alias]", alias.into_token_stream()),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// A map with compile-time keys, looked up with a binary search.
+///
+/// ```ignore
+/// const PORTS: abc::StaticMap<&str, u16> = static_map!(&str => u16 {
+///     "http" => 80,
+///     "https" => 443,
+///     "ssh" => 22,
+/// });
+/// assert_eq!(PORTS.get("ssh"), Some(&22));
+/// ```
+///
+/// The input is the key type, `=>`, the value type, and then the entries
+/// in braces. Keys must be string, byte string, integer, char or bool
+/// literals, all of the same kind; values can be any constant expression.
+/// The macro sorts the entries by key, and a duplicate key is a compile
+/// error. The expansion is a constant `abc::StaticMap`, so it can also
+/// initialize a `static`.
+#[cfg(feature = "static_map")]
+#[proc_macro]
+pub fn static_map(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as StaticMapInput);
+    expand::finish("static_map!", input.into_token_stream())
+}
+
+/// A config struct with a `Default` impl, from a block of `key = value`
+/// entries.
+///
+/// ```ignore
+/// kv_config! {
+///     #[derive(Clone, Debug)]
+///     pub Config {
+///         /// Where to connect.
+///         host = "localhost",
+///         port: u16 = 8080,
+///         ratio = 0.75,
+///         verbose = false,
+///         tags = ["fast", "local"],
+///         timeout: Duration = Duration::from_secs(5),
+///     }
+/// }
+///
+/// let config = Config { verbose: true, ..Config::default() };
+/// ```
+///
+/// Each entry becomes a field, with the same visibility as the struct,
+/// and its value is the field's default. The field's type comes from the
+/// value: a string is a `&'static str`, an integer without a suffix is an
+/// `i32` and a float is an `f64`, as elsewhere in Rust, and an array of
+/// literals is a static slice. Anything else needs its type written out,
+/// as `key: Type = value`.
+#[cfg(feature = "kv_config")]
+#[proc_macro]
+pub fn kv_config(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as KvConfigInput);
+    expand::finish("kv_config!", input.into_token_stream())
+}
+
+/// Check the version of rustc, at compile time.
+///
+/// ```ignore
+/// if rustc_version_at_least!(1.70) {
+///     println!("a recent compiler");
+/// }
+///
+/// rustc_version_at_least!(1.70 {
+///     fn first_is_even(v: Option<u32>) -> bool {
+///         v.is_some_and(|n| n % 2 == 0)
+///     }
+/// } else {
+///     fn first_is_even(v: Option<u32>) -> bool {
+///         v.map_or(false, |n| n % 2 == 0)
+///     }
+/// });
+/// ```
+///
+/// The version can be a number like `1.70`, or a string like `"1.70.1"`.
+/// On its own, the macro expands to `true` or `false`. Followed by code in
+/// braces, it expands to that code if rustc is at least that version, and
+/// otherwise to the code in the `else` braces, or nothing. Only the code
+/// that's chosen has to compile, which is what makes it useful for newer
+/// APIs. A nightly or beta compiler counts as the version it will be
+/// released as.
+#[cfg(feature = "rustc_version")]
+#[proc_macro]
+pub fn rustc_version_at_least(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as RustcVersionInput);
+    expand::finish("rustc_version_at_least!", input.into_token_stream())
+}
+
+/// Define a struct, and a struct-of-arrays container for it.
+///
+/// ```ignore
+/// soa! {
+///     #[derive(Clone, Debug)]
+///     #[soa(derive(Debug))]
+///     pub struct Particle {
+///         pub x: f32,
+///         pub y: f32,
+///         pub mass: f32,
+///     }
+/// }
+///
+/// let mut particles = ParticleVec::new();
+/// particles.push(Particle { x: 0.0, y: 1.0, mass: 2.0 });
+/// let total: f32 = particles.mass().iter().sum();
+/// for p in particles.x_mut() {
+///     *p += 1.0;
+/// }
+/// ```
+///
+/// The struct is emitted as it was, along with `ParticleVec`, which keeps
+/// a `Vec` for each field so that a loop over one field reads it from
+/// contiguous memory, and `ParticleRef<'a>`, which borrows one element's
+/// fields. The vectors are private, so their lengths always match.
+///
+/// `ParticleVec` has `new`, `with_capacity`, `len`, `is_empty`, `push`,
+/// `pop`, `clear`, `get` and `iter`, along with `Default`, `Extend` and
+/// `FromIterator`. Each field `x` gets `x()` and `x_mut()`, returning its
+/// slice, with the field's visibility. `#[soa(derive(...))]` adds derives
+/// to `ParticleVec` and `ParticleRef`, which is always `Clone` and `Copy`.
+#[cfg(feature = "soa")]
+#[proc_macro]
+pub fn soa(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as SoaInput);
+    expand::finish("soa!", input.into_token_stream())
+}
+
+/// Forward methods to a field, or to any expression on `self`.
+///
+/// ```ignore
+/// struct Stack<T> {
+///     items: Vec<T>,
+/// }
+///
+/// impl<T> Stack<T> {
+///     delegate! {
+///         to self.items {
+///             pub fn len(&self) -> usize;
+///             pub fn is_empty(&self) -> bool;
+///             pub fn push(&mut self, item: T);
+///             #[call(last)]
+///             pub fn peek(&self) -> Option<&T>;
+///         }
+///     }
+/// }
+/// ```
+///
+/// Use it inside an `impl` block. Each method is written as a signature
+/// ending in `;`, and becomes a method that calls the method of the same
+/// name on the target, passing its arguments on. `#[call(name)]` calls a
+/// method with a different name. Other attributes, like doc comments, are
+/// kept. There can be several `to` blocks, and the target can be any
+/// expression, like `self.0` or `self.inner.borrow()`.
+#[cfg(feature = "delegate")]
+#[proc_macro]
+pub fn delegate(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as DelegateInput);
+    expand::finish("delegate!", input.into_token_stream())
+}
+
+/// Generate a mock of a trait, for unit tests.
+///
+/// ```ignore
+/// trait Store {
+///     fn get(&self, key: &str) -> Option<String>;
+///     fn put(&mut self, key: &str, value: String);
+/// }
+///
+/// mock_trait! {
+///     trait Store {
+///         fn get(&self, key: &str) -> Option<String>;
+///         fn put(&mut self, key: &str, value: String);
+///     }
+/// }
+///
+/// let mut store = MockStore::new();
+/// store.on_get(|key| Some(key.to_uppercase()));
+/// store.on_put(|_, _| ());
+/// store.put("a", String::from("b"));
+/// assert_eq!(store.get("a").as_deref(), Some("A"));
+/// assert_eq!(store.get.calls(), [r#"get("a")"#]);
+/// assert_eq!(store.put.count(), 1);
+/// ```
+///
+/// The input is a copy of the trait's definition; the trait itself isn't
+/// emitted, so it must be in scope. This generates `MockStore`, which
+/// implements `Store`. Each method `x` is answered by a closure, set with
+/// `on_x`, which takes the method's arguments, and calling a method that
+/// has no answer panics. The field `x` is an `abc::MockFn` that records
+/// each call, with its arguments formatted with `Debug` (or shown as `..`
+/// if they don't implement it).
+///
+/// Methods may have lifetime parameters and may be `async`, but not type
+/// parameters or `impl Trait` arguments, and a generic trait can't be
+/// mocked.
+#[cfg(feature = "mock_trait")]
+#[proc_macro]
+pub fn mock_trait(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as MockTraitInput);
+    expand::finish("mock_trait!", input.into_token_stream())
+}
+
+/// Generate a builder that checks, at compile time, that every required
+/// field is set.
+///
+/// ```ignore
+/// typestate! {
+///     #[typestate(required(url, method))]
+///     pub struct Request {
+///         url: String,
+///         method: Method,
+///         retries: u8,
+///     }
+/// }
+///
+/// let request = Request::builder()
+///     .url("/".to_string())
+///     .retries(3)
+///     .method(Method::Get)
+///     .build();
+/// ```
+///
+/// Like `#[derive(Builder)]`, this generates `RequestBuilder`, with a
+/// setter for each field, and `Request::builder()`. But `RequestBuilder`
+/// has a type parameter for each required field, which is `abc::Unset`
+/// until the field's setter is called, and `abc::Set` after. `build()`
+/// only exists once they're all `Set`, so it returns the value itself,
+/// and leaving out a required field is a compile error, like "no method
+/// named `build` found for struct `RequestBuilder<Set>`" (parameters that
+/// are still `Unset` at the end aren't shown). A required field can only
+/// be set once.
+///
+/// The other fields start out as `Default::default()`. The struct can't be
+/// generic.
+#[cfg(feature = "typestate")]
+#[proc_macro]
+pub fn typestate(tokens: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(tokens as TypestateInput);
+    expand::finish("typestate!", input.into_token_stream())
 }
 
 /// Read a file and return an array of words.
 ///
 /// If the file contains "hello world", this macro will return:
 /// `["hello", "world"]`
+#[cfg(feature = "file_words")]
 #[proc_macro]
-pub fn file_words(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+pub fn file_words(tokens: TokenStream) -> TokenStream {
     // Since the macro input already matches an existing rust syntax item (LitStr),
     // we can have syn parse it for us.
     let _filename = parse_macro_input!(tokens as LitStr);
@@ -61,12 +1999,13 @@ pub fn file_words(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let expanded = quote! {
         []
     };
-    expanded.into()
+    expand::finish("file_words!", expanded)
 }
 
 // Uncomment this section to try the enum_ranges! macro
-mod enum_ranges;
-use enum_ranges::RangedEnum;
+// The parser is in abc-macros-core, so it can be used without a proc macro.
+#[cfg(feature = "enum_ranges")]
+use abc_macros_core::RangedEnum;
 
 /// enum_ranges! will create an enum to represent numeric ranges.
 ///
@@ -82,29 +2021,35 @@ use enum_ranges::RangedEnum;
 /// )
 /// ```
 ///
-/// This will emit the following code:
+/// This will emit the following code. Every path in it is absolute, so it
+/// means the same thing in any scope: in a module with its own `Result`,
+/// say, or in a `#![no_std]` crate.
 ///
 /// ```
-/// # use core::convert::TryFrom;
 /// enum Color {
 ///     Blue,
 ///     Green,
 ///     Yellow,
 /// }
 ///
-/// impl TryFrom<u64> for Color {
+/// impl ::core::convert::TryFrom<u64> for Color {
 ///     type Error = u64;
 ///
-///     fn try_from(x: u64) -> Result<Self, u64> {
-///         if (450..495).contains(&x) { Ok(Color::Blue) }
-///         else if (495..570).contains(&x) { Ok(Color::Green) }
-///         else if (570..590).contains(&x) { Ok(Color::Yellow) }
-///         else { Err(x) }
+///     fn try_from(x: u64) -> ::core::result::Result<Self, u64> {
+///         if (450..495).contains(&x) { ::core::result::Result::Ok(Color::Blue) }
+///         else if (495..570).contains(&x) { ::core::result::Result::Ok(Color::Green) }
+///         else if (570..590).contains(&x) { ::core::result::Result::Ok(Color::Yellow) }
+///         else { ::core::result::Result::Err(x) }
 ///     }
 /// }
 /// ```
+///
+/// A number can only convert to one variant, so ranges that overlap are
+/// compile errors, as are empty ranges and repeated names, and all of them
+/// are reported at once.
+#[cfg(feature = "enum_ranges")]
 #[proc_macro]
-pub fn enum_ranges(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+pub fn enum_ranges(tokens: TokenStream) -> TokenStream {
     let ranged = parse_macro_input!(tokens as RangedEnum);
-    ranged.into_token_stream().into()
+    expand::finish("enum_ranges!", ranged.into_token_stream())
 }
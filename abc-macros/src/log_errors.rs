@@ -0,0 +1,280 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, FnArg, Ident, ItemFn, LitStr, Meta, NestedMeta, Pat, Path, ReturnType, Token, Type,
+};
+
+use crate::attr::{self, lit_str};
+use crate::wrap;
+
+/// Where `#[log_errors]` reports errors.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+enum Sink {
+    /// Print to stderr.
+    Eprintln,
+    /// Emit a `tracing` event.
+    Tracing,
+    /// Call a function with the name, the arguments and the error.
+    Function(Path),
+}
+
+/// The arguments to `#[log_errors(...)]`.
+///
+/// Example: `#[log_errors]` or `#[log_errors(args, sink = "tracing")]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct LogErrorsArgs {
+    // `sink = "..."`: where the errors go.
+    sink: Sink,
+    // `args`: include the arguments in the log.
+    args: bool,
+    // `crate = "..."`: the path to the `abc` crate.
+    crate_path: Path,
+}
+
+fn parse_sink(lit: &LitStr) -> syn::parse::Result<Sink> {
+    match lit.value().as_str() {
+        "eprintln" => Ok(Sink::Eprintln),
+        "tracing" if cfg!(feature = "tracing") => Ok(Sink::Tracing),
+        "tracing" => Err(syn::Error::new_spanned(
+            lit,
+            "the tracing sink needs abc's `tracing` feature",
+        )),
+        _ => Ok(Sink::Function(lit.parse()?)),
+    }
+}
+
+impl Parse for LogErrorsArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = LogErrorsArgs {
+            sink: Sink::Eprintln,
+            args: false,
+            crate_path: parse_quote!(::abc),
+        };
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("args") => {
+                    args.args = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("sink") => {
+                    args.sink = parse_sink(lit_str(&nv.lit)?)?;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    args.crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown log_errors option",
+                        &["args", "sink", "crate"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a function with `#[log_errors]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct LogErrors {
+    args: LogErrorsArgs,
+    function: ItemFn,
+}
+
+/// Check whether a return type looks like a `Result`, including aliases
+/// like `io::Result<T>`.
+fn returns_result(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map_or(false, |segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+impl LogErrors {
+    pub fn new(args: LogErrorsArgs, function: ItemFn) -> syn::parse::Result<Self> {
+        if let Some(constness) = &function.sig.constness {
+            return Err(syn::Error::new_spanned(
+                constness,
+                "log_errors can't be used on a const fn",
+            ));
+        }
+        if !returns_result(&function.sig.output) {
+            return Err(syn::Error::new_spanned(
+                &function.sig.ident,
+                "log_errors needs a function that returns a `Result`",
+            ));
+        }
+        Ok(LogErrors { args, function })
+    }
+}
+
+/// Emit the function, with a check for an error after its body.
+impl ToTokens for LogErrors {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let krate = &self.args.crate_path;
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = &self.function;
+        let name = sig.ident.unraw().to_string();
+        let call = wrap::call_body(krate, sig, block);
+
+        // The body takes ownership of the arguments, so they're formatted
+        // before it runs. As in `#[trace]`, only arguments bound to a
+        // plain name are shown.
+        let format_args = if self.args.args {
+            let names: Vec<Ident> = sig
+                .inputs
+                .iter()
+                .filter_map(|input| match input {
+                    FnArg::Receiver(receiver) => Some(Ident::new("self", receiver.self_token.span)),
+                    FnArg::Typed(typed) => match &*typed.pat {
+                        Pat::Ident(pat) => Some(pat.ident.clone()),
+                        _ => None,
+                    },
+                })
+                .collect();
+            let placeholders: Vec<String> = names
+                .iter()
+                .map(|arg| format!("{} = {{:?}}", arg.unraw()))
+                .collect();
+            let format = placeholders.join(", ");
+            quote! {
+                let __args = {
+                    use #krate::__private::{DebugField as _, OpaqueField as _};
+                    #krate::__private::alloc::format!(#format #(, (&#krate::__private::Probe(&#names)).as_debug())*)
+                };
+            }
+        } else {
+            quote! { let __args = ""; }
+        };
+
+        let report = match &self.args.sink {
+            Sink::Eprintln if self.args.args => {
+                let message = format!("{}({{}}) failed: {{:?}}", name);
+                quote! { #krate::__private::std::eprintln!(#message, __args, __error); }
+            }
+            Sink::Eprintln => {
+                let message = format!("{} failed: {{:?}}", name);
+                quote! { #krate::__private::std::eprintln!(#message, __error); }
+            }
+            Sink::Tracing if self.args.args => quote! {
+                #krate::__private::tracing::error!(
+                    function = #name,
+                    args = %__args,
+                    error = ?__error,
+                    "failed",
+                );
+            },
+            Sink::Tracing => quote! {
+                #krate::__private::tracing::error!(function = #name, error = ?__error, "failed");
+            },
+            Sink::Function(path) => quote! { #path(#name, &__args, __error); },
+        };
+
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis #sig {
+                #format_args
+                let __result = #call;
+                if let ::core::result::Result::Err(__error) = &__result {
+                    use #krate::__private::{DebugField as _, OpaqueField as _};
+                    let __error = (&#krate::__private::Probe(__error)).as_debug();
+                    #report
+                }
+                __result
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_errors(args: TokenStream, function: TokenStream) -> syn::parse::Result<LogErrors> {
+        LogErrors::new(syn::parse2(args)?, syn::parse2(function)?)
+    }
+
+    #[test]
+    fn parse_args() {
+        let parsed = log_errors(
+            quote!(),
+            quote!(
+                fn f() -> io::Result<()> {
+                    Ok(())
+                }
+            ),
+        )
+        .unwrap();
+        assert_eq!(parsed.args.sink, Sink::Eprintln);
+        assert!(!parsed.args.args);
+
+        let parsed = log_errors(
+            quote!(args, sink = "metrics::record"),
+            quote!(
+                fn f() -> Result<(), String> {
+                    Ok(())
+                }
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.args.sink,
+            Sink::Function(parse_quote!(metrics::record))
+        );
+        assert!(parsed.args.args);
+
+        let tracing = syn::parse_str::<LogErrorsArgs>("sink = \"tracing\"");
+        assert_eq!(tracing.is_ok(), cfg!(feature = "tracing"));
+
+        log_errors(
+            quote!(),
+            quote!(
+                fn f() -> u8 {
+                    0
+                }
+            ),
+        )
+        .unwrap_err();
+        log_errors(
+            quote!(),
+            quote!(
+                fn f() {}
+            ),
+        )
+        .unwrap_err();
+        log_errors(
+            quote!(),
+            quote!(
+                const fn f() -> Result<(), ()> {
+                    Ok(())
+                }
+            ),
+        )
+        .unwrap_err();
+        log_errors(
+            quote!(bogus),
+            quote!(
+                fn f() -> Result<(), ()> {
+                    Ok(())
+                }
+            ),
+        )
+        .unwrap_err();
+    }
+}
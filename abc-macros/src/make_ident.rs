@@ -0,0 +1,232 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+
+use crate::case::RenameRule;
+
+/// This is the entire input to `make_ident!`: any tokens, where each
+/// `[< ... >]` is replaced by a single identifier.
+///
+/// Example: `make_ident!(fn [<get_ $field>]() {})`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct MakeIdentInput {
+    // The input, with the identifiers already pasted together.
+    output: TokenStream,
+}
+
+/// The case conversions that can follow a piece, like `$name:snake`.
+fn modifier(ident: &Ident) -> syn::parse::Result<RenameRule> {
+    match ident.to_string().as_str() {
+        "lower" => Ok(RenameRule::Lower),
+        "upper" => Ok(RenameRule::Upper),
+        "snake" => Ok(RenameRule::Snake),
+        "camel" => Ok(RenameRule::Camel),
+        "pascal" => Ok(RenameRule::Pascal),
+        "screaming" => Ok(RenameRule::ScreamingSnake),
+        _ => Err(syn::Error::new_spanned(
+            ident,
+            "unknown case; expected `lower`, `upper`, `snake`, `camel`, `pascal`, \
+             or `screaming`",
+        )),
+    }
+}
+
+/// The text of each piece inside `[< ... >]`, with its span.
+///
+/// Metavariables from `macro_rules!` arrive wrapped in invisible groups,
+/// which are flattened here.
+///
+fn pieces(tokens: TokenStream, out: &mut Vec<(String, Span)>) -> syn::parse::Result<()> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) => {
+                let text = ident.to_string();
+                let text = text.strip_prefix("r#").unwrap_or(&text).to_string();
+                out.push((text, ident.span()));
+            }
+            TokenTree::Literal(lit) => {
+                // Strings contribute their contents, and numbers their
+                // digits.
+                let text = match syn::parse2::<syn::Lit>(lit.to_token_stream()) {
+                    Ok(syn::Lit::Str(s)) => s.value(),
+                    Ok(syn::Lit::Int(i)) => i.base10_digits().to_string(),
+                    _ => {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "only strings and integers can be part of an identifier",
+                        ))
+                    }
+                };
+                out.push((text, lit.span()));
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                pieces(group.stream(), out)?;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ':' => {
+                let ident = match tokens.next() {
+                    Some(TokenTree::Ident(ident)) => ident,
+                    _ => {
+                        return Err(syn::Error::new(
+                            punct.span(),
+                            "expected a case after `:`, like `:snake`",
+                        ))
+                    }
+                };
+                let rule = modifier(&ident)?;
+                match out.last_mut() {
+                    Some((text, _)) => *text = rule.apply(text),
+                    None => {
+                        return Err(syn::Error::new(
+                            punct.span(),
+                            "a case conversion must follow a piece of the identifier",
+                        ))
+                    }
+                }
+            }
+            // A `$` left over outside of `macro_rules!` is ignored.
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {}
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "unexpected token in an identifier",
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// If this group is `[< ... >]`, return what's between the angle brackets.
+fn paste_contents(group: &Group) -> Option<TokenStream> {
+    if group.delimiter() != Delimiter::Bracket {
+        return None;
+    }
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    match (tokens.first(), tokens.last()) {
+        (Some(TokenTree::Punct(open)), Some(TokenTree::Punct(close)))
+            if tokens.len() >= 2 && open.as_char() == '<' && close.as_char() == '>' =>
+        {
+            Some(tokens[1..tokens.len() - 1].iter().cloned().collect())
+        }
+        _ => None,
+    }
+}
+
+/// Paste together the pieces of one identifier.
+fn paste(contents: TokenStream, span: Span) -> syn::parse::Result<Ident> {
+    let mut out = Vec::new();
+    pieces(contents, &mut out)?;
+    let text: String = out.iter().map(|(text, _)| text.as_str()).collect();
+    let span = out.first().map(|(_, span)| *span).unwrap_or(span);
+    let valid = text.starts_with(|c: char| c == '_' || c.is_alphabetic())
+        && text.chars().all(|c| c == '_' || c.is_alphanumeric());
+    if !valid {
+        return Err(syn::Error::new(
+            span,
+            format!("`{}` isn't a valid identifier", text),
+        ));
+    }
+    Ok(Ident::new(&text, span))
+}
+
+/// Replace every `[< ... >]` in a token stream.
+fn expand(tokens: TokenStream) -> syn::parse::Result<TokenStream> {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => match paste_contents(&group) {
+                Some(contents) => Ok(TokenTree::Ident(paste(contents, group.span())?)),
+                None => {
+                    let mut expanded = Group::new(group.delimiter(), expand(group.stream())?);
+                    expanded.set_span(group.span());
+                    Ok(TokenTree::Group(expanded))
+                }
+            },
+            other => Ok(other),
+        })
+        .collect()
+}
+
+/// Parse the input to `make_ident!`, pasting the identifiers.
+impl Parse for MakeIdentInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let tokens: TokenStream = input.parse()?;
+        Ok(MakeIdentInput {
+            output: expand(tokens)?,
+        })
+    }
+}
+
+/// Emit the input, with the pasted identifiers.
+impl ToTokens for MakeIdentInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.output.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn expand_str(tokens: TokenStream) -> syn::parse::Result<String> {
+        let input: MakeIdentInput = syn::parse2(tokens)?;
+        Ok(input.output.to_string())
+    }
+
+    #[test]
+    fn paste_idents() {
+        let expanded = expand_str(quote!(fn [<get_ retry_count>]() {})).unwrap();
+        assert_eq!(
+            expanded,
+            quote!(
+                fn get_retry_count() {}
+            )
+            .to_string()
+        );
+
+        let expanded =
+            expand_str(quote!(struct [<HttpRequest:snake _ "v" 2>]; const X: [u8; 1] = [1];))
+                .unwrap();
+        assert_eq!(
+            expanded,
+            quote!(
+                struct http_request_v2;
+                const X: [u8; 1] = [1];
+            )
+            .to_string()
+        );
+
+        let expanded = expand_str(quote!(mod m { fn [<set_ Name:upper>]() {} })).unwrap();
+        assert_eq!(
+            expanded,
+            quote!(
+                mod m {
+                    fn set_NAME() {}
+                }
+            )
+            .to_string()
+        );
+
+        let expanded = expand_str(quote!(type [<retry_count:pascal Builder>] = ();)).unwrap();
+        assert_eq!(
+            expanded,
+            quote!(
+                type RetryCountBuilder = ();
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn bad_idents() {
+        expand_str(quote!([<1 a>])).unwrap_err();
+        expand_str(quote!([<a:kebab>])).unwrap_err();
+        expand_str(quote!([<:snake a>])).unwrap_err();
+        expand_str(quote!([<a + b>])).unwrap_err();
+        expand_str(quote!([<a 1.5>])).unwrap_err();
+        expand_str(quote!([<>])).unwrap_err();
+    }
+}
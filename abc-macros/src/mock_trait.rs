@@ -0,0 +1,254 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{FnArg, GenericParam, ItemTrait, Lifetime, ReturnType, TraitItem, Type};
+
+use crate::wrap::{mentions, Forward};
+
+/// One method of the trait.
+struct Method {
+    forward: Forward,
+    // The method's lifetime parameters, which the closure has to be
+    // generic over.
+    lifetimes: Vec<Lifetime>,
+    types: Vec<Type>,
+}
+
+/// This is the entire input to `mock_trait!`: a copy of the trait's
+/// definition.
+///
+/// Example: `mock_trait! { trait Store { fn get(&self, key: &str) -> Option<String>; } }`
+///
+pub struct MockTraitInput {
+    item: ItemTrait,
+    methods: Vec<Method>,
+}
+
+/// Parse the input to `mock_trait!`.
+impl Parse for MockTraitInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let item: ItemTrait = input.parse()?;
+        if !item.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.generics,
+                "mock_trait! can't mock a generic trait",
+            ));
+        }
+        let mut methods = Vec::new();
+        for trait_item in &item.items {
+            let method = match trait_item {
+                TraitItem::Method(method) => method,
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "mock_trait! can only mock methods",
+                    ))
+                }
+            };
+            let sig = &method.sig;
+            let mut lifetimes = Vec::new();
+            for param in &sig.generics.params {
+                match param {
+                    GenericParam::Lifetime(def) => lifetimes.push(def.lifetime.clone()),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            param,
+                            "mock_trait! can't mock a method with type or const parameters",
+                        ))
+                    }
+                }
+            }
+            if !sig
+                .inputs
+                .iter()
+                .any(|input| matches!(input, FnArg::Receiver(_)))
+            {
+                return Err(syn::Error::new_spanned(
+                    &sig.ident,
+                    "a mocked method needs a `self` parameter",
+                ));
+            }
+            if let Some(variadic) = &sig.variadic {
+                return Err(syn::Error::new_spanned(
+                    variadic,
+                    "mock_trait! can't mock variadic arguments",
+                ));
+            }
+            let mut types = Vec::new();
+            for input in &sig.inputs {
+                if let FnArg::Typed(typed) = input {
+                    if mentions(typed.ty.to_token_stream(), "impl") {
+                        return Err(syn::Error::new_spanned(
+                            &typed.ty,
+                            "mock_trait! can't mock an `impl Trait` argument",
+                        ));
+                    }
+                    types.push((*typed.ty).clone());
+                }
+            }
+            methods.push(Method {
+                forward: Forward::new(sig),
+                lifetimes,
+                types,
+            });
+        }
+        Ok(MockTraitInput { item, methods })
+    }
+}
+
+/// Emit the mock struct, its setters, and its impl of the trait.
+impl ToTokens for MockTraitInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let trait_name = &self.item.ident;
+        let vis = &self.item.vis;
+        let name = format_ident!("Mock{}", trait_name);
+        let doc = format!(
+            "A mock [`{}`], which records each call and answers it with a closure.",
+            trait_name
+        );
+
+        let mut fields = Vec::new();
+        let mut setters = Vec::new();
+        let mut impls = Vec::new();
+        for method in &self.methods {
+            let Method {
+                forward,
+                lifetimes,
+                types,
+            } = method;
+            let sig = &forward.sig;
+            let ident = &sig.ident;
+            let args = &forward.args;
+            let output = match &sig.output {
+                ReturnType::Default => quote! {},
+                ReturnType::Type(arrow, ty) => quote! { #arrow #ty },
+            };
+            let for_lifetimes = if lifetimes.is_empty() {
+                quote! {}
+            } else {
+                quote! { for<#(#lifetimes),*> }
+            };
+            let closure = quote! { #for_lifetimes ::core::ops::FnMut(#(#types),*) #output };
+
+            let field_doc = format!("The calls to `{}`, and its answer.", ident);
+            fields.push(quote! {
+                #[doc = #field_doc]
+                #vis #ident: ::abc::MockFn<dyn #closure + ::core::marker::Send>,
+            });
+
+            let setter = format_ident!("on_{}", ident);
+            let setter_doc = format!("Answer calls to `{}` with a closure.", ident);
+            setters.push(quote! {
+                #[doc = #setter_doc]
+                #vis fn #setter(
+                    &self,
+                    answer: impl #closure + ::core::marker::Send + 'static,
+                ) -> &Self {
+                    ::abc::__private::mock_answer(&self.#ident, ::abc::__private::alloc::boxed::Box::new(answer));
+                    self
+                }
+            });
+
+            // A call is recorded like `get("key")`.
+            let format = format!("{}({})", ident, vec!["{:?}"; args.len()].join(", "));
+            let unanswered = format!(
+                "{}::{} was called, but it has no answer; set one with `{}`",
+                name, ident, setter
+            );
+            impls.push(quote! {
+                #sig {
+                    let __call = {
+                        use ::abc::__private::{DebugField as _, OpaqueField as _};
+                        ::abc::__private::alloc::format!(#format, #((&::abc::__private::Probe(&#args)).as_debug()),*)
+                    };
+                    let mut __answer = ::abc::__private::mock_call(&self.#ident, __call);
+                    match __answer.as_mut() {
+                        ::core::option::Option::Some(answer) => answer(#(#args),*),
+                        ::core::option::Option::None => ::core::panic!(#unanswered),
+                    }
+                }
+            });
+        }
+
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #[derive(::core::fmt::Debug, ::core::default::Default)]
+            #vis struct #name {
+                #(#fields)*
+            }
+
+            impl #name {
+                /// A mock with no calls, and no answers.
+                #vis fn new() -> Self {
+                    ::core::default::Default::default()
+                }
+
+                #(#setters)*
+            }
+
+            impl #trait_name for #name {
+                #(#impls)*
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> syn::parse::Result<MockTraitInput> {
+        syn::parse_str(input)
+    }
+
+    #[test]
+    fn closure_types() {
+        let input = parse(
+            "pub trait Store {
+                fn get(&self, key: &str) -> Option<String>;
+                fn put(&mut self, (key, value): (String, String));
+                fn first<'a>(&self, keys: &'a [String]) -> &'a str;
+            }",
+        )
+        .unwrap();
+        let types: Vec<String> = input
+            .methods
+            .iter()
+            .map(|method| {
+                let types = &method.types;
+                quote!(#(#types),*).to_string()
+            })
+            .collect();
+        assert_eq!(types, ["& str", "(String , String)", "& 'a [String]"]);
+        assert_eq!(input.methods[2].lifetimes.len(), 1);
+        // The mock's name comes from the trait's.
+        let expanded = input.into_token_stream().to_string();
+        assert!(expanded.contains("pub struct MockStore"));
+        assert!(expanded.contains("impl Store for MockStore"));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input| parse(input).err().unwrap().to_string();
+        assert_eq!(
+            error("trait Store<K> { fn get(&self, key: K); }"),
+            "mock_trait! can't mock a generic trait"
+        );
+        assert_eq!(
+            error("trait Store { type Key; }"),
+            "mock_trait! can only mock methods"
+        );
+        assert_eq!(
+            error("trait Store { fn get<K: Hash>(&self, key: K); }"),
+            "mock_trait! can't mock a method with type or const parameters"
+        );
+        assert_eq!(
+            error("trait Store { fn new() -> Self; }"),
+            "a mocked method needs a `self` parameter"
+        );
+        assert_eq!(
+            error("trait Store { fn get(&self, key: impl Hash); }"),
+            "mock_trait! can't mock an `impl Trait` argument"
+        );
+    }
+}
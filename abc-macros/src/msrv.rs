@@ -0,0 +1,79 @@
+//! The oldest Rust that the calling crate supports.
+//!
+//! Cargo passes a package's `rust-version` (its MSRV) to rustc as
+//! `CARGO_PKG_RUST_VERSION`, and a proc macro runs inside rustc, so the
+//! macros can read it while they expand. A crate with `rust-version =
+//! "1.60"` gets expansions that build on Rust 1.60: anything newer is
+//! replaced with an older equivalent. A crate without a `rust-version`
+//! gets the newer code.
+
+use std::env;
+
+/// Parse the minor version out of `1.60` or `1.60.0`.
+fn parse_minor(text: &str) -> Option<u32> {
+    let mut parts = text.trim().split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+/// Whether code that needs Rust `1.minor` is fine for a crate whose
+/// `rust-version` is `msrv`.
+///
+/// An MSRV that can't be parsed is ignored, rather than reported: it's
+/// cargo's to check, not the macro's.
+///
+fn supports(msrv: Option<&str>, minor: u32) -> bool {
+    msrv.and_then(parse_minor)
+        .map_or(true, |msrv| msrv >= minor)
+}
+
+/// Whether the calling crate only needs to build on Rust `1.minor` and
+/// later.
+pub fn at_least(minor: u32) -> bool {
+    supports(env::var("CARGO_PKG_RUST_VERSION").ok().as_deref(), minor)
+}
+
+/// The path of `OnceLock`. It's stable since Rust 1.70, and abc has a
+/// stand-in for older compilers.
+#[cfg(any(feature = "regex", feature = "singleton"))]
+pub fn once_lock() -> proc_macro2::TokenStream {
+    if at_least(70) {
+        quote::quote! { ::abc::__private::std::sync::OnceLock }
+    } else {
+        quote::quote! { ::abc::__private::OnceLock }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(parse_minor("1.60"), Some(60));
+        assert_eq!(parse_minor("1.81.0"), Some(81));
+        assert_eq!(parse_minor(""), None);
+        assert_eq!(parse_minor("2.0"), None);
+        assert_eq!(parse_minor("1.x"), None);
+    }
+
+    // Cargo passes the tests this crate's own `rust-version`, so the unit
+    // tests expand the macros for a crate that supports Rust 1.60.
+    #[test]
+    fn tests_expand_for_1_60() {
+        assert_eq!(env::var("CARGO_PKG_RUST_VERSION").unwrap(), "1.60");
+        assert!(at_least(60));
+        assert!(!at_least(61));
+    }
+
+    #[test]
+    fn fallbacks() {
+        assert!(supports(None, 81));
+        assert!(supports(Some(""), 81));
+        assert!(supports(Some("1.81"), 81));
+        assert!(!supports(Some("1.60"), 70));
+        assert!(supports(Some("1.60"), 60));
+    }
+}
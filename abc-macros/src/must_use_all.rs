@@ -0,0 +1,134 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{parse_quote, ImplItem, ItemImpl, ReturnType, Type, Visibility};
+
+/// This is an `impl` block with `#[must_use_all]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct MustUseAll {
+    item: ItemImpl,
+}
+
+/// Whether a method's return value is worth using: it returns something
+/// other than `()` or `!`.
+fn returns_value(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) => !tuple.elems.is_empty(),
+            Type::Never(_) => false,
+            _ => true,
+        },
+    }
+}
+
+impl MustUseAll {
+    pub fn new(mut item: ItemImpl) -> syn::parse::Result<Self> {
+        if let Some((_, path, _)) = &item.trait_ {
+            // `#[must_use]` does nothing on a trait impl's methods; it has
+            // to go on the trait.
+            return Err(syn::Error::new_spanned(
+                path,
+                "must_use_all only works on inherent impl blocks",
+            ));
+        }
+        for impl_item in &mut item.items {
+            let method = match impl_item {
+                ImplItem::Method(method) => method,
+                _ => continue,
+            };
+            let mut opted_out = false;
+            let mut already = false;
+            let mut error = None;
+            method.attrs.retain(|attr| {
+                if attr.path.is_ident("allow_unused_result") {
+                    if !attr.tokens.is_empty() {
+                        error = Some(syn::Error::new_spanned(
+                            attr,
+                            "expected `#[allow_unused_result]`",
+                        ));
+                    }
+                    opted_out = true;
+                    return false;
+                }
+                already |= attr.path.is_ident("must_use");
+                true
+            });
+            if let Some(error) = error {
+                return Err(error);
+            }
+            let public = !matches!(method.vis, Visibility::Inherited);
+            if public && !opted_out && !already && returns_value(&method.sig.output) {
+                method.attrs.push(parse_quote!(#[must_use]));
+            }
+        }
+        Ok(MustUseAll { item })
+    }
+}
+
+/// Emit the `impl` block, with the `#[must_use]` attributes added.
+impl ToTokens for MustUseAll {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.item.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn must_use(input: &str) -> syn::parse::Result<Vec<String>> {
+        let must_use_all = MustUseAll::new(syn::parse_str(input)?)?;
+        Ok(must_use_all
+            .item
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Method(method)
+                    if method
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path.is_ident("must_use")) =>
+                {
+                    Some(method.sig.ident.to_string())
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    #[test]
+    fn marked_methods() {
+        assert_eq!(
+            must_use(
+                "impl Builder {
+                    pub fn new() -> Self { todo!() }
+                    pub fn name(self, name: &str) -> Self { todo!() }
+                    pub(crate) fn len(&self) -> usize { 0 }
+                    fn private(&self) -> usize { 0 }
+                    pub fn reset(&mut self) {}
+                    pub fn unit(&mut self) -> () {}
+                    pub fn abort(&self) -> ! { panic!() }
+                    #[allow_unused_result]
+                    pub fn push(&mut self, x: u8) -> &mut Self { self }
+                    #[must_use = \"it's lazy\"]
+                    pub fn lazy(&self) -> u8 { 0 }
+                    pub const LIMIT: usize = 3;
+                }"
+            )
+            .unwrap(),
+            ["new", "name", "len", "lazy"]
+        );
+
+        // The opt-out attribute is removed.
+        let must_use_all: MustUseAll = MustUseAll::new(
+            syn::parse_str("impl S { #[allow_unused_result] pub fn f(&self) -> u8 { 0 } }")
+                .unwrap(),
+        )
+        .unwrap();
+        let output = must_use_all.into_token_stream().to_string();
+        assert!(!output.contains("allow_unused_result"));
+
+        must_use("impl Clone for S { fn clone(&self) -> Self { S } }").unwrap_err();
+        must_use("impl S { #[allow_unused_result(x)] pub fn f(&self) -> u8 { 0 } }").unwrap_err();
+    }
+}
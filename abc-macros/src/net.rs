@@ -0,0 +1,139 @@
+use std::net::{IpAddr, SocketAddr};
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+use crate::msrv;
+
+/// The module the address types are in. They moved into `core` in Rust
+/// 1.77.
+fn net() -> TokenStream {
+    if msrv::at_least(77) {
+        quote! { ::core::net }
+    } else {
+        quote! { ::abc::__private::std::net }
+    }
+}
+
+/// An expression that constructs `ip`, usable in a `const`.
+fn ip_tokens(ip: &IpAddr) -> TokenStream {
+    let net = net();
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            quote! { #net::Ipv4Addr::new(#(#octets),*) }
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            quote! { #net::Ipv6Addr::new(#(#segments),*) }
+        }
+    }
+}
+
+/// This is the entire input to `ip!`.
+///
+/// Example: `ip!("10.0.0.1")` or `ip!("::1")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct IpInput {
+    ip: IpAddr,
+}
+
+/// Parse the input to `ip!`, checking the address.
+impl Parse for IpInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let ip = lit
+            .value()
+            .parse()
+            .map_err(|_| syn::Error::new(lit.span(), "expected an IPv4 or IPv6 address"))?;
+        Ok(IpInput { ip })
+    }
+}
+
+/// Emit an `Ipv4Addr` or an `Ipv6Addr`.
+impl ToTokens for IpInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(ip_tokens(&self.ip));
+    }
+}
+
+/// This is the entire input to `sockaddr!`.
+///
+/// Example: `sockaddr!("10.0.0.1:8080")` or `sockaddr!("[::1]:8080")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct SockAddrInput {
+    addr: SocketAddr,
+}
+
+/// Parse the input to `sockaddr!`, checking the address.
+impl Parse for SockAddrInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let addr = lit.value().parse().map_err(|_| {
+            syn::Error::new(
+                lit.span(),
+                "expected an address and port, like `10.0.0.1:8080` or `[::1]:8080`",
+            )
+        })?;
+        Ok(SockAddrInput { addr })
+    }
+}
+
+/// Emit a `SocketAddr`.
+impl ToTokens for SockAddrInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ip = ip_tokens(&self.addr.ip());
+        let port = self.addr.port();
+        let net = net();
+        tokens.extend(match &self.addr {
+            SocketAddr::V4(_) => quote! {
+                #net::SocketAddr::V4(#net::SocketAddrV4::new(#ip, #port))
+            },
+            SocketAddr::V6(addr) => {
+                let flowinfo = addr.flowinfo();
+                let scope_id = addr.scope_id();
+                quote! {
+                    #net::SocketAddr::V6(
+                        #net::SocketAddrV6::new(#ip, #port, #flowinfo, #scope_id),
+                    )
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_addresses() {
+        let input: IpInput = syn::parse_str("\"10.0.0.1\"").unwrap();
+        assert_eq!(input.ip, IpAddr::from([10, 0, 0, 1]));
+        syn::parse_str::<IpInput>("\"::1\"").unwrap();
+        syn::parse_str::<IpInput>("\"10.0.0.256\"").unwrap_err();
+        syn::parse_str::<IpInput>("\"10.0.0.1:80\"").unwrap_err();
+
+        let input: SockAddrInput = syn::parse_str("\"[::1]:8080\"").unwrap();
+        assert_eq!(input.addr.port(), 8080);
+        syn::parse_str::<SockAddrInput>("\"10.0.0.1\"").unwrap_err();
+        syn::parse_str::<SockAddrInput>("\"10.0.0.1:65536\"").unwrap_err();
+    }
+
+    // This crate's `rust-version` is 1.60, older than `core::net`.
+    #[test]
+    fn old_rust_version() {
+        let input: SockAddrInput = syn::parse_str("\"[::1]:8080\"").unwrap();
+        let output = input.to_token_stream().to_string();
+        assert!(
+            output.contains(":: abc :: __private :: std :: net :: SocketAddrV6"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("core :: net"), "{}", output);
+    }
+}
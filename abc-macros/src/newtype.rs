@@ -0,0 +1,213 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, Data, DataStruct, DeriveInput, Fields, Ident, Token, Type};
+
+/// The operator traits that `newtype!` can forward, with their methods.
+const BINARY_OPS: &[(&str, &str)] = &[
+    ("Add", "add"),
+    ("Sub", "sub"),
+    ("Mul", "mul"),
+    ("Div", "div"),
+    ("Rem", "rem"),
+];
+
+/// The traits that `newtype!` can implement, beyond the operators.
+const OTHER_TRAITS: &[&str] = &["Neg", "Display", "Deref", "DerefMut", "From", "Into"];
+
+/// This is the entire input to `newtype!`.
+///
+/// Example: `newtype!(pub struct Meters(f64); derive(Add, Sub, Display))`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct NewtypeInput {
+    item: DeriveInput,
+    inner: Type,
+    // The traits listed in `derive(...)`.
+    traits: Vec<Ident>,
+}
+
+/// Check whether a name is one of the traits `newtype!` implements.
+fn is_known(name: &str) -> bool {
+    let op = |name: &str| BINARY_OPS.iter().any(|(op, _)| *op == name);
+    match name.strip_suffix("Assign") {
+        Some(base) => op(base),
+        None => op(name) || OTHER_TRAITS.contains(&name),
+    }
+}
+
+/// Parse the input to `newtype!`.
+impl Parse for NewtypeInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let item: DeriveInput = input.parse()?;
+        let inner = match &item.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Unnamed(fields),
+                ..
+            }) if fields.unnamed.len() == 1 => fields.unnamed[0].ty.clone(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    "expected a tuple struct with one field, like `struct Meters(f64);`",
+                ))
+            }
+        };
+        if !item.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.generics,
+                "newtype! doesn't support generic types",
+            ));
+        }
+
+        let mut traits = Vec::new();
+        if !input.is_empty() {
+            let keyword: Ident = input.parse()?;
+            if keyword != "derive" {
+                return Err(syn::Error::new_spanned(keyword, "expected `derive(...)`"));
+            }
+            let content;
+            parenthesized!(content in input);
+            for name in Punctuated::<Ident, Token![,]>::parse_terminated(&content)? {
+                if !is_known(&name.to_string()) {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        "newtype! can't implement this trait",
+                    ));
+                }
+                if !traits.contains(&name) {
+                    traits.push(name);
+                }
+            }
+            input.parse::<Option<Token![;]>>()?;
+        }
+        Ok(NewtypeInput {
+            item,
+            inner,
+            traits,
+        })
+    }
+}
+
+impl NewtypeInput {
+    fn has(&self, name: &str) -> bool {
+        self.traits.iter().any(|t| t == name)
+    }
+}
+
+/// Emit the struct, and an impl for each trait.
+impl ToTokens for NewtypeInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.item.ident;
+        let inner = &self.inner;
+        self.item.to_tokens(tokens);
+
+        for (op, method) in BINARY_OPS {
+            let trait_name = format_ident!("{}", op);
+            let method = format_ident!("{}", method);
+            if self.has(op) {
+                tokens.extend(quote! {
+                    impl ::core::ops::#trait_name for #name {
+                        type Output = #name;
+                        fn #method(self, rhs: #name) -> #name {
+                            #name(::core::ops::#trait_name::#method(self.0, rhs.0))
+                        }
+                    }
+                });
+            }
+            let assign = format_ident!("{}Assign", op);
+            let assign_method = format_ident!("{}_assign", method);
+            if self.has(&assign.to_string()) {
+                tokens.extend(quote! {
+                    impl ::core::ops::#assign for #name {
+                        fn #assign_method(&mut self, rhs: #name) {
+                            ::core::ops::#assign::#assign_method(&mut self.0, rhs.0);
+                        }
+                    }
+                });
+            }
+        }
+
+        if self.has("Neg") {
+            tokens.extend(quote! {
+                impl ::core::ops::Neg for #name {
+                    type Output = #name;
+                    fn neg(self) -> #name {
+                        #name(::core::ops::Neg::neg(self.0))
+                    }
+                }
+            });
+        }
+        if self.has("Display") {
+            tokens.extend(quote! {
+                impl ::core::fmt::Display for #name {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        ::core::fmt::Display::fmt(&self.0, f)
+                    }
+                }
+            });
+        }
+        // `DerefMut` needs `Deref`, so asking for it implies both.
+        if self.has("Deref") || self.has("DerefMut") {
+            tokens.extend(quote! {
+                impl ::core::ops::Deref for #name {
+                    type Target = #inner;
+                    fn deref(&self) -> &#inner {
+                        &self.0
+                    }
+                }
+            });
+        }
+        if self.has("DerefMut") {
+            tokens.extend(quote! {
+                impl ::core::ops::DerefMut for #name {
+                    fn deref_mut(&mut self) -> &mut #inner {
+                        &mut self.0
+                    }
+                }
+            });
+        }
+        if self.has("From") {
+            tokens.extend(quote! {
+                impl ::core::convert::From<#inner> for #name {
+                    fn from(value: #inner) -> #name {
+                        #name(value)
+                    }
+                }
+            });
+        }
+        // `Into` comes from a `From` impl on the inner type.
+        if self.has("Into") {
+            tokens.extend(quote! {
+                impl ::core::convert::From<#name> for #inner {
+                    fn from(value: #name) -> #inner {
+                        value.0
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_traits() {
+        let input: NewtypeInput =
+            syn::parse_str("pub struct Meters(f64); derive(Add, AddAssign, Display, Add)").unwrap();
+        let traits: Vec<String> = input.traits.iter().map(|t| t.to_string()).collect();
+        assert_eq!(traits, ["Add", "AddAssign", "Display"]);
+
+        let input: NewtypeInput = syn::parse_str("struct Id(u32);").unwrap();
+        assert!(input.traits.is_empty());
+
+        syn::parse_str::<NewtypeInput>("struct Id(u32); derive(Hash)").unwrap_err();
+        syn::parse_str::<NewtypeInput>("struct Id(u32); derive(NegAssign)").unwrap_err();
+        syn::parse_str::<NewtypeInput>("struct Id(u32); impl(Add)").unwrap_err();
+        syn::parse_str::<NewtypeInput>("struct Id(u32, u32);").unwrap_err();
+        syn::parse_str::<NewtypeInput>("struct Id { id: u32 }").unwrap_err();
+        syn::parse_str::<NewtypeInput>("struct Id<T>(T);").unwrap_err();
+    }
+}
@@ -0,0 +1,63 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+use crate::msrv;
+
+/// This is the entire input to `regex!`.
+///
+/// Example: `regex!(r"^\d{4}-\d{2}-\d{2}$")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct RegexInput {
+    pattern: LitStr,
+}
+
+/// Parse the input to `regex!`, checking that the pattern compiles.
+impl Parse for RegexInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let pattern: LitStr = input.parse()?;
+        // The error points at the whole literal: on stable Rust, a proc
+        // macro can't point inside one. `regex`'s message makes up for
+        // that, by quoting the pattern and underlining the part that's
+        // wrong.
+        if let Err(e) = regex::Regex::new(&pattern.value()) {
+            return Err(syn::Error::new(
+                pattern.span(),
+                format!("invalid regex: {}", e),
+            ));
+        }
+        Ok(RegexInput { pattern })
+    }
+}
+
+/// Emit a `&'static Regex`, compiled the first time it's used.
+impl ToTokens for RegexInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let pattern = &self.pattern;
+        let once_lock = msrv::once_lock();
+        tokens.extend(quote! {
+            {
+                static REGEX: #once_lock<::abc::__private::regex::Regex> = #once_lock::new();
+                REGEX.get_or_init(|| {
+                    ::abc::__private::regex::Regex::new(#pattern)
+                        .expect("the pattern was checked when it was compiled")
+                })
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_patterns() {
+        syn::parse_str::<RegexInput>(r#"r"^\d+$""#).unwrap();
+        let err = syn::parse_str::<RegexInput>(r#""(unclosed""#).unwrap_err();
+        assert!(err.to_string().contains("unclosed group"), "{}", err);
+        syn::parse_str::<RegexInput>("pattern").unwrap_err();
+    }
+}
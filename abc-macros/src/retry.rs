@@ -0,0 +1,213 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, ItemFn, Lit, Meta, NestedMeta, Path, Token};
+
+use crate::attr::{self, lit_str};
+use crate::wrap;
+
+/// The arguments to `#[retry(...)]`.
+///
+/// Example: `#[retry(times = 3, backoff_ms = 100)]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct RetryArgs {
+    // `times = N`: how many attempts to make in total.
+    times: u32,
+    // `backoff_ms = N`: the delay before the first retry, which doubles
+    // after each one.
+    backoff_ms: u64,
+    // `sleep = "..."`: the function used to wait between attempts.
+    sleep: Option<Path>,
+    // `crate = "..."`: the path to the `abc` crate.
+    crate_path: Path,
+}
+
+fn lit_int<N>(lit: &Lit) -> syn::parse::Result<N>
+where
+    N: std::str::FromStr,
+    N::Err: std::fmt::Display,
+{
+    match lit {
+        Lit::Int(int) => int.base10_parse(),
+        _ => Err(syn::Error::new_spanned(lit, "expected an integer")),
+    }
+}
+
+impl Parse for RetryArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = RetryArgs {
+            times: 3,
+            backoff_ms: 0,
+            sleep: None,
+            crate_path: parse_quote!(::abc),
+        };
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("times") => {
+                    args.times = lit_int(&nv.lit)?;
+                    if args.times == 0 {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "a function needs at least one attempt",
+                        ));
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("backoff_ms") => {
+                    args.backoff_ms = lit_int(&nv.lit)?;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("sleep") => {
+                    args.sleep = Some(lit_str(&nv.lit)?.parse()?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    args.crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown retry option",
+                        &["times", "backoff_ms", "sleep", "crate"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a function with `#[retry]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct Retry {
+    args: RetryArgs,
+    function: ItemFn,
+}
+
+impl Retry {
+    pub fn new(args: RetryArgs, function: ItemFn) -> syn::parse::Result<Self> {
+        let sig = &function.sig;
+        if let Some(constness) = &sig.constness {
+            return Err(syn::Error::new_spanned(
+                constness,
+                "retry can't be used on a const fn",
+            ));
+        }
+        // Blocking in an async fn would stall the executor, and there's no
+        // portable way to sleep without one, so async fns have to choose.
+        if sig.asyncness.is_some() && args.backoff_ms > 0 && args.sleep.is_none() {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                "an async fn with a backoff needs an async sleep function, \
+                 like `#[retry(sleep = \"tokio::time::sleep\")]`",
+            ));
+        }
+        Ok(Retry { args, function })
+    }
+}
+
+/// Emit the function, with its body run in a retry loop.
+impl ToTokens for Retry {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let RetryArgs {
+            times,
+            backoff_ms,
+            crate_path: krate,
+            ..
+        } = &self.args;
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = &self.function;
+
+        // Unlike `wrap::call_body`, the body borrows the arguments rather
+        // than taking them, since it may run more than once.
+        let stmts = &block.stmts;
+        let fake_return = wrap::fake_return(sig);
+        let attempt = if sig.asyncness.is_some() {
+            quote! { async { #fake_return #(#stmts)* }.await }
+        } else {
+            quote! { #krate::__private::call_once(|| { #fake_return #(#stmts)* }) }
+        };
+
+        let (init, wait) = if *backoff_ms == 0 {
+            (quote! {}, quote! {})
+        } else {
+            let duration = quote! { ::core::time::Duration::from_millis(__delay_ms) };
+            let sleep = match (&self.args.sleep, sig.asyncness.is_some()) {
+                (Some(sleep), true) => quote! { #sleep(#duration).await; },
+                (Some(sleep), false) => quote! { #sleep(#duration); },
+                (None, _) => quote! { #krate::__private::std::thread::sleep(#duration); },
+            };
+            (
+                quote! { let mut __delay_ms: u64 = #backoff_ms; },
+                quote! {
+                    #sleep
+                    __delay_ms = __delay_ms.saturating_mul(2);
+                },
+            )
+        };
+
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis #sig {
+                #init
+                let mut __attempt: u32 = 1;
+                loop {
+                    match #attempt {
+                        ::core::result::Result::Err(_) if __attempt < #times => {
+                            #wait
+                            __attempt += 1;
+                        }
+                        __result => return __result,
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let args: RetryArgs = syn::parse_str("").unwrap();
+        assert_eq!((args.times, args.backoff_ms), (3, 0));
+
+        let args: RetryArgs =
+            syn::parse_str("times = 5, backoff_ms = 100, sleep = \"tokio::time::sleep\"").unwrap();
+        assert_eq!((args.times, args.backoff_ms), (5, 100));
+        assert_eq!(args.sleep, Some(parse_quote!(tokio::time::sleep)));
+
+        syn::parse_str::<RetryArgs>("times = 0").unwrap_err();
+        syn::parse_str::<RetryArgs>("times = \"3\"").unwrap_err();
+        syn::parse_str::<RetryArgs>("backoff_ms = -1").unwrap_err();
+        syn::parse_str::<RetryArgs>("bogus").unwrap_err();
+    }
+
+    #[test]
+    fn async_sleep() {
+        let retry = |args: &str, function: &str| {
+            Retry::new(
+                syn::parse_str(args).unwrap(),
+                syn::parse_str(function).unwrap(),
+            )
+        };
+        retry(
+            "backoff_ms = 10",
+            "async fn f() -> Result<(), ()> { Ok(()) }",
+        )
+        .unwrap_err();
+        retry(
+            "backoff_ms = 10, sleep = \"sleep\"",
+            "async fn f() -> Result<(), ()> { Ok(()) }",
+        )
+        .unwrap();
+        retry("", "async fn f() -> Result<(), ()> { Ok(()) }").unwrap();
+        retry("backoff_ms = 10", "fn f() -> Result<(), ()> { Ok(()) }").unwrap();
+    }
+}
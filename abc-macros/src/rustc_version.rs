@@ -0,0 +1,148 @@
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Lit, Token};
+
+/// The version of rustc, from the build script.
+const RUSTC_VERSION: Option<&str> = option_env!("ABC_RUSTC_VERSION");
+
+type Version = (u64, u64, u64);
+
+/// Parse `1.70` or `1.70.0`, ignoring a suffix like `-nightly`.
+fn parse_version(text: &str) -> Option<Version> {
+    let release = text.split('-').next()?;
+    let mut parts = release.split('.').map(|part| {
+        if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+            part.parse::<u64>().ok()
+        } else {
+            None
+        }
+    });
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = match parts.next() {
+        Some(patch) => patch?,
+        None => 0,
+    };
+    match parts.next() {
+        Some(_) => None,
+        None => Some((major, minor, patch)),
+    }
+}
+
+/// This is the entire input to `rustc_version_at_least!`.
+///
+/// Example: `rustc_version_at_least!(1.70)` or
+/// `rustc_version_at_least!("1.70.0" { new(); } else { old(); })`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct RustcVersionInput {
+    at_least: bool,
+    // The code to use if the version is new enough, and if it isn't.
+    then: Option<TokenStream>,
+    otherwise: Option<TokenStream>,
+}
+
+impl RustcVersionInput {
+    fn new(
+        required: Version,
+        current: Option<&str>,
+        span: Span,
+        then: Option<TokenStream>,
+        otherwise: Option<TokenStream>,
+    ) -> syn::parse::Result<Self> {
+        let current = match current.and_then(parse_version) {
+            Some(current) => current,
+            None => {
+                return Err(syn::Error::new(
+                    span,
+                    "couldn't find out the version of rustc",
+                ))
+            }
+        };
+        Ok(RustcVersionInput {
+            at_least: current >= required,
+            then,
+            otherwise,
+        })
+    }
+}
+
+/// Parse the input to `rustc_version_at_least!`.
+impl Parse for RustcVersionInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: Lit = input.parse()?;
+        let text = match &lit {
+            Lit::Float(float) if float.suffix().is_empty() => float.base10_digits().to_string(),
+            Lit::Str(string) => string.value(),
+            _ => String::new(),
+        };
+        let required = parse_version(&text).ok_or_else(|| {
+            syn::Error::new_spanned(&lit, "expected a version, like `1.70` or \"1.70.0\"")
+        })?;
+
+        let mut then = None;
+        let mut otherwise = None;
+        if !input.is_empty() {
+            let content;
+            braced!(content in input);
+            then = Some(content.parse()?);
+            if input.peek(Token![else]) {
+                input.parse::<Token![else]>()?;
+                let content;
+                braced!(content in input);
+                otherwise = Some(content.parse()?);
+            }
+        }
+        RustcVersionInput::new(required, RUSTC_VERSION, lit.span(), then, otherwise)
+    }
+}
+
+/// Emit `true` or `false`, or the code for the version.
+impl ToTokens for RustcVersionInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match (&self.then, &self.otherwise) {
+            (None, _) => self.at_least.to_tokens(tokens),
+            (Some(then), _) if self.at_least => tokens.extend(then.clone()),
+            (Some(_), Some(otherwise)) => tokens.extend(otherwise.clone()),
+            (Some(_), None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(parse_version("1.70"), Some((1, 70, 0)));
+        assert_eq!(parse_version("1.70.1"), Some((1, 70, 1)));
+        assert_eq!(parse_version("1.72.0-nightly"), Some((1, 72, 0)));
+        assert_eq!(parse_version("1"), None);
+        assert_eq!(parse_version("1.70.0.0"), None);
+        assert_eq!(parse_version("1.x"), None);
+        assert_eq!(parse_version("1.+7"), None);
+
+        // The build script found the version.
+        assert!(RUSTC_VERSION.and_then(parse_version).is_some());
+    }
+
+    #[test]
+    fn compare_versions() {
+        let at_least = |required, current| {
+            RustcVersionInput::new(required, Some(current), Span::call_site(), None, None)
+                .map(|input| input.at_least)
+        };
+        assert!(at_least((1, 70, 0), "1.70.0").unwrap());
+        assert!(at_least((1, 70, 0), "1.71.0-beta.2").unwrap());
+        assert!(!at_least((1, 70, 1), "1.70.0").unwrap());
+        assert!(!at_least((2, 0, 0), "1.99.0").unwrap());
+        assert!(at_least((1, 70, 0), "unknown").is_err());
+
+        syn::parse_str::<RustcVersionInput>("1.70").unwrap();
+        syn::parse_str::<RustcVersionInput>("\"1.0.0\" { a } else { b }").unwrap();
+        syn::parse_str::<RustcVersionInput>("1.70f32").unwrap_err();
+        syn::parse_str::<RustcVersionInput>("70").unwrap_err();
+    }
+}
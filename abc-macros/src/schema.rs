@@ -0,0 +1,199 @@
+//! JSON Schema generation for `#[describe(json_schema)]`.
+//!
+//! The schema is built from the syntax of the type definition, so it can
+//! only understand field types it recognizes by name: primitives,
+//! strings, and the common std containers. Anything else is allowed to
+//! be any value (`{}`).
+
+use std::fmt::{self, Display, Write};
+
+use syn::{GenericArgument, PathArguments, Type};
+
+/// A JSON value, just capable enough to write out a schema.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Build an object from key/value pairs.
+    pub fn object<'a>(pairs: impl IntoIterator<Item = (&'a str, Json)>) -> Json {
+        Json::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(s: impl Into<String>) -> Json {
+        Json::String(s.into())
+    }
+}
+
+/// Write a JSON string, with quotes and escapes.
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(pairs) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// The single generic type argument of a path segment, like the `T` in `Vec<T>`.
+fn generic_args(args: &PathArguments) -> Vec<&Type> {
+    match args {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn array_of(items: Json) -> Json {
+    Json::object(vec![("type", Json::string("array")), ("items", items)])
+}
+
+/// If this is `Option<T>`, return `T`.
+///
+/// Optional fields are left out of an object's `required` list.
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last()?;
+            match generic_args(&segment.arguments).as_slice() {
+                [inner] if segment.ident == "Option" => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The schema of a field's type.
+pub fn type_schema(ty: &Type) -> Json {
+    let simple = |name: &str| Json::object(vec![("type", Json::string(name))]);
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = match path.path.segments.last() {
+                Some(segment) => segment,
+                None => return Json::object(vec![]),
+            };
+            let args = generic_args(&segment.arguments);
+            match (segment.ident.to_string().as_str(), args.as_slice()) {
+                ("bool", []) => simple("boolean"),
+                (
+                    "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                    | "i128" | "isize",
+                    [],
+                ) => simple("integer"),
+                ("f32" | "f64", []) => simple("number"),
+                ("String" | "str" | "char", []) => simple("string"),
+                ("Vec" | "VecDeque" | "HashSet" | "BTreeSet", [item]) => {
+                    array_of(type_schema(item))
+                }
+                ("HashMap" | "BTreeMap", [_, value]) => Json::object(vec![
+                    ("type", Json::string("object")),
+                    ("additionalProperties", type_schema(value)),
+                ]),
+                // serde represents these the same way as what they contain.
+                ("Option" | "Box" | "Rc" | "Arc" | "Cow", [inner]) => type_schema(inner),
+                _ => Json::object(vec![]),
+            }
+        }
+        Type::Reference(reference) => type_schema(&reference.elem),
+        Type::Array(array) => array_of(type_schema(&array.elem)),
+        Type::Slice(slice) => array_of(type_schema(&slice.elem)),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => simple("null"),
+        Type::Tuple(tuple) => Json::object(vec![
+            ("type", Json::string("array")),
+            (
+                "items",
+                Json::Array(tuple.elems.iter().map(type_schema).collect()),
+            ),
+        ]),
+        Type::Paren(paren) => type_schema(&paren.elem),
+        Type::Group(group) => type_schema(&group.elem),
+        _ => Json::object(vec![]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn schema(ty: &str) -> String {
+        type_schema(&syn::parse_str(ty).unwrap()).to_string()
+    }
+
+    #[test]
+    fn field_types() {
+        assert_eq!(schema("u8"), r#"{"type":"integer"}"#);
+        assert_eq!(schema("&'static str"), r#"{"type":"string"}"#);
+        assert_eq!(schema("Option<f64>"), r#"{"type":"number"}"#);
+        assert_eq!(
+            schema("Vec<bool>"),
+            r#"{"type":"array","items":{"type":"boolean"}}"#
+        );
+        assert_eq!(
+            schema("std::collections::HashMap<String, [u8; 4]>"),
+            r#"{"type":"object","additionalProperties":{"type":"array","items":{"type":"integer"}}}"#
+        );
+        assert_eq!(
+            schema("(u8, String)"),
+            r#"{"type":"array","items":[{"type":"integer"},{"type":"string"}]}"#
+        );
+        assert_eq!(schema("MyType"), "{}");
+    }
+
+    #[test]
+    fn escapes() {
+        let json = Json::string("a \"quote\"\n\\ \u{1}");
+        assert_eq!(json.to_string(), r#""a \"quote\"\n\\ \u0001""#);
+    }
+}
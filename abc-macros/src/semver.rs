@@ -0,0 +1,190 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+/// A version number, parsed following the Semantic Versioning 2.0.0
+/// rules.
+#[derive(Debug, PartialEq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: String,
+    build: String,
+}
+
+/// Parse a number. Leading zeros aren't allowed.
+fn parse_number(text: &str, part: &str) -> Result<u64, String> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("the {} version must be a number", part));
+    }
+    if text.len() > 1 && text.starts_with('0') {
+        return Err(format!("the {} version can't have leading zeros", part));
+    }
+    text.parse()
+        .map_err(|_| format!("the {} version is too large", part))
+}
+
+/// Check the dot-separated identifiers of a pre-release or build.
+fn check_identifiers(text: &str, part: &str) -> Result<(), String> {
+    for identifier in text.split('.') {
+        if identifier.is_empty() {
+            return Err(format!("the {} has an empty identifier", part));
+        }
+        if !identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(format!(
+                "`{}` in the {} may only use letters, digits, and `-`",
+                identifier, part
+            ));
+        }
+        // Only pre-release numbers are compared as numbers, so only
+        // they can't have leading zeros.
+        let numeric = identifier.chars().all(|c| c.is_ascii_digit());
+        if part == "pre-release" && numeric && identifier.len() > 1 && identifier.starts_with('0') {
+            return Err(format!(
+                "`{}` in the pre-release can't have leading zeros",
+                identifier
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_version(text: &str) -> Result<Version, String> {
+    let (text, build) = match text.split_once('+') {
+        Some((text, build)) => {
+            check_identifiers(build, "build metadata")?;
+            (text, build)
+        }
+        None => (text, ""),
+    };
+    let (text, pre) = match text.split_once('-') {
+        Some((text, pre)) => {
+            check_identifiers(pre, "pre-release")?;
+            (text, pre)
+        }
+        None => (text, ""),
+    };
+    let mut numbers = text.split('.');
+    let (major, minor, patch) = match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(major), Some(minor), Some(patch)) if numbers.next().is_none() => (
+            parse_number(major, "major")?,
+            parse_number(minor, "minor")?,
+            parse_number(patch, "patch")?,
+        ),
+        _ => return Err("expected MAJOR.MINOR.PATCH, like `1.2.3`".to_string()),
+    };
+    Ok(Version {
+        major,
+        minor,
+        patch,
+        pre: pre.to_string(),
+        build: build.to_string(),
+    })
+}
+
+/// This is the entire input to `semver!`.
+///
+/// Example: `semver!("1.2.3-beta.1")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct SemverInput {
+    version: Version,
+}
+
+/// Parse the input to `semver!`, checking the version.
+impl Parse for SemverInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let version = parse_version(&lit.value()).map_err(|message| {
+            syn::Error::new(lit.span(), format!("invalid version: {}", message))
+        })?;
+        Ok(SemverInput { version })
+    }
+}
+
+/// Emit a `(major, minor, patch, pre)` tuple, or a `semver::Version` with
+/// the `semver` feature.
+impl ToTokens for SemverInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Version {
+            major,
+            minor,
+            patch,
+            pre,
+            build,
+        } = &self.version;
+        if cfg!(feature = "semver") {
+            let semver = quote! { ::abc::__private::semver };
+            let pre = if pre.is_empty() {
+                quote! { #semver::Prerelease::EMPTY }
+            } else {
+                quote! { #semver::Prerelease::new(#pre).unwrap() }
+            };
+            let build = if build.is_empty() {
+                quote! { #semver::BuildMetadata::EMPTY }
+            } else {
+                quote! { #semver::BuildMetadata::new(#build).unwrap() }
+            };
+            tokens.extend(quote! {
+                #semver::Version {
+                    major: #major,
+                    minor: #minor,
+                    patch: #patch,
+                    pre: #pre,
+                    build: #build,
+                }
+            });
+        } else {
+            tokens.extend(quote! {
+                (#major, #minor, #patch, #pre)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        let version = parse_version("1.2.3-beta.1+build.5").unwrap();
+        assert_eq!(
+            version,
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                pre: "beta.1".to_string(),
+                build: "build.5".to_string(),
+            }
+        );
+        let version = parse_version("0.10.0-x-y.0a").unwrap();
+        assert_eq!(version.pre, "x-y.0a");
+        parse_version("1.0.0+001").unwrap();
+
+        let err = |text| parse_version(text).unwrap_err();
+        assert_eq!(err("1.2"), "expected MAJOR.MINOR.PATCH, like `1.2.3`");
+        assert_eq!(err("1.2.3.4"), "expected MAJOR.MINOR.PATCH, like `1.2.3`");
+        assert_eq!(err("01.2.3"), "the major version can't have leading zeros");
+        assert_eq!(err("1.x.3"), "the minor version must be a number");
+        assert_eq!(err("1.2.3-"), "the pre-release has an empty identifier");
+        assert_eq!(
+            err("1.2.3-beta.01"),
+            "`01` in the pre-release can't have leading zeros"
+        );
+        assert_eq!(
+            err("1.2.3+a_b"),
+            "`a_b` in the build metadata may only use letters, digits, and `-`"
+        );
+        assert_eq!(
+            err("1.2.99999999999999999999"),
+            "the patch version is too large"
+        );
+    }
+}
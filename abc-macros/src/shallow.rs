@@ -0,0 +1,162 @@
+//! Parse an enum without its fields, for the derives that only look at
+//! the variants' names.
+//!
+//! A `DeriveInput` parses every field's type and every discriminant. The
+//! derives for fieldless enums never look at those, so here they're
+//! skipped over as tokens; the compiler checks them anyway.
+
+use proc_macro2::{Delimiter, Group, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::ParseStream;
+use syn::{braced, Attribute, Expr, Generics, Ident, Token, Visibility};
+
+/// An enum, parsed as far as the derives for fieldless enums need.
+#[cfg_attr(test, derive(Debug))]
+pub struct ShallowEnum {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub ident: Ident,
+    pub generics: Generics,
+    pub variants: Vec<ShallowVariant>,
+}
+
+/// One variant, with its fields left unparsed.
+#[cfg_attr(test, derive(Debug))]
+pub struct ShallowVariant {
+    pub attrs: Vec<Attribute>,
+    pub ident: Ident,
+    // The fields, with their parentheses or braces; `None` for a unit
+    // variant.
+    pub fields: Option<Group>,
+}
+
+impl ShallowEnum {
+    /// Parse the input to `#[derive(...)]`; `derive` names the trait, for
+    /// the error on anything but an enum.
+    pub fn parse(input: ParseStream, derive: &str) -> syn::parse::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis: Visibility = input.parse()?;
+        let lookahead = input.lookahead1();
+        if lookahead.peek(Token![struct]) || lookahead.peek(Token![union]) {
+            // Parse the name, so the error can point at it.
+            if input.peek(Token![struct]) {
+                input.parse::<Token![struct]>()?;
+            } else {
+                input.parse::<Token![union]>()?;
+            }
+            let ident: Ident = input.parse()?;
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("{} can only be derived for enums", derive),
+            ));
+        }
+        if !lookahead.peek(Token![enum]) {
+            return Err(lookahead.error());
+        }
+        input.parse::<Token![enum]>()?;
+        let ident: Ident = input.parse()?;
+        let mut generics: Generics = input.parse()?;
+        generics.where_clause = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let mut variants = Vec::new();
+        while !content.is_empty() {
+            let attrs = content.call(Attribute::parse_outer)?;
+            // The compiler rejects a visibility here, but it parses.
+            let _: Visibility = content.parse()?;
+            let ident: Ident = content.parse()?;
+            let fields = if content.peek(syn::token::Paren) || content.peek(syn::token::Brace) {
+                Some(content.parse()?)
+            } else {
+                None
+            };
+            if content.peek(Token![=]) {
+                content.parse::<Token![=]>()?;
+                let _: Expr = content.parse()?;
+            }
+            variants.push(ShallowVariant {
+                attrs,
+                ident,
+                fields,
+            });
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+
+        Ok(ShallowEnum {
+            attrs,
+            vis,
+            ident,
+            generics,
+            variants,
+        })
+    }
+}
+
+impl ShallowVariant {
+    /// A pattern for the fields that matches any of them: `{ .. }`,
+    /// `(..)`, or nothing for a unit variant.
+    pub fn rest_pattern(&self) -> TokenStream {
+        match self.fields.as_ref().map(Group::delimiter) {
+            Some(Delimiter::Brace) => quote! { { .. } },
+            Some(_) => quote! { (..) },
+            None => quote! {},
+        }
+    }
+}
+
+/// The variant as it was written, without any discriminant, for errors
+/// to point at.
+impl ToTokens for ShallowVariant {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for attr in &self.attrs {
+            attr.to_tokens(tokens);
+        }
+        self.ident.to_tokens(tokens);
+        self.fields.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::Parser;
+
+    fn parse(text: &str) -> syn::parse::Result<ShallowEnum> {
+        (|input: ParseStream| ShallowEnum::parse(input, "Foo")).parse_str(text)
+    }
+
+    #[test]
+    fn parse_variants() {
+        let input = parse(
+            "#[repr(u8)] pub enum E<T: Copy> where T: Default { \
+             A, #[cfg(x)] B(u8, T), C { c: Vec<u8> } = 3, D = 1 << 2, }",
+        )
+        .unwrap();
+        assert_eq!(input.ident, "E");
+        assert_eq!(input.attrs.len(), 1);
+        assert_eq!(input.generics.params.len(), 1);
+        assert!(input.generics.where_clause.is_some());
+        let names: Vec<String> = input.variants.iter().map(|v| v.ident.to_string()).collect();
+        assert_eq!(names, ["A", "B", "C", "D"]);
+        assert_eq!(input.variants[1].attrs.len(), 1);
+        let patterns: Vec<String> = input
+            .variants
+            .iter()
+            .map(|v| v.rest_pattern().to_string())
+            .collect();
+        assert_eq!(patterns, ["", "(..)", "{ .. }", ""]);
+
+        assert!(parse("enum Never {}").unwrap().variants.is_empty());
+        assert_eq!(
+            parse("struct Foo;").unwrap_err().to_string(),
+            "Foo can only be derived for enums"
+        );
+        parse("union Foo { a: u8 }").unwrap_err();
+        parse("enum Foo { A B }").unwrap_err();
+        parse("enum Foo { A = }").unwrap_err();
+    }
+}
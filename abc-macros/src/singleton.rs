@@ -0,0 +1,353 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{GenericArgument, Item, ItemFn, ItemStatic, PathArguments, ReturnType, Token, Type};
+
+use crate::msrv;
+
+/// The arguments to `#[singleton(...)]`.
+///
+/// Example: `#[singleton]` or `#[singleton(try)]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct SingletonArgs {
+    // `try`: the constructor returns a `Result`, so the accessor is
+    // `try_instance`.
+    fallible: bool,
+}
+
+impl Parse for SingletonArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        if input.is_empty() {
+            return Ok(SingletonArgs { fallible: false });
+        }
+        if input.peek(Token![try]) {
+            input.parse::<Token![try]>()?;
+            if input.is_empty() {
+                return Ok(SingletonArgs { fallible: true });
+            }
+        }
+        Err(input.error("expected `try`, or nothing"))
+    }
+}
+
+/// How the instance is created.
+#[cfg_attr(test, derive(Debug))]
+enum Source {
+    // A constructor function, which is emitted as it was.
+    Function(ItemFn),
+    // A static, which becomes the `OnceLock` holding the instance.
+    Static(ItemStatic),
+}
+
+/// This is an item with `#[singleton]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct Singleton {
+    source: Source,
+    // The singleton's type.
+    ty: Type,
+    // For `try`, the error type.
+    error: Option<Type>,
+}
+
+/// Split `Result<T, E>` into `T` and `E`.
+fn result_types(ty: &Type) -> Option<(Type, Type)> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Result" {
+        return None;
+    }
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return None,
+    };
+    match (args.first(), args.iter().nth(1), args.len()) {
+        (Some(GenericArgument::Type(ok)), Some(GenericArgument::Type(err)), 2) => {
+            Some((ok.clone(), err.clone()))
+        }
+        _ => None,
+    }
+}
+
+impl Singleton {
+    pub fn new(args: SingletonArgs, item: Item) -> syn::parse::Result<Self> {
+        let (source, ty) = match item {
+            Item::Fn(function) => {
+                let sig = &function.sig;
+                if let Some(asyncness) = &sig.asyncness {
+                    return Err(syn::Error::new_spanned(
+                        asyncness,
+                        "a singleton constructor can't be async",
+                    ));
+                }
+                if !sig.inputs.is_empty() || !sig.generics.params.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        &sig.ident,
+                        "a singleton constructor can't take arguments or be generic",
+                    ));
+                }
+                let ty = match &sig.output {
+                    ReturnType::Type(_, ty) => (**ty).clone(),
+                    ReturnType::Default => {
+                        return Err(syn::Error::new_spanned(
+                            &sig.ident,
+                            "a singleton constructor must return the instance",
+                        ))
+                    }
+                };
+                (Source::Function(function), ty)
+            }
+            Item::Static(item) => {
+                if let Some(mutability) = &item.mutability {
+                    return Err(syn::Error::new_spanned(
+                        mutability,
+                        "a singleton static can't be `mut`",
+                    ));
+                }
+                let ty = (*item.ty).clone();
+                (Source::Static(item), ty)
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "singleton can only be used on a function or a static",
+                ))
+            }
+        };
+        let (ty, error) = if args.fallible {
+            match result_types(&ty) {
+                Some((ok, err)) => (ok, Some(err)),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "`singleton(try)` needs a constructor that returns `Result<T, E>`",
+                    ))
+                }
+            }
+        } else {
+            (ty, None)
+        };
+        if let Type::Path(path) = &ty {
+            if path.path.is_ident("Self") {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "name the type instead of using `Self`; the accessor is added in an impl of its own",
+                ));
+            }
+        }
+        Ok(Singleton { source, ty, error })
+    }
+}
+
+/// Emit the constructor or static, and an impl with the accessor.
+impl ToTokens for Singleton {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ty = &self.ty;
+        let once_lock = msrv::once_lock();
+        // Where the instance is kept, and the expression that creates it.
+        let (item, vis, cell, create) = match &self.source {
+            Source::Function(function) => {
+                let name = &function.sig.ident;
+                let item = quote! { #function };
+                let cell = quote! {
+                    static __INSTANCE: #once_lock<#ty> = #once_lock::new();
+                    let __cell = &__INSTANCE;
+                };
+                (item, &function.vis, cell, quote! { #name() })
+            }
+            Source::Static(item) => {
+                let ItemStatic {
+                    attrs,
+                    vis,
+                    ident,
+                    expr,
+                    ..
+                } = item;
+                let item = quote! {
+                    #(#attrs)*
+                    #vis static #ident: #once_lock<#ty> = #once_lock::new();
+                };
+                (
+                    item,
+                    vis,
+                    quote! { let __cell = &#ident; },
+                    quote! { #expr },
+                )
+            }
+        };
+
+        let accessor = match &self.error {
+            None => quote! {
+                /// The shared instance, which is created the first time
+                /// this is called.
+                #vis fn instance() -> &'static #ty {
+                    #cell
+                    __cell.get_or_init(|| #create)
+                }
+            },
+            Some(error) => quote! {
+                /// The shared instance, which is created the first time
+                /// this succeeds.
+                ///
+                /// Until then, each call tries again, and returns the
+                /// error if it fails.
+                #vis fn try_instance() -> ::core::result::Result<&'static #ty, #error> {
+                    #cell
+                    if let ::core::option::Option::Some(__instance) = __cell.get() {
+                        return ::core::result::Result::Ok(__instance);
+                    }
+                    let __instance = #create?;
+                    // If another thread got there first, its instance wins.
+                    ::core::result::Result::Ok(__cell.get_or_init(|| __instance))
+                }
+            },
+        };
+
+        tokens.extend(quote! {
+            #item
+
+            impl #ty {
+                #accessor
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn singleton(args: TokenStream, item: TokenStream) -> syn::parse::Result<Singleton> {
+        Singleton::new(syn::parse2(args)?, syn::parse2(item)?)
+    }
+
+    #[test]
+    fn parse_items() {
+        let parsed = singleton(
+            quote!(),
+            quote!(
+                fn config() -> Config {
+                    Config
+                }
+            ),
+        );
+        assert!(parsed.unwrap().error.is_none());
+        let parsed = singleton(
+            quote!(),
+            quote!(
+                static CONFIG: Config = Config::load();
+            ),
+        );
+        assert!(parsed.is_ok());
+        let parsed = singleton(
+            quote!(try),
+            quote!(
+                fn config() -> Result<Config, Error> {
+                    todo!()
+                }
+            ),
+        )
+        .unwrap();
+        assert_eq!(parsed.ty.to_token_stream().to_string(), "Config");
+        assert_eq!(parsed.error.unwrap().to_token_stream().to_string(), "Error");
+
+        singleton(
+            quote!(try),
+            quote!(
+                fn config() -> Config {
+                    Config
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(try),
+            quote!(
+                fn config() -> io::Result<Config> {
+                    todo!()
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(lazy),
+            quote!(
+                fn config() -> Config {
+                    Config
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                fn config(path: &str) -> Config {
+                    Config
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                async fn config() -> Config {
+                    Config
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                fn config() {}
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                fn new() -> Self {
+                    Self
+                }
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                static mut CONFIG: Config = Config;
+            ),
+        )
+        .unwrap_err();
+        singleton(
+            quote!(),
+            quote!(
+                struct Config;
+            ),
+        )
+        .unwrap_err();
+    }
+
+    // This crate's `rust-version` is 1.60, older than `OnceLock`.
+    #[test]
+    fn old_rust_version() {
+        let parsed = singleton(
+            quote!(),
+            quote!(
+                fn config() -> Config {
+                    Config
+                }
+            ),
+        )
+        .unwrap();
+        let output = parsed.to_token_stream().to_string();
+        assert!(
+            output.contains(":: abc :: __private :: OnceLock < Config >"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("std :: sync"), "{}", output);
+    }
+}
@@ -0,0 +1,305 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Fields, Ident, Meta, NestedMeta, Path, Type,
+    Visibility,
+};
+
+use crate::attr;
+
+/// The container's methods, which a field's accessors can't share a
+/// name with.
+const METHODS: &[&str] = &[
+    "new",
+    "with_capacity",
+    "len",
+    "is_empty",
+    "push",
+    "pop",
+    "clear",
+    "get",
+    "iter",
+];
+
+/// This is the entire input to `soa!`: a struct with named fields.
+///
+/// Example: `soa! { pub struct Particle { pub x: f32, pub y: f32 } }`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct SoaInput {
+    item: DeriveInput,
+    // Extra derives for the container and the view, from
+    // `#[soa(derive(...))]`.
+    derives: Vec<Path>,
+    // Each field's name, type, visibility and doc comments.
+    fields: Vec<(Ident, Type, Visibility, Vec<Attribute>)>,
+}
+
+/// Parse the input to `soa!`.
+impl Parse for SoaInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut item: DeriveInput = input.parse()?;
+        let mut derives = Vec::new();
+        for nested in attr::options(&item.attrs, "soa")? {
+            match &nested {
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("derive") => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::Path(path)) => derives.push(path.clone()),
+                            _ => return Err(syn::Error::new_spanned(nested, "expected a trait")),
+                        }
+                    }
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown soa option",
+                        &["derive"],
+                    ))
+                }
+            }
+        }
+        item.attrs.retain(|attr| !attr.path.is_ident("soa"));
+
+        if !item.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.generics,
+                "soa! doesn't support generic structs",
+            ));
+        }
+        let named = match &item.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(named),
+                ..
+            }) if !named.named.is_empty() => named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    "soa! needs a struct with named fields",
+                ))
+            }
+        };
+        for field in &named.named {
+            let ident = field.ident.as_ref().unwrap();
+            let taken = METHODS
+                .iter()
+                .any(|method| ident == method || *ident == format!("{}_mut", method));
+            if taken {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    format!(
+                        "a field can't be named `{}`, because of the container's methods",
+                        ident
+                    ),
+                ));
+            }
+        }
+        let fields = named
+            .named
+            .iter()
+            .map(|field| {
+                let docs = field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path.is_ident("doc"))
+                    .cloned()
+                    .collect();
+                (
+                    field.ident.clone().unwrap(),
+                    field.ty.clone(),
+                    field.vis.clone(),
+                    docs,
+                )
+            })
+            .collect();
+        Ok(SoaInput {
+            item,
+            derives,
+            fields,
+        })
+    }
+}
+
+/// Emit the struct, its `Vec` container, and its `Ref` view.
+impl ToTokens for SoaInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let item = &self.item;
+        let name = &item.ident;
+        let vis = &item.vis;
+        let vec_name = format_ident!("{}Vec", name);
+        let ref_name = format_ident!("{}Ref", name);
+        let derives = &self.derives;
+        // The view is always `Clone` and `Copy`; deriving them twice
+        // would conflict.
+        let ref_derives = derives
+            .iter()
+            .filter(|path| !path.is_ident("Clone") && !path.is_ident("Copy"));
+
+        let vec_derives = if derives.is_empty() {
+            quote! {}
+        } else {
+            quote! { #[derive(#(#derives),*)] }
+        };
+
+        let names: Vec<&Ident> = self.fields.iter().map(|(ident, ..)| ident).collect();
+        let types: Vec<&Type> = self.fields.iter().map(|(_, ty, ..)| ty).collect();
+        let field_vis: Vec<&Visibility> = self.fields.iter().map(|(_, _, vis, _)| vis).collect();
+        let docs: Vec<&Vec<Attribute>> = self.fields.iter().map(|(.., docs)| docs).collect();
+        let names_mut: Vec<Ident> = names
+            .iter()
+            .map(|ident| format_ident!("{}_mut", ident))
+            .collect();
+        let first = names[0];
+
+        let vec_doc = format!("A list of [`{}`], stored as a `Vec` for each field.", name);
+        let ref_doc = format!("A borrowed [`{}`] in a [`{}`].", name, vec_name);
+
+        tokens.extend(quote! {
+            #item
+
+            #[doc = #vec_doc]
+            #vec_derives
+            #vis struct #vec_name {
+                #(#names: ::abc::__private::alloc::vec::Vec<#types>,)*
+            }
+
+            #[doc = #ref_doc]
+            #[derive(::core::clone::Clone, ::core::marker::Copy #(, #ref_derives)*)]
+            #vis struct #ref_name<'a> {
+                #(
+                    #(#docs)*
+                    #field_vis #names: &'a #types,
+                )*
+            }
+
+            impl #vec_name {
+                /// An empty list.
+                #vis fn new() -> Self {
+                    #vec_name {
+                        #(#names: ::abc::__private::alloc::vec::Vec::new(),)*
+                    }
+                }
+
+                /// An empty list, with room for `capacity` elements.
+                #vis fn with_capacity(capacity: usize) -> Self {
+                    #vec_name {
+                        #(#names: ::abc::__private::alloc::vec::Vec::with_capacity(capacity),)*
+                    }
+                }
+
+                /// The number of elements.
+                #vis fn len(&self) -> usize {
+                    self.#first.len()
+                }
+
+                /// Whether there are no elements.
+                #vis fn is_empty(&self) -> bool {
+                    self.#first.is_empty()
+                }
+
+                /// Add an element to the end.
+                #vis fn push(&mut self, value: #name) {
+                    let #name { #(#names),* } = value;
+                    #(self.#names.push(#names);)*
+                }
+
+                /// Remove the last element, and return it.
+                #vis fn pop(&mut self) -> ::core::option::Option<#name> {
+                    if self.is_empty() {
+                        return ::core::option::Option::None;
+                    }
+                    ::core::option::Option::Some(#name {
+                        #(#names: self.#names.pop().unwrap(),)*
+                    })
+                }
+
+                /// Remove every element.
+                #vis fn clear(&mut self) {
+                    #(self.#names.clear();)*
+                }
+
+                /// The element at `index`, if there is one.
+                #vis fn get(&self, index: usize) -> ::core::option::Option<#ref_name<'_>> {
+                    if index >= self.len() {
+                        return ::core::option::Option::None;
+                    }
+                    ::core::option::Option::Some(#ref_name {
+                        #(#names: &self.#names[index],)*
+                    })
+                }
+
+                /// Iterate over the elements, in order.
+                #vis fn iter(&self) -> impl ::core::iter::Iterator<Item = #ref_name<'_>> + '_ {
+                    (0..self.len()).map(move |index| #ref_name {
+                        #(#names: &self.#names[index],)*
+                    })
+                }
+
+                #(
+                    #(#docs)*
+                    #field_vis fn #names(&self) -> &[#types] {
+                        &self.#names
+                    }
+
+                    #(#docs)*
+                    #field_vis fn #names_mut(&mut self) -> &mut [#types] {
+                        &mut self.#names
+                    }
+                )*
+            }
+
+            impl ::core::default::Default for #vec_name {
+                fn default() -> Self {
+                    #vec_name::new()
+                }
+            }
+
+            impl ::core::iter::Extend<#name> for #vec_name {
+                fn extend<I: ::core::iter::IntoIterator<Item = #name>>(&mut self, iter: I) {
+                    for value in iter {
+                        self.push(value);
+                    }
+                }
+            }
+
+            impl ::core::iter::FromIterator<#name> for #vec_name {
+                fn from_iter<I: ::core::iter::IntoIterator<Item = #name>>(iter: I) -> Self {
+                    let mut list = #vec_name::new();
+                    list.extend(iter);
+                    list
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> syn::parse::Result<SoaInput> {
+        syn::parse_str(input)
+    }
+
+    #[test]
+    fn parse_soa() {
+        let input = parse(
+            "#[derive(Debug)] #[soa(derive(Debug, Clone))] pub struct P { pub x: f32, y: u8 }",
+        )
+        .unwrap();
+        let names: Vec<String> = input.fields.iter().map(|f| f.0.to_string()).collect();
+        assert_eq!(names, ["x", "y"]);
+        assert_eq!(input.derives.len(), 2);
+        // `#[soa]` isn't passed on to the struct.
+        assert_eq!(input.item.attrs.len(), 1);
+
+        parse("struct P;").unwrap_err();
+        parse("struct P {}").unwrap_err();
+        parse("struct P(f32);").unwrap_err();
+        parse("struct P<T> { x: T }").unwrap_err();
+        parse("#[soa(bogus)] struct P { x: f32 }").unwrap_err();
+        parse("struct P { len: f32 }").unwrap_err();
+        parse("struct P { iter_mut: f32 }").unwrap_err();
+    }
+}
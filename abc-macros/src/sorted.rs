@@ -0,0 +1,315 @@
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+use syn::{Attribute, Expr, ExprMatch, Item, Lit, Local, Pat, Path};
+
+/// What an element is sorted by.
+///
+/// Numbers sort before strings, so a list can't mix them and still be
+/// in order.
+///
+#[derive(Debug, PartialEq, PartialOrd)]
+enum Key {
+    Int(u128),
+    Str(String),
+}
+
+/// An element of a sorted list: its key, how to show it in an error, and
+/// where it is.
+struct Element {
+    key: Key,
+    name: String,
+    span: Span,
+}
+
+impl Element {
+    fn new(key: Key, name: String, span: Span) -> Self {
+        Element { key, name, span }
+    }
+
+    /// An element sorted by its text, like a name or a path.
+    fn text(name: String, span: Span) -> Self {
+        Element::new(Key::Str(name.clone()), name, span)
+    }
+}
+
+fn path_name(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn lit_element(lit: &Lit) -> Element {
+    match lit {
+        Lit::Str(s) => Element::new(Key::Str(s.value()), format!("{:?}", s.value()), s.span()),
+        Lit::Int(i) => match i.base10_parse() {
+            Ok(n) => Element::new(Key::Int(n), i.to_string(), i.span()),
+            Err(_) => Element::text(i.to_string(), i.span()),
+        },
+        other => Element::text(other.to_token_stream().to_string(), other.span()),
+    }
+}
+
+/// The sort key of an array element. A tuple is sorted by its first
+/// field, so tables of `(key, value)` pairs work.
+fn expr_element(expr: &Expr) -> Element {
+    match expr {
+        Expr::Lit(lit) => lit_element(&lit.lit),
+        Expr::Path(path) => Element::text(path_name(&path.path), path.span()),
+        Expr::Tuple(tuple) if !tuple.elems.is_empty() => expr_element(&tuple.elems[0]),
+        Expr::Group(group) => expr_element(&group.expr),
+        Expr::Paren(paren) => expr_element(&paren.expr),
+        other => Element::text(other.to_token_stream().to_string(), other.span()),
+    }
+}
+
+/// The sort key of a match arm's pattern. Catch-all arms, like `_` or
+/// `other`, have no key, and can go anywhere.
+fn pat_element(pat: &Pat) -> Option<Element> {
+    let element = match pat {
+        Pat::Wild(_) => return None,
+        Pat::Ident(ident) if ident.subpat.is_none() => return None,
+        Pat::Lit(lit) => expr_element(&lit.expr),
+        Pat::Path(path) => Element::text(path_name(&path.path), path.span()),
+        Pat::TupleStruct(tuple) => Element::text(path_name(&tuple.path), tuple.path.span()),
+        Pat::Struct(s) => Element::text(path_name(&s.path), s.path.span()),
+        Pat::Or(or) => return or.cases.first().and_then(pat_element),
+        Pat::Range(range) => expr_element(&range.lo),
+        other => Element::text(other.to_token_stream().to_string(), other.span()),
+    };
+    Some(element)
+}
+
+/// Check that the elements are in order, pointing at the first one
+/// that isn't.
+fn check_order(elements: &[Element]) -> syn::parse::Result<()> {
+    for (i, element) in elements.iter().enumerate().skip(1) {
+        if element.key < elements[i - 1].key {
+            // Name the element it should have gone before.
+            let before = elements[..i]
+                .iter()
+                .find(|other| element.key < other.key)
+                .unwrap();
+            return Err(syn::Error::new(
+                element.span,
+                format!("{} should sort before {}", element.name, before.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The elements of an array expression, looking through a `&`.
+fn array_elements(expr: &Expr) -> Option<Vec<Element>> {
+    match expr {
+        Expr::Array(array) => Some(array.elems.iter().map(expr_element).collect()),
+        Expr::Reference(reference) => array_elements(&reference.expr),
+        _ => None,
+    }
+}
+
+fn check_array(expr: &Expr) -> syn::parse::Result<()> {
+    match array_elements(expr) {
+        Some(elements) => check_order(&elements),
+        None => Err(syn::Error::new_spanned(
+            expr,
+            "expected an array or slice literal, like `&[...]`",
+        )),
+    }
+}
+
+/// Remove any `#[sorted]` from a list of attributes, returning whether
+/// there was one.
+fn take_sorted(attrs: &mut Vec<Attribute>) -> bool {
+    let before = attrs.len();
+    attrs.retain(|attr| !attr.path.is_ident("sorted"));
+    attrs.len() != before
+}
+
+/// Checks every `#[sorted]` match and `let` inside a function.
+#[derive(Default)]
+struct CheckFn {
+    errors: Vec<syn::Error>,
+}
+
+impl CheckFn {
+    fn result(&mut self, result: syn::parse::Result<()>) {
+        if let Err(e) = result {
+            self.errors.push(e);
+        }
+    }
+}
+
+impl VisitMut for CheckFn {
+    fn visit_expr_match_mut(&mut self, expr: &mut ExprMatch) {
+        if take_sorted(&mut expr.attrs) {
+            let elements: Vec<Element> = expr
+                .arms
+                .iter()
+                .filter_map(|arm| pat_element(&arm.pat))
+                .collect();
+            self.result(check_order(&elements));
+        }
+        visit_mut::visit_expr_match_mut(self, expr);
+    }
+
+    fn visit_local_mut(&mut self, local: &mut Local) {
+        if take_sorted(&mut local.attrs) {
+            let result = match &local.init {
+                Some((_, init)) => check_array(init),
+                None => Err(syn::Error::new_spanned(
+                    &local.pat,
+                    "a sorted `let` needs an array to check",
+                )),
+            };
+            self.result(result);
+        }
+        visit_mut::visit_local_mut(self, local);
+    }
+}
+
+/// This is an item with `#[sorted]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct Sorted {
+    item: Item,
+}
+
+impl Sorted {
+    pub fn new(mut item: Item) -> syn::parse::Result<Self> {
+        match &mut item {
+            Item::Enum(item) => {
+                let elements: Vec<Element> = item
+                    .variants
+                    .iter()
+                    .map(|variant| Element::text(variant.ident.to_string(), variant.ident.span()))
+                    .collect();
+                check_order(&elements)?;
+            }
+            Item::Const(item) => check_array(&item.expr)?,
+            Item::Static(item) => check_array(&item.expr)?,
+            Item::Fn(item) => {
+                let mut check = CheckFn::default();
+                check.visit_item_fn_mut(item);
+                let mut errors = check.errors.into_iter();
+                if let Some(mut error) = errors.next() {
+                    error.extend(errors);
+                    return Err(error);
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "sorted can be used on an enum, a const or static array, or a fn",
+                ))
+            }
+        }
+        Ok(Sorted { item })
+    }
+}
+
+/// Emit the item unchanged, apart from any `#[sorted]` inside it.
+impl ToTokens for Sorted {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.item.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn sorted(item: TokenStream) -> Result<String, String> {
+        let item: Item = syn::parse2(item).unwrap();
+        Sorted::new(item)
+            .map(|sorted| sorted.into_token_stream().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn enums() {
+        sorted(quote!(
+            enum Fruit {
+                Apple,
+                Banana(u8),
+                Cherry {},
+            }
+        ))
+        .unwrap();
+        let err = sorted(quote!(
+            enum Fruit {
+                Banana,
+                Cherry,
+                Apple,
+            }
+        ))
+        .unwrap_err();
+        assert_eq!(err, "Apple should sort before Banana");
+    }
+
+    #[test]
+    fn arrays() {
+        sorted(quote!(
+            const A: &[(&str, u8)] = &[("a", 1), ("b", 0)];
+        ))
+        .unwrap();
+        sorted(quote!(
+            static A: [u32; 3] = [2, 10, 300];
+        ))
+        .unwrap();
+        let err = sorted(quote!(
+            const A: [&str; 3] = ["a", "c", "b"];
+        ))
+        .unwrap_err();
+        assert_eq!(err, "\"b\" should sort before \"c\"");
+        let err = sorted(quote!(
+            const A: [u8; 3] = [1, 10, 9];
+        ))
+        .unwrap_err();
+        assert_eq!(err, "9 should sort before 10");
+        sorted(quote!(
+            const A: u8 = 1;
+        ))
+        .unwrap_err();
+        sorted(quote!(
+            struct A;
+        ))
+        .unwrap_err();
+    }
+
+    #[test]
+    fn functions() {
+        let expanded = sorted(quote! {
+            fn f(x: E) -> u8 {
+                #[sorted]
+                let table = [1, 2];
+                #[sorted]
+                match x {
+                    E::A | E::Z => 0,
+                    E::B(_) => 1,
+                    E::C { .. } => 2,
+                    _ => 3,
+                }
+            }
+        })
+        .unwrap();
+        assert!(!expanded.contains("sorted"));
+
+        let err = sorted(quote! {
+            fn f(x: E) {
+                #[sorted]
+                match x {
+                    E::B => {}
+                    E::A => {}
+                }
+                #[sorted]
+                let table = ["b", "a"];
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err, "E::A should sort before E::B");
+    }
+}
@@ -0,0 +1,297 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, Attribute, Ident, Path, Token, Visibility};
+
+/// An enum declaration, for the states or the events.
+///
+/// Example: `#[derive(Hash)] pub enum State { Idle, Running }`
+///
+#[cfg_attr(test, derive(Debug))]
+struct EnumDecl {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    variants: Vec<(Vec<Attribute>, Ident)>,
+}
+
+impl Parse for EnumDecl {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        input.parse::<Token![enum]>()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut variants = Vec::new();
+        while !content.is_empty() {
+            let variant_attrs = Attribute::parse_outer(&content)?;
+            variants.push((variant_attrs, content.parse()?));
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+        Ok(EnumDecl {
+            attributes,
+            vis,
+            name,
+            variants,
+        })
+    }
+}
+
+impl EnumDecl {
+    /// Check that `name` is one of the variants.
+    fn check(&self, name: &Ident, kind: &str) -> syn::parse::Result<()> {
+        if self.variants.iter().any(|(_, variant)| variant == name) {
+            Ok(())
+        } else {
+            Err(syn::Error::new_spanned(
+                name,
+                format!("`{}` is not a {} of `{}`", name, kind, self.name),
+            ))
+        }
+    }
+}
+
+/// Emit the enum.
+impl ToTokens for EnumDecl {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let EnumDecl {
+            attributes,
+            vis,
+            name,
+            ..
+        } = self;
+        let variants = self.variants.iter().map(|(attributes, variant)| {
+            quote! { #(#attributes)* #variant }
+        });
+        tokens.extend(quote! {
+            #(#attributes)*
+            #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::fmt::Debug, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis enum #name {
+                #(#variants,)*
+            }
+        });
+    }
+}
+
+/// One line of the transition table.
+///
+/// Example: `Idle + Start => Running` or `Running | Done + Reset => Idle`
+///
+#[cfg_attr(test, derive(Debug))]
+struct Transition {
+    from: Vec<Ident>,
+    event: Ident,
+    to: Ident,
+}
+
+impl Parse for Transition {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let from = Punctuated::<Ident, Token![|]>::parse_separated_nonempty(input)?;
+        input.parse::<Token![+]>()?;
+        let event = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let to = input.parse()?;
+        Ok(Transition {
+            from: from.into_iter().collect(),
+            event,
+            to,
+        })
+    }
+}
+
+/// This is the entire input to the `state_machine!` macro.
+///
+/// ```ignore
+/// state_machine! {
+///     pub enum State { Idle, Running }
+///     pub enum Event { Start, Stop }
+///     on_enter = log_state;
+///     transitions {
+///         Idle + Start => Running,
+///         Running + Stop => Idle,
+///     }
+/// }
+/// ```
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct StateMachine {
+    states: EnumDecl,
+    events: EnumDecl,
+    // `on_enter = path;`: called with the new state and the event.
+    on_enter: Option<Path>,
+    // `on_exit = path;`: called with the old state and the event.
+    on_exit: Option<Path>,
+    transitions: Vec<Transition>,
+}
+
+/// Parse the macro syntax for `state_machine!`.
+impl Parse for StateMachine {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let states: EnumDecl = input.parse()?;
+        let events: EnumDecl = input.parse()?;
+
+        let mut errors = Errors::new();
+        let mut on_enter = None;
+        let mut on_exit = None;
+        let transitions: Vec<Transition> = loop {
+            let keyword: Ident = input.parse()?;
+            if keyword == "transitions" {
+                let content;
+                braced!(content in input);
+                break errors.parse_terminated(&content);
+            }
+            let hook = match keyword.to_string().as_str() {
+                "on_enter" => &mut on_enter,
+                "on_exit" => &mut on_exit,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &keyword,
+                        "expected `on_enter`, `on_exit` or `transitions`",
+                    ))
+                }
+            };
+            if hook.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &keyword,
+                    format!("duplicate `{}`", keyword),
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            *hook = Some(input.parse()?);
+            input.parse::<Token![;]>()?;
+        };
+        if !input.is_empty() {
+            errors.push(input.error("unexpected input after the transitions"));
+        }
+
+        let mut seen: Vec<(&Ident, &Ident)> = Vec::new();
+        for transition in &transitions {
+            errors.push_result(events.check(&transition.event, "event"));
+            errors.push_result(states.check(&transition.to, "state"));
+            for from in &transition.from {
+                errors.push_result(states.check(from, "state"));
+                if seen.contains(&(from, &transition.event)) {
+                    errors.push_spanned(
+                        &transition.event,
+                        format!(
+                            "there's already a transition from `{}` on `{}`",
+                            from, transition.event
+                        ),
+                    );
+                }
+                seen.push((from, &transition.event));
+            }
+        }
+        errors.finish()?;
+
+        Ok(StateMachine {
+            states,
+            events,
+            on_enter,
+            on_exit,
+            transitions,
+        })
+    }
+}
+
+/// Emit the two enums, and the transition methods.
+impl ToTokens for StateMachine {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let StateMachine { states, events, .. } = self;
+        let state = &states.name;
+        let event = &events.name;
+        let arms = self.transitions.iter().map(|transition| {
+            let from = &transition.from;
+            let on = &transition.event;
+            let to = &transition.to;
+            quote! {
+                (#(#state::#from)|*, #event::#on) => ::core::option::Option::Some(#state::#to),
+            }
+        });
+        let on_exit = self.on_exit.as_ref().map(|hook| {
+            quote! { #hook(*self, event); }
+        });
+        let on_enter = self.on_enter.as_ref().map(|hook| {
+            quote! { #hook(next, event); }
+        });
+
+        tokens.extend(quote! {
+            #states
+            #events
+
+            impl #state {
+                /// The state that `event` leads to from this one, if any.
+                #[allow(unreachable_patterns)]
+                pub const fn next_state(self, event: #event) -> ::core::option::Option<Self> {
+                    match (self, event) {
+                        #(#arms)*
+                        _ => ::core::option::Option::None,
+                    }
+                }
+
+                /// Move to the state that `event` leads to, and return it.
+                ///
+                /// If there's no transition, the state is left unchanged.
+                pub fn transition(
+                    &mut self,
+                    event: #event,
+                ) -> ::core::result::Result<Self, ::abc::InvalidTransition<Self, #event>> {
+                    match self.next_state(event) {
+                        ::core::option::Option::Some(next) => {
+                            #on_exit
+                            *self = next;
+                            #on_enter
+                            ::core::result::Result::Ok(next)
+                        }
+                        ::core::option::Option::None => {
+                            ::core::result::Result::Err(::abc::InvalidTransition {
+                                state: *self,
+                                event,
+                            })
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(transitions: &str) -> syn::parse::Result<StateMachine> {
+        syn::parse_str(&format!(
+            "enum S {{ A, B, C }} enum E {{ Go, Stop }} {}",
+            transitions
+        ))
+    }
+
+    #[test]
+    fn parse_machine() {
+        let parsed =
+            machine("on_exit = exit; transitions { A | B + Go => C, C + Stop => A }").unwrap();
+        assert_eq!(parsed.transitions.len(), 2);
+        assert_eq!(parsed.transitions[0].from.len(), 2);
+        assert!(parsed.on_exit.is_some());
+        assert!(parsed.on_enter.is_none());
+
+        machine("transitions {}").unwrap();
+        machine("transitions { D + Go => A }").unwrap_err();
+        machine("transitions { A + Go => D }").unwrap_err();
+        machine("transitions { A + Jump => B }").unwrap_err();
+        machine("transitions { A + Go => B, A | C + Go => C }").unwrap_err();
+        machine("on_exit = a; on_exit = b; transitions {}").unwrap_err();
+        machine("on_leave = a; transitions {}").unwrap_err();
+        machine("").unwrap_err();
+        let error = machine("transitions { D + Go => A, A + Jump => B, A + Go => B, A + Go => C }")
+            .unwrap_err();
+        assert_eq!(error.into_iter().count(), 3);
+    }
+}
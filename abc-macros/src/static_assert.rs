@@ -0,0 +1,67 @@
+use proc_macro2::TokenStream;
+use quote::{quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Expr, LitStr, Token};
+
+/// This is the entire input to `static_assert!`.
+///
+/// Example: `static_assert!(size_of::<Header>() == 16, "Header must fit in a block")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct StaticAssertInput {
+    condition: Expr,
+    message: Option<LitStr>,
+}
+
+/// Parse the input to `static_assert!`.
+impl Parse for StaticAssertInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let condition = input.parse()?;
+        let mut message = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            message = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(StaticAssertInput { condition, message })
+    }
+}
+
+/// Emit a `const` that fails to evaluate if the condition is false.
+impl ToTokens for StaticAssertInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let condition = &self.condition;
+        // The message is built here, since a panic in a const can only
+        // use a plain string.
+        let expr = condition.to_token_stream().to_string();
+        let text = match &self.message {
+            Some(message) => format!("static assertion failed: {}: `{}`", message.value(), expr),
+            None => format!("static assertion failed: `{}`", expr),
+        };
+        tokens.extend(quote_spanned! {condition.span()=>
+            const _: () = {
+                if !(#condition) {
+                    ::core::panic!(#text);
+                }
+            };
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        let input: StaticAssertInput = syn::parse_str("1 + 1 == 2, \"math works\"").unwrap();
+        let expanded = input.into_token_stream().to_string();
+        assert!(expanded.contains("\"static assertion failed: math works: `1 + 1 == 2`\""));
+
+        let input: StaticAssertInput = syn::parse_str("true,").unwrap();
+        let expanded = input.into_token_stream().to_string();
+        assert!(expanded.contains("\"static assertion failed: `true`\""));
+
+        syn::parse_str::<StaticAssertInput>("true, message").unwrap_err();
+    }
+}
@@ -0,0 +1,201 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Expr, Lit, Token, Type};
+
+/// The value of a key, for sorting and for finding duplicates.
+///
+/// This has to sort the same way as the key type's `Ord` impl: strings
+/// and byte strings compare bytewise, and integers by value.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyValue {
+    Str(String),
+    ByteStr(Vec<u8>),
+    // Byte literals are integers too, so `b'a'` and `97` are the same key.
+    Int(i128),
+    Char(char),
+    Bool(bool),
+}
+
+impl KeyValue {
+    fn same_kind(&self, other: &KeyValue) -> bool {
+        core::mem::discriminant(self) == core::mem::discriminant(other)
+    }
+}
+
+/// One `key => value` entry.
+struct Entry {
+    key: TokenStream,
+    key_value: KeyValue,
+    value: Expr,
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let minus: Option<Token![-]> = input.parse()?;
+        let lit: Lit = input.parse()?;
+        let key_value = match (&lit, minus) {
+            (Lit::Int(int), Some(_)) => KeyValue::Int(-int.base10_parse::<i128>()?),
+            (Lit::Int(int), None) => KeyValue::Int(int.base10_parse()?),
+            (Lit::Byte(byte), None) => KeyValue::Int(byte.value().into()),
+            (Lit::Str(string), None) => KeyValue::Str(string.value()),
+            (Lit::ByteStr(bytes), None) => KeyValue::ByteStr(bytes.value()),
+            (Lit::Char(c), None) => KeyValue::Char(c.value()),
+            (Lit::Bool(value), None) => KeyValue::Bool(value.value),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &lit,
+                    "a key must be a string, byte string, integer, char or bool literal",
+                ))
+            }
+        };
+        let key = quote! { #minus #lit };
+        input.parse::<Token![=>]>()?;
+        let value = input.parse()?;
+        Ok(Entry {
+            key,
+            key_value,
+            value,
+        })
+    }
+}
+
+/// This is the entire input to `static_map!`.
+///
+/// Example: `static_map!(&str => u32 { "a" => 1, "b" => 2 })`
+///
+pub struct StaticMapInput {
+    key_type: Type,
+    value_type: Type,
+    // Sorted by key.
+    entries: Vec<Entry>,
+}
+
+/// Parse the input to `static_map!`.
+impl Parse for StaticMapInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let key_type = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let value_type = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let mut entries: Vec<Entry> = Vec::new();
+        for entry in errors.parse_terminated::<Entry>(&content) {
+            if let Some(first) = entries.first() {
+                if !entry.key_value.same_kind(&first.key_value) {
+                    errors
+                        .push_spanned(&entry.key, "all the keys must be the same kind of literal");
+                    continue;
+                }
+            }
+            if entries
+                .iter()
+                .any(|other| other.key_value == entry.key_value)
+            {
+                errors.push_spanned(&entry.key, format!("duplicate key `{}`", entry.key));
+                continue;
+            }
+            entries.push(entry);
+        }
+        errors.finish()?;
+        entries.sort_by(|a, b| a.key_value.cmp(&b.key_value));
+        Ok(StaticMapInput {
+            key_type,
+            value_type,
+            entries,
+        })
+    }
+}
+
+/// Emit the map, as a constant `abc::StaticMap`.
+impl ToTokens for StaticMapInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let StaticMapInput {
+            key_type,
+            value_type,
+            ..
+        } = self;
+        let keys = self.entries.iter().map(|entry| &entry.key);
+        let values = self.entries.iter().map(|entry| &entry.value);
+        tokens.extend(quote! {
+            {
+                const MAP: ::abc::StaticMap<#key_type, #value_type> =
+                    ::abc::__private::static_map(&[#((#keys, #values)),*]);
+                MAP
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(input: &str) -> Vec<KeyValue> {
+        let input: StaticMapInput = syn::parse_str(input).unwrap();
+        input.entries.into_iter().map(|e| e.key_value).collect()
+    }
+
+    fn error(input: &str) -> String {
+        match syn::parse_str::<StaticMapInput>(input) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_static_map() {
+        use KeyValue::*;
+        assert_eq!(
+            keys(r#"&str => u32 { "b" => 2, "a" => 1, "B" => 3 }"#),
+            [Str("B".into()), Str("a".into()), Str("b".into())]
+        );
+        assert_eq!(
+            keys("i8 => &str { 3 => \"x\", -1 => \"y\", 0x2 => \"z\", }"),
+            [Int(-1), Int(2), Int(3)]
+        );
+        assert_eq!(keys("u8 => u8 { b'b' => 1, 97 => 2 }"), [Int(97), Int(98)]);
+        assert_eq!(keys("u8 => u8 {}"), []);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            error(r#"&str => u32 { "a" => 1, "b" => 2, "a" => 3 }"#),
+            "duplicate key `\"a\"`"
+        );
+        assert_eq!(
+            error("u8 => u32 { 0x61 => 1, b'a' => 2 }"),
+            "duplicate key `b'a'`"
+        );
+        assert_eq!(
+            error(r#"&str => u32 { "a" => 1, 'b' => 2 }"#),
+            "all the keys must be the same kind of literal"
+        );
+        assert_eq!(
+            error("f64 => u32 { 1.5 => 1 }"),
+            "a key must be a string, byte string, integer, char or bool literal"
+        );
+    }
+
+    #[test]
+    fn every_error() {
+        let messages: Vec<String> =
+            syn::parse_str::<StaticMapInput>(r#"&str => u32 { "a" => 1, 'b' => 2, "a" => 3, 4 }"#)
+                .err()
+                .unwrap()
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect();
+        assert_eq!(
+            messages,
+            [
+                "expected `=>`",
+                "all the keys must be the same kind of literal",
+                "duplicate key `\"a\"`",
+            ]
+        );
+    }
+}
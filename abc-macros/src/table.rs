@@ -0,0 +1,230 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    braced, bracketed, parse_quote, Attribute, Expr, Ident, Token, Type, TypeReference, Visibility,
+};
+
+/// A column of the table: `code: &str`, or `#[key] code: &str`.
+#[cfg_attr(test, derive(Debug))]
+struct Column {
+    // `#[key]`: generate a lookup by this column.
+    key: bool,
+    name: Ident,
+    ty: Type,
+}
+
+impl Parse for Column {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut key = false;
+        for attr in Attribute::parse_outer(input)? {
+            if attr.path.is_ident("key") && attr.tokens.is_empty() {
+                key = true;
+            } else {
+                return Err(syn::Error::new_spanned(attr, "unknown column attribute"));
+            }
+        }
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let mut ty: Type = input.parse()?;
+        StaticLifetimes.visit_type_mut(&mut ty);
+        Ok(Column { key, name, ty })
+    }
+}
+
+/// Gives every reference without a lifetime the `'static` lifetime,
+/// since the table is a static.
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(parse_quote!('static));
+        }
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+}
+
+/// This is the entire input to `table!`.
+///
+/// Example: `table!(Country { code: &str, dial: u16 } ["US", 1], ["DE", 49])`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct TableInput {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Expr>>,
+}
+
+/// Parse the input to `table!`.
+impl Parse for TableInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let mut columns: Vec<Column> = errors.parse_terminated(&content);
+        if columns.is_empty() {
+            // Nothing else can be checked without the columns.
+            errors.finish()?;
+            return Err(syn::Error::new_spanned(
+                &name,
+                "a table needs at least one column",
+            ));
+        }
+        // Without any `#[key]`, the first column is the key.
+        if !columns.iter().any(|column| column.key) {
+            columns[0].key = true;
+        }
+
+        let mut rows = Vec::new();
+        while !input.is_empty() {
+            let content;
+            let brackets = bracketed!(content in input);
+            let row = errors.push_result(Punctuated::<Expr, Token![,]>::parse_terminated(&content));
+            match row {
+                Some(row) if row.len() != columns.len() => errors.push(syn::Error::new(
+                    brackets.span,
+                    format!(
+                        "expected {} values in this row, one for each column, found {}",
+                        columns.len(),
+                        row.len()
+                    ),
+                )),
+                Some(row) => rows.push(row.into_iter().collect::<Vec<Expr>>()),
+                None => {}
+            }
+            if !input.is_empty() && errors.push_result(input.parse::<Token![,]>()).is_none() {
+                break;
+            }
+        }
+
+        // A lookup finds the first matching row, so a repeated key would
+        // hide the rows after it. Only identical expressions are caught
+        // here, which covers the usual literal keys.
+        for (index, column) in columns.iter().enumerate().filter(|(_, c)| c.key) {
+            let mut seen = Vec::new();
+            for row in &rows {
+                let value = row[index].to_token_stream().to_string();
+                if seen.contains(&value) {
+                    errors.push_spanned(
+                        &row[index],
+                        format!("duplicate value for the key column `{}`", column.name),
+                    );
+                }
+                seen.push(value);
+            }
+        }
+        errors.finish()?;
+
+        Ok(TableInput {
+            attributes,
+            vis,
+            name,
+            columns,
+            rows,
+        })
+    }
+}
+
+/// Emit the row struct and its lookup functions.
+impl ToTokens for TableInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let TableInput {
+            attributes,
+            vis,
+            name,
+            ..
+        } = self;
+        let column_names: Vec<&Ident> = self.columns.iter().map(|column| &column.name).collect();
+        let column_types = self.columns.iter().map(|column| &column.ty);
+        let count = self.rows.len();
+        let rows = self.rows.iter().map(|values| {
+            quote! { #name { #(#column_names: #values),* } }
+        });
+
+        let lookups = self
+            .columns
+            .iter()
+            .filter(|column| column.key)
+            .map(|column| {
+                let Column {
+                    name: column, ty, ..
+                } = column;
+                let lookup = format_ident!("by_{}", column);
+                let doc = format!("Find the row where `{}` is `value`.", column);
+                // References are looked up by any reference, not only a
+                // `'static` one.
+                let param = match ty {
+                    Type::Reference(reference) => {
+                        let elem = &reference.elem;
+                        quote! { &#elem }
+                    }
+                    ty => quote! { #ty },
+                };
+                quote! {
+                    #[doc = #doc]
+                    #vis fn #lookup(value: #param) -> ::core::option::Option<&'static #name> {
+                        Self::rows().iter().find(|row| row.#column == value)
+                    }
+                }
+            });
+
+        tokens.extend(quote! {
+            #(#attributes)*
+            #vis struct #name {
+                #(#vis #column_names: #column_types,)*
+            }
+
+            impl #name {
+                /// All of the rows in the table, in order.
+                #vis fn rows() -> &'static [#name] {
+                    static __ROWS: [#name; #count] = [#(#rows),*];
+                    &__ROWS
+                }
+
+                #(#lookups)*
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_table() {
+        let input: TableInput = syn::parse_str(
+            "pub Country { code: &str, #[key] dial: u16, name: &[&str] } [\"US\", 1, &[]]",
+        )
+        .unwrap();
+        let keys: Vec<bool> = input.columns.iter().map(|column| column.key).collect();
+        assert_eq!(keys, [false, true, false]);
+        let ty = &input.columns[2].ty;
+        assert_eq!(
+            quote!(#ty).to_string(),
+            quote!(&'static [&'static str]).to_string()
+        );
+
+        let input: TableInput = syn::parse_str("Empty { a: u8 }").unwrap();
+        assert!(input.columns[0].key && input.rows.is_empty());
+
+        syn::parse_str::<TableInput>("T { a: u8, b: u8 } [1]").unwrap_err();
+        syn::parse_str::<TableInput>("T { a: u8 } [1], [1]").unwrap_err();
+        syn::parse_str::<TableInput>("T { #[index] a: u8 } [1]").unwrap_err();
+        syn::parse_str::<TableInput>("T {} [1]").unwrap_err();
+        let error = syn::parse_str::<TableInput>("T { a: u8, b: u8 } [1, 2], [3], [1, 4], [5, +]")
+            .err()
+            .unwrap();
+        assert_eq!(error.into_iter().count(), 3);
+    }
+}
@@ -0,0 +1,303 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, Attribute, Expr, FnArg, Ident, ItemFn, Pat, Token, Type};
+
+/// One argument's values, like `x = [1, 2, 3]`.
+#[cfg_attr(test, derive(Debug))]
+struct Values {
+    name: Ident,
+    values: Vec<Expr>,
+}
+
+impl Parse for Values {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let name: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let content;
+        let brackets = bracketed!(content in input);
+        let values: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect();
+        if values.is_empty() {
+            return Err(syn::Error::new(
+                brackets.span,
+                "expected at least one value",
+            ));
+        }
+        Ok(Values { name, values })
+    }
+}
+
+/// The arguments to `#[test_matrix(...)]`.
+///
+/// Example: `#[test_matrix(x = [1, 2, 3], y = ["a", "b"])]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct TestMatrixArgs {
+    args: Vec<Values>,
+}
+
+impl Parse for TestMatrixArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut errors = Errors::new();
+        let mut args: Vec<Values> = Vec::new();
+        for values in errors.parse_terminated::<Values>(input) {
+            if args.iter().any(|other| other.name == values.name) {
+                errors.push_spanned(&values.name, "duplicate argument");
+                continue;
+            }
+            args.push(values);
+        }
+        errors.finish()?;
+        Ok(TestMatrixArgs { args })
+    }
+}
+
+/// A test function with `#[test_matrix]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct TestMatrix {
+    function: ItemFn,
+    // The attributes for every test, like `#[should_panic]`.
+    attrs: Vec<Attribute>,
+    // Each parameter's pattern and type, in the order they're listed in
+    // the attribute.
+    params: Vec<(Pat, Type)>,
+    args: Vec<Values>,
+}
+
+/// Turn a value into something that can go in a test's name: `-1.5`
+/// becomes `neg1_5`, and `"Hello world"` becomes `hello_world`.
+fn value_name(value: &Expr) -> String {
+    let text = value.to_token_stream().to_string();
+    let text = match text.strip_prefix('-') {
+        Some(rest) => format!("neg{}", rest.trim_start()),
+        None => text,
+    };
+    let mut name = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// The names for an argument's values, or their positions if the values
+/// don't have distinct names.
+fn value_names(values: &[Expr]) -> Vec<String> {
+    let names: Vec<String> = values.iter().map(value_name).collect();
+    let distinct = names
+        .iter()
+        .enumerate()
+        .all(|(index, name)| !name.is_empty() && !names[..index].contains(name));
+    if distinct {
+        names
+    } else {
+        (0..values.len()).map(|index| index.to_string()).collect()
+    }
+}
+
+impl TestMatrix {
+    pub fn new(args: TestMatrixArgs, mut function: ItemFn) -> syn::parse::Result<Self> {
+        let sig = &function.sig;
+        if let Some(asyncness) = &sig.asyncness {
+            return Err(syn::Error::new_spanned(
+                asyncness,
+                "test_matrix can't be used on an async fn",
+            ));
+        }
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "test_matrix can't be used on a generic fn",
+            ));
+        }
+        let mut by_name: Vec<(Ident, Pat, Type)> = Vec::new();
+        for input in &sig.inputs {
+            let typed = match input {
+                FnArg::Typed(typed) => typed,
+                FnArg::Receiver(receiver) => {
+                    return Err(syn::Error::new_spanned(
+                        receiver,
+                        "a test can't take `self`",
+                    ))
+                }
+            };
+            let ident = match &*typed.pat {
+                Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => &pat.ident,
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "test_matrix needs each parameter to be a plain name",
+                    ))
+                }
+            };
+            if !args.args.iter().any(|values| values.name == *ident) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    format!("no values for `{}`", ident),
+                ));
+            }
+            by_name.push((ident.clone(), (*typed.pat).clone(), (*typed.ty).clone()));
+        }
+        let mut params = Vec::new();
+        for values in &args.args {
+            match by_name.iter().find(|(ident, _, _)| *ident == values.name) {
+                Some((_, pat, ty)) => params.push((pat.clone(), ty.clone())),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &values.name,
+                        format!("no parameter named `{}`", values.name),
+                    ))
+                }
+            }
+        }
+
+        // Every generated test is a `#[test]`, so don't repeat one that's
+        // already there.
+        let attrs = function
+            .attrs
+            .drain(..)
+            .filter(|attr| !attr.path.is_ident("test"))
+            .collect();
+        Ok(TestMatrix {
+            function,
+            attrs,
+            params,
+            args: args.args,
+        })
+    }
+}
+
+/// Emit one `#[test]` for each combination of values.
+impl ToTokens for TestMatrix {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.function.sig.ident;
+        let output = &self.function.sig.output;
+        let block = &self.function.block;
+        let attrs = &self.attrs;
+        let names: Vec<Vec<String>> = self
+            .args
+            .iter()
+            .map(|values| value_names(&values.values))
+            .collect();
+
+        // Count through the combinations, with the last argument changing
+        // fastest.
+        let mut choice = vec![0; self.args.len()];
+        loop {
+            let mut test_name = name.to_string();
+            for (index, values) in self.args.iter().enumerate() {
+                test_name.push_str(&format!("_{}_{}", values.name, names[index][choice[index]]));
+            }
+            let test_name = format_ident!("{}", test_name, span = name.span());
+            let lets = self.params.iter().zip(&self.args).zip(&choice).map(
+                |(((pat, ty), values), &index)| {
+                    let value = &values.values[index];
+                    quote! { let #pat: #ty = #value; }
+                },
+            );
+            tokens.extend(quote! {
+                #[test]
+                #(#attrs)*
+                fn #test_name() #output {
+                    #(#lets)*
+                    #block
+                }
+            });
+
+            let mut position = choice.len();
+            loop {
+                if position == 0 {
+                    return;
+                }
+                position -= 1;
+                choice[position] += 1;
+                if choice[position] < self.args[position].values.len() {
+                    break;
+                }
+                choice[position] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_names(args: &str, function: &str) -> syn::parse::Result<Vec<String>> {
+        let args: TestMatrixArgs = syn::parse_str(args)?;
+        let matrix = TestMatrix::new(args, syn::parse_str(function)?)?;
+        let file: syn::File = syn::parse2(matrix.into_token_stream()).unwrap();
+        Ok(file
+            .items
+            .iter()
+            .map(|item| match item {
+                syn::Item::Fn(function) => function.sig.ident.to_string(),
+                _ => panic!("expected a fn"),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn generated_names() {
+        assert_eq!(
+            test_names(
+                r#"x = [1, -2], y = ["a", "Hello world"]"#,
+                "fn check(y: &str, x: i32) {}"
+            )
+            .unwrap(),
+            [
+                "check_x_1_y_a",
+                "check_x_1_y_hello_world",
+                "check_x_neg2_y_a",
+                "check_x_neg2_y_hello_world",
+            ]
+        );
+        // Values that don't have distinct names are numbered.
+        assert_eq!(
+            test_names(r#"s = ["a b", "a_b", "c"]"#, "fn check(s: &str) {}").unwrap(),
+            ["check_s_0", "check_s_1", "check_s_2"]
+        );
+        assert_eq!(
+            test_names("v = [vec![], Vec::new()]", "fn check(v: Vec<u8>) {}").unwrap(),
+            ["check_v_vec", "check_v_vec_new"]
+        );
+        assert_eq!(test_names("", "fn check() {}").unwrap(), ["check"]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |args, function| test_names(args, function).unwrap_err().to_string();
+        assert_eq!(
+            error("x = [1]", "fn check(x: u8, y: u8) {}"),
+            "no values for `y`"
+        );
+        assert_eq!(
+            error("x = [1], y = [2]", "fn check(x: u8) {}"),
+            "no parameter named `y`"
+        );
+        assert_eq!(
+            error("x = []", "fn check(x: u8) {}"),
+            "expected at least one value"
+        );
+        assert_eq!(
+            error("x = [1], x = [2]", "fn check(x: u8) {}"),
+            "duplicate argument"
+        );
+        assert_eq!(
+            error("x = [(1, 2)]", "fn check((a, b): (u8, u8)) {}"),
+            "test_matrix needs each parameter to be a plain name"
+        );
+        assert_eq!(
+            error("x = [1]", "async fn check(x: u8) {}"),
+            "test_matrix can't be used on an async fn"
+        );
+    }
+}
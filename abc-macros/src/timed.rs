@@ -0,0 +1,154 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, ItemFn, LitStr, Meta, NestedMeta, Path, Token};
+
+use crate::attr::{self, lit_str};
+use crate::wrap;
+
+/// Where `#[timed]` reports durations.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+enum Sink {
+    /// Print to stderr.
+    Eprintln,
+    /// Emit a `tracing` event.
+    Tracing,
+    /// Call a function with the name and the duration.
+    Function(Path),
+}
+
+/// The arguments to `#[timed(...)]`.
+///
+/// Example: `#[timed]` or `#[timed(sink = "tracing")]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct TimedArgs {
+    // `sink = "..."`: where the duration goes.
+    sink: Sink,
+    // `crate = "..."`: the path to the `abc` crate.
+    crate_path: Path,
+}
+
+fn parse_sink(lit: &LitStr) -> syn::parse::Result<Sink> {
+    match lit.value().as_str() {
+        "eprintln" => Ok(Sink::Eprintln),
+        "tracing" if cfg!(feature = "tracing") => Ok(Sink::Tracing),
+        "tracing" => Err(syn::Error::new_spanned(
+            lit,
+            "the tracing sink needs abc's `tracing` feature",
+        )),
+        _ => Ok(Sink::Function(lit.parse()?)),
+    }
+}
+
+impl Parse for TimedArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = TimedArgs {
+            sink: Sink::Eprintln,
+            crate_path: parse_quote!(::abc),
+        };
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("sink") => {
+                    args.sink = parse_sink(lit_str(&nv.lit)?)?;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    args.crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown timed option",
+                        &["sink", "crate"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a function with `#[timed]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct Timed {
+    args: TimedArgs,
+    function: ItemFn,
+}
+
+impl Timed {
+    pub fn new(args: TimedArgs, function: ItemFn) -> syn::parse::Result<Self> {
+        if let Some(constness) = &function.sig.constness {
+            return Err(syn::Error::new_spanned(
+                constness,
+                "timed can't be used on a const fn",
+            ));
+        }
+        Ok(Timed { args, function })
+    }
+}
+
+/// Emit the function, with timing around its body.
+impl ToTokens for Timed {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let krate = &self.args.crate_path;
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = &self.function;
+        let name = sig.ident.unraw().to_string();
+        let call = wrap::call_body(krate, sig, block);
+
+        let report = match &self.args.sink {
+            Sink::Eprintln => {
+                let message = format!("{} took {{:?}}", name);
+                quote! { #krate::__private::std::eprintln!(#message, __elapsed); }
+            }
+            Sink::Tracing => quote! {
+                #krate::__private::tracing::info!(
+                    function = #name,
+                    elapsed = ?__elapsed,
+                    "timed",
+                );
+            },
+            Sink::Function(path) => quote! { #path(#name, __elapsed); },
+        };
+
+        // An async fn's body doesn't run until the future is polled, so
+        // the clock starts at the first poll, not when the fn is called.
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis #sig {
+                let __start = #krate::__private::std::time::Instant::now();
+                let __result = #call;
+                let __elapsed = __start.elapsed();
+                #report
+                __result
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let args: TimedArgs = syn::parse_str("").unwrap();
+        assert_eq!(args.sink, Sink::Eprintln);
+
+        let args: TimedArgs = syn::parse_str("sink = \"metrics::record\"").unwrap();
+        assert_eq!(args.sink, Sink::Function(parse_quote!(metrics::record)));
+
+        let tracing = syn::parse_str::<TimedArgs>("sink = \"tracing\"");
+        assert_eq!(tracing.is_ok(), cfg!(feature = "tracing"));
+
+        syn::parse_str::<TimedArgs>("sink = \"not a path\"").unwrap_err();
+        syn::parse_str::<TimedArgs>("bogus").unwrap_err();
+    }
+}
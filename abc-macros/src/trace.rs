@@ -0,0 +1,200 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, FnArg, Ident, ItemFn, Meta, NestedMeta, Pat, Path, Token};
+
+use crate::attr::{self, lit_str};
+use crate::wrap;
+
+/// The arguments to `#[trace(...)]`.
+///
+/// Example: `#[trace]` or `#[trace(skip(password))]`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct TraceArgs {
+    // `skip(...)`: arguments that aren't logged.
+    skip: Vec<Ident>,
+    // `crate = "..."`: the path to the `abc` crate.
+    crate_path: Path,
+}
+
+impl Parse for TraceArgs {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut args = TraceArgs {
+            skip: Vec::new(),
+            crate_path: parse_quote!(::abc),
+        };
+        let nested = Punctuated::<NestedMeta, Token![,]>::parse_terminated(input)?;
+        for meta in nested {
+            match &meta {
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("skip") => {
+                    for name in &list.nested {
+                        match name {
+                            NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => {
+                                args.skip.push(path.get_ident().unwrap().clone());
+                            }
+                            other => {
+                                return Err(syn::Error::new_spanned(
+                                    other,
+                                    "expected an argument name",
+                                ))
+                            }
+                        }
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    args.crate_path = lit_str(&nv.lit)?.parse()?;
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        meta,
+                        "unknown trace option",
+                        &["skip", "crate"],
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// This is a function with `#[trace]` applied.
+#[cfg_attr(test, derive(Debug))]
+pub struct Trace {
+    args: TraceArgs,
+    function: ItemFn,
+    // The arguments that are logged, in order.
+    logged: Vec<Ident>,
+}
+
+impl Trace {
+    pub fn new(args: TraceArgs, function: ItemFn) -> syn::parse::Result<Self> {
+        if let Some(constness) = &function.sig.constness {
+            return Err(syn::Error::new_spanned(
+                constness,
+                "trace can't be used on a const fn",
+            ));
+        }
+        // Only arguments bound to a plain name can be logged; anything
+        // destructured by a pattern is left out.
+        let mut names = Vec::new();
+        for input in &function.sig.inputs {
+            match input {
+                FnArg::Receiver(receiver) => {
+                    names.push(Ident::new("self", receiver.self_token.span))
+                }
+                FnArg::Typed(typed) => {
+                    if let Pat::Ident(pat) = &*typed.pat {
+                        names.push(pat.ident.clone());
+                    }
+                }
+            }
+        }
+        for skipped in &args.skip {
+            if !names.contains(skipped) {
+                return Err(syn::Error::new_spanned(
+                    skipped,
+                    "this function has no argument with this name",
+                ));
+            }
+        }
+        let logged = names
+            .into_iter()
+            .filter(|name| !args.skip.contains(name))
+            .collect();
+        Ok(Trace {
+            args,
+            function,
+            logged,
+        })
+    }
+}
+
+/// Emit the function, with logging around its body.
+impl ToTokens for Trace {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let krate = &self.args.crate_path;
+        let ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        } = &self.function;
+        let name = sig.ident.unraw().to_string();
+
+        // Arguments are formatted like `add(a = 1, b = 2)`. Values that
+        // don't implement `Debug` print as `..`.
+        let placeholders: Vec<String> = self
+            .logged
+            .iter()
+            .map(|arg| format!("{} = {{:?}}", arg.unraw()))
+            .collect();
+        let entry = format!("-> {}({})", name, placeholders.join(", "));
+        let exit = format!("<- {} = {{:?}}", name);
+        let logged = &self.logged;
+
+        let call = wrap::call_body(krate, sig, block);
+
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis #sig {
+                {
+                    use #krate::__private::{DebugField as _, OpaqueField as _};
+                    #krate::__private::std::eprintln!(#entry #(, (&#krate::__private::Probe(&#logged)).as_debug())*);
+                }
+                let __result = #call;
+                {
+                    use #krate::__private::{DebugField as _, OpaqueField as _};
+                    #krate::__private::std::eprintln!(#exit, (&#krate::__private::Probe(&__result)).as_debug());
+                }
+                __result
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(args: TokenStream, function: TokenStream) -> syn::parse::Result<Trace> {
+        Trace::new(syn::parse2(args)?, syn::parse2(function)?)
+    }
+
+    #[test]
+    fn logged_args() {
+        let traced = trace(
+            quote!(skip(password)),
+            quote!(
+                fn login(&self, user: &str, password: &str, (a, b): (u8, u8)) {}
+            ),
+        )
+        .unwrap();
+        let logged: Vec<String> = traced.logged.iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(logged, ["self", "user"]);
+
+        trace(
+            quote!(skip(missing)),
+            quote!(
+                fn f(a: u8) {}
+            ),
+        )
+        .unwrap_err();
+        trace(
+            quote!(skip("a")),
+            quote!(
+                fn f(a: u8) {}
+            ),
+        )
+        .unwrap_err();
+        trace(
+            quote!(bogus),
+            quote!(
+                fn f() {}
+            ),
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, Meta, NestedMeta};
+
+use crate::shallow::ShallowEnum;
+
+/// The integer types that can be an enum's `#[repr]`.
+const INTEGER_REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// This is the entire input to `#[derive(TryFromPrimitive)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct TryFromPrimitiveInput {
+    input: ShallowEnum,
+    // The integer type from `#[repr(...)]`.
+    repr: Ident,
+    variants: Vec<Ident>,
+}
+
+/// Find the integer type in an enum's `#[repr(...)]` attributes.
+fn integer_repr(input: &ShallowEnum) -> syn::parse::Result<Ident> {
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested {
+                if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                    if let Some(ident) = path.get_ident() {
+                        if INTEGER_REPRS.iter().any(|repr| ident == repr) {
+                            return Ok(ident.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(
+        &input.ident,
+        "TryFromPrimitive needs an integer repr, like `#[repr(u8)]`",
+    ))
+}
+
+/// Parse the input to `#[derive(TryFromPrimitive)]`.
+impl Parse for TryFromPrimitiveInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "TryFromPrimitive")?;
+        let variants = input
+            .variants
+            .iter()
+            .map(|variant| match variant.fields {
+                None => Ok(variant.ident.clone()),
+                Some(_) => Err(syn::Error::new_spanned(
+                    variant,
+                    "TryFromPrimitive can only be derived for enums without fields",
+                )),
+            })
+            .collect::<syn::parse::Result<_>>()?;
+        let repr = integer_repr(&input)?;
+        Ok(TryFromPrimitiveInput {
+            input,
+            repr,
+            variants,
+        })
+    }
+}
+
+/// Emit the `TryFrom` impl.
+impl ToTokens for TryFromPrimitiveInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let repr = &self.repr;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+
+        // The compiler works out each discriminant, whether it was written
+        // explicitly or follows from the previous one. Naming them as
+        // consts lets us match on them.
+        let variants = &self.variants;
+        let consts: Vec<Ident> = variants
+            .iter()
+            .map(|variant| format_ident!("__{}", variant))
+            .collect();
+
+        tokens.extend(quote! {
+            impl #impl_generics ::core::convert::TryFrom<#repr> for #name #ty_generics #where_clause {
+                type Error = #repr;
+
+                #[allow(non_upper_case_globals)]
+                fn try_from(value: #repr) -> ::core::result::Result<Self, #repr> {
+                    #(const #consts: #repr = #name::#variants as #repr;)*
+                    match value {
+                        #(#consts => ::core::result::Result::Ok(#name::#variants),)*
+                        _ => ::core::result::Result::Err(value),
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_repr() {
+        let input: TryFromPrimitiveInput =
+            syn::parse_str("#[derive(Debug)] #[repr(C, u16)] enum Foo { A = 1, B }").unwrap();
+        assert_eq!(input.repr, "u16");
+        assert_eq!(input.variants.len(), 2);
+
+        syn::parse_str::<TryFromPrimitiveInput>("enum Foo { A = 1 }").unwrap_err();
+        syn::parse_str::<TryFromPrimitiveInput>("#[repr(C)] enum Foo { A = 1 }").unwrap_err();
+        syn::parse_str::<TryFromPrimitiveInput>("#[repr(u8)] enum Foo { A(u8) }").unwrap_err();
+        syn::parse_str::<TryFromPrimitiveInput>("#[repr(u8)] struct Foo;").unwrap_err();
+    }
+}
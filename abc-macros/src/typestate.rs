@@ -0,0 +1,326 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Fields, Ident, Meta, NestedMeta, Type, Visibility,
+};
+
+use crate::attr;
+use crate::case::RenameRule;
+
+/// A field of the struct.
+#[cfg_attr(test, derive(Debug))]
+struct Field {
+    ident: Ident,
+    ty: Type,
+    docs: Vec<Attribute>,
+    // For a required field, the builder's type parameter that tracks
+    // whether it's been set.
+    state: Option<Ident>,
+}
+
+/// This is the entire input to `typestate!`: a struct with named fields,
+/// and a list of the ones that are required.
+///
+/// Example: `typestate! { #[typestate(required(url))] pub struct Request { url: String } }`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct TypestateInput {
+    item: DeriveInput,
+    fields: Vec<Field>,
+}
+
+/// Parse the input to `typestate!`.
+impl Parse for TypestateInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let mut item: DeriveInput = input.parse()?;
+        let mut required: Vec<Ident> = Vec::new();
+        for nested in attr::options(&item.attrs, "typestate")? {
+            match &nested {
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("required") => {
+                    for nested in &list.nested {
+                        let ident = match nested {
+                            NestedMeta::Meta(Meta::Path(path)) => path.get_ident(),
+                            _ => None,
+                        };
+                        match ident {
+                            Some(ident) if required.contains(ident) => {
+                                return Err(syn::Error::new_spanned(ident, "duplicate field"))
+                            }
+                            Some(ident) => required.push(ident.clone()),
+                            None => {
+                                return Err(syn::Error::new_spanned(
+                                    nested,
+                                    "expected a field name",
+                                ))
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(attr::unknown_option(
+                        nested,
+                        "unknown typestate option",
+                        &["required"],
+                    ))
+                }
+            }
+        }
+        item.attrs.retain(|attr| !attr.path.is_ident("typestate"));
+        if required.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.ident,
+                "typestate! needs a list of required fields, like \
+                 `#[typestate(required(field, ...))]`",
+            ));
+        }
+
+        if !item.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.generics,
+                "typestate! doesn't support generic structs",
+            ));
+        }
+        let named = match &item.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(named),
+                ..
+            }) => named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    "typestate! needs a struct with named fields",
+                ))
+            }
+        };
+        if let Some(missing) = required.iter().find(|ident| {
+            !named
+                .named
+                .iter()
+                .any(|field| field.ident.as_ref() == Some(*ident))
+        }) {
+            return Err(syn::Error::new_spanned(
+                missing,
+                format!("no field named `{}`", missing),
+            ));
+        }
+        let fields = named
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.clone().unwrap();
+                let state = if required.contains(&ident) {
+                    let pascal = RenameRule::Pascal.apply(&ident.unraw().to_string());
+                    Some(format_ident!("__{}", pascal))
+                } else {
+                    None
+                };
+                let docs = field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path.is_ident("doc"))
+                    .cloned()
+                    .collect();
+                Field {
+                    ident,
+                    ty: field.ty.clone(),
+                    docs,
+                    state,
+                }
+            })
+            .collect();
+        Ok(TypestateInput { item, fields })
+    }
+}
+
+/// Emit the struct, and its builder.
+impl ToTokens for TypestateInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let item = &self.item;
+        let name = &item.ident;
+        let vis: &Visibility = &item.vis;
+        let builder = format_ident!("{}Builder", name);
+
+        let idents: Vec<&Ident> = self.fields.iter().map(|field| &field.ident).collect();
+        let states: Vec<&Ident> = self
+            .fields
+            .iter()
+            .filter_map(|field| field.state.as_ref())
+            .collect();
+        let field_types = self.fields.iter().map(|field| {
+            let ty = &field.ty;
+            match field.state {
+                Some(_) => quote! { ::core::option::Option<#ty> },
+                None => quote! { #ty },
+            }
+        });
+        let initial = self.fields.iter().map(|field| match field.state {
+            Some(_) => quote! { ::core::option::Option::None },
+            None => quote! { ::core::default::Default::default() },
+        });
+
+        let setters = self.fields.iter().map(|field| {
+            let Field {
+                ident, ty, docs, ..
+            } = field;
+            match &field.state {
+                // Setting a required field changes its state from `Unset`
+                // to `Set`, so it can only be set once.
+                Some(state) => {
+                    let params = states.iter().filter(|other| *other != &state);
+                    let before = states.iter().map(|other| {
+                        if *other == state {
+                            quote! { ::abc::Unset }
+                        } else {
+                            quote! { #other }
+                        }
+                    });
+                    let after = states.iter().map(|other| {
+                        if *other == state {
+                            quote! { ::abc::Set }
+                        } else {
+                            quote! { #other }
+                        }
+                    });
+                    let others = idents.iter().filter(|other| **other != ident);
+                    quote! {
+                        impl<#(#params),*> #builder<#(#before),*> {
+                            #(#docs)*
+                            #vis fn #ident(self, value: #ty) -> #builder<#(#after),*> {
+                                #builder {
+                                    #ident: ::core::option::Option::Some(value),
+                                    #(#others: self.#others,)*
+                                    __state: ::core::marker::PhantomData,
+                                }
+                            }
+                        }
+                    }
+                }
+                None => quote! {
+                    impl<#(#states),*> #builder<#(#states),*> {
+                        #(#docs)*
+                        #vis fn #ident(mut self, value: #ty) -> Self {
+                            self.#ident = value;
+                            self
+                        }
+                    }
+                },
+            }
+        });
+
+        let values = self.fields.iter().map(|field| {
+            let ident = &field.ident;
+            match field.state {
+                Some(_) => quote! {
+                    match self.#ident {
+                        ::core::option::Option::Some(value) => value,
+                        ::core::option::Option::None => ::core::unreachable!(),
+                    }
+                },
+                None => quote! { self.#ident },
+            }
+        });
+        let all_set = states.iter().map(|_| quote! { ::abc::Set });
+
+        let builder_doc = format!(
+            "A builder for [`{}`], which can only build one once every required field is set.",
+            name
+        );
+        tokens.extend(quote! {
+            #item
+
+            #[doc = #builder_doc]
+            ///
+            /// Each type parameter is `abc::Set` or `abc::Unset`, for one of
+            /// the required fields, in order.
+            #vis struct #builder<#(#states = ::abc::Unset),*> {
+                #(#idents: #field_types,)*
+                __state: ::core::marker::PhantomData<fn() -> (#(#states,)*)>,
+            }
+
+            impl #name {
+                /// Start building a value, with no fields set.
+                #vis fn builder() -> #builder {
+                    #builder {
+                        #(#idents: #initial,)*
+                        __state: ::core::marker::PhantomData,
+                    }
+                }
+            }
+
+            #(#setters)*
+
+            impl #builder<#(#all_set),*> {
+                /// Build the value.
+                #vis fn build(self) -> #name {
+                    #name {
+                        #(#idents: #values,)*
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> syn::parse::Result<TypestateInput> {
+        syn::parse_str(input)
+    }
+
+    #[test]
+    fn parse_typestate() {
+        let input = parse(
+            "#[typestate(required(url, r#type))] #[derive(Debug)]
+            pub struct Request { url: String, retries: u8, r#type: Kind }",
+        )
+        .unwrap();
+        let states: Vec<Option<String>> = input
+            .fields
+            .iter()
+            .map(|field| field.state.as_ref().map(Ident::to_string))
+            .collect();
+        assert_eq!(
+            states,
+            [Some("__Url".to_string()), None, Some("__Type".to_string())]
+        );
+        // `#[typestate]` isn't passed on to the struct.
+        assert_eq!(input.item.attrs.len(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input| parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("#[typestate(required(host))] struct Request { url: String }"),
+            "no field named `host`"
+        );
+        assert_eq!(
+            error("#[typestate(required(url, url))] struct Request { url: String }"),
+            "duplicate field"
+        );
+        assert_eq!(
+            error("#[typestate(required(\"url\"))] struct Request { url: String }"),
+            "expected a field name"
+        );
+        assert_eq!(
+            error("#[typestate(require(url))] struct Request { url: String }"),
+            "unknown typestate option\nhelp: did you mean `required`?"
+        );
+        assert_eq!(
+            error("struct Request { url: String }"),
+            "typestate! needs a list of required fields, like `#[typestate(required(field, ...))]`"
+        );
+        assert_eq!(
+            error("#[typestate(required(url))] struct Request<T> { url: T }"),
+            "typestate! doesn't support generic structs"
+        );
+        assert_eq!(
+            error("#[typestate(required(url))] struct Request(String);"),
+            "typestate! needs a struct with named fields"
+        );
+    }
+}
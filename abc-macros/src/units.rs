@@ -0,0 +1,300 @@
+use abc_macros_core::Errors;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Ident, Lit, Token, Type, Visibility};
+
+/// One unit, and its size in terms of the others.
+///
+/// Example: `Feet = 0.3048`
+///
+#[cfg_attr(test, derive(Debug))]
+struct Unit {
+    attributes: Vec<Attribute>,
+    name: Ident,
+    factor: f64,
+}
+
+impl Parse for Unit {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let lit: Lit = input.parse()?;
+        let factor = match &lit {
+            Lit::Float(float) => float.base10_parse::<f64>()?,
+            Lit::Int(int) => int.base10_parse::<f64>()?,
+            _ => return Err(syn::Error::new_spanned(&lit, "expected a number")),
+        };
+        if !(factor.is_finite() && factor > 0.0) {
+            return Err(syn::Error::new_spanned(
+                &lit,
+                "a conversion factor must be greater than zero",
+            ));
+        }
+        Ok(Unit {
+            attributes,
+            name,
+            factor,
+        })
+    }
+}
+
+/// This is the entire input to the `units!` macro.
+///
+/// ```ignore
+/// units!(
+///     pub Length {
+///         Meters = 1.0,
+///         Feet = 0.3048,
+///     }
+/// )
+/// ```
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct UnitsInput {
+    attributes: Vec<Attribute>,
+    vis: Visibility,
+    // The name of the trait shared by the units.
+    family: Ident,
+    // `: f32` after the name; by default, `f64`.
+    value_type: Type,
+    units: Vec<Unit>,
+}
+
+/// Parse the macro syntax for `units!`.
+impl Parse for UnitsInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let attributes = Attribute::parse_outer(input)?;
+        let vis = input.parse()?;
+        let family = input.parse()?;
+        let value_type = if input.parse::<Option<Token![:]>>()?.is_some() {
+            input.parse()?
+        } else {
+            syn::parse_quote!(f64)
+        };
+        let content;
+        braced!(content in input);
+        let mut errors = Errors::new();
+        let units: Vec<Unit> = errors.parse_terminated(&content);
+        for (index, unit) in units.iter().enumerate() {
+            if units[..index].iter().any(|other| other.name == unit.name) {
+                errors.push_spanned(&unit.name, "duplicate unit");
+            }
+        }
+        errors.finish()?;
+        Ok(UnitsInput {
+            attributes,
+            vis,
+            family,
+            value_type,
+            units,
+        })
+    }
+}
+
+/// The arithmetic within one unit.
+fn unit_ops(name: &Ident, value: &Type) -> TokenStream {
+    quote! {
+        impl ::core::ops::Add for #name {
+            type Output = #name;
+
+            fn add(self, other: #name) -> #name {
+                #name(self.0 + other.0)
+            }
+        }
+
+        impl ::core::ops::Sub for #name {
+            type Output = #name;
+
+            fn sub(self, other: #name) -> #name {
+                #name(self.0 - other.0)
+            }
+        }
+
+        impl ::core::ops::AddAssign for #name {
+            fn add_assign(&mut self, other: #name) {
+                self.0 += other.0;
+            }
+        }
+
+        impl ::core::ops::SubAssign for #name {
+            fn sub_assign(&mut self, other: #name) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl ::core::ops::Neg for #name {
+            type Output = #name;
+
+            fn neg(self) -> #name {
+                #name(-self.0)
+            }
+        }
+
+        impl ::core::ops::Mul<#value> for #name {
+            type Output = #name;
+
+            fn mul(self, scale: #value) -> #name {
+                #name(self.0 * scale)
+            }
+        }
+
+        impl ::core::ops::Mul<#name> for #value {
+            type Output = #name;
+
+            fn mul(self, unit: #name) -> #name {
+                #name(self * unit.0)
+            }
+        }
+
+        impl ::core::ops::MulAssign<#value> for #name {
+            fn mul_assign(&mut self, scale: #value) {
+                self.0 *= scale;
+            }
+        }
+
+        impl ::core::ops::Div<#value> for #name {
+            type Output = #name;
+
+            fn div(self, scale: #value) -> #name {
+                #name(self.0 / scale)
+            }
+        }
+
+        impl ::core::ops::DivAssign<#value> for #name {
+            fn div_assign(&mut self, scale: #value) {
+                self.0 /= scale;
+            }
+        }
+
+        /// The ratio of two amounts of the same unit.
+        impl ::core::ops::Div for #name {
+            type Output = #value;
+
+            fn div(self, other: #name) -> #value {
+                self.0 / other.0
+            }
+        }
+
+        impl ::core::iter::Sum for #name {
+            fn sum<I: ::core::iter::Iterator<Item = #name>>(iter: I) -> #name {
+                #name(iter.map(|unit| unit.0).sum())
+            }
+        }
+    }
+}
+
+/// Emit the trait, the unit structs, and their conversions.
+impl ToTokens for UnitsInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let UnitsInput {
+            attributes,
+            vis,
+            family,
+            value_type,
+            ..
+        } = self;
+        let trait_doc = format!(" A unit of {}.", family);
+        let attributes = if attributes.iter().any(|attr| attr.path.is_ident("doc")) {
+            quote! { #(#attributes)* }
+        } else {
+            quote! { #[doc = #trait_doc] #(#attributes)* }
+        };
+
+        let structs = self.units.iter().map(|unit| {
+            let unit_attrs = &unit.attributes;
+            let name = &unit.name;
+            // The factor is emitted as a literal of the value type.
+            let factor = syn::LitFloat::new(&format!("{:?}", unit.factor), name.span());
+            let ops = unit_ops(name, value_type);
+            quote! {
+                #(#unit_attrs)*
+                #[derive(::core::clone::Clone, ::core::marker::Copy, ::core::fmt::Debug, ::core::default::Default, ::core::cmp::PartialEq, ::core::cmp::PartialOrd)]
+                #vis struct #name(pub #value_type);
+
+                impl #family for #name {
+                    const FACTOR: #value_type = #factor;
+
+                    fn new(value: #value_type) -> Self {
+                        #name(value)
+                    }
+
+                    fn value(self) -> #value_type {
+                        self.0
+                    }
+                }
+
+                #ops
+            }
+        });
+
+        let mut conversions = Vec::new();
+        for from in &self.units {
+            for to in &self.units {
+                if from.name == to.name {
+                    continue;
+                }
+                let (from, to) = (&from.name, &to.name);
+                conversions.push(quote! {
+                    impl ::core::convert::From<#from> for #to {
+                        fn from(unit: #from) -> #to {
+                            #family::convert(unit)
+                        }
+                    }
+                });
+            }
+        }
+
+        tokens.extend(quote! {
+            #attributes
+            #vis trait #family: ::core::marker::Copy {
+                /// The size of one of this unit, relative to the others.
+                const FACTOR: #value_type;
+
+                /// An amount of this unit.
+                fn new(value: #value_type) -> Self;
+
+                /// The amount, in this unit.
+                fn value(self) -> #value_type;
+
+                /// The same amount, in another unit.
+                fn convert<U: #family>(self) -> U {
+                    U::new(self.value() * Self::FACTOR / U::FACTOR)
+                }
+            }
+
+            #(#structs)*
+            #(#conversions)*
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_units() {
+        let input: UnitsInput =
+            syn::parse_str("pub Length { Meters = 1.0, Feet = 0.3048, Kilometers = 1000 }")
+                .unwrap();
+        let factors: Vec<f64> = input.units.iter().map(|unit| unit.factor).collect();
+        assert_eq!(factors, [1.0, 0.3048, 1000.0]);
+
+        let input: UnitsInput = syn::parse_str("Mass: f32 { Grams = 1.0 }").unwrap();
+        assert_eq!(
+            input.value_type.to_token_stream().to_string(),
+            quote!(f32).to_string()
+        );
+
+        syn::parse_str::<UnitsInput>("Length { Meters = 0.0 }").unwrap_err();
+        syn::parse_str::<UnitsInput>("Length { Meters = \"1\" }").unwrap_err();
+        syn::parse_str::<UnitsInput>("Length { Meters = 1.0, Meters = 2.0 }").unwrap_err();
+
+        let error = syn::parse_str::<UnitsInput>("Length { Meters = 0.0, Feet = 1.0, Feet = 2.0 }")
+            .err()
+            .unwrap();
+        assert_eq!(error.into_iter().count(), 2);
+    }
+}
@@ -0,0 +1,126 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::LitStr;
+
+/// This is the entire input to `uuid!`.
+///
+/// Example: `uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8")`
+///
+#[cfg_attr(test, derive(Debug))]
+pub struct UuidInput {
+    bytes: [u8; 16],
+}
+
+/// Parse a UUID in any of its usual forms: hyphenated, 32 bare hex
+/// digits, in braces, or as a `urn:uuid:` URN.
+fn parse_uuid(input: &str) -> Result<[u8; 16], String> {
+    let mut text = input;
+    if let Some(rest) = text.strip_prefix("urn:uuid:") {
+        text = rest;
+    } else if let Some(rest) = text.strip_prefix('{') {
+        text = rest
+            .strip_suffix('}')
+            .ok_or_else(|| "expected a closing `}`".to_string())?;
+    }
+
+    let digits: String = match text.len() {
+        32 => text.to_string(),
+        36 => {
+            // Hyphens go between the groups of 8-4-4-4-12 digits.
+            for position in [8, 13, 18, 23] {
+                if text.as_bytes()[position] != b'-' {
+                    return Err(format!("expected `-` at position {}", position));
+                }
+            }
+            text.chars().filter(|&c| c != '-').collect()
+        }
+        len => {
+            return Err(format!(
+                "expected 32 hex digits, optionally with hyphens, found {} characters",
+                len
+            ))
+        }
+    };
+
+    let mut bytes = [0; 16];
+    let digit = |d: u8| (d as char).to_digit(16);
+    for (byte, pair) in bytes.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => *byte = (high << 4 | low) as u8,
+            _ => {
+                return Err(format!(
+                    "`{}` is not a hex byte",
+                    String::from_utf8_lossy(pair)
+                ))
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Parse the input to `uuid!`.
+impl Parse for UuidInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        let bytes = parse_uuid(&lit.value())
+            .map_err(|message| syn::Error::new(lit.span(), format!("invalid UUID: {}", message)))?;
+        Ok(UuidInput { bytes })
+    }
+}
+
+/// Emit the UUID's bytes, or a `uuid::Uuid` with the `uuid` feature.
+impl ToTokens for UuidInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let bytes = &self.bytes;
+        let array = quote! { [#(#bytes),*] };
+        if cfg!(feature = "uuid") {
+            tokens.extend(quote! {
+                ::abc::__private::uuid::Uuid::from_bytes(#array)
+            });
+        } else {
+            array.to_tokens(tokens);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_forms() {
+        let expected = [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ];
+        for input in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "67E55044-10B1-426F-9247-BB680E5FE0C8",
+            "67e5504410b1426f9247bb680e5fe0c8",
+            "{67e55044-10b1-426f-9247-bb680e5fe0c8}",
+            "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8",
+        ] {
+            assert_eq!(parse_uuid(input).unwrap(), expected, "{}", input);
+        }
+
+        assert_eq!(
+            parse_uuid("67e55044-10b1-426f-9247-bb680e5fe0c").unwrap_err(),
+            "expected 32 hex digits, optionally with hyphens, found 35 characters"
+        );
+        assert_eq!(
+            parse_uuid("67e55044_10b1-426f-9247-bb680e5fe0c8").unwrap_err(),
+            "expected `-` at position 8"
+        );
+        assert_eq!(
+            parse_uuid("67e55044-10b1-426f-9247-bb680e5fe0g8").unwrap_err(),
+            "`g8` is not a hex byte"
+        );
+        assert_eq!(
+            parse_uuid("67e5504410b1426f9247bb680e5fe0+8").unwrap_err(),
+            "`+8` is not a hex byte"
+        );
+        parse_uuid("{67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap_err();
+        parse_uuid("67e55044-10b1-426f-9247-bb680e5fé0c").unwrap_err();
+    }
+}
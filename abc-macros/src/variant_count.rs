@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::Attribute;
+
+use crate::shallow::ShallowEnum;
+
+/// This is the entire input to `#[derive(VariantCount)]`.
+#[cfg_attr(test, derive(Debug))]
+pub struct VariantCountInput {
+    input: ShallowEnum,
+    // The number of variants that are always there.
+    count: usize,
+    // The `#[cfg]` attributes of each variant that has them.
+    conditional: Vec<Vec<Attribute>>,
+}
+
+/// Parse the input to `#[derive(VariantCount)]`.
+impl Parse for VariantCountInput {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let input = ShallowEnum::parse(input, "VariantCount")?;
+        let mut count = 0;
+        let mut conditional = Vec::new();
+        for variant in &input.variants {
+            let cfgs: Vec<Attribute> = variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("cfg"))
+                .cloned()
+                .collect();
+            if cfgs.is_empty() {
+                count += 1;
+            } else {
+                conditional.push(cfgs);
+            }
+        }
+        Ok(VariantCountInput {
+            input,
+            count,
+            conditional,
+        })
+    }
+}
+
+/// Emit `const VARIANT_COUNT`.
+impl ToTokens for VariantCountInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let count = self.count;
+        // A variant behind `#[cfg]` only counts if it's compiled in.
+        let conditional = self.conditional.iter().map(|cfgs| {
+            quote! {
+                #(#cfgs)*
+                let count = count + 1;
+            }
+        });
+        tokens.extend(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The number of variants.
+                pub const VARIANT_COUNT: usize = {
+                    let count: usize = #count;
+                    #(#conditional)*
+                    count
+                };
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_variants() {
+        let input: VariantCountInput =
+            syn::parse_str("enum E { A, B(u8), C { c: u8 }, #[cfg(test)] D, E = 7 }").unwrap();
+        assert_eq!(input.count, 4);
+        assert_eq!(input.conditional.len(), 1);
+
+        let input: VariantCountInput = syn::parse_str("enum Never {}").unwrap();
+        assert_eq!(input.count, 0);
+
+        syn::parse_str::<VariantCountInput>("struct Foo;").unwrap_err();
+    }
+}
@@ -0,0 +1,147 @@
+//! Helpers for attribute macros that wrap a function's body, or forward
+//! calls to another function.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Block, FnArg, GenericParam, Ident, Pat, PatIdent, Path, ReturnType, Signature, Type};
+
+/// Check whether some tokens mention an identifier, like `Self`, or
+/// `impl` (for `impl Trait`).
+pub fn mentions(tokens: TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Check whether a type mentions `impl Trait`, which can't be written in
+/// a `let` binding.
+fn contains_impl_trait(ty: &Type) -> bool {
+    mentions(ty.to_token_stream(), "impl")
+}
+
+/// An unreachable `return` that pins the enclosing closure or async
+/// block's return type to the function's.
+///
+/// The return type can't always be inferred (`?` converts errors with
+/// `From`), so wrapped bodies start with this. It's empty if the type
+/// can't be written out.
+///
+pub fn fake_return(sig: &Signature) -> TokenStream {
+    match &sig.output {
+        ReturnType::Type(_, ty) if !contains_impl_trait(ty) => quote! {
+            #[allow(unreachable_code, clippy::diverging_sub_expression)]
+            if false {
+                let __fake_return: #ty = loop {};
+                return __fake_return;
+            }
+        },
+        _ => quote! {},
+    }
+}
+
+/// An expression that runs a function's body and evaluates to its result.
+///
+/// The body runs in a closure (or an async block), so that a `return`
+/// inside it ends only the body, and code after this expression still
+/// runs.
+///
+pub fn call_body(krate: &Path, sig: &Signature, block: &Block) -> TokenStream {
+    let stmts = &block.stmts;
+    let fake_return = fake_return(sig);
+    if sig.asyncness.is_some() {
+        quote! { async move { #fake_return #(#stmts)* }.await }
+    } else {
+        quote! { #krate::__private::call_once(move || { #fake_return #(#stmts)* }) }
+    }
+}
+
+/// A signature for a function that passes its arguments on to another.
+pub struct Forward {
+    /// The signature, with each argument renamed to `__argN`, so that
+    /// patterns like `(a, b): (u8, u8)` can be passed on whole.
+    pub sig: Signature,
+    /// Whether there's a `self` parameter. It isn't in `args`.
+    pub receiver: bool,
+    pub args: Vec<Ident>,
+    /// The type and const parameters, as a turbofish, unless an argument
+    /// is `impl Trait`, which rules one out.
+    pub turbofish: TokenStream,
+}
+
+impl Forward {
+    pub fn new(sig: &Signature) -> Self {
+        let mut sig = sig.clone();
+        let mut receiver = false;
+        let mut args = Vec::new();
+        for (index, input) in sig.inputs.iter_mut().enumerate() {
+            match input {
+                FnArg::Receiver(_) => receiver = true,
+                FnArg::Typed(typed) => {
+                    let arg = format_ident!("__arg{}", index);
+                    typed.attrs.clear();
+                    *typed.pat = Pat::Ident(PatIdent {
+                        attrs: Vec::new(),
+                        by_ref: None,
+                        mutability: None,
+                        ident: arg.clone(),
+                        subpat: None,
+                    });
+                    args.push(arg);
+                }
+            }
+        }
+        let params: Vec<TokenStream> = sig
+            .generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                GenericParam::Type(ty) => Some(ty.ident.to_token_stream()),
+                GenericParam::Const(konst) => Some(konst.ident.to_token_stream()),
+                GenericParam::Lifetime(_) => None,
+            })
+            .collect();
+        let impl_trait_args = sig
+            .inputs
+            .iter()
+            .any(|input| mentions(input.to_token_stream(), "impl"));
+        let turbofish = if params.is_empty() || impl_trait_args {
+            quote! {}
+        } else {
+            quote! { ::<#(#params),*> }
+        };
+        Forward {
+            sig,
+            receiver,
+            args,
+            turbofish,
+        }
+    }
+
+    /// Finish a call expression: `.await` it if the function is async,
+    /// and put it in an `unsafe` block if the function is unsafe.
+    pub fn finish(&self, call: TokenStream) -> TokenStream {
+        let mut call = call;
+        if self.sig.asyncness.is_some() {
+            call = quote! { #call.await };
+        }
+        if self.sig.unsafety.is_some() {
+            call = quote! { unsafe { #call } };
+        }
+        call
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn impl_trait() {
+        assert!(contains_impl_trait(&parse_quote!(impl Iterator<Item = u8>)));
+        assert!(contains_impl_trait(&parse_quote!(Box<impl Debug>)));
+        assert!(!contains_impl_trait(&parse_quote!(Result<Vec<u8>, Error>)));
+    }
+}
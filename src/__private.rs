@@ -0,0 +1,234 @@
+//! Support code used by the output of `#[derive(DescribeStruct)]` and the
+//! other macros.
+//!
+//! A macro can't tell what traits a field's type implements, so the
+//! generated code asks the compiler instead, using autoref-based method
+//! resolution. For example, `(&Probe(&field)).nested_description()` finds
+//! the `DescribeNested` impl on `Probe` if the field is `DescribeStruct`,
+//! and otherwise falls back to the `DescribeOpaque` impl on `&Probe`.
+//!
+//! Note that this only works for concrete types; a field of a generic
+//! type `T` is always treated as if it implements nothing.
+
+#[cfg(feature = "std")]
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug};
+#[cfg(feature = "std")]
+use core::mem::MaybeUninit;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::sync::{MutexGuard, Once};
+
+#[cfg(feature = "std")]
+use crate::mock::lock;
+#[cfg(feature = "std")]
+use crate::MockFn;
+use crate::StaticMap;
+#[cfg(feature = "alloc")]
+use crate::{DescribeStruct, Description};
+
+// Expansions name `alloc` and `std` through here, rather than as `::alloc`
+// and `::std`, so that they also work in a `#![no_std]` crate. Only the
+// macros whose features turn on `alloc` or `std` use them.
+#[cfg(feature = "alloc")]
+pub extern crate alloc;
+#[cfg(feature = "registry")]
+pub use inventory;
+#[cfg(feature = "regex")]
+pub use regex;
+#[cfg(feature = "semver")]
+pub use semver;
+#[cfg(feature = "std")]
+pub extern crate std;
+#[cfg(feature = "tracing")]
+pub use tracing;
+#[cfg(feature = "uuid")]
+pub use uuid;
+
+/// Wraps a reference to a field, so its traits can be probed.
+pub struct Probe<'a, T: ?Sized>(pub &'a T);
+
+#[cfg(feature = "alloc")]
+pub trait DescribeNested {
+    fn nested_description(&self) -> Option<Description>;
+}
+
+#[cfg(feature = "alloc")]
+impl<T: DescribeStruct> DescribeNested for Probe<'_, T> {
+    fn nested_description(&self) -> Option<Description> {
+        Some(self.0.describe())
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub trait DescribeOpaque {
+    fn nested_description(&self) -> Option<Description>;
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> DescribeOpaque for &Probe<'_, T> {
+    fn nested_description(&self) -> Option<Description> {
+        None
+    }
+}
+
+pub trait DebugField<'a> {
+    fn as_debug(&self) -> &'a dyn Debug;
+}
+
+impl<'a, T: Debug> DebugField<'a> for Probe<'a, T> {
+    fn as_debug(&self) -> &'a dyn Debug {
+        self.0
+    }
+}
+
+pub trait OpaqueField<'a> {
+    fn as_debug(&self) -> &'a dyn Debug;
+}
+
+impl<'a, T: ?Sized> OpaqueField<'a> for &Probe<'a, T> {
+    fn as_debug(&self) -> &'a dyn Debug {
+        &Opaque
+    }
+}
+
+/// Stands in for the value of a field that doesn't implement `Debug`.
+pub struct Opaque;
+
+impl Debug for Opaque {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("..")
+    }
+}
+
+/// Call a closure once.
+///
+/// Attribute macros like `#[trace]` run a function's body in a closure.
+/// Calling it through here makes it an `FnOnce`, so the body can return
+/// references to the arguments it captured.
+pub fn call_once<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// A cell that's written once, for `#[singleton]` and `regex!` in a crate
+/// whose `rust-version` is older than `std::sync::OnceLock` (Rust 1.70).
+///
+/// It has the part of `OnceLock`'s API that the expansions use. Unlike
+/// `OnceLock`, if the first initializer panics, so does every later call.
+#[cfg(feature = "std")]
+pub struct OnceLock<T> {
+    once: Once,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Like `std::sync::OnceLock`: the value is shared between threads, and it
+// may be created on one thread and dropped on another.
+#[cfg(feature = "std")]
+unsafe impl<T: Send + Sync> Sync for OnceLock<T> {}
+
+#[cfg(feature = "std")]
+impl<T> OnceLock<T> {
+    pub const fn new() -> Self {
+        OnceLock {
+            once: Once::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        if self.once.is_completed() {
+            // Safety: the value was written before `once` completed, and
+            // isn't written again.
+            Some(unsafe { &*(*self.value.get()).as_ptr() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        self.once.call_once(|| {
+            // Safety: `call_once` runs this at most once, and nothing can
+            // read the value until it has finished.
+            unsafe { (*self.value.get()).as_mut_ptr().write(f()) };
+        });
+        self.get().expect("the value was just set")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for OnceLock<T> {
+    fn default() -> Self {
+        OnceLock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for OnceLock<T> {
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // Safety: the value was written, and this is the only drop.
+            unsafe { (*self.value.get()).as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+/// Match `text` against a glob pattern, for `enum_strings!`.
+///
+/// `*` matches any run of characters, and `?` matches any one character;
+/// everything else matches itself.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    // Positions are byte offsets, always at the start of a character.
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`: the pattern position after
+    // it, and how much text it has taken so far.
+    let mut star: Option<(usize, usize)> = None;
+    while let Some(c) = text[t..].chars().next() {
+        match pattern[p..].chars().next() {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(pc) if pc == '?' || pc == c => {
+                p += pc.len_utf8();
+                t += c.len_utf8();
+            }
+            _ => match star {
+                // Let the `*` take one more character, and try again.
+                Some((after, taken)) => {
+                    let taken = taken + text[taken..].chars().next().map_or(0, char::len_utf8);
+                    p = after;
+                    t = taken;
+                    star = Some((after, taken));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].chars().all(|c| c == '*')
+}
+
+/// Build a `StaticMap`, for `static_map!`.
+///
+/// The macro has already sorted the entries and checked for duplicate
+/// keys.
+pub const fn static_map<K, V>(entries: &'static [(K, V)]) -> StaticMap<K, V> {
+    StaticMap { entries }
+}
+
+/// Set the closure that answers a mocked method's calls, for
+/// `mock_trait!`.
+#[cfg(feature = "std")]
+pub fn mock_answer<F: ?Sized>(method: &MockFn<F>, answer: Box<F>) {
+    *lock(&method.answer) = Some(answer);
+}
+
+/// Record a call to a mocked method, and return its answer, if it has
+/// one.
+#[cfg(feature = "std")]
+pub fn mock_call<F: ?Sized>(method: &MockFn<F>, call: String) -> MutexGuard<'_, Option<Box<F>>> {
+    lock(&method.calls).push(call);
+    lock(&method.answer)
+}
@@ -0,0 +1,114 @@
+//! `DescribeStruct` impls for references and smart pointers.
+//!
+//! These forward every method (including the ones with default
+//! implementations) to the pointee, so that `&Foo`, `Box<Foo>` and
+//! `Box<dyn DescribeStruct>` all describe the same way `Foo` does.
+//!
+//! `set_field` needs mutable access to the pointee, which a `&T` never
+//! has and an `Arc<T>` only has while no other `Arc` shares it.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use crate::{DescribeStruct, DescribeVisitor, Description, FieldError};
+
+macro_rules! forward_describe {
+    ($($ty:ty => $get_mut:expr),*) => {
+        $(
+            impl<T: DescribeStruct + ?Sized> DescribeStruct for $ty {
+                fn struct_name(&self) -> &'static str {
+                    (**self).struct_name()
+                }
+
+                fn type_path(&self) -> &'static str {
+                    (**self).type_path()
+                }
+
+                fn description(&self) -> &'static str {
+                    (**self).description()
+                }
+
+                fn field_count(&self) -> usize {
+                    (**self).field_count()
+                }
+
+                fn variant_count(&self) -> usize {
+                    (**self).variant_count()
+                }
+
+                fn structure_version(&self) -> u64 {
+                    (**self).structure_version()
+                }
+
+                fn size_of(&self) -> usize {
+                    (**self).size_of()
+                }
+
+                fn align_of(&self) -> usize {
+                    (**self).align_of()
+                }
+
+                fn get_field(&self, name: &str) -> Option<&dyn core::any::Any> {
+                    (**self).get_field(name)
+                }
+
+                fn set_field(
+                    &mut self,
+                    name: &str,
+                    value: Box<dyn core::any::Any>,
+                ) -> Result<(), FieldError> {
+                    let get_mut: fn(&mut Self) -> Option<&mut T> = $get_mut;
+                    match get_mut(self) {
+                        Some(inner) => inner.set_field(name, value),
+                        None => Err(FieldError::ReadOnly(name.into())),
+                    }
+                }
+
+                fn field_offsets(&self) -> &'static [usize] {
+                    (**self).field_offsets()
+                }
+
+                fn field_docs(&self) -> &'static [(&'static str, &'static str)] {
+                    (**self).field_docs()
+                }
+
+                fn visit_fields(&self, visitor: &mut dyn DescribeVisitor) {
+                    (**self).visit_fields(visitor)
+                }
+
+                fn variant_names(&self) -> &'static [(&'static str, Option<i128>)] {
+                    (**self).variant_names()
+                }
+
+                fn discriminant(&self) -> Option<i128> {
+                    (**self).discriminant()
+                }
+
+                fn describe(&self) -> Description {
+                    (**self).describe()
+                }
+
+                fn describe_pretty(&self) -> String {
+                    (**self).describe_pretty()
+                }
+
+                #[cfg(feature = "serde")]
+                fn describe_json(&self) -> String {
+                    (**self).describe_json()
+                }
+
+                #[cfg(feature = "tracing")]
+                fn trace_span(&self) -> tracing::Span {
+                    (**self).trace_span()
+                }
+            }
+        )*
+    };
+}
+
+forward_describe!(
+    &T => |_| None,
+    Box<T> => |inner| Some(&mut **inner),
+    Arc<T> => Arc::get_mut
+);
@@ -0,0 +1,159 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The structure of a type, as reported by [`DescribeStruct::describe`].
+///
+/// [`DescribeStruct::describe`]: crate::DescribeStruct::describe
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Description {
+    /// The name of the type, e.g. `"Foo"`.
+    pub name: &'static str,
+    /// What kind of type this is.
+    pub kind: Kind,
+    /// The fields of a struct or union, in declaration order.
+    pub fields: Vec<FieldDescription>,
+    /// The variants of an enum, in declaration order. Structs and unions
+    /// have none.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub variants: Vec<VariantDescription>,
+}
+
+impl Description {
+    /// An indented, multi-line rendering of the type's structure.
+    ///
+    /// Each field is shown on its own line with its type, followed by the
+    /// fields of any nested description:
+    ///
+    /// ```text
+    /// Outer (struct)
+    ///   inner: Inner
+    ///     count: u32
+    ///   list: Vec<u8>
+    /// ```
+    ///
+    /// An enum shows each variant on its own line, followed by its fields.
+    pub fn pretty(&self) -> String {
+        let mut out = format!("{} ({})\n", self.name, self.kind);
+        self.write_fields(&mut out, 1);
+        out
+    }
+
+    fn write_fields(&self, out: &mut String, depth: usize) {
+        write_field_list(&self.fields, out, depth);
+        for variant in &self.variants {
+            out.push_str(&format!("{}{}\n", "  ".repeat(depth), variant.name));
+            write_field_list(&variant.fields, out, depth + 1);
+        }
+    }
+}
+
+fn write_field_list(fields: &[FieldDescription], out: &mut String, depth: usize) {
+    for field in fields {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!("{}{}: {}\n", indent, field.name, field.type_name));
+        if let Some(nested) = &field.nested {
+            nested.write_fields(out, depth + 1);
+        }
+    }
+}
+
+/// The kind of type that was described.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Kind {
+    Struct,
+    Enum,
+    Union,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Kind::Struct => "struct",
+            Kind::Enum => "enum",
+            Kind::Union => "union",
+        })
+    }
+}
+
+/// A single enum variant within a [`Description`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VariantDescription {
+    /// The variant name.
+    pub name: &'static str,
+    /// How the variant's fields are written.
+    pub shape: VariantShape,
+    /// The variant's fields, in declaration order. Their `nested`
+    /// descriptions are always `None`.
+    pub fields: Vec<FieldDescription>,
+}
+
+/// The form of an enum variant's payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VariantShape {
+    /// A variant with no fields, like `Quit`.
+    Unit,
+    /// A variant with unnamed fields, like `Data(u32, Vec<u8>)`.
+    Tuple,
+    /// A variant with named fields, like `Move { x: i32, y: i32 }`.
+    Struct,
+}
+
+/// A single field within a [`Description`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldDescription {
+    /// The field name. Tuple struct fields are named by position: `"0"`,
+    /// `"1"`, and so on.
+    pub name: &'static str,
+    /// The field's type, as written in the source.
+    pub type_name: &'static str,
+    /// A human-friendly name, from `#[describe(label = "...")]`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<&'static str>,
+    /// A human-friendly description, from `#[describe(doc = "...")]`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub doc: Option<&'static str>,
+    /// The size of the field's type, in bytes.
+    pub size: usize,
+    /// If the field's type also implements `DescribeStruct`, its description.
+    ///
+    /// This is always `None` for union fields, because reading one would
+    /// require knowing which field is active.
+    pub nested: Option<Description>,
+}
+
+/// A const-constructible description of a type.
+///
+/// `#[derive(DescribeStruct)]` generates one of these as an associated
+/// const, `Foo::DESCRIPTOR`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StructDescriptor {
+    /// The name of the type, e.g. `"Foo"`.
+    pub name: &'static str,
+    /// The fields of a struct or union, in declaration order.
+    /// Enums have none.
+    pub fields: &'static [FieldDescriptor],
+}
+
+/// A single field within a [`StructDescriptor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldDescriptor {
+    /// The field name, as in [`FieldDescription::name`].
+    pub name: &'static str,
+    /// The field's type, as written in the source.
+    pub type_name: &'static str,
+    /// A human-friendly name, from `#[describe(label = "...")]`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<&'static str>,
+    /// A human-friendly description, from `#[describe(doc = "...")]`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub doc: Option<&'static str>,
+}
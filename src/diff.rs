@@ -0,0 +1,57 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use crate::DescribeVisitor;
+
+/// A field whose value differs between two values of the same type, from
+/// [`DescribeStruct::describe_diff`].
+///
+/// [`DescribeStruct::describe_diff`]: crate::DescribeStruct::describe_diff
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The field name, as reported by `visit_fields`.
+    pub field: &'static str,
+    /// The `Debug` representation of the field in `self`, or `None` if
+    /// `self` doesn't have the field (it's a different enum variant).
+    pub left: Option<String>,
+    /// The `Debug` representation of the field in `other`, or `None` if
+    /// `other` doesn't have the field.
+    pub right: Option<String>,
+}
+
+/// Collects the name and `Debug` representation of each visited field.
+#[derive(Default)]
+pub(crate) struct DebugFields(pub(crate) Vec<(&'static str, String)>);
+
+impl DescribeVisitor for DebugFields {
+    fn field(&mut self, name: &'static str, value: &dyn Debug) {
+        self.0.push((name, format!("{:?}", value)));
+    }
+}
+
+/// Compare two lists of fields by name, in the order they first appear.
+pub(crate) fn diff_fields(left: DebugFields, right: DebugFields) -> Vec<FieldDiff> {
+    let mut right = right.0;
+    let mut diffs = Vec::new();
+    for (field, value) in left.0 {
+        let other = right
+            .iter()
+            .position(|(name, _)| *name == field)
+            .map(|index| right.remove(index).1);
+        if other.as_ref() != Some(&value) {
+            diffs.push(FieldDiff {
+                field,
+                left: Some(value),
+                right: other,
+            });
+        }
+    }
+    diffs.extend(right.into_iter().map(|(field, value)| FieldDiff {
+        field,
+        left: None,
+        right: Some(value),
+    }));
+    diffs
+}
@@ -0,0 +1,104 @@
+//! The error types. They implement `std::error::Error` with the `std`
+//! feature.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+
+/// The error returned by [`DescribeStruct::set_field`].
+///
+/// [`DescribeStruct::set_field`]: crate::DescribeStruct::set_field
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldError {
+    /// There's no field with this name that can be set.
+    NotFound(String),
+    /// The value is shared, so its fields can't be set: it's behind a `&`
+    /// reference, or an `Arc` that isn't unique.
+    ReadOnly(String),
+    /// The new value doesn't have the field's type.
+    WrongType {
+        /// The name of the field.
+        field: &'static str,
+        /// The field's type, as written in the source.
+        expected: &'static str,
+    },
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::NotFound(name) => write!(f, "no field named `{}`", name),
+            FieldError::ReadOnly(name) => write!(f, "can't set field `{}` of a shared value", name),
+            FieldError::WrongType { field, expected } => {
+                write!(
+                    f,
+                    "field `{}` expects a value of type `{}`",
+                    field, expected
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldError {}
+
+/// The error returned by the `build()` method of a `#[derive(Builder)]`
+/// builder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuilderError {
+    /// A field without a default was never set.
+    MissingField(&'static str),
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::MissingField(name) => write!(f, "field `{}` was not set", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuilderError {}
+
+/// The error returned by `from_str` for an enum with `#[derive(FromStr)]`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseEnumError {
+    /// The name of the enum.
+    pub type_name: &'static str,
+    /// The string that didn't match any variant.
+    pub input: String,
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a valid {}", self.input, self.type_name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseEnumError {}
+
+/// The error returned by the `transition` method of a `state_machine!`
+/// state, when there's no transition for the event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidTransition<S, E> {
+    /// The state, which is left unchanged.
+    pub state: S,
+    /// The event that has no transition from this state.
+    pub event: E,
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for InvalidTransition<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no transition from {:?} on {:?}", self.state, self.event)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: fmt::Debug, E: fmt::Debug> std::error::Error for InvalidTransition<S, E> {}
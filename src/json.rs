@@ -0,0 +1,72 @@
+/// A JSON value, built at compile time by the `json!` macro.
+///
+/// Everything is borrowed from static data, so a `Json` can be a `const`.
+/// An object's entries are sorted by key, so that [`get`](Json::get) can
+/// use a binary search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(&'static str),
+    Array(&'static [Json]),
+    Object(&'static [(&'static str, Json)]),
+}
+
+impl Json {
+    /// The value of `key`, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&'static Json> {
+        match self {
+            Json::Object(entries) => entries
+                .binary_search_by(|(name, _)| (*name).cmp(key))
+                .ok()
+                .map(|index| &entries[index].1),
+            _ => None,
+        }
+    }
+
+    /// Whether this is `null`.
+    pub const fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    /// The value, if this is `true` or `false`.
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value, if this is a number.
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value, if this is a string.
+    pub const fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The elements, if this is an array.
+    pub const fn as_array(&self) -> Option<&'static [Json]> {
+        match self {
+            Json::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// The entries, sorted by key, if this is an object.
+    pub const fn as_object(&self) -> Option<&'static [(&'static str, Json)]> {
+        match self {
+            Json::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
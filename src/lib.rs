@@ -1,34 +1,3896 @@
-pub use abc_macros::{enum_ranges, file_words, DescribeStruct};
+// Only the `alloc` and `std` features link more than `core`. The tests
+// use `std` either way.
+#![cfg_attr(not(test), no_std)]
 
-trait DescribeStruct {
+// The derive refers to the trait and its support types as `::abc::...`;
+// this lets that path resolve inside this crate too.
+extern crate self as abc;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+#[cfg(feature = "base64")]
+pub use abc_macros::base64;
+#[cfg(feature = "bitfield")]
+pub use abc_macros::bitfield;
+#[cfg(feature = "byte_size")]
+pub use abc_macros::byte_size;
+#[cfg(feature = "color")]
+pub use abc_macros::color;
+#[cfg(feature = "compile_time")]
+pub use abc_macros::compile_time;
+#[cfg(feature = "cstr")]
+pub use abc_macros::cstr;
+#[cfg(feature = "delegate")]
+pub use abc_macros::delegate;
+#[cfg(feature = "deprecated_alias")]
+pub use abc_macros::deprecated_alias;
+#[cfg(feature = "describe_all")]
+pub use abc_macros::describe_all;
+#[cfg(feature = "duration")]
+pub use abc_macros::duration;
+#[cfg(feature = "enum_bitmask")]
+pub use abc_macros::enum_bitmask;
+#[cfg(feature = "enum_ranges")]
+pub use abc_macros::enum_ranges;
+#[cfg(feature = "enum_strings")]
+pub use abc_macros::enum_strings;
+#[cfg(feature = "env_or")]
+pub use abc_macros::env_or;
+#[cfg(feature = "file_words")]
+pub use abc_macros::file_words;
+#[cfg(feature = "hex")]
+pub use abc_macros::hex;
+#[cfg(feature = "json")]
+pub use abc_macros::json;
+#[cfg(feature = "kv_config")]
+pub use abc_macros::kv_config;
+#[cfg(feature = "log_errors")]
+pub use abc_macros::log_errors;
+#[cfg(feature = "make_ident")]
+pub use abc_macros::make_ident;
+#[cfg(feature = "mock_trait")]
+pub use abc_macros::mock_trait;
+#[cfg(feature = "must_use_all")]
+pub use abc_macros::must_use_all;
+#[cfg(feature = "newtype")]
+pub use abc_macros::newtype;
+#[cfg(feature = "regex")]
+pub use abc_macros::regex;
+#[cfg(feature = "retry")]
+pub use abc_macros::retry;
+#[cfg(feature = "rustc_version")]
+pub use abc_macros::rustc_version_at_least;
+#[cfg(feature = "semver_macro")]
+pub use abc_macros::semver;
+#[cfg(feature = "singleton")]
+pub use abc_macros::singleton;
+#[cfg(feature = "soa")]
+pub use abc_macros::soa;
+#[cfg(feature = "sorted")]
+pub use abc_macros::sorted;
+#[cfg(feature = "state_machine")]
+pub use abc_macros::state_machine;
+#[cfg(feature = "static_assert")]
+pub use abc_macros::static_assert;
+#[cfg(feature = "static_map")]
+pub use abc_macros::static_map;
+#[cfg(feature = "table")]
+pub use abc_macros::table;
+#[cfg(feature = "test_matrix")]
+pub use abc_macros::test_matrix;
+#[cfg(feature = "timed")]
+pub use abc_macros::timed;
+#[cfg(feature = "trace")]
+pub use abc_macros::trace;
+#[cfg(feature = "typestate")]
+pub use abc_macros::typestate;
+#[cfg(feature = "units")]
+pub use abc_macros::units;
+#[cfg(feature = "uuid_macro")]
+pub use abc_macros::uuid;
+#[cfg(feature = "as_ref_str")]
+pub use abc_macros::AsRefStr;
+#[cfg(feature = "builder")]
+pub use abc_macros::Builder;
+#[cfg(feature = "default_with_values")]
+pub use abc_macros::DefaultWithValues;
+#[cfg(feature = "describe")]
+pub use abc_macros::DescribeStruct;
+#[cfg(feature = "display")]
+pub use abc_macros::Display;
+#[cfg(feature = "enum_discriminants")]
+pub use abc_macros::EnumDiscriminants;
+#[cfg(feature = "enum_iter")]
+pub use abc_macros::EnumIter;
+#[cfg(feature = "error")]
+pub use abc_macros::Error;
+#[cfg(feature = "from")]
+pub use abc_macros::From;
+#[cfg(feature = "from_str")]
+pub use abc_macros::FromStr;
+#[cfg(feature = "into_iterator")]
+pub use abc_macros::IntoIterator;
+#[cfg(feature = "try_from_primitive")]
+pub use abc_macros::TryFromPrimitive;
+#[cfg(feature = "variant_count")]
+pub use abc_macros::VariantCount;
+#[cfg(feature = "build_info")]
+pub use abc_macros::{build_info, git_version};
+#[cfg(feature = "net")]
+pub use abc_macros::{ip, sockaddr};
+#[cfg(feature = "deref")]
+pub use abc_macros::{Deref, DerefMut};
+#[cfg(feature = "getset")]
+pub use abc_macros::{Getters, Setters};
+#[cfg(feature = "cmp")]
+pub use abc_macros::{HashSkip, PartialEqSkip};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+#[doc(hidden)]
+pub mod __private;
+#[cfg(feature = "alloc")]
+mod blanket;
+#[cfg(feature = "alloc")]
+mod description;
+#[cfg(feature = "alloc")]
+mod diff;
+mod error;
+mod json;
+#[cfg(feature = "std")]
+mod mock;
+pub mod prelude;
+mod static_map;
+mod typestate;
+mod visit;
+#[cfg(feature = "alloc")]
+pub use description::{
+    Description, FieldDescription, FieldDescriptor, Kind, StructDescriptor, VariantDescription,
+    VariantShape,
+};
+#[cfg(feature = "alloc")]
+pub use diff::FieldDiff;
+pub use error::{BuilderError, InvalidTransition};
+#[cfg(feature = "alloc")]
+pub use error::{FieldError, ParseEnumError};
+pub use json::Json;
+#[cfg(feature = "std")]
+pub use mock::MockFn;
+pub use static_map::StaticMap;
+pub use typestate::{Set, Unset};
+pub use visit::DescribeVisitor;
+
+#[cfg(feature = "registry")]
+pub mod registry;
+
+/// Describe a type's name and structure at runtime. It needs the `alloc`
+/// feature, which `describe` turns on.
+///
+/// This is normally implemented with `#[derive(DescribeStruct)]`. It's
+/// also implemented for `&T`, `Box<T>` and `Arc<T>`, forwarding to `T`.
+///
+/// The trait is object safe, so described values of different types can
+/// be collected as `Vec<Box<dyn DescribeStruct>>`. It must stay that way:
+/// methods that can't be called on a trait object (generic methods, or
+/// ones without `self`) belong in the derive's inherent impl instead, or
+/// need a `where Self: Sized` bound.
+#[cfg(feature = "alloc")]
+pub trait DescribeStruct {
+    /// The name of the type, e.g. `"Foo"`.
     fn struct_name(&self) -> &'static str;
+
+    /// The fully qualified path of the type, e.g. `"my_crate::config::Foo"`.
+    fn type_path(&self) -> &'static str;
+
+    /// The type's `///` doc comments, or `""` if it has none.
+    fn description(&self) -> &'static str;
+
+    /// The number of fields in a struct or union. Enums report zero.
+    fn field_count(&self) -> usize {
+        0
+    }
+
+    /// The number of variants in an enum. Structs and unions report zero.
+    fn variant_count(&self) -> usize {
+        0
+    }
+
+    /// A fingerprint of the type's structure.
+    ///
+    /// This is a hash of the type's name and the names and types of its
+    /// fields (or of its variants and their fields), computed at compile
+    /// time. It's stable between builds and compiler versions, and only
+    /// changes when the structure does, so it can be stored alongside
+    /// serialized data to check compatibility.
+    fn structure_version(&self) -> u64;
+
+    /// The size of the type in bytes, from `core::mem::size_of`.
+    fn size_of(&self) -> usize;
+
+    /// The alignment of the type in bytes, from `core::mem::align_of`.
+    fn align_of(&self) -> usize;
+
+    /// Call `visitor.field` with the name and value of each field.
+    ///
+    /// For an enum, this visits the fields of the current variant. Union
+    /// fields are never visited.
+    fn visit_fields(&self, visitor: &mut dyn DescribeVisitor);
+
+    /// The byte offset of each field of a struct or union, in declaration
+    /// order, from `core::mem::offset_of!`. Enums report no offsets.
+    ///
+    /// The derive also provides these as the `FIELD_OFFSETS` constant,
+    /// unless the crate's `rust-version` is older than `offset_of!` (Rust
+    /// 1.77); then it leaves both out, and there are no offsets.
+    fn field_offsets(&self) -> &'static [usize] {
+        &[]
+    }
+
+    /// Look up a field by name, for a small amount of runtime reflection.
+    ///
+    /// Names are the ones reported by [`visit_fields`](DescribeStruct::visit_fields),
+    /// and an enum only has the fields of its current variant. `Any` only
+    /// works with `'static` types, so fields whose types borrow or use a
+    /// generic type parameter aren't found, and neither are union fields.
+    fn get_field(&self, name: &str) -> Option<&dyn core::any::Any> {
+        let _ = name;
+        None
+    }
+
+    /// Replace the value of a field, looked up by name.
+    ///
+    /// The fields that can be set are the ones that
+    /// [`get_field`](DescribeStruct::get_field) can find. The value must
+    /// have exactly the field's type; otherwise the field is left alone.
+    fn set_field(&mut self, name: &str, value: Box<dyn core::any::Any>) -> Result<(), FieldError> {
+        let _ = value;
+        Err(FieldError::NotFound(name.into()))
+    }
+
+    /// The `///` doc comments of each field of a struct or union, in
+    /// declaration order, paired with the field name. Fields without docs
+    /// have `""`. Enums report no fields.
+    fn field_docs(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// The names of an enum's variants, each with its discriminant.
+    ///
+    /// Discriminants are reported for enums that give at least one variant
+    /// an explicit discriminant (`A = 1`), and for enums with no fields;
+    /// otherwise they're `None`. Structs and unions report no variants.
+    fn variant_names(&self) -> &'static [(&'static str, Option<i128>)] {
+        &[]
+    }
+
+    /// The discriminant of the current enum variant, if known.
+    ///
+    /// See [`variant_names`](DescribeStruct::variant_names) for when
+    /// discriminants are known. Structs and unions report `None`.
+    fn discriminant(&self) -> Option<i128> {
+        None
+    }
+
+    /// Describe the structure of this type, including the descriptions of
+    /// any fields whose types also implement `DescribeStruct`.
+    fn describe(&self) -> Description;
+
+    /// The output of [`describe`](DescribeStruct::describe), rendered as
+    /// indented text by [`Description::pretty`].
+    fn describe_pretty(&self) -> String {
+        self.describe().pretty()
+    }
+
+    /// Check whether another described value has the same shape as this one.
+    ///
+    /// Two types have the same shape if they have the same name and kind,
+    /// and the same field names and field types in the same order. For
+    /// enums, the variants must also have the same names, shapes and
+    /// fields. Labels, docs and the module the type lives in aren't
+    /// compared.
+    fn same_shape<T: DescribeStruct + ?Sized>(&self, other: &T) -> bool
+    where
+        Self: Sized,
+    {
+        fn same_fields(ours: &[FieldDescription], theirs: &[FieldDescription]) -> bool {
+            ours.len() == theirs.len()
+                && ours
+                    .iter()
+                    .zip(theirs)
+                    .all(|(a, b)| a.name == b.name && a.type_name == b.type_name)
+        }
+
+        let (ours, theirs) = (self.describe(), other.describe());
+        ours.name == theirs.name
+            && ours.kind == theirs.kind
+            && same_fields(&ours.fields, &theirs.fields)
+            && ours.variants.len() == theirs.variants.len()
+            && ours.variants.iter().zip(&theirs.variants).all(|(a, b)| {
+                a.name == b.name && a.shape == b.shape && same_fields(&a.fields, &b.fields)
+            })
+    }
+
+    /// List the fields whose `Debug` representations differ between two
+    /// values, in field order.
+    ///
+    /// Fields are compared the way [`visit_fields`](DescribeStruct::visit_fields)
+    /// reports them, so fields whose types don't implement `Debug` never
+    /// differ. If the values are different enum variants, fields that only
+    /// one of them has are listed too.
+    fn describe_diff(&self, other: &Self) -> Vec<FieldDiff>
+    where
+        Self: Sized,
+    {
+        let (mut left, mut right) = (diff::DebugFields::default(), diff::DebugFields::default());
+        self.visit_fields(&mut left);
+        other.visit_fields(&mut right);
+        diff::diff_fields(left, right)
+    }
+
+    /// The output of [`describe`](DescribeStruct::describe), as JSON.
+    #[cfg(feature = "serde")]
+    fn describe_json(&self) -> String {
+        serde_json::to_string(&self.describe()).expect("a Description is always serializable")
+    }
+
+    /// Create an info-level `tracing` span named after the type.
+    ///
+    /// The derive gives the span the type's name. This default, used only
+    /// by hand-written impls, uses the name `"described"` and records the
+    /// type name in a `type_name` field, because span names have to be
+    /// known at compile time.
+    #[cfg(feature = "tracing")]
+    fn trace_span(&self) -> tracing::Span {
+        tracing::info_span!("described", type_name = self.struct_name())
+    }
+}
+
+/// Helpers shared by the tests of several macros.
+#[cfg(test)]
+mod test_util {
+    /// Run a future to completion, for testing async fns without a runtime.
+    #[allow(dead_code)] // Unused if none of the macros with async tests are enabled.
+    pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+
+        struct NoopWaker;
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Arc::new(NoopWaker).into();
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "describe"))]
+mod describe_tests {
+    use super::*;
+
+    /// A struct with no fields.
+    #[derive(DescribeStruct)]
+    struct Foo;
+
+    #[test]
+    fn test_struct_name() {
+        assert_eq!(Foo.struct_name(), "Foo");
+    }
+
+    mod inner {
+        use super::*;
+
+        #[derive(DescribeStruct)]
+        pub struct Foo;
+    }
+
+    #[test]
+    fn test_type_path() {
+        assert_eq!(Foo.type_path(), "abc::describe_tests::Foo");
+        assert_eq!(inner::Foo.type_path(), "abc::describe_tests::inner::Foo");
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(Foo.description(), "A struct with no fields.");
+        assert_eq!(inner::Foo.description(), "");
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    struct Named {
+        a: u8,
+        b: u16,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    struct Tuple(u8, u16, u32);
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    enum Choice {
+        Left,
+        Right,
+    }
+
+    #[test]
+    fn test_counts() {
+        assert_eq!(Foo.field_count(), 0);
+        assert_eq!(Named { a: 0, b: 0 }.field_count(), 2);
+        assert_eq!(Tuple(0, 0, 0).field_count(), 3);
+        assert_eq!(Choice::Left.variant_count(), 2);
+        assert_eq!(Choice::Left.field_count(), 0);
+        assert_eq!(Foo.variant_count(), 0);
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    struct Outer {
+        named: Named,
+        pair: Tuple,
+        list: Vec<u8>,
+    }
+
+    #[test]
+    fn test_describe() {
+        let outer = Outer {
+            named: Named { a: 1, b: 2 },
+            pair: Tuple(1, 2, 3),
+            list: vec![],
+        };
+        let desc = outer.describe();
+        assert_eq!(desc.name, "Outer");
+        assert_eq!(desc.kind, Kind::Struct);
+        assert_eq!(desc.fields.len(), 3);
+
+        assert_eq!(desc.fields[0].name, "named");
+        assert_eq!(desc.fields[0].type_name, "Named");
+        let named = desc.fields[0].nested.as_ref().unwrap();
+        assert_eq!(named.name, "Named");
+        assert_eq!(named.fields[1].name, "b");
+        assert_eq!(named.fields[1].type_name, "u16");
+        assert_eq!(named.fields[1].nested, None);
+
+        let pair = desc.fields[1].nested.as_ref().unwrap();
+        assert_eq!(pair.fields[0].name, "0");
+        assert_eq!(pair.fields[2].type_name, "u32");
+
+        assert_eq!(desc.fields[2].type_name, "Vec<u8>");
+        assert_eq!(desc.fields[2].nested, None);
+
+        let choice = Choice::Left.describe();
+        assert_eq!(choice.kind, Kind::Enum);
+        assert!(choice.fields.is_empty());
+        assert_eq!(choice.variants.len(), 2);
+        assert!(desc.variants.is_empty());
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(rename_all = "snake_case")]
+    enum Packet {
+        Empty,
+        Data(u32, Vec<u8>),
+        Control {
+            #[describe(label = "Op code")]
+            op_code: u8,
+        },
+    }
+
+    #[test]
+    fn test_describe_variants() {
+        let desc = Packet::Empty.describe();
+        let shapes: Vec<(&str, VariantShape, usize)> = desc
+            .variants
+            .iter()
+            .map(|variant| (variant.name, variant.shape, variant.fields.len()))
+            .collect();
+        assert_eq!(
+            shapes,
+            [
+                ("empty", VariantShape::Unit, 0),
+                ("data", VariantShape::Tuple, 2),
+                ("control", VariantShape::Struct, 1),
+            ]
+        );
+
+        let data = &desc.variants[1].fields;
+        assert_eq!((data[0].name, data[0].type_name), ("0", "u32"));
+        assert_eq!((data[1].name, data[1].type_name), ("1", "Vec<u8>"));
+        assert_eq!(data[0].size, 4);
+        assert_eq!(data[1].nested, None);
+
+        let control = &desc.variants[2].fields[0];
+        assert_eq!(control.name, "op_code");
+        assert_eq!(control.label, Some("Op code"));
+
+        assert!(Packet::Empty.same_shape(&Packet::Data(0, vec![])));
+        assert!(!Packet::Empty.same_shape(&Message::Quit));
+    }
+
+    #[allow(dead_code)]
+    mod mirror {
+        #[derive(crate::DescribeStruct)]
+        pub struct Named {
+            /// Not compared.
+            #[describe(label = "A")]
+            pub a: u8,
+            pub b: u16,
+        }
+
+        #[derive(crate::DescribeStruct)]
+        pub struct Outer {
+            pub named: Named,
+            pub pair: u8,
+            pub list: Vec<u8>,
+        }
+    }
+
+    #[test]
+    fn test_same_shape() {
+        let named = Named { a: 1, b: 2 };
+        let mirrored = mirror::Named { a: 3, b: 4 };
+        assert!(named.same_shape(&mirrored));
+        assert!(mirrored.same_shape(&named));
+        assert!(!named.same_shape(&Tuple(0, 0, 0)));
+        assert!(Choice::Left.same_shape(&Choice::Right));
+        assert!(Foo.same_shape(&inner::Foo));
+
+        let outer = Outer {
+            named: Named { a: 1, b: 2 },
+            pair: Tuple(1, 2, 3),
+            list: vec![],
+        };
+        let different = mirror::Outer {
+            named: mirrored,
+            pair: 0,
+            list: vec![],
+        };
+        assert!(!outer.same_shape(&different));
+
+        let boxed: Box<dyn DescribeStruct> = Box::new(Named { a: 0, b: 0 });
+        assert!(named.same_shape(&*boxed));
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(rename_all = "kebab-case")]
+    struct Preferences {
+        /// How often to check for updates.
+        ///
+        /// In minutes.
+        update_interval: u32,
+        dark_mode: bool,
+    }
+
+    #[test]
+    fn test_field_docs() {
+        let prefs = Preferences {
+            update_interval: 60,
+            dark_mode: false,
+        };
+        assert_eq!(
+            prefs.field_docs(),
+            [
+                (
+                    "update-interval",
+                    "How often to check for updates.\n\nIn minutes."
+                ),
+                ("dark-mode", ""),
+            ]
+        );
+        assert_eq!(Tuple(0, 0, 0).field_docs().len(), 3);
+        assert!(Choice::Left.field_docs().is_empty());
+        assert_eq!(Box::new(prefs).field_docs().len(), 2);
+    }
+
+    #[test]
+    fn test_describe_diff() {
+        let before = Named { a: 1, b: 2 };
+        let after = Named { a: 1, b: 3 };
+        assert_eq!(
+            before.describe_diff(&after),
+            [FieldDiff {
+                field: "b",
+                left: Some("2".to_string()),
+                right: Some("3".to_string()),
+            }]
+        );
+        assert!(before.describe_diff(&before).is_empty());
+
+        let moved = Message::Move { x: 1, y: 2 };
+        let moved_x = Message::Move { x: 5, y: 2 };
+        assert_eq!(moved.describe_diff(&moved_x)[0].field, "x");
+        let written = Message::Write("hi".to_string());
+        let diffs = moved.describe_diff(&written);
+        let fields: Vec<_> = diffs
+            .iter()
+            .map(|diff| (diff.field, diff.left.as_deref(), diff.right.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("x", Some("1"), None),
+                ("y", Some("2"), None),
+                ("0", None, Some("\"hi\"")),
+            ]
+        );
+        assert!(Message::Quit.describe_diff(&Message::Quit).is_empty());
+    }
+
+    #[test]
+    fn test_describe_pretty() {
+        let outer = Outer {
+            named: Named { a: 1, b: 2 },
+            pair: Tuple(1, 2, 3),
+            list: vec![],
+        };
+        assert_eq!(
+            outer.describe_pretty(),
+            "\
+Outer (struct)
+  named: Named
+    a: u8
+    b: u16
+  pair: Tuple
+    0: u8
+    1: u16
+    2: u32
+  list: Vec<u8>
+"
+        );
+        assert_eq!(
+            Choice::Left.describe_pretty(),
+            "Choice (enum)\n  Left\n  Right\n"
+        );
+        assert_eq!(
+            Message::Quit.describe_pretty(),
+            "\
+Message (enum)
+  Quit
+  Move
+    x: i32
+    y: i32
+  Write
+    0: String
+"
+        );
+    }
+
+    #[derive(DescribeStruct)]
+    #[describe(display)]
+    struct Meters;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Meters.to_string(), "Meters");
+        assert_eq!(format!("{:>8}", Meters), "  Meters");
+    }
+
+    #[allow(dead_code, non_snake_case)]
+    #[derive(DescribeStruct)]
+    #[describe(display, rename_all = "snake_case")]
+    struct HttpRequest {
+        retryCount: u8,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(rename_all = "SCREAMING-KEBAB-CASE")]
+    struct Screaming {
+        retry_count: u8,
+    }
+
+    #[test]
+    fn test_rename_all() {
+        let req = HttpRequest { retryCount: 0 };
+        assert_eq!(req.struct_name(), "http_request");
+        assert_eq!(req.to_string(), "http_request");
+        assert_eq!(req.type_path(), "abc::describe_tests::HttpRequest");
+        let desc = req.describe();
+        assert_eq!(desc.name, "http_request");
+        assert_eq!(desc.fields[0].name, "retry_count");
+
+        let screaming = Screaming { retry_count: 0 };
+        assert_eq!(screaming.struct_name(), "SCREAMING");
+        assert_eq!(screaming.describe().fields[0].name, "RETRY-COUNT");
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    union Bits {
+        int: u32,
+        float: f32,
+    }
+
+    #[derive(DescribeStruct)]
+    struct Point(f32, f32);
+
+    #[test]
+    fn test_tuple_fields() {
+        let desc = Point(1.0, 2.0).describe();
+        let fields: Vec<_> = desc
+            .fields
+            .iter()
+            .map(|field| (field.name, field.type_name))
+            .collect();
+        assert_eq!(fields, [("0", "f32"), ("1", "f32")]);
+    }
+
+    #[test]
+    fn test_size_and_align() {
+        let named = Named { a: 0, b: 0 };
+        assert_eq!(named.size_of(), std::mem::size_of::<Named>());
+        assert_eq!(named.align_of(), 2);
+        assert_eq!(Foo.size_of(), 0);
+
+        let sizes: Vec<usize> = Tuple(0, 0, 0)
+            .describe()
+            .fields
+            .iter()
+            .map(|field| field.size)
+            .collect();
+        assert_eq!(sizes, [1, 2, 4]);
+    }
+
+    #[derive(DescribeStruct)]
+    struct Tagged<'a, T> {
+        id: u32,
+        #[describe(label = "Name")]
+        name: String,
+        value: T,
+        borrowed: &'a str,
+        fixed: &'static str,
+    }
+
+    #[test]
+    fn test_get_field() {
+        let tagged = Tagged {
+            id: 7,
+            name: "seven".to_string(),
+            value: 7u8,
+            borrowed: "seven",
+            fixed: "VII",
+        };
+        let id = tagged.get_field("id").unwrap();
+        assert_eq!(id.downcast_ref::<u32>(), Some(&7));
+        let name = tagged.get_field("name").unwrap();
+        assert_eq!(name.downcast_ref::<String>().unwrap(), "seven");
+        assert_eq!(
+            tagged.get_field("fixed").unwrap().downcast_ref::<&str>(),
+            Some(&"VII")
+        );
+        assert!(tagged.get_field("value").is_none());
+        assert!(tagged.get_field("borrowed").is_none());
+        assert!(tagged.get_field("missing").is_none());
+        assert_eq!(tagged.value, 7);
+        assert_eq!(tagged.borrowed.len(), 5);
+
+        let point = Point(1.0, 2.0);
+        assert_eq!(
+            point.get_field("1").unwrap().downcast_ref::<f32>(),
+            Some(&2.0)
+        );
+        let moved = Message::Move { x: 3, y: 4 };
+        assert_eq!(
+            moved.get_field("y").unwrap().downcast_ref::<i32>(),
+            Some(&4)
+        );
+        assert!(Message::Quit.get_field("x").is_none());
+        let request = HttpRequest { retryCount: 3 };
+        assert!(request.get_field("retry_count").is_some());
+        assert!(request.get_field("retryCount").is_none());
+        assert!(Bits { int: 0 }.get_field("int").is_none());
+        assert!(Box::new(point).get_field("0").is_some());
+    }
+
+    #[test]
+    fn test_set_field() {
+        let mut tagged = Tagged {
+            id: 7,
+            name: "seven".to_string(),
+            value: 7u8,
+            borrowed: "seven",
+            fixed: "VII",
+        };
+        tagged.set_field("id", Box::new(8u32)).unwrap();
+        tagged
+            .set_field("name", Box::new("eight".to_string()))
+            .unwrap();
+        assert_eq!((tagged.id, tagged.name.as_str()), (8, "eight"));
+        assert_eq!(
+            tagged.set_field("id", Box::new(9u64)),
+            Err(FieldError::WrongType {
+                field: "id",
+                expected: "u32"
+            })
+        );
+        assert_eq!(tagged.id, 8);
+        assert_eq!(
+            tagged.set_field("value", Box::new(9u8)),
+            Err(FieldError::NotFound("value".to_string()))
+        );
+
+        let mut moved = Message::Move { x: 3, y: 4 };
+        moved.set_field("x", Box::new(5i32)).unwrap();
+        assert!(matches!(moved, Message::Move { x: 5, y: 4 }));
+        Message::Quit.set_field("x", Box::new(5i32)).unwrap_err();
+
+        let mut boxed: Box<dyn DescribeStruct> = Box::new(Point(0.0, 0.0));
+        boxed.set_field("1", Box::new(2.5f32)).unwrap();
+        assert_eq!(boxed.get_field("1").unwrap().downcast_ref(), Some(&2.5f32));
+
+        let mut shared = std::sync::Arc::new(Point(0.0, 0.0));
+        let other = shared.clone();
+        assert_eq!(
+            shared.set_field("0", Box::new(1.0f32)),
+            Err(FieldError::ReadOnly("0".to_string()))
+        );
+        drop(other);
+        shared.set_field("0", Box::new(1.0f32)).unwrap();
+        assert_eq!(shared.0, 1.0);
+        assert_eq!(
+            FieldError::WrongType {
+                field: "id",
+                expected: "u32"
+            }
+            .to_string(),
+            "field `id` expects a value of type `u32`"
+        );
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[repr(C)]
+    struct Header {
+        tag: u8,
+        len: u32,
+        flags: u16,
+    }
+
+    #[test]
+    fn test_field_offsets() {
+        let header = Header {
+            tag: 0,
+            len: 0,
+            flags: 0,
+        };
+        assert_eq!(header.field_offsets(), [0, 4, 8]);
+        assert_eq!(Header::FIELD_OFFSETS, [0, 4, 8]);
+        assert_eq!(Point(0.0, 0.0).field_offsets().len(), 2);
+        assert_eq!(Bits { int: 0 }.field_offsets(), [0, 0]);
+        assert_eq!(Buf([0u8; 4]).field_offsets(), [0]);
+        assert!(Foo.field_offsets().is_empty());
+        assert!(Choice::Left.field_offsets().is_empty());
+        assert_eq!(Box::new(header).field_offsets(), [0, 4, 8]);
+    }
+
+    struct Marker;
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(skip_zst)]
+    struct Marked<T> {
+        id: u32,
+        kind: std::marker::PhantomData<T>,
+        marker: Marker,
+        value: T,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(skip_zst)]
+    enum MarkedEnum {
+        Tagged(Marker, u8),
+    }
+
+    #[test]
+    fn test_skip_zst() {
+        let marked = Marked {
+            id: 1,
+            kind: std::marker::PhantomData,
+            marker: Marker,
+            value: 2u8,
+        };
+        let names: Vec<&str> = marked.describe().fields.iter().map(|f| f.name).collect();
+        assert_eq!(names, ["id", "value"]);
+        let mut collect = Collect::default();
+        marked.visit_fields(&mut collect);
+        // `T` has no `Debug` bound, so its value is opaque.
+        assert_eq!(collect.0, ["id=1", "value=.."]);
+        assert_eq!(marked.field_count(), 4);
+
+        // A generic field is only skipped when it's zero-sized.
+        let unit = Marked {
+            id: 1,
+            kind: std::marker::PhantomData,
+            marker: Marker,
+            value: (),
+        };
+        assert_eq!(unit.describe().fields.len(), 1);
+
+        let tagged = MarkedEnum::Tagged(Marker, 3);
+        assert_eq!(tagged.describe().variants[0].fields[0].name, "1");
+        let mut collect = Collect::default();
+        tagged.visit_fields(&mut collect);
+        assert_eq!(collect.0, ["1=3"]);
+    }
+
+    // `any()` is always false and `test` is always true here, standing in
+    // for features that are off and on.
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[repr(C)]
+    struct Configured {
+        #[cfg(any())]
+        missing: NotDefinedAnywhere,
+        #[cfg_attr(test, describe(label = "Present"))]
+        present: u32,
+        #[cfg(test)]
+        also_present: u8,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    enum ConfiguredEnum {
+        #[cfg(any())]
+        Missing(NotDefinedAnywhere),
+        Present {
+            #[cfg(any())]
+            missing: NotDefinedAnywhere,
+            present: u8,
+        },
+    }
+
+    #[test]
+    fn test_cfg_fields() {
+        let mut configured = Configured {
+            present: 1,
+            also_present: 2,
+        };
+        assert_eq!(configured.field_count(), 2);
+        assert_eq!(configured.field_offsets(), [0, 4]);
+        assert_eq!(configured.describe().fields[0].label, Some("Present"));
+        assert_eq!(Configured::DESCRIPTOR.fields.len(), 2);
+        assert!(configured.get_field("missing").is_none());
+        configured.set_field("also_present", Box::new(3u8)).unwrap();
+        assert_eq!(configured.also_present, 3);
+
+        let present = ConfiguredEnum::Present { present: 1 };
+        assert_eq!(present.variant_count(), 1);
+        assert_eq!(present.describe().variants[0].fields.len(), 1);
+        let mut collect = Collect::default();
+        present.visit_fields(&mut collect);
+        assert_eq!(collect.0, ["present=1"]);
+    }
+
+    #[derive(Default)]
+    struct Collect(Vec<String>);
+
+    impl DescribeVisitor for Collect {
+        fn field(&mut self, name: &'static str, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={:?}", name, value));
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    enum Message {
+        Quit,
+        Move { x: i32, y: i32 },
+        Write(String),
+    }
+
+    // This only needs to compile.
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    enum Never {}
+
+    #[test]
+    fn test_visit_fields() {
+        let mut collect = Collect::default();
+        Named { a: 1, b: 2 }.visit_fields(&mut collect);
+        assert_eq!(collect.0, ["a=1", "b=2"]);
+
+        // `Named` doesn't implement Debug.
+        let mut collect = Collect::default();
+        Outer {
+            named: Named { a: 1, b: 2 },
+            pair: Tuple(1, 2, 3),
+            list: vec![4],
+        }
+        .visit_fields(&mut collect);
+        assert_eq!(collect.0, ["named=..", "pair=..", "list=[4]"]);
+
+        let mut collect = Collect::default();
+        Message::Move { x: 1, y: -1 }.visit_fields(&mut collect);
+        Message::Write("hi".to_string()).visit_fields(&mut collect);
+        Message::Quit.visit_fields(&mut collect);
+        assert_eq!(collect.0, ["x=1", "y=-1", "0=\"hi\""]);
+    }
+
+    #[test]
+    fn test_union() {
+        let bits = Bits { int: 0 };
+        assert_eq!(bits.struct_name(), "Bits");
+        assert_eq!(bits.field_count(), 2);
+        let desc = bits.describe();
+        assert_eq!(desc.kind, Kind::Union);
+        assert_eq!(desc.fields[0].name, "int");
+        assert_eq!(desc.fields[1].name, "float");
+        assert_eq!(desc.fields[1].type_name, "f32");
+        assert_eq!(desc.fields[1].nested, None);
+    }
+
+    #[cfg(feature = "registry")]
+    #[test]
+    fn test_registry() {
+        let info = registry::lookup("Point").unwrap();
+        assert_eq!(info.type_path, "abc::describe_tests::Point");
+
+        let info = registry::lookup_path("abc::describe_tests::inner::Foo").unwrap();
+        assert_eq!(info.name, "Foo");
+        assert_eq!(
+            registry::iter().filter(|info| info.name == "Foo").count(),
+            2
+        );
+
+        assert_eq!(registry::lookup("http_request").unwrap().description, "");
+        assert!(registry::lookup("HttpRequest").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_describe_json() {
+        let json = Point(1.0, 2.0).describe_json();
+        assert_eq!(
+            json,
+            r#"{"name":"Point","kind":"Struct","fields":["#.to_string()
+                + r#"{"name":"0","type_name":"f32","size":4,"nested":null},"#
+                + r#"{"name":"1","type_name":"f32","size":4,"nested":null}]}"#
+        );
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(display)]
+    struct Buf<const N: usize>([u8; N]);
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    struct Wrapper<'a, T: Clone>
+    where
+        T: Default,
+    {
+        inner: &'a T,
+    }
+
+    #[test]
+    fn test_generics() {
+        let buf = Buf([0; 4]);
+        assert_eq!(buf.struct_name(), "Buf<N>");
+        assert_eq!(buf.to_string(), "Buf<N>");
+        assert_eq!(buf.type_path(), "abc::describe_tests::Buf<N>");
+        assert_eq!(buf.size_of(), 4);
+        assert_eq!(buf.describe().fields[0].type_name, "[u8; N]");
+
+        let wrapper = Wrapper { inner: &5u32 };
+        assert_eq!(wrapper.struct_name(), "Wrapper<'a, T>");
+        assert_eq!(wrapper.describe().fields[0].type_name, "&'a T");
+    }
+
+    const BASE: u8 = 0x10;
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[repr(u8)]
+    enum Opcode {
+        Nop,
+        Load = BASE,
+        Store,
+        Jump = 0x20,
+        Halt = -1i8 as u8,
+    }
+
+    #[test]
+    fn test_discriminants() {
+        assert_eq!(
+            Opcode::Nop.variant_names(),
+            [
+                ("Nop", Some(0)),
+                ("Load", Some(0x10)),
+                ("Store", Some(0x11)),
+                ("Jump", Some(0x20)),
+                ("Halt", Some(0xff)),
+            ]
+        );
+        assert_eq!(Opcode::Store.discriminant(), Some(0x11));
+        assert_eq!(Opcode::Halt.discriminant(), Some(0xff));
+
+        assert_eq!(Choice::Right.discriminant(), Some(1));
+        assert_eq!(Message::Quit.discriminant(), None);
+        assert_eq!(
+            Message::Quit.variant_names(),
+            [("Quit", None), ("Move", None), ("Write", None)]
+        );
+        assert_eq!(Foo.variant_names(), []);
+        assert_eq!(Foo.discriminant(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    mod serde_names {
+        use crate::*;
+
+        #[allow(dead_code)]
+        #[derive(DescribeStruct, serde::Serialize)]
+        #[describe(use_serde_names, rename_all = "SCREAMING_SNAKE_CASE")]
+        #[serde(rename = "request", rename_all = "camelCase")]
+        pub struct Request {
+            pub retry_count: u8,
+            #[serde(rename = "ID")]
+            pub id: u32,
+        }
+
+        #[allow(dead_code)]
+        #[derive(DescribeStruct, serde::Serialize)]
+        #[describe(use_serde_names, rename_all = "SCREAMING_SNAKE_CASE")]
+        #[serde(rename_all = "kebab-case")]
+        pub enum Event {
+            #[serde(rename_all = "PascalCase")]
+            KeyPress {
+                key_code: u8,
+            },
+            MouseMove {
+                delta_x: i32,
+            },
+            #[serde(rename(serialize = "closed"))]
+            WindowClose,
+        }
+
+        #[test]
+        fn test_serde_names() {
+            let request = Request {
+                retry_count: 0,
+                id: 0,
+            };
+            assert_eq!(request.struct_name(), "request");
+            let desc = request.describe();
+            assert_eq!(desc.fields[0].name, "retryCount");
+            assert_eq!(desc.fields[1].name, "ID");
+
+            // serde doesn't rename the enum, so `rename_all` applies.
+            let event = Event::KeyPress { key_code: 1 };
+            assert_eq!(event.struct_name(), "EVENT");
+            let names: Vec<_> = event.variant_names().iter().map(|v| v.0).collect();
+            assert_eq!(names, ["key-press", "mouse-move", "closed"]);
+
+            let mut fields = Vec::new();
+            struct Names<'a>(&'a mut Vec<&'static str>);
+            impl DescribeVisitor for Names<'_> {
+                fn field(&mut self, name: &'static str, _: &dyn std::fmt::Debug) {
+                    self.0.push(name);
+                }
+            }
+            event.visit_fields(&mut Names(&mut fields));
+            Event::MouseMove { delta_x: 0 }.visit_fields(&mut Names(&mut fields));
+            assert_eq!(fields, ["KeyCode", "DELTA_X"]);
+        }
+    }
+
+    static OUTER: StructDescriptor = Outer::DESCRIPTOR;
+
+    const fn field_count_of(descriptor: &StructDescriptor) -> usize {
+        descriptor.fields.len()
+    }
+
+    #[test]
+    fn test_descriptor() {
+        assert_eq!(OUTER.name, "Outer");
+        let fields: Vec<_> = OUTER.fields.iter().map(|f| (f.name, f.type_name)).collect();
+        assert_eq!(
+            fields,
+            [("named", "Named"), ("pair", "Tuple"), ("list", "Vec<u8>")]
+        );
+
+        const COUNT: usize = field_count_of(&Point::DESCRIPTOR);
+        assert_eq!(COUNT, 2);
+        assert_eq!(Buf::<4>::DESCRIPTOR.name, "Buf<N>");
+        assert_eq!(HttpRequest::DESCRIPTOR.fields[0].name, "retry_count");
+        assert!(Choice::DESCRIPTOR.fields.is_empty());
+    }
+
+    /// Connection settings.
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(json_schema, rename_all = "camelCase")]
+    struct Settings {
+        /// Where to connect.
+        host: String,
+        retry_count: Option<u8>,
+        tags: Vec<&'static str>,
+        extra: Named,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(json_schema)]
+    enum Level {
+        Low,
+        High,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    #[describe(json_schema)]
+    struct Id(u64);
+
+    #[test]
+    fn test_json_schema() {
+        assert_eq!(
+            Settings::json_schema(),
+            concat!(
+                r#"{"$schema":"http://json-schema.org/draft-07/schema#","#,
+                r#""title":"settings","description":"Connection settings.","#,
+                r#""type":"object","properties":{"#,
+                r#""host":{"type":"string","description":"Where to connect."},"#,
+                r#""retryCount":{"type":"integer"},"#,
+                r#""tags":{"type":"array","items":{"type":"string"}},"#,
+                r#""extra":{}},"#,
+                r#""required":["host","tags","extra"]}"#,
+            )
+        );
+        assert_eq!(
+            Level::json_schema(),
+            concat!(
+                r#"{"$schema":"http://json-schema.org/draft-07/schema#","title":"Level","#,
+                r#""type":"string","enum":["Low","High"]}"#,
+            )
+        );
+        assert_eq!(
+            Id::json_schema(),
+            concat!(
+                r#"{"$schema":"http://json-schema.org/draft-07/schema#","title":"Id","#,
+                r#""type":"integer"}"#,
+            )
+        );
+    }
+
+    mod version_check {
+        /// Same structure as `super::Named`, different docs.
+        #[allow(dead_code)]
+        #[derive(crate::DescribeStruct)]
+        pub struct Named {
+            pub a: u8,
+            pub b: u16,
+        }
+    }
+
+    #[test]
+    fn test_structure_version() {
+        let named = Named { a: 0, b: 0 };
+        let other = version_check::Named { a: 0, b: 0 };
+        assert_eq!(named.structure_version(), other.structure_version());
+        assert_ne!(
+            named.structure_version(),
+            Tuple(0, 0, 0).structure_version()
+        );
+        assert_ne!(
+            Choice::Left.structure_version(),
+            Level::Low.structure_version()
+        );
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    struct RetryPolicy {
+        #[describe(label = "Retry count", doc = "Number of times to retry")]
+        retries: u8,
+        backoff_ms: u32,
+    }
+
+    #[test]
+    fn test_field_labels() {
+        let desc = RetryPolicy {
+            retries: 0,
+            backoff_ms: 0,
+        }
+        .describe();
+        assert_eq!(desc.fields[0].label, Some("Retry count"));
+        assert_eq!(desc.fields[0].doc, Some("Number of times to retry"));
+        assert_eq!(desc.fields[1].label, None);
+        assert_eq!(desc.fields[1].doc, None);
+
+        let fields = RetryPolicy::DESCRIPTOR.fields;
+        assert_eq!(fields[0].label, Some("Retry count"));
+        assert_eq!(fields[1].doc, None);
+    }
+
+    #[test]
+    fn test_dyn_describe() {
+        let values: Vec<Box<dyn DescribeStruct>> = vec![
+            Box::new(Foo),
+            Box::new(Point(1.0, 2.0)),
+            Box::new(Choice::Right),
+        ];
+        let names: Vec<_> = values.iter().map(|value| value.struct_name()).collect();
+        assert_eq!(names, ["Foo", "Point", "Choice"]);
+        assert_eq!(values[1].field_count(), 2);
+        assert_eq!(values[2].discriminant(), Some(1));
+
+        // Boxed trait objects, references, and Arcs forward too.
+        let boxed: Box<dyn DescribeStruct> = Box::new(Point(1.0, 2.0));
+        assert_eq!(boxed.describe(), Point(1.0, 2.0).describe());
+        assert_eq!((&&Foo).type_path(), Foo.type_path());
+        assert_eq!(std::sync::Arc::new(Meters).size_of(), 0);
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(DescribeStruct)]
+    #[describe(display)]
+    struct r#Loop {
+        r#type: u32,
+        r#match: r#Loop2,
+    }
+
+    #[allow(dead_code)]
+    #[derive(DescribeStruct)]
+    enum r#Loop2 {
+        r#Struct,
+    }
+
+    #[test]
+    fn test_raw_identifiers() {
+        let value = r#Loop {
+            r#type: 0,
+            r#match: r#Loop2::r#Struct,
+        };
+        assert_eq!(value.struct_name(), "Loop");
+        assert_eq!(value.to_string(), "Loop");
+        assert_eq!(value.type_path(), "abc::describe_tests::Loop");
+        let desc = value.describe();
+        assert_eq!(desc.fields[0].name, "type");
+        assert_eq!(desc.fields[1].name, "match");
+        assert_eq!(desc.fields[1].type_name, "Loop2");
+        assert_eq!(r#Loop2::r#Struct.variant_names(), [("Struct", Some(0))]);
+    }
+
+    #[cfg(feature = "describe_all")]
+    #[describe_all]
+    #[allow(dead_code)]
+    mod messages {
+        pub struct Ping;
+
+        /// A reply.
+        #[describe(rename_all = "snake_case")]
+        pub enum Reply {
+            PongBack,
+        }
+
+        #[derive(crate::DescribeStruct)]
+        pub struct AlreadyDerived;
+
+        pub mod nested {
+            pub struct Deep(pub u8);
+        }
+    }
+
+    #[cfg(feature = "describe_all")]
+    #[test]
+    fn test_describe_all() {
+        assert_eq!(messages::Ping.struct_name(), "Ping");
+        assert_eq!(messages::Reply::PongBack.description(), "A reply.");
+        assert_eq!(
+            messages::Reply::PongBack.variant_names(),
+            [("pong_back", Some(0))]
+        );
+        assert_eq!(messages::AlreadyDerived.struct_name(), "AlreadyDerived");
+        assert_eq!(
+            messages::nested::Deep(0).type_path(),
+            "abc::describe_tests::messages::nested::Deep"
+        );
+    }
+
+    // Stands in for a crate that re-exports `abc`.
+    mod facade {
+        pub use crate::*;
+    }
+
+    mod uses_facade {
+        // Note: the trait isn't in scope here.
+        #[derive(crate::DescribeStruct)]
+        #[describe(crate = "crate::describe_tests::facade")]
+        pub struct ViaFacade;
+    }
+
+    #[test]
+    fn test_crate_path() {
+        assert_eq!(uses_facade::ViaFacade.struct_name(), "ViaFacade");
+    }
+
+    #[cfg(feature = "tracing")]
+    mod spans {
+        use crate::*;
+        use std::fmt::Debug;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        use super::Foo;
+
+        type Spans = Arc<Mutex<Vec<(&'static str, Vec<String>)>>>;
+
+        // Records the name and fields of every new span.
+        #[derive(Default)]
+        struct Recorder {
+            spans: Spans,
+            next_id: AtomicU64,
+        }
+
+        struct FieldList<'a>(&'a mut Vec<String>);
+
+        impl Visit for FieldList<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0.push(format!("{}={:?}", field.name(), value));
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = Vec::new();
+                span.record(&mut FieldList(&mut fields));
+                let name = span.metadata().name();
+                self.spans.lock().unwrap().push((name, fields));
+                Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        #[allow(dead_code)]
+        #[derive(DescribeStruct)]
+        #[describe(instrument)]
+        struct Job {
+            id: u32,
+        }
+
+        struct Manual;
+
+        impl DescribeStruct for Manual {
+            fn struct_name(&self) -> &'static str {
+                "Manual"
+            }
+            fn type_path(&self) -> &'static str {
+                "spans::Manual"
+            }
+            fn description(&self) -> &'static str {
+                ""
+            }
+            fn structure_version(&self) -> u64 {
+                0
+            }
+            fn size_of(&self) -> usize {
+                0
+            }
+            fn align_of(&self) -> usize {
+                1
+            }
+            fn visit_fields(&self, _: &mut dyn DescribeVisitor) {}
+            fn describe(&self) -> Description {
+                Description {
+                    name: "Manual",
+                    kind: Kind::Struct,
+                    fields: Vec::new(),
+                    variants: Vec::new(),
+                }
+            }
+        }
+
+        #[test]
+        fn test_trace_span() {
+            let recorder = Recorder::default();
+            let spans = recorder.spans.clone();
+            tracing::subscriber::with_default(recorder, || {
+                let _ = Foo.trace_span();
+                let _ = Job { id: 1 }.trace_span();
+                let _ = Box::new(Foo).trace_span();
+                let _ = Manual.trace_span();
+            });
+            let spans = spans.lock().unwrap();
+            assert_eq!(spans[0], ("Foo", vec![]));
+            assert_eq!(
+                spans[1],
+                (
+                    "Job",
+                    vec![
+                        "type_name=\"Job\"".to_string(),
+                        "type_path=\"abc::describe_tests::spans::Job\"".to_string(),
+                    ]
+                )
+            );
+            assert_eq!(spans[2], ("Foo", vec![]));
+            assert_eq!(
+                spans[3],
+                ("described", vec!["type_name=\"Manual\"".to_string()])
+            );
+        }
+    }
+
+    #[test]
+    fn describe_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/describe_fail.rs");
+        t.compile_fail("tests/build_failures/deny_names_fail.rs");
+        #[cfg(feature = "describe_regex")]
+        t.compile_fail("tests/build_failures/name_pattern_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod builder_tests {
+    use super::*;
+
+    #[derive(Builder, Debug, PartialEq)]
+    struct Request {
+        /// Where to send the request.
+        #[builder(into)]
+        url: String,
+        #[builder(default)]
+        retries: u8,
+        r#type: u16,
+    }
+
+    #[test]
+    fn test_builder() {
+        let request = Request::builder()
+            .url("/index.html")
+            .r#type(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request,
+            Request {
+                url: "/index.html".to_string(),
+                retries: 0,
+                r#type: 2,
+            }
+        );
+
+        let request = RequestBuilder::default()
+            .url("/")
+            .retries(3)
+            .r#type(1)
+            .build()
+            .unwrap();
+        assert_eq!(request.retries, 3);
+
+        let err = Request::builder().url("/").build().unwrap_err();
+        assert_eq!(err, BuilderError::MissingField("type"));
+        assert_eq!(err.to_string(), "field `type` was not set");
+    }
+
+    #[derive(Builder)]
+    struct Pair<'a, T: Clone, const N: usize>
+    where
+        T: Default,
+    {
+        name: &'a str,
+        #[builder(default)]
+        values: Vec<T>,
+        bytes: [u8; N],
+    }
+
+    #[test]
+    fn test_generic_builder() {
+        let pair: Pair<u8, 2> = Pair::builder().name("pair").bytes([1, 2]).build().unwrap();
+        assert_eq!(pair.name, "pair");
+        assert!(pair.values.is_empty());
+        assert_eq!(pair.bytes, [1, 2]);
+    }
+
+    #[test]
+    fn builder_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/builder_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "getset"))]
+mod getset_tests {
+    use super::*;
+
+    mod account {
+        use crate::{Getters, Setters};
+
+        #[derive(Default, Getters, Setters)]
+        pub struct Account {
+            /// The account holder.
+            name: String,
+            #[getset(vis = "pub(crate)")]
+            balance: i64,
+            #[getset(skip)]
+            pub audit: Vec<String>,
+            r#type: u8,
+        }
+    }
+
+    #[test]
+    fn test_getters_and_setters() {
+        let mut account = account::Account::default();
+        account.set_name("Ada".to_string());
+        account.set_balance(10);
+        account.set_type(2);
+        assert_eq!(account.name(), "Ada");
+        assert_eq!(*account.balance(), 10);
+        assert_eq!(*account.r#type(), 2);
+        assert!(account.audit.is_empty());
+    }
+
+    #[derive(Getters)]
+    #[getset(vis = "")]
+    struct Wrapper<'a, T> {
+        inner: &'a T,
+    }
+
+    #[test]
+    fn test_generic_getters() {
+        let wrapper = Wrapper { inner: &5 };
+        assert_eq!(**wrapper.inner(), 5);
+    }
+}
+
+#[cfg(all(test, feature = "enum_iter"))]
+mod enum_iter_tests {
+    use super::*;
+
+    #[derive(EnumIter, Debug, PartialEq)]
+    enum Suit {
+        Clubs,
+        Diamonds = 5,
+        Hearts,
+        Spades,
+    }
+
+    #[derive(EnumIter)]
+    enum Empty {}
+
+    #[test]
+    fn test_enum_iter() {
+        let suits: Vec<Suit> = Suit::iter().collect();
+        assert_eq!(
+            suits,
+            [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades]
+        );
+        assert_eq!(Suit::iter().map(|suit| suit as u8).last(), Some(7));
+        assert_eq!(Empty::iter().count(), 0);
+    }
+}
+
+#[cfg(all(test, feature = "try_from_primitive"))]
+mod try_from_primitive_tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[derive(TryFromPrimitive, Debug, PartialEq)]
+    #[repr(u8)]
+    enum Command {
+        Read = 1,
+        Write = 2,
+        Verify,
+        Erase = 0x10,
+    }
+
+    #[derive(TryFromPrimitive, Debug, PartialEq)]
+    #[repr(i16)]
+    enum Offset {
+        Back = -1,
+        Here,
+    }
+
+    #[test]
+    fn test_try_from_primitive() {
+        assert_eq!(Command::try_from(1), Ok(Command::Read));
+        assert_eq!(Command::try_from(3), Ok(Command::Verify));
+        assert_eq!(Command::try_from(0x10), Ok(Command::Erase));
+        assert_eq!(Command::try_from(4), Err(4));
+        assert_eq!(Offset::try_from(-1), Ok(Offset::Back));
+        assert_eq!(Offset::try_from(0), Ok(Offset::Here));
+        assert_eq!(Offset::try_from(1), Err(1));
+    }
+}
+
+#[cfg(all(test, feature = "display"))]
+mod display_tests {
+    use super::*;
+
+    #[derive(Display)]
+    #[display("{name} ({id})")]
+    struct User {
+        name: String,
+        id: u32,
+    }
+
+    #[derive(Display)]
+    #[display("{0}x{1} {{px}}")]
+    struct Size(u32, u32);
+
+    #[derive(Display)]
+    enum NetError {
+        #[display("connection lost")]
+        Lost,
+        #[display("timed out after {0} seconds")]
+        Timeout(u32),
+        #[display("refused by {host}:{port:05}")]
+        Refused {
+            host: String,
+            port: u16,
+        },
+        Reset,
+    }
+
+    #[derive(Display)]
+    #[display("generic error")]
+    enum Generic<T: std::fmt::Display> {
+        Only(T),
+        #[display("special: {0}")]
+        Special(T),
+    }
+
+    #[test]
+    fn test_display() {
+        let user = User {
+            name: "ada".to_string(),
+            id: 7,
+        };
+        assert_eq!(user.to_string(), "ada (7)");
+        assert_eq!(Size(640, 480).to_string(), "640x480 {px}");
+        assert_eq!(NetError::Lost.to_string(), "connection lost");
+        assert_eq!(
+            NetError::Timeout(30).to_string(),
+            "timed out after 30 seconds"
+        );
+        let refused = NetError::Refused {
+            host: "localhost".to_string(),
+            port: 80,
+        };
+        assert_eq!(refused.to_string(), "refused by localhost:00080");
+        assert_eq!(NetError::Reset.to_string(), "Reset");
+        assert_eq!(Generic::Only(1).to_string(), "generic error");
+        assert_eq!(Generic::Special(2).to_string(), "special: 2");
+    }
+}
+
+#[cfg(all(test, feature = "from_str"))]
+mod from_str_tests {
+    use super::*;
+
+    #[derive(FromStr, Debug, PartialEq)]
+    enum Level {
+        Debug,
+        #[from_str(rename = "warn", ascii_case_insensitive)]
+        Warning,
+        r#Error,
+    }
+
+    #[derive(FromStr, Debug, PartialEq)]
+    #[from_str(ascii_case_insensitive)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("Debug".parse(), Ok(Level::Debug));
+        assert_eq!("WARN".parse(), Ok(Level::Warning));
+        assert_eq!("Error".parse(), Ok(Level::Error));
+        let err = "debug".parse::<Level>().unwrap_err();
+        assert_eq!(
+            err,
+            ParseEnumError {
+                type_name: "Level",
+                input: "debug".to_string(),
+            }
+        );
+        assert_eq!(err.to_string(), "`debug` is not a valid Level");
+        assert!("Warning".parse::<Level>().is_err());
+
+        assert_eq!("green".parse(), Ok(Color::Green));
+        assert_eq!("RED".parse(), Ok(Color::Red));
+        assert!("blue".parse::<Color>().is_err());
+    }
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod trace_tests {
+    use super::*;
+    use crate::test_util::block_on;
+    use std::num::ParseIntError;
+
+    struct NoDebug;
+
+    #[trace(skip(secret))]
+    fn add(a: u32, b: u32, secret: &str, _opaque: NoDebug) -> u32 {
+        if secret.is_empty() {
+            return 0;
+        }
+        a + b
+    }
+
+    #[trace]
+    fn parse_sum(a: &str, b: &str) -> Result<u32, ParseIntError> {
+        let sum = a.parse::<u32>()? + b.parse::<u32>()?;
+        Ok(sum)
+    }
+
+    #[trace]
+    fn first<T>((left, _): (T, T)) -> T {
+        left
+    }
+
+    #[derive(Debug)]
+    struct Counter(u32);
+
+    impl Counter {
+        #[trace]
+        fn bump(&mut self, by: u32) -> &u32 {
+            self.0 += by;
+            &self.0
+        }
+    }
+
+    #[trace]
+    async fn double(x: u32) -> u32 {
+        x * 2
+    }
+
+    #[test]
+    fn test_trace() {
+        assert_eq!(add(1, 2, "x", NoDebug), 3);
+        assert_eq!(add(1, 2, "", NoDebug), 0);
+        assert_eq!(parse_sum("1", "2"), Ok(3));
+        assert!(parse_sum("1", "two").is_err());
+        assert_eq!(first((1, 2)), 1);
+
+        let mut counter = Counter(0);
+        assert_eq!(*counter.bump(2), 2);
+        assert_eq!(counter.0, 2);
+
+        assert_eq!(block_on(double(4)), 8);
+    }
+}
+
+#[cfg(all(test, feature = "timed"))]
+mod timed_tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::time::Duration;
+
+    thread_local! {
+        static REPORTS: RefCell<Vec<(&'static str, Duration)>> =
+            const { RefCell::new(Vec::new()) };
+    }
+
+    fn record(name: &'static str, elapsed: Duration) {
+        REPORTS.with(|reports| reports.borrow_mut().push((name, elapsed)));
+    }
+
+    #[timed]
+    fn checked_sum(values: &[u32]) -> Option<u32> {
+        let mut sum: u32 = 0;
+        for value in values {
+            sum = sum.checked_add(*value)?;
+        }
+        Some(sum)
+    }
+
+    #[timed(sink = "record")]
+    fn nap(ms: u64) -> &'static str {
+        std::thread::sleep(Duration::from_millis(ms));
+        "rested"
+    }
+
+    #[timed(sink = "record")]
+    async fn async_nap(ms: u64) {
+        std::thread::sleep(Duration::from_millis(ms));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[timed(sink = "tracing")]
+    fn traced() -> u8 {
+        7
+    }
+
+    #[test]
+    fn test_timed() {
+        #[cfg(feature = "tracing")]
+        assert_eq!(traced(), 7);
+        assert_eq!(checked_sum(&[1, 2]), Some(3));
+        assert_eq!(checked_sum(&[u32::MAX, 1]), None);
+
+        assert_eq!(nap(5), "rested");
+        crate::test_util::block_on(async_nap(5));
+        let reports = REPORTS.with(|reports| reports.take());
+        let names: Vec<_> = reports.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["nap", "async_nap"]);
+        assert!(reports
+            .iter()
+            .all(|(_, elapsed)| *elapsed >= Duration::from_millis(5)));
+    }
+}
+
+#[cfg(all(test, feature = "retry"))]
+mod retry_tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    #[derive(Debug, PartialEq)]
+    struct Flaky(u32);
+
+    // Fails until it has been called `succeed_on` times.
+    #[retry(times = 3)]
+    fn flaky(calls: &Cell<u32>, succeed_on: u32) -> Result<u32, Flaky> {
+        calls.set(calls.get() + 1);
+        if calls.get() < succeed_on {
+            return Err(Flaky(calls.get()));
+        }
+        Ok(calls.get())
+    }
+
+    #[retry(times = 3, backoff_ms = 5)]
+    fn parse_later(input: &str) -> Result<u32, std::num::ParseIntError> {
+        let value = input.parse()?;
+        Ok(value)
+    }
+
+    #[retry(times = 2)]
+    async fn async_flaky(calls: &Cell<u32>) -> Result<(), Flaky> {
+        calls.set(calls.get() + 1);
+        Err(Flaky(calls.get()))
+    }
+
+    #[test]
+    fn test_retry() {
+        let calls = Cell::new(0);
+        assert_eq!(flaky(&calls, 3), Ok(3));
+
+        let calls = Cell::new(0);
+        assert_eq!(flaky(&calls, 4), Err(Flaky(3)));
+        assert_eq!(calls.get(), 3);
+
+        // Waits 5ms, then 10ms.
+        let start = Instant::now();
+        assert!(parse_later("x").is_err());
+        assert!(start.elapsed() >= Duration::from_millis(15));
+        assert_eq!(parse_later("4"), Ok(4));
+
+        let calls = Cell::new(0);
+        let result = crate::test_util::block_on(async_flaky(&calls));
+        assert_eq!(result, Err(Flaky(2)));
+    }
+}
+
+#[cfg(all(test, feature = "hex"))]
+mod hex_tests {
+    use super::*;
+
+    const KEY: [u8; 4] = hex!("deadbeef");
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(KEY, [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(hex!("00 01\n  FF"), [0, 1, 255]);
+        let empty: [u8; 0] = hex!("");
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn hex_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/hex_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "uuid_macro"))]
+mod uuid_tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid() {
+        let id = uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8");
+        let bytes = [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ];
+        #[cfg(not(feature = "uuid"))]
+        assert_eq!(id, bytes);
+        #[cfg(feature = "uuid")]
+        assert_eq!(id, ::uuid::Uuid::from_bytes(bytes));
+    }
+
+    #[test]
+    fn uuid_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/uuid_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "regex"))]
+mod regex_tests {
+    use super::*;
+
+    fn is_date(s: &str) -> bool {
+        regex!(r"^\d{4}-\d{2}-\d{2}$").is_match(s)
+    }
+
+    #[test]
+    fn test_regex() {
+        assert!(is_date("2021-03-04"));
+        assert!(!is_date("March 4"));
+        // Each use is compiled once, and then reused.
+        let same: Vec<*const _> = (0..2).map(|_| regex!("b+") as *const _).collect();
+        assert_eq!(same[0], same[1]);
+    }
+
+    #[test]
+    fn regex_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/regex_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "build_info"))]
+mod build_info_tests {
+    use super::*;
+
+    build_info!(pub(crate));
+
+    const VERSION: &str = git_version!();
+
+    #[test]
+    fn test_build_info() {
+        // This may be built outside of a git repository.
+        if let Some(commit) = GIT_COMMIT {
+            assert_eq!(commit.len(), 40);
+            assert!(commit.chars().all(|c| c.is_ascii_hexdigit()));
+            assert_eq!(VERSION.ends_with("-dirty"), GIT_DIRTY);
+        } else {
+            assert_eq!(VERSION, "unknown");
+        }
+        const { assert!(BUILD_TIMESTAMP > 1_600_000_000) };
+    }
+}
+
+#[cfg(all(test, feature = "compile_time"))]
+mod compile_time_tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_time() {
+        let date = compile_time!("%Y-%m-%d");
+        assert_eq!(date.len(), 10);
+        assert_eq!(&date[4..5], "-");
+        let stamp = compile_time!();
+        assert!(stamp.len() == 20 && stamp.ends_with('Z'));
+        let epoch: u64 = compile_time!("%s", source_date_epoch).parse().unwrap();
+        assert!(epoch > 0);
+    }
+}
+
+#[cfg(all(test, feature = "env_or"))]
+mod env_or_tests {
+    use super::*;
+
+    const PORT: u16 = env_or!("ABC_TEST_UNSET_PORT", 8080u16);
+    const OFFSET: i32 = env_or!("ABC_TEST_UNSET_OFFSET", -4);
+    // Cargo always sets this one.
+    const NAME: &str = env_or!("CARGO_PKG_NAME", "unknown");
+
+    #[test]
+    fn test_env_or() {
+        assert_eq!(PORT, 8080);
+        assert_eq!(OFFSET, -4);
+        assert_eq!(NAME, "abc");
+        let ratio: f32 = env_or!("ABC_TEST_UNSET_RATIO", 0.5);
+        assert_eq!(ratio, 0.5);
+    }
+}
+
+#[cfg(all(test, feature = "static_assert"))]
+mod static_assert_tests {
+    use super::*;
+
+    const BLOCK: usize = 16;
+
+    static_assert!(core::mem::size_of::<u64>() * 2 == BLOCK);
+    static_assert!(BLOCK.is_power_of_two(), "BLOCK must be a power of two");
+
+    #[test]
+    fn test_static_assert() {
+        static_assert!(BLOCK > 0);
+    }
+
+    #[test]
+    fn static_assert_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/static_assert_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "newtype"))]
+mod newtype_tests {
+    use super::*;
+
+    newtype! {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct Meters(f64);
+        derive(Add, Sub, Mul, Neg, AddAssign, Display, From, Into)
+    }
+
+    newtype! {
+        #[derive(Debug, Default)]
+        struct Names(Vec<String>);
+        derive(DerefMut)
+    }
+
+    #[test]
+    fn test_newtype() {
+        let mut distance = Meters(1.5) + Meters(2.0) - Meters(0.5);
+        assert_eq!(distance, Meters(3.0));
+        distance += Meters(1.0);
+        assert_eq!(distance * Meters(2.0), Meters(8.0));
+        assert_eq!(-distance, Meters(-4.0));
+        assert_eq!(distance.to_string(), "4");
+        assert_eq!(format!("{:.2}", distance), "4.00");
+        assert_eq!(Meters::from(2.0), Meters(2.0));
+        let raw: f64 = distance.into();
+        assert_eq!(raw, 4.0);
+
+        let mut names = Names::default();
+        names.push("a".to_string());
+        assert_eq!(names.len(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "bitfield"))]
+mod bitfield_tests {
+    use super::*;
+
+    bitfield! {
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        pub Flags: u16 {
+            enable: 0,
+            mode: 1..3,
+            count: 3..8,
+            high: 15,
+        }
+    }
+
+    #[test]
+    fn test_bitfield() {
+        let mut flags = Flags::default();
+        flags.set_enable(true);
+        flags.set_mode(2);
+        flags.set_count(31);
+        assert_eq!(flags.bits(), 0b1111_1101);
+        assert!(flags.enable());
+        assert_eq!(flags.mode(), 2);
+        assert_eq!(flags.count(), 31);
+        assert!(!flags.high());
+
+        flags.set_mode(1);
+        flags.set_enable(false);
+        flags.set_high(true);
+        assert_eq!(flags.bits(), 0b1000_0000_1111_1010);
+        assert_eq!(Flags::from_bits(flags.bits()), flags);
+    }
+
+    #[test]
+    #[should_panic(expected = "value too large for 2-bit field `mode`")]
+    #[cfg(debug_assertions)]
+    fn bitfield_overflow() {
+        Flags::default().set_mode(4);
+    }
+
+    #[test]
+    fn bitfield_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/bitfield_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "semver_macro"))]
+mod semver_tests {
+    use super::*;
+
+    #[cfg(not(feature = "semver"))]
+    const MIN_PLUGIN_API: (u64, u64, u64, &str) = semver!("1.4.0");
+
+    #[test]
+    fn test_semver() {
+        #[cfg(not(feature = "semver"))]
+        {
+            assert_eq!(MIN_PLUGIN_API, (1, 4, 0, ""));
+            assert_eq!(semver!("2.0.0-rc.1+build.7"), (2, 0, 0, "rc.1"));
+        }
+        #[cfg(feature = "semver")]
+        {
+            let version = semver!("2.0.0-rc.1+build.7");
+            assert_eq!(
+                version,
+                ::semver::Version::parse("2.0.0-rc.1+build.7").unwrap()
+            );
+            assert_eq!(semver!("1.4.0"), ::semver::Version::new(1, 4, 0));
+        }
+    }
+
+    #[test]
+    fn semver_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/semver_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "net"))]
+mod net_tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    const GATEWAY: Ipv4Addr = ip!("10.0.0.1");
+    const LISTEN: SocketAddr = sockaddr!("0.0.0.0:8080");
+
+    #[test]
+    fn test_net() {
+        assert_eq!(GATEWAY, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(ip!("::1"), Ipv6Addr::LOCALHOST);
+        let any: IpAddr = ip!("fe80::1:2").into();
+        assert_eq!(any, "fe80::1:2".parse::<IpAddr>().unwrap());
+
+        assert_eq!(LISTEN, "0.0.0.0:8080".parse().unwrap());
+        assert_eq!(sockaddr!("[::1]:443"), "[::1]:443".parse().unwrap());
+    }
+}
+
+#[cfg(all(test, feature = "cstr"))]
+mod cstr_tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    const GREETING: &CStr = cstr!("hello");
+
+    #[test]
+    fn test_cstr() {
+        assert_eq!(GREETING.to_bytes_with_nul(), b"hello\0");
+        assert_eq!(cstr!(b"caf\xe9").to_bytes(), b"caf\xe9");
+        assert!(cstr!("").to_bytes().is_empty());
+    }
+
+    #[test]
+    fn cstr_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/cstr_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "make_ident"))]
+mod make_ident_tests {
+    use super::*;
+
+    struct Config {
+        retry_count: u32,
+        timeout_ms: u64,
+    }
+
+    macro_rules! accessors {
+        ($($field:ident: $ty:ty),*) => {
+            make_ident! {
+                impl Config {
+                    $(
+                        fn [<get_ $field>](&self) -> $ty {
+                            self.$field
+                        }
+
+                        const [<$field:screaming _DEFAULT>]: $ty = 0;
+                    )*
+                }
+
+                $(struct [<$field:pascal Marker>];)*
+            }
+        };
+    }
+
+    accessors!(retry_count: u32, timeout_ms: u64);
+
+    #[test]
+    fn test_make_ident() {
+        let config = Config {
+            retry_count: 3,
+            timeout_ms: 500,
+        };
+        assert_eq!(config.get_retry_count(), 3);
+        assert_eq!(config.get_timeout_ms(), 500);
+        assert_eq!(Config::RETRY_COUNT_DEFAULT, 0);
+        assert_eq!(Config::TIMEOUT_MS_DEFAULT, 0);
+        let _ = (RetryCountMarker, TimeoutMsMarker);
+    }
+}
+
+#[cfg(all(test, feature = "sorted"))]
+mod sorted_tests {
+    use super::*;
+
+    #[sorted]
+    #[derive(Debug)]
+    enum Command {
+        Add,
+        Commit,
+        Status,
+    }
+
+    #[sorted]
+    const ALIASES: &[(&str, Command)] = &[
+        ("ci", Command::Commit),
+        ("st", Command::Status),
+        ("stage", Command::Add),
+    ];
+
+    #[sorted]
+    fn usage(command: &Command) -> &'static str {
+        #[sorted]
+        let _ports = [22, 80, 443];
+        #[sorted]
+        match command {
+            Command::Add => "add files",
+            Command::Commit => "record changes",
+            Command::Status => "show status",
+        }
+    }
+
+    #[test]
+    fn test_sorted() {
+        assert_eq!(ALIASES.len(), 3);
+        assert_eq!(usage(&ALIASES[0].1), "record changes");
+    }
+
+    #[test]
+    fn sorted_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/sorted_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "default_with_values"))]
+mod default_tests {
+    use super::*;
+
+    #[derive(DefaultWithValues, Debug, PartialEq)]
+    struct Config {
+        #[default(8080)]
+        port: u16,
+        #[default("localhost")]
+        host: String,
+        #[default("info")]
+        level: &'static str,
+        #[default(vec![1, 2])]
+        retries: Vec<u32>,
+        verbose: bool,
+    }
+
+    #[derive(DefaultWithValues, Debug, PartialEq)]
+    struct Pair<T: Default>(#[default(-1)] i64, T);
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            Config::default(),
+            Config {
+                port: 8080,
+                host: "localhost".to_string(),
+                level: "info",
+                retries: vec![1, 2],
+                verbose: false,
+            }
+        );
+        assert_eq!(Pair::<u8>::default(), Pair(-1, 0));
+    }
+}
+
+#[cfg(all(test, feature = "table"))]
+mod table_tests {
+    use super::*;
+
+    table! {
+        #[derive(Debug, PartialEq)]
+        pub Country { #[key] code: &str, #[key] dial: u16, name: &str }
+        ["US", 1, "United States"],
+        ["DE", 49, "Germany"],
+        ["JP", 81, "Japan"],
+    }
+
+    table! {
+        Unit { name: &str, scale: f64 } ["k", 1e3], ["M", 1e6]
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(Country::rows().len(), 3);
+        assert_eq!(Country::by_code("DE").unwrap().name, "Germany");
+        let code = String::from("JP");
+        assert_eq!(Country::by_code(&code).unwrap().dial, 81);
+        assert_eq!(Country::by_dial(1).unwrap().code, "US");
+        assert_eq!(Country::by_dial(44), None);
+
+        assert_eq!(Unit::by_name("M").unwrap().scale, 1e6);
+    }
+}
+
+#[cfg(all(test, feature = "enum_strings"))]
+mod enum_strings_tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    enum_strings!(
+        #[derive(Debug, PartialEq)]
+        pub FileKind {
+            Readme: "README" | "README.md",
+            Image: "img_*",
+            Rust: "*.rs",
+            Test: "test_?*.py",
+            Other: _,
+        }
+    );
+
+    enum_strings!(
+        #[derive(Debug, PartialEq)]
+        Answer {
+            Yes: "y" | "yes",
+            No: "n" | "no",
+        }
+    );
+
+    #[test]
+    fn test_enum_strings() {
+        assert_eq!(FileKind::from("README.md"), FileKind::Readme);
+        assert_eq!(FileKind::from("img_cat.png"), FileKind::Image);
+        assert_eq!(FileKind::from("main.rs"), FileKind::Rust);
+        // Exact strings come first, and then the patterns in order.
+        assert_eq!(FileKind::from("img_.rs"), FileKind::Image);
+        assert_eq!(FileKind::from("test_a.py"), FileKind::Test);
+        assert_eq!(FileKind::from("test_.py"), FileKind::Other);
+        assert_eq!(FileKind::from("notes.txt"), FileKind::Other);
+
+        assert_eq!(Answer::try_from("yes"), Ok(Answer::Yes));
+        assert_eq!(Answer::try_from("n"), Ok(Answer::No));
+        assert_eq!(Answer::try_from("maybe"), Err("maybe"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        use crate::__private::glob_match;
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(!glob_match("a*b*c", "acb"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("?é?", "xéy"));
+        assert!(!glob_match("??", "x"));
+        assert!(glob_match("*a*", "bab"));
+        assert!(!glob_match("a", "ab"));
+    }
+}
+
+#[cfg(all(test, feature = "enum_bitmask"))]
+mod enum_bitmask_tests {
+    use super::*;
+
+    enum_bitmask!(
+        #[derive(Debug)]
+        pub Permission: u8 => Permissions {
+            Read: 1 << 0,
+            Write: 1 << 1,
+            /// Run it.
+            Exec: 1 << 4,
+        }
+    );
+
+    #[test]
+    fn test_enum_bitmask() {
+        assert_eq!(Permission::Exec.mask(), 0x10);
+        assert_eq!(Permission::Exec as u8, 0x10);
+
+        let mut perms = Permission::Read | Permission::Exec;
+        assert!(perms.contains(Permission::Read));
+        assert!(!perms.contains(Permission::Write));
+        assert_eq!(perms.bits(), 0x11);
+        assert_eq!(
+            perms.iter().collect::<Vec<_>>(),
+            [Permission::Read, Permission::Exec]
+        );
+        assert_eq!(format!("{:?}", perms), "Permissions(Read | Exec)");
+
+        perms.remove(Permission::Read);
+        perms |= Permission::Write;
+        assert_eq!(perms, Permission::Write | Permission::Exec);
+        assert_eq!(format!("{:?}", Permissions::empty()), "Permissions()");
+        assert!(Permissions::default().is_empty());
+
+        assert_eq!(Permissions::all().bits(), 0x13);
+        assert_eq!(
+            Permissions::from_bits(0x03),
+            Some(Permission::Read | Permission::Write)
+        );
+        assert_eq!(Permissions::from_bits(0x04), None);
+        let collected: Permissions = Permission::ALL.iter().copied().collect();
+        assert_eq!(collected, Permissions::all());
+    }
+
+    enum_bitmask!(
+        Register: u32 {
+            Ready: 1 << 31,
+        }
+    );
+
+    #[test]
+    fn test_enum_bitmask_default_name() {
+        assert_eq!(RegisterFlags::from(Register::Ready).bits(), 0x8000_0000);
+    }
+}
+
+#[cfg(all(test, feature = "state_machine"))]
+mod state_machine_tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn exit(state: State, event: Event) {
+        LOG.with(|log| {
+            log.borrow_mut()
+                .push(format!("exit {:?} on {:?}", state, event))
+        });
+    }
+
+    fn enter(state: State, event: Event) {
+        LOG.with(|log| {
+            log.borrow_mut()
+                .push(format!("enter {:?} on {:?}", state, event))
+        });
+    }
+
+    state_machine! {
+        #[derive(Hash)]
+        pub enum State { Idle, Running, Done }
+        pub enum Event { Start, Finish, Reset }
+
+        on_exit = exit;
+        on_enter = enter;
+
+        transitions {
+            Idle + Start => Running,
+            Running + Finish => Done,
+            Running | Done + Reset => Idle,
+        }
+    }
+
+    #[test]
+    fn test_state_machine() {
+        let mut state = State::Idle;
+        assert_eq!(state.transition(Event::Start), Ok(State::Running));
+        assert_eq!(state.transition(Event::Finish), Ok(State::Done));
+        assert_eq!(
+            state.transition(Event::Start),
+            Err(InvalidTransition {
+                state: State::Done,
+                event: Event::Start,
+            })
+        );
+        assert_eq!(state, State::Done);
+        assert_eq!(state.transition(Event::Reset), Ok(State::Idle));
+
+        const NEXT: Option<State> = State::Running.next_state(Event::Reset);
+        assert_eq!(NEXT, Some(State::Idle));
+        assert_eq!(State::Idle.next_state(Event::Reset), None);
+
+        let err = State::Idle.transition(Event::Finish).unwrap_err();
+        assert_eq!(err.to_string(), "no transition from Idle on Finish");
+
+        let log = LOG.with(|log| log.borrow().clone());
+        assert_eq!(
+            log,
+            [
+                "exit Idle on Start",
+                "enter Running on Start",
+                "exit Running on Finish",
+                "enter Done on Finish",
+                "exit Done on Reset",
+                "enter Idle on Reset",
+            ]
+        );
+    }
+
+    #[test]
+    fn state_machine_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/state_machine_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "units"))]
+mod units_tests {
+    use super::*;
+
+    units!(
+        pub Length {
+            Meters = 1.0,
+            /// International feet.
+            Feet = 0.3048,
+            Kilometers = 1000,
+        }
+    );
+
+    units!(
+        /// Masses, in single precision.
+        Mass: f32 {
+            Grams = 1.0,
+            Kilograms = 1000.0,
+        }
+    );
+
+    fn total_meters<L: Length>(lengths: &[L]) -> Meters {
+        lengths
+            .iter()
+            .map(|length| length.convert::<Meters>())
+            .sum()
+    }
+
+    #[test]
+    fn test_units() {
+        let feet = Feet::from(Meters(3.048));
+        assert!((feet.0 - 10.0).abs() < 1e-9);
+        let meters: Meters = Kilometers(1.5).into();
+        assert_eq!(meters, Meters(1500.0));
+        assert_eq!(Kilometers::from(Meters(250.0)), Kilometers(0.25));
+
+        let mut distance = Meters(1.0) + Meters(2.0) - Meters(0.5);
+        distance *= 2.0;
+        distance += Meters(1.0);
+        assert_eq!(distance, Meters(6.0));
+        assert_eq!(-distance / 3.0, Meters(-2.0));
+        assert_eq!(2.0 * Feet(1.5), Feet(3.0));
+        assert_eq!(Meters(6.0) / Meters(1.5), 4.0);
+        assert!(Meters(1.0) < Meters(2.0));
+
+        assert_eq!(
+            total_meters(&[Kilometers(1.0), Kilometers(0.5)]),
+            Meters(1500.0)
+        );
+        assert_eq!(Feet::FACTOR, 0.3048);
+
+        assert_eq!(Grams::from(Kilograms(2.0)), Grams(2000.0f32));
+    }
+}
+
+#[cfg(all(test, feature = "duration"))]
+mod duration_tests {
+    use super::*;
+    use core::time::Duration;
+
+    #[test]
+    fn test_duration() {
+        const TIMEOUT: Duration = duration!("1h 30m");
+        assert_eq!(TIMEOUT, Duration::from_secs(90 * 60));
+        assert_eq!(duration!(250ms), Duration::from_millis(250));
+        assert_eq!(duration!(1h 30m), TIMEOUT);
+        assert_eq!(duration!(1.5s), Duration::from_millis(1500));
+        assert_eq!(duration!("2d 3µs"), Duration::new(2 * 86400, 3000));
+    }
+
+    #[test]
+    fn duration_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/duration_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "byte_size"))]
+mod byte_size_tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_size() {
+        const BUFFER: usize = byte_size!("4 MiB");
+        assert_eq!(BUFFER, 4 * 1024 * 1024);
+        let buf = [0u8; byte_size!("1 KiB")];
+        assert_eq!(buf.len(), 1024);
+
+        let disk: u64 = byte_size!("1.5 TB" as u64);
+        assert_eq!(disk, 1_500_000_000_000);
+        assert_eq!(byte_size!("64 KiB" as u32), 65536u32);
+        assert_eq!(byte_size!("255" as u8), 255u8);
+    }
+
+    #[test]
+    fn byte_size_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/byte_size_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "color"))]
+mod color_tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Rgba(u8, u8, u8, u8);
+
+    impl Rgba {
+        const fn opaque(r: u8, g: u8, b: u8, _a: u8) -> Self {
+            Rgba(r, g, b, 255)
+        }
+    }
+
+    #[test]
+    fn test_color() {
+        const DODGER_BLUE: (u8, u8, u8, u8) = color!("#1e90ff");
+        assert_eq!(DODGER_BLUE, (0x1e, 0x90, 0xff, 0xff));
+        assert_eq!(color!("#f808"), (0xff, 0x88, 0x00, 0x88));
+        assert_eq!(color!("#1e90ff80", Rgba), Rgba(0x1e, 0x90, 0xff, 0x80));
+        const OPAQUE: Rgba = color!("#00000000", Rgba::opaque);
+        assert_eq!(OPAQUE, Rgba(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn color_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/color_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "as_ref_str"))]
+mod as_ref_str_tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(AsRefStr)]
+    #[as_str(rename_all = "snake_case")]
+    enum Event<T> {
+        KeyDown(T),
+        #[as_str(rename = "click")]
+        MouseClick {
+            x: i32,
+            y: i32,
+        },
+        Resize,
+    }
+
+    #[test]
+    fn test_as_ref_str() {
+        const KEY: &str = Event::KeyDown(13).as_str();
+        assert_eq!(KEY, "key_down");
+        let click: Event<u8> = Event::MouseClick { x: 1, y: 2 };
+        assert_eq!(click.as_ref(), "click");
+        let name: &'static str = (&click).into();
+        assert_eq!(name, "click");
+        let name: &'static str = Event::<u8>::Resize.into();
+        assert_eq!(name, "resize");
+    }
+}
+
+#[cfg(all(test, feature = "into_iterator"))]
+mod into_iterator_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[derive(IntoIterator)]
+    struct Names(Vec<String>);
+
+    #[derive(IntoIterator)]
+    #[into_iterator(field = items)]
+    struct Cart<T> {
+        id: u32,
+        items: Vec<T>,
+    }
+
+    #[derive(IntoIterator)]
+    #[into_iterator(owned, ref)]
+    struct Tags {
+        tags: HashSet<&'static str>,
+    }
+
+    #[test]
+    fn test_into_iterator() {
+        let names = Names(vec!["a".to_string(), "b".to_string()]);
+        let lengths: Vec<usize> = (&names).into_iter().map(|name| name.len()).collect();
+        assert_eq!(lengths, [1, 1]);
+        let owned: Vec<String> = names.into_iter().collect();
+        assert_eq!(owned, ["a", "b"]);
+
+        let mut cart = Cart {
+            id: 7,
+            items: vec![1, 2, 3],
+        };
+        for item in &mut cart {
+            *item *= 10;
+        }
+        assert_eq!(cart.id, 7);
+        assert_eq!((&cart).into_iter().sum::<i32>(), 60);
+
+        let tags = Tags {
+            tags: ["x"].iter().copied().collect(),
+        };
+        assert_eq!((&tags).into_iter().count(), 1);
+        assert_eq!(tags.into_iter().next(), Some("x"));
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod json_tests {
+    use super::*;
+
+    const CONFIG: Json = json!(
+        r#"{
+            "name": "abc",
+            "retries": 3,
+            "verbose": false,
+            "tags": ["fast", null, -0.5],
+            "limits": {"depth": 10}
+        }"#
+    );
+
+    #[test]
+    fn test_json() {
+        assert_eq!(CONFIG.get("name").and_then(Json::as_str), Some("abc"));
+        assert_eq!(CONFIG.get("retries").and_then(Json::as_f64), Some(3.0));
+        assert_eq!(CONFIG.get("verbose").and_then(Json::as_bool), Some(false));
+        assert_eq!(CONFIG.get("missing"), None);
+        let depth = CONFIG.get("limits").and_then(|limits| limits.get("depth"));
+        assert_eq!(depth, Some(&Json::Number(10.0)));
+
+        let tags = CONFIG.get("tags").and_then(Json::as_array).unwrap();
+        assert_eq!(tags, [Json::String("fast"), Json::Null, Json::Number(-0.5)]);
+        assert!(tags[1].is_null());
+
+        // Entries are sorted by key.
+        let keys: Vec<&str> = CONFIG
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| *key)
+            .collect();
+        assert_eq!(keys, ["limits", "name", "retries", "tags", "verbose"]);
+
+        let value = json!("[]");
+        assert_eq!(value.as_array(), Some(&[][..]));
+    }
+
+    #[test]
+    fn json_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/json_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "base64"))]
+mod base64_tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        const HELLO: [u8; 5] = base64!("aGVsbG8=");
+        assert_eq!(&HELLO, b"hello");
+        assert_eq!(base64!("AAEC/w=="), [0, 1, 2, 255]);
+        assert_eq!(base64!("AAEC_w"), [0, 1, 2, 255]);
+        let empty: [u8; 0] = base64!("");
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn base64_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/base64_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "singleton"))]
+mod singleton_tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    pub struct Config {
+        name: String,
+    }
+
+    #[singleton]
+    pub fn load_config() -> Config {
+        CREATED.fetch_add(1, Ordering::SeqCst);
+        Config {
+            name: "abc".to_string(),
+        }
+    }
+
+    struct Registry(Vec<&'static str>);
+
+    #[singleton]
+    static REGISTRY: Registry = Registry(vec!["a", "b"]);
+
+    #[derive(Debug)]
+    struct Connection(u32);
+
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    #[singleton(try)]
+    fn connect() -> Result<Connection, String> {
+        match ATTEMPTS.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("refused".to_string()),
+            n => Ok(Connection(n as u32)),
+        }
+    }
+
+    #[test]
+    fn test_singleton() {
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| Config::instance() as *const Config as usize))
+            .collect();
+        let addresses: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert!(addresses.iter().all(|&address| address == addresses[0]));
+        assert_eq!(Config::instance().name, "abc");
+        assert_eq!(CREATED.load(Ordering::SeqCst), 1);
+
+        assert!(REGISTRY.get().is_none());
+        assert_eq!(Registry::instance().0, ["a", "b"]);
+        assert!(REGISTRY.get().is_some());
+
+        assert_eq!(Connection::try_instance().unwrap_err(), "refused");
+        assert_eq!(Connection::try_instance().unwrap().0, 1);
+        assert_eq!(Connection::try_instance().unwrap().0, 1);
+        assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+    }
+
+    // What the expansions use instead of `OnceLock` for an older
+    // `rust-version`.
+    #[test]
+    fn once_lock_stand_in() {
+        static CELL: __private::OnceLock<String> = __private::OnceLock::new();
+        assert!(CELL.get().is_none());
+        assert_eq!(CELL.get_or_init(|| "first".to_string()), "first");
+        assert_eq!(CELL.get_or_init(|| "second".to_string()), "first");
+        assert_eq!(CELL.get().map(String::as_str), Some("first"));
+
+        let cell = __private::OnceLock::new();
+        let value = std::sync::Arc::new(());
+        cell.get_or_init(|| value.clone());
+        drop(cell);
+        assert_eq!(std::sync::Arc::strong_count(&value), 1);
+    }
+}
+
+#[cfg(all(test, feature = "log_errors"))]
+mod log_errors_tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::fmt::Debug;
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn report(name: &'static str, args: &str, error: &dyn Debug) {
+        LOG.with(|log| {
+            log.borrow_mut()
+                .push(format!("{}({}): {:?}", name, args, error))
+        });
+    }
+
+    fn take_log() -> Vec<String> {
+        LOG.with(|log| log.borrow_mut().drain(..).collect())
+    }
+
+    #[log_errors(sink = "report")]
+    fn parse(text: &str) -> Result<u8, std::num::ParseIntError> {
+        let value = text.parse()?;
+        Ok(value)
+    }
+
+    struct Secret;
+
+    #[log_errors(args, sink = "report")]
+    fn check(limit: u8, _secret: Secret, value: u8) -> Result<u8, String> {
+        if value > limit {
+            return Err(format!("{} is over {}", value, limit));
+        }
+        Ok(value)
+    }
+
+    #[log_errors(args, sink = "report")]
+    async fn fetch(id: u32) -> Result<(), &'static str> {
+        if id == 0 {
+            Err("no such id")
+        } else {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_log_errors() {
+        assert_eq!(parse("12"), Ok(12));
+        assert!(take_log().is_empty());
+        assert!(parse("x").is_err());
+        assert_eq!(
+            take_log(),
+            ["parse(): ParseIntError { kind: InvalidDigit }"]
+        );
+
+        assert_eq!(check(5, Secret, 3), Ok(3));
+        assert!(check(5, Secret, 9).is_err());
+        assert_eq!(
+            take_log(),
+            ["check(limit = 5, _secret = .., value = 9): \"9 is over 5\""]
+        );
+
+        let block_on = crate::test_util::block_on;
+        assert_eq!(block_on(fetch(1)), Ok(()));
+        assert_eq!(block_on(fetch(0)), Err("no such id"));
+        assert_eq!(take_log(), ["fetch(id = 0): \"no such id\""]);
+    }
 }
 
-#[cfg(test)]
-mod describe_tests {
+#[cfg(all(test, feature = "deref"))]
+mod deref_tests {
     use super::*;
 
-    #[derive(DescribeStruct)]
-    struct Foo;
+    #[derive(Deref, DerefMut)]
+    struct Name(String);
+
+    #[derive(Deref, DerefMut)]
+    struct Tagged<T> {
+        tag: &'static str,
+        #[deref]
+        value: T,
+    }
 
     #[test]
-    fn test_struct_name() {
-        assert_eq!(Foo.struct_name(), "Foo");
+    fn test_deref() {
+        let mut name = Name("abc".to_string());
+        assert_eq!(name.len(), 3);
+        name.push('d');
+        assert_eq!(&*name, "abcd");
+
+        let mut tagged = Tagged {
+            tag: "numbers",
+            value: vec![1, 2],
+        };
+        tagged.push(3);
+        assert_eq!(tagged.iter().sum::<i32>(), 6);
+        assert_eq!(tagged.tag, "numbers");
+    }
+}
+
+#[cfg(all(test, feature = "from"))]
+mod from_tests {
+    use super::*;
+    use std::num::ParseIntError;
+
+    #[derive(From, Debug, PartialEq)]
+    struct UserId(u32);
+
+    #[derive(From, Debug)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    #[allow(dead_code)]
+    #[derive(From, Debug)]
+    enum Error {
+        Io(std::io::Error),
+        Parse {
+            source: ParseIntError,
+        },
+        #[from(skip)]
+        Exit(i32),
+        Message(&'static str),
+        Unknown,
+    }
+
+    fn parse(text: &str) -> Result<u32, Error> {
+        Ok(text.parse()?)
     }
 
-    // Uncomment this to work on the graceful error.
-    /*
     #[test]
-    fn describe_fail() {
+    fn test_from() {
+        assert_eq!(UserId::from(7), UserId(7));
+        let id: UserId = 8.into();
+        assert_eq!(id, UserId(8));
+        assert_eq!(Wrapper::from("x").inner, "x");
+
+        assert!(matches!(parse("x"), Err(Error::Parse { .. })));
+        let error: Error = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert!(matches!(error, Error::Io(_)));
+        assert!(matches!(Error::from("oops"), Error::Message("oops")));
+    }
+}
+
+#[cfg(all(test, feature = "error"))]
+mod error_tests {
+    use super::*;
+    use std::error::Error as _;
+    use std::num::ParseIntError;
+
+    #[derive(Error, Debug)]
+    enum ConfigError {
+        #[error("can't read the config file")]
+        Io(#[from] std::io::Error),
+        #[error("line {line}: bad number")]
+        Parse {
+            line: u32,
+            #[source]
+            cause: ParseIntError,
+        },
+        #[error("missing key `{0}`")]
+        Missing(String),
+        #[error("plugin failed")]
+        Plugin {
+            source: Box<dyn std::error::Error + Send + Sync>,
+        },
+    }
+
+    #[derive(Error, Debug)]
+    #[error("request {id} failed")]
+    struct RequestError {
+        id: u32,
+        #[source]
+        cause: ConfigError,
+    }
+
+    fn parse(line: u32, text: &str) -> Result<u32, ConfigError> {
+        text.parse()
+            .map_err(|cause| ConfigError::Parse { line, cause })
+    }
+
+    #[test]
+    fn test_error() {
+        let error = parse(3, "x").unwrap_err();
+        assert_eq!(error.to_string(), "line 3: bad number");
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "invalid digit found in string"
+        );
+
+        let error: ConfigError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert_eq!(error.to_string(), "can't read the config file");
+        assert!(error.source().unwrap().is::<std::io::Error>());
+
+        let error = ConfigError::Missing("name".to_string());
+        assert_eq!(error.to_string(), "missing key `name`");
+        assert!(error.source().is_none());
+
+        let error = ConfigError::Plugin {
+            source: "boom".into(),
+        };
+        assert_eq!(error.source().unwrap().to_string(), "boom");
+
+        let error = RequestError {
+            id: 9,
+            cause: ConfigError::Missing("x".to_string()),
+        };
+        assert_eq!(error.to_string(), "request 9 failed");
+        assert_eq!(error.source().unwrap().to_string(), "missing key `x`");
+    }
+}
+
+#[cfg(all(test, feature = "deprecated_alias"))]
+#[allow(deprecated)]
+mod deprecated_alias_tests {
+    use super::*;
+
+    #[deprecated_alias(add_all)]
+    pub fn sum<T: Copy + Into<u64>, const N: usize>(values: [T; N]) -> u64 {
+        values.iter().map(|&value| value.into()).sum()
+    }
+
+    #[deprecated_alias(greet_all, since = "0.2.0", note = "use `greet`")]
+    fn greet(names: &[impl AsRef<str>]) -> String {
+        let names: Vec<&str> = names.iter().map(|name| name.as_ref()).collect();
+        format!("hello, {}", names.join(" and "))
+    }
+
+    struct Counter(u32);
+
+    impl Counter {
+        #[deprecated_alias(create)]
+        fn new() -> Self {
+            Counter(0)
+        }
+
+        #[deprecated_alias(incr)]
+        fn increment(&mut self, (by, times): (u32, u32)) -> u32 {
+            self.0 += by * times;
+            self.0
+        }
+
+        #[deprecated_alias(fetch_old)]
+        async fn fetch(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_deprecated_alias() {
+        assert_eq!(add_all([1u8, 2, 3]), 6);
+        assert_eq!(greet_all(&["a", "b"]), "hello, a and b");
+
+        let mut counter = Counter::create();
+        assert_eq!(counter.incr((2, 3)), 6);
+        assert_eq!(counter.increment((1, 1)), 7);
+        assert_eq!(crate::test_util::block_on(counter.fetch_old()), 7);
+    }
+}
+
+#[cfg(all(test, feature = "static_map"))]
+mod static_map_tests {
+    use super::*;
+
+    const PORTS: StaticMap<&str, u16> = static_map!(&str => u16 {
+        "ssh" => 22,
+        "http" => 80,
+        "https" => 443,
+    });
+
+    static OFFSETS: StaticMap<i32, char> =
+        static_map!(i32 => char { 1 => 'b', -1 => 'z', 0 => 'a' });
+
+    #[test]
+    fn test_static_map() {
+        assert_eq!(PORTS.get("ssh"), Some(&22));
+        assert_eq!(PORTS.get("ftp"), None);
+        assert!(PORTS.contains_key("https"));
+        assert_eq!(PORTS.get_entry("http"), Some(&("http", 80)));
+        assert_eq!(PORTS.len(), 3);
+        assert!(!PORTS.is_empty());
+        // Entries are sorted by key.
+        assert_eq!(
+            PORTS.keys().collect::<Vec<_>>(),
+            [&"http", &"https", &"ssh"]
+        );
+        assert_eq!(PORTS.values().copied().sum::<u16>(), 545);
+        assert_eq!(
+            format!("{:?}", PORTS),
+            r#"{"http": 80, "https": 443, "ssh": 22}"#
+        );
+
+        let key = String::from("https");
+        assert_eq!(PORTS.get(key.as_str()), Some(&443));
+
+        assert_eq!(OFFSETS.get(&-1), Some(&'z'));
+        let chars: String = OFFSETS.into_iter().map(|(_, c)| c).collect();
+        assert_eq!(chars, "zab");
+
+        let empty: StaticMap<u8, u8> = static_map!(u8 => u8 {});
+        assert!(empty.is_empty());
+        assert_eq!(empty.get(&0), None);
+    }
+
+    #[test]
+    fn static_map_fail() {
         let t = trybuild::TestCases::new();
-        t.compile_fail("tests/build_failures/describe_fail.rs");
+        t.compile_fail("tests/build_failures/static_map_fail.rs");
+    }
+}
+
+#[cfg(all(test, feature = "cmp"))]
+mod cmp_tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[derive(Debug, PartialEqSkip, HashSkip)]
+    struct Document {
+        text: String,
+        #[cmp(skip)]
+        reads: std::cell::Cell<u32>,
+    }
+
+    // The skipped fields are never read, since they're never compared.
+    #[allow(dead_code)]
+    #[derive(Debug, PartialEqSkip, HashSkip)]
+    struct Tagged<T>(T, #[cmp(skip)] f64);
+
+    #[allow(dead_code)]
+    #[derive(Debug, PartialEqSkip, HashSkip)]
+    enum Shape {
+        Empty,
+        Circle {
+            radius: u32,
+            #[cmp(skip)]
+            area: f64,
+        },
+        Square(u32),
+    }
+
+    // `#[derive(Eq)]` would insist on `f64: Eq`.
+    impl Eq for Shape {}
+
+    #[test]
+    fn test_cmp_skip() {
+        let a = Document {
+            text: "hi".into(),
+            reads: 1.into(),
+        };
+        let b = Document {
+            text: "hi".into(),
+            reads: 2.into(),
+        };
+        assert_eq!(a, b);
+        assert_ne!(a.reads.get(), b.reads.get());
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_ne!(
+            a,
+            Document {
+                text: "bye".into(),
+                reads: 1.into(),
+            }
+        );
+
+        assert_eq!(Tagged("x", 1.0), Tagged("x", 2.0));
+        assert_ne!(Tagged(1, 1.0), Tagged(2, 1.0));
+        assert_eq!(hash_of(&Tagged(7, 0.5)), hash_of(&Tagged(7, 1.5)));
+
+        let circle = |radius, area| Shape::Circle { radius, area };
+        assert_eq!(circle(2, 12.5), circle(2, 12.6));
+        assert_ne!(circle(2, 12.5), circle(3, 12.5));
+        assert_ne!(Shape::Square(2), circle(2, 0.0));
+        assert_eq!(Shape::Empty, Shape::Empty);
+        assert_ne!(hash_of(&Shape::Square(1)), hash_of(&circle(1, 0.0)));
+
+        let shapes: HashSet<Shape> = vec![circle(1, 3.1), circle(1, 3.2), Shape::Empty]
+            .into_iter()
+            .collect();
+        assert_eq!(shapes.len(), 2);
+    }
+}
+
+#[cfg(all(test, feature = "kv_config"))]
+mod kv_config_tests {
+    use super::*;
+    use std::time::Duration;
+
+    kv_config! {
+        #[derive(Clone, Debug, PartialEq)]
+        pub Config {
+            /// Where to connect.
+            host = "localhost",
+            port: u16 = 8080,
+            retries = 3,
+            offset = -1.5,
+            verbose = false,
+            tags = ["fast", "local"],
+            timeout: Duration = Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_kv_config() {
+        let config = Config::default();
+        assert_eq!(config.host, "localhost");
+        assert_eq!(config.port, 8080);
+        let retries: i32 = config.retries;
+        assert_eq!(retries, 3);
+        assert_eq!(config.offset, -1.5);
+        assert!(!config.verbose);
+        assert_eq!(config.tags, ["fast", "local"]);
+        assert_eq!(config.timeout, Duration::from_secs(5));
+
+        let verbose = Config {
+            verbose: true,
+            ..Config::default()
+        };
+        assert_ne!(verbose, config);
+        assert_eq!(verbose.port, config.clone().port);
+    }
+}
+
+#[cfg(all(test, feature = "variant_count"))]
+mod variant_count_tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(VariantCount)]
+    enum Message {
+        Ping,
+        Text(String),
+        Move {
+            x: i32,
+            y: i32,
+        },
+        #[cfg(test)]
+        Test,
+        #[cfg(not(test))]
+        NotTest,
+    }
+
+    #[derive(VariantCount)]
+    enum Never {}
+
+    #[test]
+    fn test_variant_count() {
+        assert_eq!(Message::VARIANT_COUNT, 4);
+        assert_eq!(Never::VARIANT_COUNT, 0);
+        let seen = [0u8; Message::VARIANT_COUNT];
+        assert_eq!(seen.len(), 4);
+    }
+}
+
+#[cfg(all(test, feature = "enum_discriminants"))]
+mod enum_discriminants_tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[allow(dead_code)]
+    #[derive(EnumDiscriminants)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { width: f64, height: f64 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(EnumDiscriminants)]
+    #[discriminants(name = "Tag", derive(PartialOrd, Ord))]
+    enum Token<'a, T> {
+        Word(&'a str),
+        Value(T),
+        #[cfg(not(test))]
+        Missing,
+    }
+
+    #[derive(EnumDiscriminants)]
+    enum Never {}
+
+    #[test]
+    fn test_enum_discriminants() {
+        assert_eq!(Shape::Empty.kind(), ShapeKind::Empty);
+        assert_eq!(Shape::Circle(1.0).kind(), ShapeKind::Circle);
+        let rect = Shape::Rect {
+            width: 1.0,
+            height: 2.0,
+        };
+        assert_eq!(ShapeKind::from(&rect), ShapeKind::Rect);
+        assert_eq!(format!("{:?}", rect.kind()), "Rect");
+
+        let kinds: HashSet<ShapeKind> = [Shape::Empty, Shape::Circle(1.0), Shape::Circle(2.0)]
+            .iter()
+            .map(Shape::kind)
+            .collect();
+        assert_eq!(kinds.len(), 2);
+
+        assert_eq!(Token::Word::<u8>("hi").kind(), Tag::Word);
+        assert!(Token::Word::<u8>("hi").kind() < Token::Value(1).kind());
+
+        fn never_kind(never: &Never) -> NeverKind {
+            never.kind()
+        }
+        let _ = never_kind;
+    }
+}
+
+#[cfg(all(test, feature = "must_use_all"))]
+mod must_use_all_tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Request {
+        headers: Vec<(String, String)>,
+        sent: bool,
+    }
+
+    #[must_use_all]
+    impl Request {
+        pub fn header(mut self, name: &str, value: &str) -> Self {
+            self.headers.push((name.into(), value.into()));
+            self
+        }
+
+        pub fn clear(&mut self) {
+            self.headers.clear();
+        }
+
+        #[allow_unused_result]
+        pub fn send(&mut self) -> &mut Self {
+            self.sent = true;
+            self
+        }
+    }
+
+    #[test]
+    fn test_must_use_all() {
+        let mut request = Request::default().header("a", "1").header("b", "2");
+        assert_eq!(request.headers.len(), 2);
+        // `send` opted out, so ignoring its result isn't a warning.
+        request.send();
+        assert!(request.sent);
+        request.clear();
+        assert!(request.send().headers.is_empty());
+    }
+}
+
+#[cfg(all(test, feature = "test_matrix"))]
+mod test_matrix_tests {
+    use super::*;
+
+    #[test_matrix(width = [1, 8, 64], text = ["", "hello world", "a b c"])]
+    fn wrap_fits(width: usize, text: &str) {
+        let mut lines = vec![String::new()];
+        for word in text.split(' ') {
+            let line = lines.last_mut().unwrap();
+            if !line.is_empty() && line.len() + 1 + word.len() > width {
+                lines.push(word.to_string());
+            } else {
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+            }
+        }
+        assert_eq!(lines.join(" "), text);
+    }
+
+    #[test_matrix(n = [-1, 0, 1])]
+    fn parses(mut n: i32) -> Result<(), std::num::ParseIntError> {
+        n *= 10;
+        assert_eq!(n.to_string().parse::<i32>()?, n);
+        Ok(())
+    }
+
+    #[test_matrix(divisor = [0])]
+    #[should_panic(expected = "divisor must be positive")]
+    fn divides(divisor: u32) {
+        assert!(divisor > 0, "divisor must be positive");
+    }
+
+    #[test]
+    fn test_matrix_names() {
+        // The generated tests can be called by name, too.
+        wrap_fits_width_8_text_0();
+        wrap_fits_width_64_text_1();
+        parses_n_neg1().unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "rustc_version"))]
+mod rustc_version_tests {
+    use super::*;
+
+    rustc_version_at_least!(1.0 {
+        const NEW_ENOUGH: bool = true;
+    } else {
+        const NEW_ENOUGH: bool = false;
+    });
+
+    rustc_version_at_least!("999.0.0" {
+        compile_error!("this isn't compiled");
+    });
+
+    #[test]
+    fn test_rustc_version_at_least() {
+        const { assert!(rustc_version_at_least!(1.31)) };
+        const { assert!(rustc_version_at_least!("1.31.0")) };
+        const { assert!(!rustc_version_at_least!(999.0)) };
+        const { assert!(NEW_ENOUGH) };
+
+        let newer = rustc_version_at_least!(1.0 { "newer" } else { "older" });
+        assert_eq!(newer, "newer");
+    }
+}
+
+#[cfg(all(test, feature = "soa"))]
+mod soa_tests {
+    use super::*;
+
+    soa! {
+        #[derive(Clone, Debug, PartialEq)]
+        #[soa(derive(Clone, Debug, PartialEq))]
+        pub struct Particle {
+            /// Position.
+            pub x: f32,
+            pub y: f32,
+            pub(crate) name: String,
+        }
+    }
+
+    fn particle(x: f32, name: &str) -> Particle {
+        Particle {
+            x,
+            y: -x,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn test_soa() {
+        let mut particles = ParticleVec::with_capacity(2);
+        assert!(particles.is_empty());
+        particles.push(particle(1.0, "a"));
+        particles.push(particle(2.0, "b"));
+        assert_eq!(particles.len(), 2);
+        assert_eq!(particles.x(), [1.0, 2.0]);
+        assert_eq!(particles.y(), [-1.0, -2.0]);
+
+        for x in particles.x_mut() {
+            *x *= 10.0;
+        }
+        let first = particles.get(0).unwrap();
+        assert_eq!((*first.x, first.name.as_str()), (10.0, "a"));
+        assert!(particles.get(2).is_none());
+
+        let names: Vec<&str> = particles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(
+            format!("{:?}", particles.get(1).unwrap()),
+            r#"ParticleRef { x: 20.0, y: -2.0, name: "b" }"#
+        );
+
+        let copy = particles.clone();
+        let mut last = particle(20.0, "b");
+        last.y = -2.0;
+        assert_eq!(particles.pop(), Some(last));
+        assert_ne!(copy, particles);
+        particles.clear();
+        assert_eq!(particles.pop(), None);
+
+        let collected: ParticleVec = vec![particle(1.0, "x"), particle(2.0, "y")]
+            .into_iter()
+            .collect();
+        assert_eq!(collected.name(), ["x", "y"]);
+        assert!(ParticleVec::default().is_empty());
+    }
+}
+
+#[cfg(all(test, feature = "delegate"))]
+mod delegate_tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct Stack<T> {
+        items: Vec<T>,
+        log: RefCell<Vec<String>>,
+    }
+
+    impl<T> Stack<T> {
+        delegate! {
+            to self.items {
+                pub fn len(&self) -> usize;
+                pub fn push(&mut self, item: T);
+                /// The item on top.
+                #[call(last)]
+                pub fn peek(&self) -> Option<&T>;
+                pub fn get<I: std::slice::SliceIndex<[T]>>(&self, index: I) -> Option<&I::Output>;
+            }
+            to self.log.borrow_mut() {
+                fn push_str(&self, (prefix, line): (&str, &str));
+            }
+        }
+    }
+
+    trait PushStr {
+        fn push_str(&mut self, entry: (&str, &str));
+    }
+
+    impl PushStr for Vec<String> {
+        fn push_str(&mut self, (prefix, line): (&str, &str)) {
+            self.push(format!("{}{}", prefix, line));
+        }
+    }
+
+    struct Counter(std::sync::atomic::AtomicU32);
+
+    impl Counter {
+        delegate! {
+            to self.0 {
+                pub unsafe fn as_ptr(&self) -> *mut u32;
+            }
+        }
+    }
+
+    #[test]
+    fn test_delegate() {
+        let mut stack = Stack {
+            items: Vec::new(),
+            log: RefCell::new(Vec::new()),
+        };
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.peek(), Some(&2));
+        assert_eq!(stack.get(0), Some(&1));
+        assert_eq!(stack.get(..), Some(&[1, 2][..]));
+        stack.push_str(("> ", "hi"));
+        assert_eq!(*stack.log.borrow(), ["> hi"]);
+
+        let counter = Counter(7.into());
+        assert_eq!(unsafe { *counter.as_ptr() }, 7);
+    }
+}
+
+#[cfg(all(test, feature = "mock_trait"))]
+mod mock_trait_tests {
+    use super::*;
+
+    pub trait Store {
+        fn get(&self, key: &str) -> Option<String>;
+        fn put(&mut self, key: &str, value: String);
+        fn first<'a>(&self, keys: &'a [String]) -> &'a str;
+    }
+
+    mock_trait! {
+        pub trait Store {
+            fn get(&self, key: &str) -> Option<String>;
+            fn put(&mut self, key: &str, value: String);
+            fn first<'a>(&self, keys: &'a [String]) -> &'a str;
+        }
+    }
+
+    struct NoDebug;
+
+    trait Sink {
+        fn send(&self, item: NoDebug, retries: u8) -> bool;
+    }
+
+    mock_trait! {
+        trait Sink {
+            fn send(&self, item: NoDebug, retries: u8) -> bool;
+        }
+    }
+
+    /// Code under test only sees the trait.
+    fn copy(store: &mut impl Store, from: &str, to: &str) -> bool {
+        match store.get(from) {
+            Some(value) => {
+                store.put(to, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn mock_trait() {
+        let mut store = MockStore::new();
+        store.on_get(|key| (key == "a").then(|| String::from("x")));
+        store.on_put(|_, _| ()).on_first(|keys| &keys[0]);
+
+        assert!(copy(&mut store, "a", "b"));
+        assert!(!copy(&mut store, "c", "d"));
+        assert_eq!(store.get.calls(), [r#"get("a")"#, r#"get("c")"#]);
+        assert_eq!(store.put.calls(), [r#"put("b", "x")"#]);
+        assert_eq!(store.first(&[String::from("k")]), "k");
+        assert_eq!(store.first.count(), 1);
+
+        // A new answer replaces the old one.
+        let mut calls = 0;
+        store.on_get(move |_| {
+            calls += 1;
+            Some(calls.to_string())
+        });
+        assert_eq!(store.get("z").as_deref(), Some("1"));
+        assert_eq!(store.get("z").as_deref(), Some("2"));
+        store.get.clear();
+        assert_eq!(store.get.count(), 0);
+
+        // Arguments that aren't `Debug` are still recorded.
+        let sink = MockSink::new();
+        sink.on_send(|_, retries| retries > 0);
+        assert!(sink.send(NoDebug, 3));
+        assert_eq!(sink.send.calls(), ["send(.., 3)"]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "MockStore::get was called, but it has no answer; set one with `on_get`"
+    )]
+    fn mock_trait_unanswered() {
+        MockStore::new().get("a");
+    }
+}
+
+#[cfg(all(test, feature = "typestate"))]
+mod typestate_tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Method {
+        Get,
+        Post,
+    }
+
+    typestate! {
+        #[typestate(required(url, method))]
+        #[derive(Debug, PartialEq)]
+        pub struct Request {
+            /// Where to send the request.
+            url: String,
+            method: Method,
+            retries: u8,
+            tags: Vec<&'static str>,
+        }
+    }
+
+    #[test]
+    fn typestate() {
+        let request = Request::builder()
+            .url("/".to_string())
+            .retries(3)
+            .method(Method::Post)
+            .build();
+        assert_eq!(
+            request,
+            Request {
+                url: "/".to_string(),
+                method: Method::Post,
+                retries: 3,
+                tags: Vec::new(),
+            }
+        );
+
+        // The required fields can be set in any order, and the builder's
+        // state can be named.
+        let builder: RequestBuilder<Set, Unset> = Request::builder().url("/a".to_string());
+        let request = builder.tags(vec!["x"]).method(Method::Get).build();
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.retries, 0);
+        assert_eq!(request.tags, ["x"]);
+    }
+
+    #[test]
+    fn typestate_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/typestate_fail.rs");
+    }
+}
+
+// The macros' output doesn't rely on the prelude, or on `std` being in
+// scope, so it builds in a `#![no_std]` crate. With the default features,
+// `abc` links `std`; to check that the other macros don't need it, run
+// this with `--no-default-features` and the features of the macros that
+// don't turn on `std`.
+#[cfg(test)]
+mod no_std_tests {
+    #[test]
+    fn no_std() {
+        let t = trybuild::TestCases::new();
+        t.pass("tests/no_std/macros.rs");
     }
-    */
 }
 
 // Uncomment this to work on the file_words! macro.
 /*
-#[cfg(test)]
+#[cfg(all(test, feature = "file_words"))]
 mod words_tests {
     use super::*;
 
@@ -42,7 +3904,7 @@ mod words_tests {
 
 // Uncomment this to work on the enum_ranges! macro.
 /*
-#[cfg(test)]
+#[cfg(all(test, feature = "enum_ranges"))]
 mod enum_ranges_tests {
     use super::*;
     use std::convert::TryFrom;
@@ -67,3 +3929,14 @@ mod enum_ranges_tests {
     }
 }
 */
+
+// The input is checked by the parser, so its errors can be tested before
+// the expansion is written.
+#[cfg(all(test, feature = "enum_ranges"))]
+mod enum_ranges_fail_tests {
+    #[test]
+    fn enum_ranges_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/build_failures/enum_ranges_fail.rs");
+    }
+}
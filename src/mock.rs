@@ -0,0 +1,56 @@
+use std::boxed::Box;
+use std::fmt;
+use std::string::String;
+use std::sync::{Mutex, MutexGuard};
+use std::vec::Vec;
+
+/// One method of a mock generated by `mock_trait!`: the closure that
+/// answers its calls, and a record of the calls so far.
+pub struct MockFn<F: ?Sized> {
+    pub(crate) answer: Mutex<Option<Box<F>>>,
+    pub(crate) calls: Mutex<Vec<String>>,
+}
+
+/// Lock a mutex, even if a panicking test poisoned it.
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl<F: ?Sized> MockFn<F> {
+    /// The calls so far, in order, like `get("key")`.
+    ///
+    /// Each argument is shown with `Debug`, or as `..` if its type
+    /// doesn't implement it.
+    pub fn calls(&self) -> Vec<String> {
+        lock(&self.calls).clone()
+    }
+
+    /// The number of calls so far.
+    pub fn count(&self) -> usize {
+        lock(&self.calls).len()
+    }
+
+    /// Forget the calls so far.
+    pub fn clear(&self) {
+        lock(&self.calls).clear();
+    }
+}
+
+impl<F: ?Sized> Default for MockFn<F> {
+    fn default() -> Self {
+        MockFn {
+            answer: Mutex::new(None),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for MockFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockFn")
+            .field("calls", &self.calls())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,143 @@
+//! The traits and macros, for a glob import.
+//!
+//! ```
+//! # #[cfg(feature = "describe")]
+//! # fn main() {
+//! use abc::prelude::*;
+//!
+//! #[derive(DescribeStruct)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! assert_eq!(Point { x: 1, y: 2 }.field_count(), 2);
+//! # }
+//! # #[cfg(not(feature = "describe"))]
+//! # fn main() {}
+//! ```
+//!
+//! Some of the derives are named after the trait they implement, like
+//! `Display` and `FromStr`. They're only macros, so they don't hide the
+//! traits of the same name.
+
+// `DescribeStruct` is the trait and, with the `describe` feature, its
+// derive.
+#[cfg(feature = "alloc")]
+pub use crate::DescribeStruct;
+pub use crate::DescribeVisitor;
+
+#[cfg(feature = "base64")]
+pub use crate::base64;
+#[cfg(feature = "bitfield")]
+pub use crate::bitfield;
+#[cfg(feature = "byte_size")]
+pub use crate::byte_size;
+#[cfg(feature = "color")]
+pub use crate::color;
+#[cfg(feature = "compile_time")]
+pub use crate::compile_time;
+#[cfg(feature = "cstr")]
+pub use crate::cstr;
+#[cfg(feature = "delegate")]
+pub use crate::delegate;
+#[cfg(feature = "deprecated_alias")]
+pub use crate::deprecated_alias;
+#[cfg(feature = "describe_all")]
+pub use crate::describe_all;
+#[cfg(feature = "duration")]
+pub use crate::duration;
+#[cfg(feature = "enum_bitmask")]
+pub use crate::enum_bitmask;
+#[cfg(feature = "enum_ranges")]
+pub use crate::enum_ranges;
+#[cfg(feature = "enum_strings")]
+pub use crate::enum_strings;
+#[cfg(feature = "env_or")]
+pub use crate::env_or;
+#[cfg(feature = "file_words")]
+pub use crate::file_words;
+#[cfg(feature = "hex")]
+pub use crate::hex;
+#[cfg(feature = "json")]
+pub use crate::json;
+#[cfg(feature = "kv_config")]
+pub use crate::kv_config;
+#[cfg(feature = "log_errors")]
+pub use crate::log_errors;
+#[cfg(feature = "make_ident")]
+pub use crate::make_ident;
+#[cfg(feature = "mock_trait")]
+pub use crate::mock_trait;
+#[cfg(feature = "must_use_all")]
+pub use crate::must_use_all;
+#[cfg(feature = "newtype")]
+pub use crate::newtype;
+#[cfg(feature = "regex")]
+pub use crate::regex;
+#[cfg(feature = "retry")]
+pub use crate::retry;
+#[cfg(feature = "rustc_version")]
+pub use crate::rustc_version_at_least;
+#[cfg(feature = "semver_macro")]
+pub use crate::semver;
+#[cfg(feature = "singleton")]
+pub use crate::singleton;
+#[cfg(feature = "soa")]
+pub use crate::soa;
+#[cfg(feature = "sorted")]
+pub use crate::sorted;
+#[cfg(feature = "state_machine")]
+pub use crate::state_machine;
+#[cfg(feature = "static_assert")]
+pub use crate::static_assert;
+#[cfg(feature = "static_map")]
+pub use crate::static_map;
+#[cfg(feature = "table")]
+pub use crate::table;
+#[cfg(feature = "test_matrix")]
+pub use crate::test_matrix;
+#[cfg(feature = "timed")]
+pub use crate::timed;
+#[cfg(feature = "trace")]
+pub use crate::trace;
+#[cfg(feature = "typestate")]
+pub use crate::typestate;
+#[cfg(feature = "units")]
+pub use crate::units;
+#[cfg(feature = "uuid_macro")]
+pub use crate::uuid;
+#[cfg(feature = "as_ref_str")]
+pub use crate::AsRefStr;
+#[cfg(feature = "builder")]
+pub use crate::Builder;
+#[cfg(feature = "default_with_values")]
+pub use crate::DefaultWithValues;
+#[cfg(feature = "display")]
+pub use crate::Display;
+#[cfg(feature = "enum_discriminants")]
+pub use crate::EnumDiscriminants;
+#[cfg(feature = "enum_iter")]
+pub use crate::EnumIter;
+#[cfg(feature = "error")]
+pub use crate::Error;
+#[cfg(feature = "from")]
+pub use crate::From;
+#[cfg(feature = "from_str")]
+pub use crate::FromStr;
+#[cfg(feature = "into_iterator")]
+pub use crate::IntoIterator;
+#[cfg(feature = "try_from_primitive")]
+pub use crate::TryFromPrimitive;
+#[cfg(feature = "variant_count")]
+pub use crate::VariantCount;
+#[cfg(feature = "build_info")]
+pub use crate::{build_info, git_version};
+#[cfg(feature = "net")]
+pub use crate::{ip, sockaddr};
+#[cfg(feature = "deref")]
+pub use crate::{Deref, DerefMut};
+#[cfg(feature = "getset")]
+pub use crate::{Getters, Setters};
+#[cfg(feature = "cmp")]
+pub use crate::{HashSkip, PartialEqSkip};
@@ -0,0 +1,39 @@
+//! A runtime registry of every type that derives `DescribeStruct`.
+//!
+//! Each derive submits a [`TypeInfo`] at compile time, using the
+//! [`inventory`] crate. The entries are collected when the program starts,
+//! so they can be enumerated or looked up by name.
+//!
+//! Generic types aren't registered, because there's no single concrete
+//! type to describe.
+
+/// Information about a registered type.
+#[derive(Debug)]
+pub struct TypeInfo {
+    /// The reported name of the type, as from `DescribeStruct::struct_name`.
+    pub name: &'static str,
+    /// The fully qualified path of the type, as from `DescribeStruct::type_path`.
+    pub type_path: &'static str,
+    /// The type's doc comments, as from `DescribeStruct::description`.
+    pub description: &'static str,
+}
+
+inventory::collect!(TypeInfo);
+
+/// Iterate over every registered type, in no particular order.
+pub fn iter() -> impl Iterator<Item = &'static TypeInfo> {
+    inventory::iter::<TypeInfo>.into_iter()
+}
+
+/// Find a registered type by name.
+///
+/// If several types have the same name, one of them is returned; use
+/// [`lookup_path`] to pick a specific one.
+pub fn lookup(name: &str) -> Option<&'static TypeInfo> {
+    iter().find(|info| info.name == name)
+}
+
+/// Find a registered type by its fully qualified path.
+pub fn lookup_path(type_path: &str) -> Option<&'static TypeInfo> {
+    iter().find(|info| info.type_path == type_path)
+}
@@ -0,0 +1,98 @@
+use core::borrow::Borrow;
+use core::fmt;
+
+/// A map built at compile time by the `static_map!` macro.
+///
+/// The entries are a static slice sorted by key, so a `StaticMap` can be
+/// a `const`, and a lookup is a binary search.
+pub struct StaticMap<K: 'static, V: 'static> {
+    pub(crate) entries: &'static [(K, V)],
+}
+
+impl<K, V> StaticMap<K, V> {
+    /// The value for `key`, if there is one.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'static V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(key).map(|(_, value)| value)
+    }
+
+    /// The key and value for `key`, if there is one.
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<&'static (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let entries = self.entries;
+        entries
+            .binary_search_by(|(other, _)| other.borrow().cmp(key))
+            .ok()
+            .map(|index| &entries[index])
+    }
+
+    /// Whether there's a value for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(key).is_some()
+    }
+
+    /// The number of entries.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries, sorted by key.
+    pub const fn entries(&self) -> &'static [(K, V)] {
+        self.entries
+    }
+
+    /// Iterate over the keys and values, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static K, &'static V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Iterate over the keys, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &'static K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// Iterate over the values, in key order.
+    pub fn values(&self) -> impl Iterator<Item = &'static V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+// These aren't derived, because that would require `K` and `V` to be
+// `Clone` too.
+impl<K, V> Clone for StaticMap<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for StaticMap<K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for StaticMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> IntoIterator for StaticMap<K, V> {
+    type Item = &'static (K, V);
+    type IntoIter = core::slice::Iter<'static, (K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,7 @@
+/// A required field that the builder has a value for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Set;
+
+/// A required field that the builder doesn't have a value for yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Unset;
@@ -0,0 +1,12 @@
+use core::fmt::Debug;
+
+/// Receives the fields of a value, from [`DescribeStruct::visit_fields`].
+///
+/// Fields whose types don't implement `Debug` are still visited, but
+/// their value prints as `..`.
+///
+/// [`DescribeStruct::visit_fields`]: crate::DescribeStruct::visit_fields
+pub trait DescribeVisitor {
+    /// Called once for each field, in declaration order.
+    fn field(&mut self, name: &'static str, value: &dyn Debug);
+}
@@ -0,0 +1,6 @@
+use abc_macros::base64;
+
+fn main() {
+    let _ = base64!("aGV*bG8=");
+    let _ = base64!("aGk==");
+}
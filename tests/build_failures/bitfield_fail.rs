@@ -0,0 +1,12 @@
+use abc_macros::bitfield;
+
+// Every problem is reported, not just the first.
+bitfield!(Flags: u8 {
+    enable: 0,
+    mode: 0..2,
+    count: x,
+    level: 4..9,
+    high: 2-4,
+});
+
+fn main() {}
@@ -0,0 +1,10 @@
+use abc_macros::Builder;
+
+// A misspelled option gets a suggestion.
+#[derive(Builder)]
+struct Request {
+    #[builder(defualt)]
+    retries: u8,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use abc_macros::byte_size;
+
+fn main() {
+    let _ = byte_size!("4 Mib");
+    let _ = byte_size!("1 MiB" as u16);
+}
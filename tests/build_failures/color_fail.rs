@@ -0,0 +1,6 @@
+use abc_macros::color;
+
+fn main() {
+    let _ = color!("#1e90fg");
+    let _ = color!("#1e90f");
+}
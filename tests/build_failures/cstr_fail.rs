@@ -0,0 +1,5 @@
+use abc_macros::cstr;
+
+fn main() {
+    let _ = cstr!("nul\0inside");
+}
@@ -0,0 +1,7 @@
+use abc_macros::DescribeStruct;
+
+#[derive(DescribeStruct)]
+#[describe(deny_names("Temp", "Foo2"))]
+struct Temp;
+
+fn main() {}
@@ -0,0 +1,6 @@
+use abc_macros::duration;
+
+fn main() {
+    let _ = duration!("1h 30x");
+    let _ = duration!(1h 5kg);
+}
@@ -0,0 +1,14 @@
+use abc_macros::enum_ranges;
+
+// Every problem is reported, not just the first.
+enum_ranges!(Color {
+    Red: 620..750,
+    Blue: 450..495,
+    Violet: 380..451,
+    Green: x,
+    Red: 0,
+    Orange: 600..600,
+    Yellow: 570-590,
+});
+
+fn main() {}
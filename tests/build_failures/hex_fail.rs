@@ -0,0 +1,6 @@
+use abc_macros::hex;
+
+fn main() {
+    let _ = hex!("abc");
+    let _ = hex!("0x12");
+}
@@ -0,0 +1,6 @@
+use abc_macros::json;
+
+fn main() {
+    let _ = json!(r#"{"name": "abc", "tags": [1, 2,]}"#);
+    let _ = json!(r#"{"a": 1, "a": 2}"#);
+}
@@ -0,0 +1,7 @@
+use abc_macros::DescribeStruct;
+
+#[derive(DescribeStruct)]
+#[describe(require_name_matches = "Request$")]
+struct GetReply;
+
+fn main() {}
@@ -0,0 +1,5 @@
+use abc_macros::regex;
+
+fn main() {
+    let _ = regex!(r"^(\d{4}-\d{2}$");
+}
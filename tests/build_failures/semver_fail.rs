@@ -0,0 +1,6 @@
+use abc_macros::semver;
+
+fn main() {
+    let _ = semver!("1.2");
+    let _ = semver!("1.02.3");
+}
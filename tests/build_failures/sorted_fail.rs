@@ -0,0 +1,13 @@
+use abc_macros::sorted;
+
+#[sorted]
+enum Fruit {
+    Banana,
+    Cherry,
+    Apple,
+}
+
+#[sorted]
+const PORTS: &[u16] = &[22, 443, 80];
+
+fn main() {}
@@ -0,0 +1,12 @@
+use abc_macros::state_machine;
+
+state_machine! {
+    enum State { Idle, Running }
+    enum Event { Start, Stop }
+    transitions {
+        Idle + Start => Running,
+        Running + Stop => Stopped,
+    }
+}
+
+fn main() {}
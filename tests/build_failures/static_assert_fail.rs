@@ -0,0 +1,7 @@
+use abc_macros::static_assert;
+
+const BLOCK: usize = 24;
+
+static_assert!(BLOCK.is_power_of_two(), "BLOCK must be a power of two");
+
+fn main() {}
@@ -0,0 +1,6 @@
+use abc_macros::static_map;
+
+fn main() {
+    let _ = static_map!(&str => u32 { "a" => 1, "b" => 2, "a" => 3 });
+    let _ = static_map!(&str => u32 { "a" => 1, 'b' => 2 });
+}
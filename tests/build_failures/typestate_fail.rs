@@ -0,0 +1,19 @@
+use abc_macros::typestate;
+
+typestate! {
+    #[typestate(required(url, method))]
+    pub struct Request {
+        url: String,
+        method: String,
+        retries: u8,
+    }
+}
+
+fn main() {
+    // `method` is required.
+    let _ = Request::builder().url("/".to_string()).retries(3).build();
+    // A required field can only be set once.
+    let _ = Request::builder()
+        .url("/".to_string())
+        .url("/a".to_string());
+}
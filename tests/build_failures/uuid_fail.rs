@@ -0,0 +1,6 @@
+use abc_macros::uuid;
+
+fn main() {
+    let _ = uuid!("67e55044-10b1-426f-9247-bb680e5fe0c");
+    let _ = uuid!("67e55044-10b1-426f-9247-bb680e5fe0g8");
+}
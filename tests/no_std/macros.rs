@@ -0,0 +1,341 @@
+// Every macro's output should build in a `#![no_std]` crate, where `std`
+// isn't in scope, and in a module that shadows the prelude.
+//
+// Each macro is only used with its feature, so this also builds with the
+// features of the macros that don't need `std`, and `abc`'s `std` feature
+// off. Then nothing links `std` but the line below, which doesn't put it
+// in scope.
+#![no_std]
+
+// A binary needs `std` to run.
+extern crate std as _;
+
+mod shadowed {
+    #![allow(dead_code)]
+    // Names the expansions might otherwise pick up by accident.
+    type Result<T> = core::result::Result<T, ()>;
+    type Option = ();
+    struct Some;
+    struct None;
+    struct Ok;
+    struct Err;
+    struct Box;
+    struct Vec;
+    struct String;
+    trait Default {}
+    trait Clone {}
+    trait Iterator {}
+    trait Into {}
+    trait From {}
+
+    #[cfg(feature = "bitfield")]
+    abc::bitfield!(pub Flags: u16 { enable: 0, mode: 1..3 });
+
+    #[cfg(feature = "enum_bitmask")]
+    abc::enum_bitmask!(pub Permission: u8 => Permissions { Read: 1, Write: 2 });
+
+    #[cfg(feature = "enum_strings")]
+    abc::enum_strings!(pub FileKind { Readme: "README", Rust: "*.rs", Other: _ });
+
+    #[cfg(feature = "newtype")]
+    abc::newtype! {
+        #[derive(Debug, PartialEq)]
+        pub struct Meters(pub f64);
+        derive(Add, Display, From)
+    }
+
+    #[cfg(feature = "units")]
+    abc::units!(pub Length { Metres = 1.0, Feet = 0.3048 });
+
+    #[cfg(feature = "table")]
+    abc::table! {
+        pub Country { #[key] code: &str, name: &str }
+        ["US", "United States"],
+        ["DE", "Germany"],
+    }
+
+    #[cfg(feature = "kv_config")]
+    abc::kv_config! {
+        pub Config {
+            host = "localhost",
+            port: u16 = 8080,
+        }
+    }
+
+    #[cfg(feature = "state_machine")]
+    abc::state_machine! {
+        pub enum State { Idle, Running }
+        pub enum Event { Start, Stop }
+        transitions {
+            Idle + Start => Running,
+            Running + Stop => Idle,
+        }
+    }
+
+    #[cfg(feature = "soa")]
+    abc::soa! {
+        pub struct Particle {
+            pub x: f32,
+        }
+    }
+
+    #[cfg(feature = "typestate")]
+    abc::typestate! {
+        #[typestate(required(url))]
+        pub struct Request {
+            pub url: &'static str,
+            retries: u8,
+        }
+    }
+
+    pub trait Store {
+        fn get(&self, key: u8) -> u8;
+    }
+
+    #[cfg(feature = "mock_trait")]
+    abc::mock_trait! {
+        pub trait Store {
+            fn get(&self, key: u8) -> u8;
+        }
+    }
+
+    #[cfg(feature = "cstr")]
+    pub const GREETING: &core::ffi::CStr = abc::cstr!("hello");
+    #[cfg(feature = "hex")]
+    pub const KEY: [u8; 2] = abc::hex!("beef");
+    #[cfg(feature = "net")]
+    pub const GATEWAY: core::net::Ipv4Addr = abc::ip!("10.0.0.1");
+    #[cfg(feature = "net")]
+    pub const LISTEN: core::net::SocketAddr = abc::sockaddr!("[::1]:8080");
+    #[cfg(feature = "duration")]
+    pub const TIMEOUT: core::time::Duration = abc::duration!("1m 30s");
+    #[cfg(feature = "byte_size")]
+    pub const BUFFER: usize = abc::byte_size!("4 KiB");
+    #[cfg(feature = "color")]
+    pub const BLUE: (u8, u8, u8, u8) = abc::color!("#1e90ff");
+    #[cfg(feature = "static_map")]
+    pub const PORTS: abc::StaticMap<&str, u16> = abc::static_map!(&str => u16 { "ssh" => 22 });
+    #[cfg(feature = "json")]
+    pub const DOCUMENT: abc::Json = abc::json!(r#"{"tags": ["a", null]}"#);
+    #[cfg(all(feature = "static_assert", feature = "byte_size"))]
+    abc::static_assert!(BUFFER == 4096);
+    #[cfg(feature = "base64")]
+    pub const HELLO: [u8; 5] = abc::base64!("aGVsbG8=");
+    #[cfg(feature = "env_or")]
+    pub const PORT: u16 = abc::env_or!("ABC_NO_STD_TEST_PORT", 8080u16);
+    // With the `semver` and `uuid` features, these expand to the crates' types.
+    #[cfg(all(feature = "semver_macro", not(feature = "semver")))]
+    pub const VERSION: (u64, u64, u64, &str) = abc::semver!("1.2.3-beta.1");
+    #[cfg(feature = "semver")]
+    pub fn major() -> u64 {
+        abc::semver!("1.2.3-beta.1").major
+    }
+    #[cfg(all(feature = "uuid_macro", not(feature = "uuid")))]
+    pub const ID: [u8; 16] = abc::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8");
+    #[cfg(feature = "uuid")]
+    pub const ID: [u8; 16] = *abc::uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8").as_bytes();
+    #[cfg(feature = "compile_time")]
+    pub const BUILT: &str = abc::compile_time!("%Y-%m-%d");
+    #[cfg(feature = "build_info")]
+    pub const GIT_VERSION: &str = abc::git_version!();
+    #[cfg(feature = "build_info")]
+    abc::build_info!(pub);
+    #[cfg(feature = "rustc_version")]
+    pub const RECENT: bool = abc::rustc_version_at_least!(1.0 { true } else { false });
+    #[cfg(feature = "regex")]
+    pub fn is_date(text: &str) -> bool {
+        abc::regex!(r"^\d{4}-\d{2}-\d{2}$").is_match(text)
+    }
+
+    #[cfg(feature = "make_ident")]
+    abc::make_ident! {
+        pub const [<MAX_ retries:upper>]: u8 = 5;
+    }
+
+    #[cfg(feature = "sorted")]
+    #[abc::sorted]
+    pub const ALIASES: &[(&str, &str)] = &[("co", "checkout"), ("st", "status")];
+
+    #[cfg(feature = "describe_all")]
+    #[abc::describe_all]
+    pub mod messages {
+        pub struct Ping;
+    }
+
+    #[cfg_attr(feature = "builder", derive(abc::Builder))]
+    #[cfg_attr(feature = "describe", derive(abc::DescribeStruct))]
+    #[cfg_attr(feature = "default_with_values", derive(abc::DefaultWithValues))]
+    #[cfg_attr(feature = "getset", derive(abc::Getters, abc::Setters))]
+    pub struct Settings {
+        #[cfg_attr(feature = "default_with_values", default(3))]
+        retries: u8,
+        verbose: bool,
+    }
+
+    #[cfg_attr(feature = "deref", derive(abc::Deref, abc::DerefMut))]
+    #[cfg_attr(feature = "into_iterator", derive(abc::IntoIterator))]
+    #[cfg_attr(feature = "cmp", derive(abc::PartialEqSkip, abc::HashSkip))]
+    pub struct Ports(pub [u16; 2]);
+
+    #[cfg_attr(feature = "must_use_all", abc::must_use_all)]
+    impl Ports {
+        pub fn swapped(self) -> Self {
+            Ports([self.0[1], self.0[0]])
+        }
+
+        pub fn first(&self) -> u16 {
+            self.0[0]
+        }
+
+        #[cfg(feature = "delegate")]
+        abc::delegate! {
+            to self.0 {
+                pub fn len(&self) -> usize;
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "as_ref_str", derive(abc::AsRefStr))]
+    #[cfg_attr(feature = "display", derive(abc::Display))]
+    #[cfg_attr(feature = "enum_discriminants", derive(abc::EnumDiscriminants))]
+    #[cfg_attr(feature = "enum_iter", derive(abc::EnumIter))]
+    #[cfg_attr(feature = "from_str", derive(abc::FromStr))]
+    #[cfg_attr(feature = "try_from_primitive", derive(abc::TryFromPrimitive))]
+    #[cfg_attr(feature = "variant_count", derive(abc::VariantCount))]
+    #[derive(Debug)]
+    #[repr(u8)]
+    pub enum Level {
+        Low = 1,
+        High = 2,
+    }
+
+    #[cfg(feature = "error")]
+    #[derive(abc::Error, Debug)]
+    pub enum ParseError {
+        #[error("empty input")]
+        Empty,
+        // `abc`'s errors only implement `Error` with `std`.
+        #[cfg(all(feature = "from_str", feature = "std"))]
+        #[error("bad level")]
+        Level(#[from] abc::ParseEnumError),
+    }
+
+    #[cfg(feature = "singleton")]
+    #[abc::singleton]
+    pub fn settings() -> Settings {
+        Settings {
+            retries: 2,
+            verbose: true,
+        }
+    }
+
+    #[cfg(feature = "from_str")]
+    #[cfg_attr(feature = "trace", abc::trace)]
+    #[cfg_attr(feature = "timed", abc::timed)]
+    #[cfg_attr(feature = "retry", abc::retry(times = 2))]
+    pub fn parse_level(text: &str) -> Result<Level> {
+        text.parse().map_err(|_| ())
+    }
+
+    #[cfg_attr(feature = "log_errors", abc::log_errors)]
+    #[cfg_attr(feature = "deprecated_alias", abc::deprecated_alias(half_of))]
+    pub fn half(n: u8) -> Result<u8> {
+        if n % 2 == 0 {
+            Result::Ok(n / 2)
+        } else {
+            Result::Err(())
+        }
+    }
+
+    #[cfg(feature = "test_matrix")]
+    #[abc::test_matrix(n = [0, 2, 4])]
+    fn halves_even(n: u8) {
+        assert_eq!(half(n), Result::Ok(n / 2));
+    }
+}
+
+fn main() {
+    #[allow(unused_imports)]
+    use core::convert::TryFrom;
+    #[allow(unused_imports)]
+    use shadowed::*;
+
+    #[cfg(feature = "bitfield")]
+    {
+        let mut flags = Flags::from_bits(0);
+        flags.set_mode(2);
+        assert_eq!(flags.bits(), 0b100);
+    }
+    #[cfg(feature = "enum_bitmask")]
+    assert!((Permission::Read | Permission::Write).contains(Permission::Write));
+    #[cfg(feature = "enum_strings")]
+    assert!(matches!(FileKind::from("main.rs"), FileKind::Rust));
+    #[cfg(feature = "newtype")]
+    assert_eq!(Meters(1.0) + Meters(2.0), Meters(3.0));
+    #[cfg(feature = "table")]
+    assert_eq!(
+        Country::by_code("DE").map(|country| country.name),
+        Some("Germany")
+    );
+    #[cfg(feature = "kv_config")]
+    assert_eq!(Config::default().port, 8080);
+    #[cfg(feature = "state_machine")]
+    assert_eq!(State::Idle.transition(Event::Start), Ok(State::Running));
+    #[cfg(feature = "typestate")]
+    assert_eq!(Request::builder().url("/").build().url, "/");
+    #[cfg(feature = "hex")]
+    assert_eq!(KEY, [0xbe, 0xef]);
+    #[cfg(feature = "duration")]
+    assert_eq!(TIMEOUT.as_secs(), 90);
+    #[cfg(feature = "static_map")]
+    assert_eq!(PORTS.get("ssh"), Some(&22));
+
+    #[cfg(feature = "mock_trait")]
+    {
+        let store = MockStore::new();
+        store.on_get(|key| key + 1);
+        assert_eq!(store.get(1), 2);
+    }
+
+    #[cfg(all(feature = "builder", feature = "getset"))]
+    assert_eq!(
+        Settings::builder()
+            .retries(2)
+            .verbose(true)
+            .build()
+            .unwrap()
+            .retries(),
+        &2
+    );
+    #[cfg(all(feature = "singleton", feature = "getset"))]
+    assert_eq!(settings().verbose(), &true);
+    #[cfg(all(feature = "try_from_primitive", feature = "as_ref_str"))]
+    assert_eq!(
+        Level::try_from(2).ok().map(|level| level.as_str()),
+        Some("High")
+    );
+    #[cfg(feature = "variant_count")]
+    assert_eq!(Level::VARIANT_COUNT, 2);
+    #[cfg(feature = "from_str")]
+    assert!(parse_level("Low").is_ok());
+    #[cfg(feature = "cmp")]
+    assert!(Ports([1, 2]) == Ports([1, 2]));
+    assert_eq!(Ports([1, 2]).swapped().first(), 2);
+    #[cfg(feature = "delegate")]
+    assert_eq!(Ports([1, 2]).len(), 2);
+    #[cfg(feature = "base64")]
+    assert_eq!(&HELLO, b"hello");
+    #[cfg(all(feature = "semver_macro", not(feature = "semver")))]
+    assert_eq!(VERSION, (1, 2, 3, "beta.1"));
+    #[cfg(feature = "semver")]
+    assert_eq!(major(), 1);
+    #[cfg(feature = "uuid_macro")]
+    assert_eq!(ID[0], 0x67);
+    #[cfg(feature = "make_ident")]
+    assert_eq!(MAX_RETRIES, 5);
+    #[cfg(feature = "rustc_version")]
+    assert!(RECENT);
+    #[cfg(feature = "regex")]
+    assert!(is_date("2021-03-04"));
+    assert!(half(3).is_err());
+}